@@ -0,0 +1,180 @@
+//! The versions of the server's JSON the viewer understands, and turning
+//! newer ones into the first, which the parsers in `http` read.
+
+use std::fmt::Display;
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::Value;
+
+use crate::handle::Operation;
+
+/// How a server lays out its answers, as its version route tells.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum ApiVersion {
+    /// Operations as numbers in strings, lists of tasks under `parents` and
+    /// `dependees`. Servers without a version route speak it.
+    #[default]
+    V1,
+    /// Operations by name, lists of tasks under `parent_tasks` and
+    /// `dependee_tasks`.
+    V2,
+}
+
+/// Fields V2 renamed, with their names in V1.
+const RENAMED: [(&str, &str); 2] = [("parent_tasks", "parents"), ("dependee_tasks", "dependees")];
+
+impl ApiVersion {
+    /// `body`, an answer in this version, in the layout of V1.
+    pub(crate) fn normalize(self, body: String) -> Result<String> {
+        match self {
+            ApiVersion::V1 => Ok(body),
+            ApiVersion::V2 => {
+                let mut json: Value = serde_json::from_str(&body).context("parsing json")?;
+                to_v1(&mut json);
+                Ok(json.to_string())
+            }
+        }
+    }
+}
+
+impl Display for ApiVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ApiVersion::V1 => "1",
+            ApiVersion::V2 => "2",
+        })
+    }
+}
+
+fn to_v1(json: &mut Value) {
+    match json {
+        Value::Object(fields) => {
+            for (new, old) in RENAMED {
+                if let Some(value) = fields.remove(new) {
+                    fields.insert(old.to_string(), value);
+                }
+            }
+            if let Some(operation) = fields.get_mut("operation") {
+                if let Ok(parsed) = parse_operation(operation) {
+                    *operation = Value::String((parsed as u8).to_string());
+                }
+            }
+            fields.values_mut().for_each(to_v1);
+        }
+        Value::Array(items) => items.iter_mut().for_each(to_v1),
+        _ => {}
+    }
+}
+
+/// An operation as any version writes it: a number, a number in a string,
+/// or a name in any case.
+pub(crate) fn parse_operation(json: &Value) -> Result<Operation> {
+    let number = match json {
+        Value::Number(number) => number.as_u64(),
+        Value::String(text) => {
+            let named = [Operation::Apply, Operation::Eval, Operation::Fill]
+                .into_iter()
+                .find(|operation| operation.to_string().eq_ignore_ascii_case(text.trim()));
+            if let Some(operation) = named {
+                return Ok(operation);
+            }
+            text.trim().parse().ok()
+        }
+        _ => None,
+    };
+    let number = number.ok_or_else(|| anyhow!("unknown operation {json}"))?;
+    u8::try_from(number)
+        .context("parsing operation as u8")?
+        .try_into()
+        .context("casting u8 to operation")
+}
+
+/// What the version route answered.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct ServerVersion {
+    pub(crate) api: ApiVersion,
+    /// The version of the fix runtime, if the server said.
+    pub(crate) runtime: Option<String>,
+}
+
+impl ServerVersion {
+    /// Read `{"api": 2, "version": "..."}`.
+    pub(crate) fn parse(body: &str) -> Result<Self> {
+        #[derive(serde::Deserialize)]
+        struct JsonVersion {
+            api: u32,
+            #[serde(default)]
+            version: Option<String>,
+        }
+        let json: JsonVersion = serde_json::from_str(body).context("parsing version")?;
+        let api = match json.api {
+            1 => ApiVersion::V1,
+            2 => ApiVersion::V2,
+            other => bail!("the server speaks API {other}, which this viewer does not know"),
+        };
+        Ok(Self {
+            api,
+            runtime: json.version,
+        })
+    }
+}
+
+impl Display for ServerVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "API {}", self.api)?;
+        if let Some(runtime) = &self.runtime {
+            write!(f, ", fix {runtime}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_operations_of_every_version() {
+        let parse = |json: &str| parse_operation(&serde_json::from_str(json).unwrap());
+        assert_eq!(parse("1").unwrap(), Operation::Eval);
+        assert_eq!(parse(r#""2""#).unwrap(), Operation::Fill);
+        assert_eq!(parse(r#""apply""#).unwrap(), Operation::Apply);
+        assert_eq!(parse(r#"" Eval ""#).unwrap(), Operation::Eval);
+        assert!(parse(r#""force""#).is_err());
+        assert!(parse("7").is_err());
+        assert!(parse("null").is_err());
+    }
+
+    #[test]
+    fn normalizes_v2_answers() {
+        let hex = "0-0-0-2400000000000000";
+        let v2 = format!(
+            r#"{{"parent_tasks": [{{"handle": "{hex}", "operation": "Eval"}}], "next": "5"}}"#
+        );
+        let v1: Value =
+            serde_json::from_str(&ApiVersion::V2.normalize(v2.clone()).unwrap()).unwrap();
+        assert_eq!(
+            v1,
+            serde_json::json!({"parents": [{"handle": hex, "operation": "1"}], "next": "5"})
+        );
+        // The first version is left as it is.
+        assert_eq!(ApiVersion::V1.normalize(v2.clone()).unwrap(), v2);
+    }
+
+    #[test]
+    fn reads_versions() {
+        assert_eq!(
+            ServerVersion::parse(r#"{"api": 2, "version": "0.9.1"}"#).unwrap(),
+            ServerVersion {
+                api: ApiVersion::V2,
+                runtime: Some("0.9.1".to_string()),
+            }
+        );
+        assert_eq!(
+            ServerVersion::parse(r#"{"api": 1}"#).unwrap().to_string(),
+            "API 1"
+        );
+        assert!(ServerVersion::parse(r#"{"api": 3}"#).is_err());
+        assert!(ServerVersion::parse("404 page not found").is_err());
+    }
+}
@@ -1,18 +1,62 @@
-use std::sync::{
-    mpsc::{channel, Receiver, Sender},
-    Arc,
-};
+use std::{collections::HashMap, sync::Arc};
 
-use anyhow::Result;
-use egui::{TextEdit, Visuals};
-use reqwest::Client;
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use egui::{
+    text::LayoutJob, DragValue, FontId, Key, KeyboardShortcut, Modifiers, Stroke, TextEdit,
+    TextFormat,
+};
 
 use crate::{
-    graphs::GraphsContainer,
-    handle::{Handle, Operation},
-    http::{self, Response},
+    cache::ResponseCache,
+    changelog,
+    commands::{Command, CommandPalette},
+    compare::ComparePanel,
+    compose::ComposePanel,
+    console::{ErrorConsole, ErrorEntry},
+    dispatch,
+    graphs::{
+        Applied, ArrowStyle, EdgeRouting, GraphsContainer, Navigation, Pruning, Spacing, Viewports,
+    },
+    handle::{Accessibility, Handle, HandleDiagnostic, Operation},
+    http::{self, DataSource},
+    link::Link,
+    logs::LogWindow,
+    metrics::LatencyWindow,
+    orphans::OrphanPanel,
+    profile::Profiles,
+    query::QueryConsole,
+    simulate::NetworkSimulation,
+    theme::{CustomColors, OperationColors, Palette, Theme},
+    workspace::Workspace,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{
+    cli::Launch,
+    export::{ExportPanel, ExportScope},
+    fixture::Fixture,
+    image_export::{ImageGraph, ImagePanel},
+    report::{Report, ReportPanel},
+    trace::TracePanel,
 };
 
+/// Sets the target from the clipboard, whichever field has focus.
+const PASTE_TARGET: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::V);
+
+/// Where the Developer section saves the session, to be opened again with
+/// `fix_viewer session.json`.
+#[cfg(not(target_arch = "wasm32"))]
+const SESSION_FILE: &str = "session.json";
+
+/// Where the Developer section writes a test fixture of the ancestry tree.
+#[cfg(not(target_arch = "wasm32"))]
+const FIXTURE_FILE: &str = "fixture.rs";
+
+/// Where settings are exported to and imported from unless another file is
+/// named.
+#[cfg(not(target_arch = "wasm32"))]
+const SETTINGS_FILE: &str = "fix_viewer_settings.json";
+
 pub struct App {
     state: State,
     storage: Storage,
@@ -22,17 +66,95 @@ pub struct App {
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
 struct Storage {
+    /// The server URL saved before there were profiles. Moved into the active
+    /// profile on startup.
+    #[serde(skip_serializing_if = "String::is_empty")]
     url: String,
+    profiles: Profiles,
+    target: Handle,
+    operation: Operation,
+    /// Seconds a fetched response is reused for before being re-fetched.
+    cache_ttl: f64,
+    /// Send no requests and answer only from the cache or a replayed trace.
+    offline: bool,
+    simulation: NetworkSimulation,
+    /// The version whose changelog was last shown, with a digest of its
+    /// entries then, as given by `changelog::current_notes`.
+    last_seen_version: String,
+    /// Where the graphs were looking for each target, keyed by its hex.
+    viewports: HashMap<String, Viewports>,
+    /// Notes on handles, keyed by their hex.
+    notes: HashMap<String, String>,
+    /// Names given to handles, keyed by their hex, drawn instead of them.
+    aliases: HashMap<String, String>,
+    edge_routing: EdgeRouting,
+    arrow_style: ArrowStyle,
+    /// How much of the ancestry tree is drawn before far generations fold.
+    pruning: Pruning,
+    spacing: Spacing,
+    navigation: Navigation,
+    theme: Theme,
+    operation_colors: OperationColors,
+    /// The operation colors used when `operation_colors` is custom.
+    custom_colors: CustomColors,
+    /// Whether elements are drawn bigger the larger their handle is.
+    size_nodes: bool,
+    /// Hex digits kept at each end of long hex numbers in element texts.
+    /// Zero shows them whole.
+    abbreviation: usize,
+    /// Fetch the parents of parents too when a handle has at most this many
+    /// parents. Zero turns this off.
+    auto_expand: usize,
+    /// Accessibilities whose handles are left out of the ancestry tree.
+    hidden_accessibilities: Vec<Accessibility>,
+    /// Only parents performing this operation are fetched, if set.
+    parent_filter: Option<Operation>,
+    /// Whether the ancestry tree links tasks to the dependees fetched.
+    dependees_shown: bool,
+    /// The graphs as they were when last saved, restored on startup.
+    session: Option<Session>,
+    workspace: Workspace,
+}
+
+/// Every response applied to the graphs of a target, so that they can be
+/// rebuilt after a crash or a reload.
+#[derive(serde::Deserialize, serde::Serialize)]
+struct Session {
     target: Handle,
     operation: Operation,
+    journal: Vec<Applied>,
 }
 
 impl Default for Storage {
     fn default() -> Self {
         Self {
             url: String::new(),
+            profiles: Profiles::default(),
             target: Handle::from_hex("0-0-0-2400000000000000").unwrap(),
             operation: Operation::Eval,
+            cache_ttl: 60.0,
+            offline: false,
+            simulation: NetworkSimulation::default(),
+            last_seen_version: String::new(),
+            viewports: HashMap::new(),
+            notes: HashMap::new(),
+            aliases: HashMap::new(),
+            edge_routing: EdgeRouting::default(),
+            arrow_style: ArrowStyle::default(),
+            pruning: Pruning::default(),
+            spacing: Spacing::default(),
+            navigation: Navigation::default(),
+            theme: Theme::default(),
+            operation_colors: OperationColors::default(),
+            custom_colors: CustomColors::default(),
+            size_nodes: false,
+            abbreviation: 6,
+            auto_expand: 0,
+            hidden_accessibilities: vec![],
+            parent_filter: None,
+            dependees_shown: true,
+            session: None,
+            workspace: Workspace::default(),
         }
     }
 }
@@ -40,57 +162,268 @@ impl Default for Storage {
 struct State {
     target_input: String,
     response: String,
-    error: String,
+    errors: ErrorConsole,
+    log: LogWindow,
+    latency: LatencyWindow,
+    query: QueryConsole,
     first_render: bool,
-    client: Arc<Client>,
-    response_tx: Sender<(usize, Handle, Result<http::Response>)>,
-    response_rx: Receiver<(usize, Handle, Result<http::Response>)>,
+    whats_new_open: bool,
+    source: Arc<DataSource>,
+    response_tx: Sender<http::Envelope>,
+    response_rx: Receiver<http::Envelope>,
     graph: Option<GraphsContainer>,
+    cache: ResponseCache,
+    /// Top-level targets from the server, once fetched.
+    roots: Option<Vec<Handle>>,
+    compare: ComparePanel,
+    orphans: OrphanPanel,
+    compose: ComposePanel,
+    palette: CommandPalette,
+    #[cfg(not(target_arch = "wasm32"))]
+    export: ExportPanel,
+    #[cfg(not(target_arch = "wasm32"))]
+    image_export: ImagePanel,
+    #[cfg(not(target_arch = "wasm32"))]
+    trace: TracePanel,
+    #[cfg(not(target_arch = "wasm32"))]
+    report: ReportPanel,
+    /// The session file to merge into the graphs.
+    #[cfg(not(target_arch = "wasm32"))]
+    merge_path: String,
+    /// The file to export settings to and import them from.
+    #[cfg(not(target_arch = "wasm32"))]
+    settings_path: String,
+    /// The link last shown in the page address.
+    #[cfg(target_arch = "wasm32")]
+    link: Link,
 }
 
 impl Default for State {
     fn default() -> Self {
-        let (tx, rx) = channel();
+        let (tx, rx) = unbounded();
         Self {
             target_input: String::new(),
             response: String::new(),
-            error: String::new(),
+            errors: ErrorConsole::default(),
+            log: LogWindow::default(),
+            latency: LatencyWindow::default(),
+            query: QueryConsole::default(),
             first_render: true,
-            client: Arc::new(Client::new()),
+            whats_new_open: false,
+            source: Arc::new(DataSource::new(NetworkSimulation::default())),
             response_tx: tx,
             response_rx: rx,
             graph: None,
+            cache: ResponseCache::new(Storage::default().cache_ttl),
+            roots: None,
+            compare: ComparePanel::default(),
+            orphans: OrphanPanel::default(),
+            compose: ComposePanel::default(),
+            palette: CommandPalette::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            export: ExportPanel::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            image_export: ImagePanel::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            trace: TracePanel::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            report: ReportPanel::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            merge_path: String::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            settings_path: String::new(),
+            #[cfg(target_arch = "wasm32")]
+            link: Link::default(),
         }
     }
 }
 
+/// The settings a team shares to look at their fix cluster the same way:
+/// every server profile, and how the graphs are drawn, filtered and moved
+/// around. Not the target, notes, aliases or sessions of whoever exported
+/// them.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct SharedSettings {
+    /// The version of fix_viewer that exported them.
+    version: String,
+    profiles: Profiles,
+    cache_ttl: f64,
+    auto_expand: usize,
+    edge_routing: EdgeRouting,
+    arrow_style: ArrowStyle,
+    pruning: Pruning,
+    spacing: Spacing,
+    navigation: Navigation,
+    theme: Theme,
+    operation_colors: OperationColors,
+    custom_colors: CustomColors,
+    size_nodes: bool,
+    abbreviation: usize,
+    hidden_accessibilities: Vec<Accessibility>,
+    parent_filter: Option<Operation>,
+    dependees_shown: bool,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Default for SharedSettings {
+    fn default() -> Self {
+        Storage::default().settings()
+    }
+}
+
+impl Session {
+    fn of(storage: &Storage, graph: &GraphsContainer) -> Self {
+        Self {
+            target: storage.target.clone(),
+            operation: storage.operation,
+            journal: graph.journal().to_vec(),
+        }
+    }
+}
+
+impl Storage {
+    /// The colors of the theme, with the operations in the colors picked.
+    fn palette(&self) -> Palette {
+        self.theme
+            .palette()
+            .with_operations(self.operation_colors, &self.custom_colors)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn settings(&self) -> SharedSettings {
+        SharedSettings {
+            version: changelog::current_version().to_string(),
+            profiles: self.profiles.clone(),
+            cache_ttl: self.cache_ttl,
+            auto_expand: self.auto_expand,
+            edge_routing: self.edge_routing,
+            arrow_style: self.arrow_style,
+            pruning: self.pruning,
+            spacing: self.spacing,
+            navigation: self.navigation,
+            theme: self.theme,
+            operation_colors: self.operation_colors,
+            custom_colors: self.custom_colors,
+            size_nodes: self.size_nodes,
+            abbreviation: self.abbreviation,
+            hidden_accessibilities: self.hidden_accessibilities.clone(),
+            parent_filter: self.parent_filter,
+            dependees_shown: self.dependees_shown,
+        }
+    }
+
+    /// Take on imported settings, leaving the target, notes, aliases and
+    /// session.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn set_settings(&mut self, settings: SharedSettings) {
+        self.profiles = settings.profiles;
+        self.cache_ttl = settings.cache_ttl;
+        self.auto_expand = settings.auto_expand;
+        self.edge_routing = settings.edge_routing;
+        self.arrow_style = settings.arrow_style;
+        self.pruning = settings.pruning;
+        self.spacing = settings.spacing;
+        self.navigation = settings.navigation;
+        self.theme = settings.theme;
+        self.operation_colors = settings.operation_colors;
+        self.custom_colors = settings.custom_colors;
+        self.size_nodes = settings.size_nodes;
+        self.abbreviation = settings.abbreviation;
+        self.hidden_accessibilities = settings.hidden_accessibilities;
+        self.parent_filter = settings.parent_filter;
+        self.dependees_shown = settings.dependees_shown;
+    }
+
+    /// Keep the filters set in the graphs' own controls.
+    fn remember_filters(&mut self, graph: &GraphsContainer) {
+        self.hidden_accessibilities = Accessibility::ALL
+            .into_iter()
+            .filter(|accessibility| !graph.accessibility_shown(*accessibility))
+            .collect();
+        self.parent_filter = graph.parent_filter();
+        self.dependees_shown = graph.dependees_shown();
+    }
+
+    /// Start on the server and target of `link`, where it names them.
+    fn open_link(&mut self, link: Link) {
+        if let Some(server) = link.server {
+            self.profiles.open_url(&server);
+        }
+        if let Some(handle) = link.handle {
+            self.target = handle;
+        }
+    }
+
+    /// Start on the target and operation of `session`, rebuilding its graphs
+    /// on the first frame.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open_session(&mut self, session: Session) {
+        self.target = session.target.clone();
+        self.operation = session.operation;
+        self.session = Some(session);
+    }
+}
+
 impl App {
     /// Called once before the first frame.
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        if let Some(storage) = cc.storage {
-            return Self {
-                state: State::default(),
-                storage: eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default(),
-            };
+        #[cfg_attr(not(target_arch = "wasm32"), allow(unused_mut))]
+        let mut storage: Storage = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, eframe::APP_KEY))
+            .unwrap_or_default();
+        // A shared link wins over where the viewer was left.
+        #[cfg(target_arch = "wasm32")]
+        if let Some(link) = crate::link::read() {
+            storage.open_link(link);
         }
 
-        cc.egui_ctx.set_visuals(Visuals::dark());
-
+        // The theme's visuals are applied on the first frame.
         App {
             state: State::default(),
-            storage: Storage::default(),
+            storage,
+        }
+    }
+
+    /// Start where the command line says, over where the viewer was left.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn launch(cc: &eframe::CreationContext<'_>, launch: Launch) -> Self {
+        let mut app = Self::new(cc);
+        if let Some(path) = launch.session {
+            let session = std::fs::File::open(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|file| Ok(serde_json::from_reader(std::io::BufReader::new(file))?));
+            match session {
+                Ok(session) => app.storage.open_session(session),
+                Err(e) => app.state.errors.record(ErrorEntry::new(
+                    0.0,
+                    None,
+                    &e.context(format!("opening session {}", path.display())),
+                )),
+            }
         }
+        app.storage.open_link(launch.link);
+        app
     }
 }
 
 impl eframe::App for App {
     /// Called by the frame work to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        let graph = self.state.graph.as_ref();
+        self.storage.session = graph.map(|graph| Session::of(&self.storage, graph));
         eframe::set_value(storage, eframe::APP_KEY, &self.storage);
     }
 
+    /// Saved this often besides on shutdown, so that a crash loses little.
+    fn auto_save_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(10)
+    }
+
     /// Called each time the UI needs repainting, which may be many times per second.
     /// Put your widgets into a `SidePanel`, `TopPanel`, `CentralPanel`, `Window` or `Area`.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
@@ -98,14 +431,62 @@ impl eframe::App for App {
         let State {
             target_input,
             response,
-            error,
+            errors,
+            log,
+            latency,
+            query,
             first_render,
-            client,
+            whats_new_open,
+            source,
             response_tx: tx,
             response_rx: rx,
             graph,
+            cache,
+            roots,
+            compare,
+            orphans,
+            compose,
+            palette,
+            #[cfg(not(target_arch = "wasm32"))]
+            export,
+            #[cfg(not(target_arch = "wasm32"))]
+            image_export,
+            #[cfg(not(target_arch = "wasm32"))]
+            trace,
+            #[cfg(not(target_arch = "wasm32"))]
+            report,
+            #[cfg(not(target_arch = "wasm32"))]
+            merge_path,
+            #[cfg(not(target_arch = "wasm32"))]
+            settings_path,
+            #[cfg(target_arch = "wasm32")]
+            link,
         } = &mut self.state;
 
+        // Taken before any widget sees the paste, so that no field gets it.
+        let pasted_target = ctx.input_mut(|i| {
+            if !i.consume_shortcut(&PASTE_TARGET) {
+                return None;
+            }
+            let mut pasted = None;
+            i.events.retain(|event| match event {
+                egui::Event::Paste(text) => {
+                    pasted = Some(text.clone());
+                    false
+                }
+                _ => true,
+            });
+            pasted
+        });
+        let command = palette
+            .show(ctx, |query| commands(query, storage, graph.as_ref()))
+            .or_else(|| compose.show(ctx).map(Command::SetTarget))
+            .or_else(|| {
+                orphans
+                    .show(ctx, source, &storage.profiles.active().url, cache)
+                    .map(Command::SetTarget)
+            });
+
         #[cfg(not(target_arch = "wasm32"))] // no File->Quit on web pages!
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
@@ -118,30 +499,72 @@ impl eframe::App for App {
         });
 
         egui::SidePanel::left("controls").show(ctx, |ui| {
-            ui.heading("Controls");
+            ui.horizontal(|ui| {
+                ui.heading("Controls");
+                if ui.button("What's new").clicked() {
+                    *whats_new_open = true;
+                }
+                errors.badge(ui);
+                log.button(ui);
+            });
 
             ui.separator();
 
-            ui.horizontal(|ui| {
-                ui.label("URL: ");
-                TextEdit::singleline(&mut storage.url)
-                    .hint_text("127.0.0.1:9090")
-                    .desired_width(f32::INFINITY)
-                    .show(ui);
-            });
+            if *first_render && !storage.url.is_empty() {
+                storage.profiles.active_mut().url = std::mem::take(&mut storage.url);
+            }
+            let timeouts = storage.profiles.active().timeouts;
+            let server_changed = storage.profiles.ui(ui);
+            if server_changed {
+                // Responses from a different server are not valid anymore.
+                cache.clear();
+            }
+            if server_changed || storage.profiles.active().timeouts != timeouts {
+                source.set_server(storage.profiles.active());
+            }
+            if let Some(version) = source.server_version() {
+                ui.weak(format!("Server: {version}"));
+            }
+            if ui
+                .checkbox(&mut storage.offline, "Offline")
+                .on_hover_text("Send no requests; answer only from the cache or a replayed trace")
+                .changed()
+            {
+                source.set_offline(storage.offline);
+                cache.set_offline(storage.offline);
+            }
 
             if *first_render {
                 *target_input = storage.target.to_hex();
+                source.set_server(storage.profiles.active());
+                source.set_offline(storage.offline);
+                cache.set_ttl(storage.cache_ttl);
+                cache.set_offline(storage.offline);
+                source.set_simulation(storage.simulation);
+                ctx.set_visuals(storage.theme.visuals());
+                // Show what changed once after every update, including
+                // updates that only added entries to the current version.
+                let notes = changelog::current_notes();
+                if storage.last_seen_version != notes {
+                    *whats_new_open = true;
+                    storage.last_seen_version = notes;
+                }
             }
             ui.horizontal(|ui| {
                 if *first_render {
-                    *graph = Some(GraphsContainer::new(
-                        ui,
-                        storage.target.clone(),
-                        storage.operation,
-                    ));
+                    *graph = Some(new_graph(ui, storage));
+                }
+                ui.label("Target: ").on_hover_text(format!(
+                    "{} pastes a handle, in hex or base64, from anywhere",
+                    ui.ctx().format_shortcut(&PASTE_TARGET)
+                ));
+                if ui
+                    .button("Compose…")
+                    .on_hover_text("Build a handle from a value, or from a hash, size and type")
+                    .clicked()
+                {
+                    compose.open();
                 }
-                ui.label("Target: ");
                 if TextEdit::singleline(target_input)
                     .desired_width(f32::INFINITY)
                     .show(ui)
@@ -151,11 +574,133 @@ impl eframe::App for App {
                 {
                     match Handle::from_hex(target_input) {
                         Ok(handle) => {
-                            error.clear();
-                            storage.target = handle.clone();
-                            *graph = Some(GraphsContainer::new(ui, handle, storage.operation));
+                            errors.resolve_target();
+                            storage.target = handle;
+                            *graph = Some(new_graph(ui, storage));
+                        }
+                        Err(e) => errors.record(ErrorEntry::new(
+                            ui.input(|i| i.time),
+                            None,
+                            &e.context("parsing target"),
+                        )),
+                    }
+                }
+                if let Some(text) = pasted_target.as_deref() {
+                    match Handle::parse(text) {
+                        Ok(handle) => {
+                            *target_input = handle.to_hex();
+                            errors.resolve_target();
+                            storage.target = handle;
+                            *graph = Some(new_graph(ui, storage));
+                        }
+                        Err(e) => errors.record(ErrorEntry::new(
+                            ui.input(|i| i.time),
+                            None,
+                            &e.context("parsing pasted target"),
+                        )),
+                    }
+                }
+            });
+            match command {
+                None => {}
+                Some(Command::SetTarget(handle)) => {
+                    *target_input = handle.to_hex();
+                    errors.resolve_target();
+                    storage.target = handle;
+                    *graph = Some(new_graph(ui, storage));
+                }
+                Some(Command::FetchRoots) => {
+                    let url = &storage.profiles.active().url;
+                    http::get_roots(source.clone(), ctx.clone(), tx.clone(), url);
+                }
+                Some(Command::FetchSelectedParents) => {
+                    if let Some(graph) = graph.as_mut() {
+                        graph.fetch_selected_parents();
+                    }
+                }
+                Some(Command::SwitchProfile(index)) => {
+                    storage.profiles.select(index);
+                    cache.clear();
+                    source.set_server(storage.profiles.active());
+                }
+                Some(Command::SetOffline(offline)) => {
+                    storage.offline = offline;
+                    source.set_offline(offline);
+                    cache.set_offline(offline);
+                }
+                Some(Command::SetTheme(theme)) => {
+                    storage.theme = theme;
+                    ctx.set_visuals(theme.visuals());
+                    if let Some(graph) = graph.as_mut() {
+                        graph.set_palette(storage.palette());
+                    }
+                }
+                Some(Command::SetOperation(operation)) => {
+                    storage.operation = operation;
+                    if let Some(graph) = graph.as_mut() {
+                        graph.set_operation(ui, operation, storage.target.clone());
+                    }
+                }
+                Some(Command::ShowAccessibility(accessibility, shown)) => {
+                    if let Some(graph) = graph.as_mut() {
+                        graph.set_accessibility_shown(ui, accessibility, shown);
+                    }
+                }
+                Some(Command::SetSizeNodes(size_nodes)) => {
+                    storage.size_nodes = size_nodes;
+                    if let Some(graph) = graph.as_mut() {
+                        graph.set_size_scaling(size_nodes);
+                    }
+                }
+                Some(Command::ClearCache) => cache.clear(),
+                Some(Command::WhatsNew) => *whats_new_open = true,
+                #[cfg(not(target_arch = "wasm32"))]
+                Some(Command::Export(format)) => export.export(ctx, format, |scope| {
+                    graph
+                        .as_ref()
+                        .map(|graph| graph.export_snapshot(scope))
+                        .unwrap_or_default()
+                }),
+            }
+            if *first_render {
+                // Rebuild the graphs as they were when last saved.
+                let session = storage.session.take().filter(|session| {
+                    session.target == storage.target && session.operation == storage.operation
+                });
+                if let (Some(session), Some(graph)) = (session, graph.as_mut()) {
+                    graph.replay(ui, session.journal);
+                }
+            }
+            if let Err(diagnostic) = Handle::validate(target_input) {
+                ui.label(diagnostic_text(ui, target_input, &diagnostic));
+            }
+            ui.collapsing("Roots", |ui| {
+                if ui
+                    .button("Fetch roots")
+                    .on_hover_text("Ask the server which targets it knows about")
+                    .clicked()
+                {
+                    let url = &storage.profiles.active().url;
+                    http::get_roots(source.clone(), ctx.clone(), tx.clone(), url);
+                }
+                match roots {
+                    None => {}
+                    Some(roots) if roots.is_empty() => {
+                        ui.label("The server knows of no roots.");
+                    }
+                    Some(roots) => {
+                        for root in roots.iter() {
+                            if ui
+                                .link(root.to_hex())
+                                .on_hover_text(root.to_string())
+                                .clicked()
+                            {
+                                *target_input = root.to_hex();
+                                errors.resolve_target();
+                                storage.target = root.clone();
+                                *graph = Some(new_graph(ui, storage));
+                            }
                         }
-                        Err(e) => *error = format!("{:#}", e),
                     }
                 }
             });
@@ -184,38 +729,406 @@ impl eframe::App for App {
                 );
             }
 
-            if let Ok(http_result) = rx.try_recv() {
-                let index = http_result.0;
-                let handle = http_result.1;
-                match http_result.2 {
-                    Ok(Response::Parents(tasks)) => {
-                        if let Some(tasks) = tasks {
-                            log::info!("Received tasks {:?}", tasks);
-                            graph.as_mut().unwrap().set_parents(ui, handle, tasks);
+            for http_result in http::drain(rx) {
+                if let Some(entry) = dispatch::dispatch(ui, graph.as_mut(), roots, http_result) {
+                    errors.record(entry);
+                }
+            }
+
+            ui.separator();
+            let palette = storage.palette();
+            for operation in [Operation::Apply, Operation::Eval, Operation::Fill] {
+                ui.colored_label(palette.operation(operation), operation.to_string());
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("Cache TTL: ");
+                if ui
+                    .add(
+                        DragValue::new(&mut storage.cache_ttl)
+                            .clamp_range(0.0..=3600.0)
+                            .suffix(" s"),
+                    )
+                    .changed()
+                {
+                    cache.set_ttl(storage.cache_ttl);
+                }
+                if ui
+                    .button("Clear")
+                    .on_hover_text(format!("{} cached responses", cache.len()))
+                    .clicked()
+                {
+                    cache.clear();
+                }
+            });
+            ui.label("Shift-click to bypass the cache.");
+            ui.horizontal(|ui| {
+                ui.label("Auto-expand: ");
+                if ui
+                    .add(
+                        DragValue::new(&mut storage.auto_expand)
+                            .clamp_range(0..=20)
+                            .suffix(" parents"),
+                    )
+                    .on_hover_text(
+                        "When a handle has at most this many parents, fetch theirs too. \
+                         0 turns this off.",
+                    )
+                    .changed()
+                {
+                    if let Some(graph) = graph.as_mut() {
+                        graph.set_auto_expand(storage.auto_expand);
+                    }
+                }
+            });
+            if let Some(hit) = cache.last_hit() {
+                ui.label(format!(
+                    "Cache hit: {} of {} ({:.1} s old)",
+                    hit.endpoint,
+                    hit.handle.to_hex(),
+                    hit.age
+                ));
+            }
+            ui.separator();
+            ui.collapsing("Hidden", |ui| {
+                if let Some(graph) = graph.as_mut() {
+                    graph.hidden_ui(ui);
+                }
+            });
+            ui.collapsing("Compare", |ui| {
+                compare.ui(
+                    ui,
+                    &storage.profiles,
+                    &storage.target,
+                    storage.simulation,
+                    storage.offline,
+                );
+            });
+            ui.collapsing("Orphans", |ui| {
+                orphans.ui(ui, source, &storage.profiles.active().url);
+            });
+            ui.collapsing("Workspace", |ui| storage.workspace.ui(ui));
+            ui.collapsing("View", |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Theme: ");
+                    let theme = storage.theme;
+                    for choice in Theme::ALL {
+                        ui.selectable_value(&mut storage.theme, choice, choice.to_string());
+                    }
+                    if theme != storage.theme {
+                        ctx.set_visuals(storage.theme.visuals());
+                        if let Some(graph) = graph.as_mut() {
+                            graph.set_palette(storage.palette());
                         }
                     }
-                    Ok(Response::Child(child)) => {
-                        if let Some(child) = child {
-                            graph.as_mut().unwrap().set_child(ui, index, child);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Operation colors: ");
+                    let colors = storage.operation_colors;
+                    for choice in OperationColors::ALL {
+                        let text = choice.to_string();
+                        ui.selectable_value(&mut storage.operation_colors, choice, text);
+                    }
+                    if colors != storage.operation_colors {
+                        if let Some(graph) = graph.as_mut() {
+                            graph.set_palette(storage.palette());
                         }
                     }
-                    Ok(Response::Dependees(tasks)) => {
-                        log::error!("received response tasks, {:?}", tasks);
-                        if let Some(tasks) = tasks {
-                            graph.as_mut().unwrap().merge_dependees(ui, index, tasks);
+                });
+                let custom = storage.operation_colors == OperationColors::Custom;
+                if custom && storage.custom_colors.ui(ui) {
+                    if let Some(graph) = graph.as_mut() {
+                        graph.set_palette(storage.palette());
+                    }
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Edges: ");
+                    let edge_routing = storage.edge_routing;
+                    for routing in EdgeRouting::ALL {
+                        ui.selectable_value(
+                            &mut storage.edge_routing,
+                            routing,
+                            routing.to_string(),
+                        );
+                    }
+                    if edge_routing != storage.edge_routing {
+                        if let Some(graph) = graph.as_mut() {
+                            graph.set_edge_routing(storage.edge_routing);
+                        }
+                    }
+                });
+                ui.collapsing("Arrows", |ui| {
+                    if storage.arrow_style.ui(ui) {
+                        if let Some(graph) = graph.as_mut() {
+                            graph.set_arrow_style(storage.arrow_style);
                         }
                     }
-                    Err(e) => *error = format!("Failed http request: {}.", e.root_cause()),
+                });
+                ui.collapsing("Pruning", |ui| {
+                    if storage.pruning.ui(ui) {
+                        if let Some(graph) = graph.as_mut() {
+                            graph.set_pruning(ui, storage.pruning);
+                        }
+                    }
+                });
+                ui.collapsing("Spacing", |ui| {
+                    if storage.spacing.ui(ui) {
+                        if let Some(graph) = graph.as_mut() {
+                            graph.set_spacing(ui, storage.spacing);
+                        }
+                    }
+                });
+                ui.collapsing("Pan and zoom", |ui| {
+                    if storage.navigation.ui(ui) {
+                        if let Some(graph) = graph.as_mut() {
+                            graph.set_navigation(storage.navigation);
+                        }
+                    }
+                });
+                if ui
+                    .checkbox(&mut storage.size_nodes, "Size by handle size")
+                    .on_hover_text("Draw handles with more bytes or entries bigger")
+                    .changed()
+                {
+                    if let Some(graph) = graph.as_mut() {
+                        graph.set_size_scaling(storage.size_nodes);
+                    }
                 }
+                ui.horizontal(|ui| {
+                    ui.label("Abbreviate hex to: ");
+                    if ui
+                        .add(
+                            DragValue::new(&mut storage.abbreviation)
+                                .clamp_range(0..=32)
+                                .suffix(" digits each end"),
+                        )
+                        .on_hover_text(
+                            "Cut long hex numbers in labels short; selecting or hovering \
+                             an element shows them whole. 0 turns this off.",
+                        )
+                        .changed()
+                    {
+                        if let Some(graph) = graph.as_mut() {
+                            graph.set_abbreviation(ui, storage.abbreviation);
+                        }
+                    }
+                });
+            });
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                ui.separator();
+                ui.collapsing("Export", |ui| {
+                    export.ui(ui, |scope| {
+                        graph
+                            .as_ref()
+                            .map(|graph| graph.export_snapshot(scope))
+                            .unwrap_or_default()
+                    });
+                    ui.separator();
+                    image_export.ui(ui, |ui, which, size| {
+                        Some(graph.as_ref()?.render_image(ui, which, size))
+                    });
+                });
+                ui.collapsing("Share settings", |ui| {
+                    let path = if settings_path.is_empty() {
+                        SETTINGS_FILE.to_string()
+                    } else {
+                        settings_path.clone()
+                    };
+                    ui.horizontal(|ui| {
+                        if ui
+                            .button("Export settings")
+                            .on_hover_text(format!(
+                                "Write every server profile and the view settings to {path}, for others to import"
+                            ))
+                            .clicked()
+                        {
+                            let written = std::fs::File::create(&path)
+                                .map_err(anyhow::Error::from)
+                                .and_then(|file| {
+                                    serde_json::to_writer_pretty(file, &storage.settings())?;
+                                    Ok(())
+                                });
+                            match written {
+                                Ok(()) => *response = format!("Exported the settings to {path}."),
+                                Err(e) => errors.record(ErrorEntry::new(
+                                    ui.input(|i| i.time),
+                                    None,
+                                    &e.context("exporting settings"),
+                                )),
+                            }
+                        }
+                        if ui
+                            .button("Import settings")
+                            .on_hover_text(format!(
+                                "Replace every server profile and the view settings with those in {path}"
+                            ))
+                            .clicked()
+                        {
+                            let settings = std::fs::File::open(&path)
+                                .map_err(anyhow::Error::from)
+                                .and_then(|file| {
+                                    let reader = std::io::BufReader::new(file);
+                                    Ok(serde_json::from_reader::<_, SharedSettings>(reader)?)
+                                });
+                            match settings {
+                                Ok(settings) => {
+                                    storage.set_settings(settings);
+                                    cache.clear();
+                                    cache.set_ttl(storage.cache_ttl);
+                                    source.set_server(storage.profiles.active());
+                                    ctx.set_visuals(storage.theme.visuals());
+                                    if let Some(graph) = graph.as_mut() {
+                                        apply_settings(ui, graph, storage);
+                                    }
+                                    *response = format!("Imported the settings from {path}.");
+                                }
+                                Err(e) => errors.record(ErrorEntry::new(
+                                    ui.input(|i| i.time),
+                                    None,
+                                    &e.context(format!("importing settings from {path}")),
+                                )),
+                            }
+                        }
+                    });
+                    TextEdit::singleline(settings_path)
+                        .hint_text(SETTINGS_FILE)
+                        .desired_width(f32::INFINITY)
+                        .show(ui);
+                });
             }
-
             ui.separator();
-            ui.colored_label(Operation::Apply.get_color(), Operation::Apply.to_string());
-            ui.colored_label(Operation::Eval.get_color(), Operation::Eval.to_string());
-            ui.colored_label(Operation::Fill.get_color(), Operation::Fill.to_string());
+            ui.collapsing("Developer", |ui| {
+                if storage.simulation.ui(ui) {
+                    source.set_simulation(storage.simulation);
+                }
+                latency.button(ui);
+                query.button(ui);
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    ui.separator();
+                    trace.ui(ui, source.tracer());
+                    ui.separator();
+                    if ui
+                        .button("Save session")
+                        .on_hover_text(format!(
+                            "Write the target and every response so far to {SESSION_FILE}, \
+                             to open with fix_viewer {SESSION_FILE}"
+                        ))
+                        .clicked()
+                    {
+                        if let Some(graph) = graph.as_ref() {
+                            let session = Session::of(storage, graph);
+                            let written = std::fs::File::create(SESSION_FILE)
+                                .map_err(anyhow::Error::from)
+                                .and_then(|file| Ok(serde_json::to_writer(file, &session)?));
+                            match written {
+                                Ok(()) => {
+                                    *response = format!("Saved the session to {SESSION_FILE}.")
+                                }
+                                Err(e) => errors.record(ErrorEntry::new(
+                                    ui.input(|i| i.time),
+                                    None,
+                                    &e.context("saving session"),
+                                )),
+                            }
+                        }
+                    }
+                    ui.horizontal(|ui| {
+                        let path = if merge_path.is_empty() {
+                            SESSION_FILE
+                        } else {
+                            merge_path.as_str()
+                        };
+                        let clicked = ui
+                            .button("Merge session")
+                            .on_hover_text(
+                                "Add the parents another session loaded to the ancestry tree, \
+                                 to combine explorations of the same computation",
+                            )
+                            .clicked();
+                        if clicked {
+                            if let Some(graph) = graph.as_mut() {
+                                let session = std::fs::File::open(path)
+                                    .map_err(anyhow::Error::from)
+                                    .and_then(|file| {
+                                        let reader = std::io::BufReader::new(file);
+                                        Ok(serde_json::from_reader::<_, Session>(reader)?)
+                                    });
+                                match session {
+                                    Ok(session) => {
+                                        let (merged, left_out) = graph.merge(ui, session.journal);
+                                        *response = format!(
+                                            "Merged {merged} responses from {path}; left out \
+                                             {left_out} for handles not in the tree or for the \
+                                             progress tree."
+                                        );
+                                    }
+                                    Err(e) => errors.record(ErrorEntry::new(
+                                        ui.input(|i| i.time),
+                                        None,
+                                        &e.context(format!("merging session {path}")),
+                                    )),
+                                }
+                            }
+                        }
+                        TextEdit::singleline(merge_path)
+                            .hint_text(SESSION_FILE)
+                            .desired_width(f32::INFINITY)
+                            .show(ui);
+                    });
+                    if ui
+                        .button("Save test fixture")
+                        .on_hover_text(format!(
+                            "Write the ancestry tree, the responses it was built from and the \
+                             latest raw responses to {FIXTURE_FILE}, for a unit test to \
+                             rebuild and compare"
+                        ))
+                        .clicked()
+                    {
+                        if let Some(graph) = graph.as_ref() {
+                            let fixture = Fixture {
+                                target: storage.target.clone(),
+                                operation: storage.operation,
+                                journal: graph.journal().to_vec(),
+                                trace: source.tracer().recent(),
+                                graph: graph.export_snapshot(ExportScope::All),
+                            };
+                            let written = std::fs::File::create(FIXTURE_FILE)
+                                .map_err(anyhow::Error::from)
+                                .and_then(|file| fixture.write(std::io::BufWriter::new(file)));
+                            match written {
+                                Ok(()) => {
+                                    *response = format!("Saved a test fixture to {FIXTURE_FILE}.")
+                                }
+                                Err(e) => errors.record(ErrorEntry::new(
+                                    ui.input(|i| i.time),
+                                    None,
+                                    &e.context("saving test fixture"),
+                                )),
+                            }
+                        }
+                    }
+                    ui.separator();
+                    report.ui(ui, |ui, description| Report {
+                        description,
+                        server: storage.profiles.active().url.clone(),
+                        target: storage.target.to_hex(),
+                        session: graph
+                            .as_ref()
+                            .map(|graph| Session::of(storage, graph))
+                            .and_then(|session| serde_json::to_string(&session).ok())
+                            .unwrap_or_default(),
+                        trace: source.tracer().recent(),
+                        errors: errors.text(),
+                        plot: graph.as_ref().map(|graph| {
+                            graph.render_image(ui, ImageGraph::Ancestry, egui::vec2(1920.0, 1080.0))
+                        }),
+                    });
+                }
+            });
             ui.separator();
             ui.label(response.as_str());
-            ui.label(error.as_str());
 
             ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
                 ui.horizontal(|ui| {
@@ -235,11 +1148,212 @@ impl eframe::App for App {
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.heading("Windows");
             ui.separator();
+            storage
+                .workspace
+                .begin(ctx, ui.available_rect_before_wrap());
         });
-        graph
-            .as_mut()
-            .unwrap()
-            .view(ctx, client.clone(), &storage.url, tx.clone());
+        let graph = graph.as_mut().unwrap();
+        graph.view(
+            ctx,
+            source.clone(),
+            &storage.profiles.active().url,
+            tx.clone(),
+            cache,
+            &mut storage.notes,
+            &mut storage.aliases,
+        );
+        storage
+            .viewports
+            .insert(storage.target.to_hex(), graph.viewports());
+        storage.remember_filters(graph);
+        compare.show(ctx);
+        #[cfg(target_arch = "wasm32")]
+        {
+            let current = Link {
+                server: Some(storage.profiles.active().url.clone()),
+                handle: Some(storage.target.clone()),
+            };
+            if *link != current {
+                crate::link::write(&current);
+                *link = current;
+            }
+        }
+        changelog::show(ctx, whats_new_open);
+        errors.show(ctx);
+        log.show(ctx);
+        if latency.show(ctx, &source.latencies().lock().unwrap()) {
+            source.latencies().lock().unwrap().clear();
+        }
+        query.show(
+            ctx,
+            source,
+            &storage.profiles.active().url,
+            tx,
+            cache,
+            Some(graph),
+        );
         *first_render = false;
     }
 }
+
+/// Everything the command palette offers, given what was typed into it.
+fn commands(
+    query: &str,
+    storage: &Storage,
+    graph: Option<&GraphsContainer>,
+) -> Vec<(String, Command)> {
+    let mut commands = vec![];
+    if let Ok(handle) = Handle::parse(query) {
+        commands.push((
+            format!("Go to {}", handle.to_hex()),
+            Command::SetTarget(handle),
+        ));
+    }
+    commands.push(("Fetch roots".to_string(), Command::FetchRoots));
+    commands.push((
+        "Fetch parents of the selected handles".to_string(),
+        Command::FetchSelectedParents,
+    ));
+    for (index, profile) in storage.profiles.all().iter().enumerate() {
+        if profile != storage.profiles.active() {
+            commands.push((
+                format!("Switch server to {}", profile.name),
+                Command::SwitchProfile(index),
+            ));
+        }
+    }
+    let offline = if storage.offline {
+        "Go online"
+    } else {
+        "Go offline"
+    };
+    commands.push((offline.to_string(), Command::SetOffline(!storage.offline)));
+    for operation in [Operation::Apply, Operation::Eval, Operation::Fill] {
+        commands.push((
+            format!("Operation: {operation}"),
+            Command::SetOperation(operation),
+        ));
+    }
+    if let Some(graph) = graph {
+        for accessibility in Accessibility::ALL {
+            let shown = graph.accessibility_shown(accessibility);
+            let verb = if shown { "Hide" } else { "Show" };
+            commands.push((
+                format!("{verb} {accessibility} handles"),
+                Command::ShowAccessibility(accessibility, !shown),
+            ));
+        }
+    }
+    for theme in Theme::ALL {
+        commands.push((format!("Theme: {theme}"), Command::SetTheme(theme)));
+    }
+    let size_nodes = if storage.size_nodes {
+        "Draw handles all alike"
+    } else {
+        "Size handles by their size"
+    };
+    commands.push((
+        size_nodes.to_string(),
+        Command::SetSizeNodes(!storage.size_nodes),
+    ));
+    commands.push(("Clear the cache".to_string(), Command::ClearCache));
+    commands.push(("What's new".to_string(), Command::WhatsNew));
+    #[cfg(not(target_arch = "wasm32"))]
+    for format in crate::export::ExportFormat::ALL {
+        commands.push((
+            format!("Export ancestry as {format}"),
+            Command::Export(format),
+        ));
+    }
+    commands
+}
+
+/// Graphs for the stored target, looking where they last were for it.
+fn new_graph(ui: &egui::Ui, storage: &Storage) -> GraphsContainer {
+    let mut graph = GraphsContainer::new(ui, storage.target.clone(), storage.operation);
+    apply_settings(ui, &mut graph, storage);
+    graph.set_notes(&storage.notes);
+    graph.set_aliases(ui, &storage.aliases);
+    if let Some(viewports) = storage.viewports.get(&storage.target.to_hex()) {
+        graph.restore_viewports(*viewports);
+    }
+    graph
+}
+
+/// Draw `graph` the way the settings in `storage` say.
+fn apply_settings(ui: &egui::Ui, graph: &mut GraphsContainer, storage: &Storage) {
+    graph.set_edge_routing(storage.edge_routing);
+    graph.set_arrow_style(storage.arrow_style);
+    graph.set_pruning(ui, storage.pruning);
+    graph.set_spacing(ui, storage.spacing);
+    graph.set_navigation(storage.navigation);
+    graph.set_palette(storage.palette());
+    graph.set_size_scaling(storage.size_nodes);
+    graph.set_abbreviation(ui, storage.abbreviation);
+    graph.set_auto_expand(storage.auto_expand);
+    for accessibility in Accessibility::ALL {
+        let shown = !storage.hidden_accessibilities.contains(&accessibility);
+        graph.set_accessibility_shown(ui, accessibility, shown);
+    }
+    graph.set_parent_filter(storage.parent_filter);
+    graph.set_dependees_shown(storage.dependees_shown);
+}
+
+/// The target input with the part that is not valid underlined, followed by
+/// what is wrong with it.
+fn diagnostic_text(ui: &egui::Ui, input: &str, diagnostic: &HandleDiagnostic) -> LayoutJob {
+    let error_color = ui.visuals().error_fg_color;
+    let plain = TextFormat::simple(FontId::monospace(12.0), ui.visuals().text_color());
+    let offending = TextFormat {
+        color: error_color,
+        underline: Stroke::new(1.0, error_color),
+        background: error_color.linear_multiply(0.15),
+        ..plain.clone()
+    };
+    let mut job = LayoutJob::default();
+    match &diagnostic.span {
+        Some(span) => {
+            job.append(&input[..span.start], 0.0, plain.clone());
+            // Leave room to underline an empty part.
+            let part = if span.is_empty() {
+                " "
+            } else {
+                &input[span.clone()]
+            };
+            job.append(part, 0.0, offending);
+            job.append(&input[span.end..], 0.0, plain);
+        }
+        None => job.append(input, 0.0, plain),
+    }
+    job.append(
+        &format!("\n{diagnostic}"),
+        0.0,
+        TextFormat::simple(FontId::proportional(12.0), error_color),
+    );
+    job
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn imported_settings_keep_defaults_for_what_they_leave_out() {
+        let settings: SharedSettings = serde_json::from_str(r#"{"theme": "Light"}"#).unwrap();
+        assert_eq!(settings.theme, Theme::Light);
+        assert_eq!(settings.cache_ttl, Storage::default().cache_ttl);
+
+        let mut storage = Storage::default();
+        storage.abbreviation = 3;
+        storage.hidden_accessibilities = vec![Accessibility::Lazy];
+        storage.parent_filter = Some(Operation::Fill);
+        storage.dependees_shown = false;
+        let exported = serde_json::to_string(&storage.settings()).unwrap();
+        let mut imported = Storage::default();
+        imported.set_settings(serde_json::from_str(&exported).unwrap());
+        assert_eq!(imported.abbreviation, 3);
+        assert_eq!(imported.hidden_accessibilities, [Accessibility::Lazy]);
+        assert_eq!(imported.parent_filter, Some(Operation::Fill));
+        assert!(!imported.dependees_shown);
+    }
+}
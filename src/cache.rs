@@ -0,0 +1,278 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+use crossbeam_channel::Sender;
+
+use crate::{
+    handle::{Handle, Operation},
+    http::{self, Envelope, Request, RequestKind, Response},
+};
+
+/// The server endpoint a response was fetched from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Endpoint {
+    Parents,
+    /// How many parents a handle has, without listing them.
+    ParentCount,
+    /// Only the parents performing an operation.
+    FilteredParents(Operation),
+    Child(Operation),
+    Dependees(Operation),
+    /// Execution status of the task performing an operation on a handle.
+    Status(Operation),
+    TaskTimes,
+    Tag,
+    Tree,
+    /// Contents of a Blob.
+    Blob,
+    /// The canonical handle of a local one.
+    Canonical,
+    /// Running the task performing an operation on a handle again, which is
+    /// never cached.
+    Rerun(Operation),
+    /// Loading the data of a lazy or shallow handle into the server's
+    /// storage, which is never cached.
+    Materialize,
+    /// Top-level targets, which belong to no handle and are never cached.
+    Roots,
+    /// Whether the server has a handle's object, which is never cached.
+    Exists,
+}
+
+/// A successful lookup, kept around so the UI can show where the last
+/// response came from.
+#[derive(Clone, Debug)]
+pub(crate) struct CacheHit {
+    pub(crate) handle: Handle,
+    pub(crate) endpoint: Endpoint,
+    /// Seconds since the cached response was received.
+    pub(crate) age: f64,
+}
+
+/// Responses keyed by (handle, endpoint). Entries older than the TTL are
+/// treated as missing. Shared between the UI and the in-flight requests,
+/// which insert their responses as soon as they are parsed.
+#[derive(Clone)]
+pub(crate) struct ResponseCache {
+    inner: Arc<Mutex<Inner>>,
+}
+
+struct Inner {
+    /// Time to live in seconds.
+    ttl: f64,
+    /// Entries do not expire while offline, since they cannot be fetched
+    /// again.
+    offline: bool,
+    /// Maps to the time of insertion and the response.
+    entries: HashMap<(Handle, Endpoint), (f64, Response)>,
+    last_hit: Option<CacheHit>,
+}
+
+impl ResponseCache {
+    pub(crate) fn new(ttl: f64) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                ttl,
+                offline: false,
+                entries: HashMap::new(),
+                last_hit: None,
+            })),
+        }
+    }
+
+    pub(crate) fn set_ttl(&self, ttl: f64) {
+        self.inner.lock().unwrap().ttl = ttl;
+    }
+
+    pub(crate) fn set_offline(&self, offline: bool) {
+        self.inner.lock().unwrap().offline = offline;
+    }
+
+    /// Look up a response that is younger than the TTL, or any response while
+    /// offline. Expired entries are dropped. Records the outcome for
+    /// `last_hit`.
+    pub(crate) fn get(&self, handle: &Handle, endpoint: Endpoint, now: f64) -> Option<Response> {
+        let mut inner = self.inner.lock().unwrap();
+        let key = (handle.clone(), endpoint);
+        let (ttl, offline) = (inner.ttl, inner.offline);
+        let fresh = match inner.entries.get(&key) {
+            Some((inserted, response)) if offline || now - inserted <= ttl => {
+                Some((now - inserted, response.clone()))
+            }
+            Some(_) => {
+                inner.entries.remove(&key);
+                None
+            }
+            None => None,
+        };
+        inner.last_hit = fresh.as_ref().map(|(age, _)| CacheHit {
+            handle: key.0,
+            endpoint,
+            age: *age,
+        });
+        fresh.map(|(_, response)| response)
+    }
+
+    pub(crate) fn insert(&self, handle: Handle, endpoint: Endpoint, now: f64, response: Response) {
+        self.inner
+            .lock()
+            .unwrap()
+            .entries
+            .insert((handle, endpoint), (now, response));
+    }
+
+    /// Drop every cached response, e.g. when switching servers.
+    pub(crate) fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.entries.clear();
+        inner.last_hit = None;
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    /// Every handle with a cached response or named in one.
+    pub(crate) fn handles(&self) -> HashSet<Handle> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .entries
+            .iter()
+            .flat_map(|((handle, _), (_, response))| {
+                std::iter::once(handle.clone()).chain(response.handles())
+            })
+            .collect()
+    }
+
+    pub(crate) fn last_hit(&self) -> Option<CacheHit> {
+        self.inner.lock().unwrap().last_hit.clone()
+    }
+
+    /// Send a cached response for `kind` through `tx` as if it had just
+    /// arrived. Returns whether there was a fresh entry to send.
+    pub(crate) fn resend(
+        &self,
+        ctx: &egui::Context,
+        kind: RequestKind,
+        tx: &Sender<Envelope>,
+    ) -> bool {
+        let (Some(handle), true) = (kind.handle(), kind.is_first_page()) else {
+            return false;
+        };
+        let now = ctx.input(|i| i.time);
+        let Some(response) = self.get(handle, kind.endpoint(), now) else {
+            return false;
+        };
+        let envelope = Envelope {
+            request: Request::new(kind),
+            result: Ok(response),
+        };
+        http::deliver(ctx, tx, envelope);
+        true
+    }
+
+    /// Wrap a response parser so that successfully parsed responses are also
+    /// stored in the cache.
+    pub(crate) fn inserting<T>(
+        &self,
+        ctx: egui::Context,
+        kind: &RequestKind,
+        map: impl FnOnce(T) -> Result<Response>,
+    ) -> impl FnOnce(T) -> Result<Response> {
+        let cache = self.clone();
+        let key = kind
+            .handle()
+            .filter(|_| kind.is_first_page())
+            .map(|handle| (handle.clone(), kind.endpoint()));
+        move |json| {
+            let response = map(json)?;
+            if let Some((handle, endpoint)) = key {
+                cache.insert(handle, endpoint, ctx.input(|i| i.time), response.clone());
+            }
+            Ok(response)
+        }
+    }
+}
+
+impl std::fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Endpoint::Parents => f.write_str("parents"),
+            Endpoint::ParentCount => f.write_str("parent count"),
+            Endpoint::FilteredParents(op) => write!(f, "parents ({op})"),
+            Endpoint::Child(op) => write!(f, "child ({op})"),
+            Endpoint::Dependees(op) => write!(f, "dependees ({op})"),
+            Endpoint::Status(op) => write!(f, "status ({op})"),
+            Endpoint::Rerun(op) => write!(f, "re-run ({op})"),
+            Endpoint::Materialize => f.write_str("materialize"),
+            Endpoint::TaskTimes => f.write_str("task times"),
+            Endpoint::Tag => f.write_str("tag"),
+            Endpoint::Tree => f.write_str("tree"),
+            Endpoint::Blob => f.write_str("blob"),
+            Endpoint::Canonical => f.write_str("canonical"),
+            Endpoint::Roots => f.write_str("roots"),
+            Endpoint::Exists => f.write_str("exists"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handle() -> Handle {
+        Handle::from_hex("d9-0-4-100000000000000").unwrap()
+    }
+
+    #[test]
+    fn expires_after_ttl() {
+        let cache = ResponseCache::new(10.0);
+        cache.insert(
+            handle(),
+            Endpoint::Parents,
+            0.0,
+            Response::Parents(None, None),
+        );
+        assert!(cache.get(&handle(), Endpoint::Parents, 5.0).is_some());
+        assert_eq!(cache.last_hit().unwrap().age, 5.0);
+        assert!(cache.get(&handle(), Endpoint::Parents, 11.0).is_none());
+        assert!(cache.last_hit().is_none());
+        assert_eq!(cache.len(), 0);
+    }
+
+    #[test]
+    fn kept_while_offline() {
+        let cache = ResponseCache::new(10.0);
+        cache.insert(
+            handle(),
+            Endpoint::Parents,
+            0.0,
+            Response::Parents(None, None),
+        );
+        cache.set_offline(true);
+        assert!(cache.get(&handle(), Endpoint::Parents, 100.0).is_some());
+        cache.set_offline(false);
+        assert!(cache.get(&handle(), Endpoint::Parents, 100.0).is_none());
+    }
+
+    #[test]
+    fn keyed_by_endpoint() {
+        let cache = ResponseCache::new(10.0);
+        cache.insert(
+            handle(),
+            Endpoint::Child(Operation::Eval),
+            0.0,
+            Response::Child(None),
+        );
+        assert!(cache.get(&handle(), Endpoint::Parents, 1.0).is_none());
+        assert!(cache
+            .get(&handle(), Endpoint::Child(Operation::Apply), 1.0)
+            .is_none());
+        assert!(cache
+            .get(&handle(), Endpoint::Child(Operation::Eval), 1.0)
+            .is_some());
+    }
+}
@@ -0,0 +1,118 @@
+use egui::{Context, RichText};
+
+/// The changelog, embedded so the app can show what changed since it was last
+/// run.
+const CHANGELOG: &str = include_str!("../CHANGELOG.md");
+
+/// The changes made in one version.
+#[derive(Debug, PartialEq)]
+struct Release<'a> {
+    version: &'a str,
+    changes: Vec<&'a str>,
+}
+
+/// Split a changelog into releases, newest first as written.
+fn parse(changelog: &str) -> Vec<Release<'_>> {
+    let mut releases: Vec<Release> = vec![];
+    for line in changelog.lines() {
+        if let Some(version) = line.strip_prefix("## ") {
+            releases.push(Release {
+                version: version.trim(),
+                changes: vec![],
+            });
+        } else if let (Some(change), Some(release)) = (line.strip_prefix("- "), releases.last_mut())
+        {
+            release.changes.push(change.trim());
+        }
+    }
+    releases
+}
+
+/// The version of the running app.
+pub(crate) fn current_version() -> &'static str {
+    env!("CARGO_PKG_VERSION")
+}
+
+/// The current version and a digest of its entries, which changes whenever
+/// an entry is added to the version, so that the entries are shown again
+/// even when the version was not bumped.
+pub(crate) fn current_notes() -> String {
+    notes_of(CHANGELOG, current_version())
+}
+
+fn notes_of(changelog: &str, version: &str) -> String {
+    // FNV-1a, which unlike the std hasher is the same on every build.
+    let mut digest: u64 = 0xcbf2_9ce4_8422_2325;
+    let changes = parse(changelog)
+        .into_iter()
+        .filter(|release| release.version == version)
+        .flat_map(|release| release.changes);
+    for change in changes {
+        for byte in change.bytes().chain([b'\n']) {
+            digest = (digest ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    format!("{version} {digest:016x}")
+}
+
+/// Show the "What's new" window while `open` is set.
+pub(crate) fn show(ctx: &Context, open: &mut bool) {
+    egui::Window::new("What's new")
+        .open(open)
+        .resizable(true)
+        .show(ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for release in parse(CHANGELOG) {
+                    let mut heading = RichText::new(release.version).heading();
+                    if release.version == current_version() {
+                        heading = heading.strong();
+                    }
+                    ui.label(heading);
+                    for change in release.changes {
+                        ui.label(format!("• {change}"));
+                    }
+                    ui.separator();
+                }
+            });
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_releases() {
+        let releases = parse("# Changelog\n\n- ignored\n## 0.2.0\n- b\n- c\n\n## 0.1.0\n- a\n");
+        assert_eq!(
+            releases,
+            vec![
+                Release {
+                    version: "0.2.0",
+                    changes: vec!["b", "c"],
+                },
+                Release {
+                    version: "0.1.0",
+                    changes: vec!["a"],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn notes_change_with_the_entries_of_the_version() {
+        let before = notes_of("## 0.2.0\n- b\n## 0.1.0\n- a\n", "0.2.0");
+        let added = notes_of("## 0.2.0\n- b\n- c\n## 0.1.0\n- a\n", "0.2.0");
+        let older = notes_of("## 0.2.0\n- b\n## 0.1.0\n- a\n- z\n", "0.2.0");
+        assert!(before.starts_with("0.2.0 "));
+        assert_ne!(before, added);
+        assert_eq!(before, older);
+    }
+
+    #[test]
+    fn lists_current_version() {
+        assert!(parse(CHANGELOG)
+            .iter()
+            .any(|release| release.version == current_version()));
+    }
+}
@@ -0,0 +1,109 @@
+//! Command-line arguments of the native viewer, so that scripts, the fix CLI
+//! or a file association can open it on a server and handle.
+
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+
+use crate::{handle::Handle, link::Link};
+
+pub const USAGE: &str = "\
+Usage: fix_viewer [--url HOST:PORT] [--handle HANDLE] [SESSION]
+
+  --url HOST:PORT  Query this server, adding a profile for it if there is none
+  --handle HANDLE  Open on this handle, in hex or base64
+  SESSION          A session saved from the Developer section, to rebuild the
+                   graphs from
+  -h, --help       Show this message";
+
+/// Where to start, as given on the command line.
+#[derive(Debug, Default, PartialEq)]
+pub struct Launch {
+    pub(crate) link: Link,
+    pub(crate) session: Option<PathBuf>,
+    /// Time drawing a synthetic tree of this many handles and print the
+    /// report instead of opening the viewer. Left out of the usage, since it
+    /// is only for measuring performance work.
+    pub(crate) bench: Option<usize>,
+}
+
+impl Launch {
+    /// Read the arguments that follow the program name. Options take their
+    /// value as the next argument or after `=`. Returns None if help was
+    /// asked for.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Option<Self>> {
+        let mut launch = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+            let mut value = || {
+                inline
+                    .clone()
+                    .or_else(|| args.next())
+                    .with_context(|| format!("{flag} needs a value"))
+            };
+            match flag {
+                "-h" | "--help" => return Ok(None),
+                "--url" => launch.link.server = Some(value()?),
+                "--bench" => {
+                    let nodes = value()?.parse().context("parsing --bench")?;
+                    launch.bench = Some(nodes);
+                }
+                "--handle" => {
+                    let handle = Handle::parse(&value()?).context("parsing --handle")?;
+                    launch.link.handle = Some(handle);
+                }
+                _ if flag.starts_with('-') => bail!("unknown option {flag}"),
+                _ if launch.session.is_none() => launch.session = Some(arg.clone().into()),
+                _ => bail!("only one session can be opened, not also {arg}"),
+            }
+        }
+        Ok(Some(launch))
+    }
+
+    /// How many handles to benchmark drawing, if asked to.
+    pub fn bench(&self) -> Option<usize> {
+        self.bench
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Launch>> {
+        Launch::from_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn arguments() {
+        assert_eq!(parse(&[]).unwrap(), Some(Launch::default()));
+        assert_eq!(parse(&["--url", "a", "-h"]).unwrap(), None);
+        let launch = parse(&[
+            "--url=127.0.0.1:9090",
+            "--handle",
+            "d9-0-4-100000000000000",
+            "s.json",
+        ])
+        .unwrap()
+        .unwrap();
+        assert_eq!(launch.link.server.as_deref(), Some("127.0.0.1:9090"));
+        assert_eq!(
+            launch.link.handle,
+            Some(Handle::from_hex("d9-0-4-100000000000000").unwrap())
+        );
+        assert_eq!(launch.session, Some(PathBuf::from("s.json")));
+        assert!(parse(&["--url"]).is_err());
+        assert!(parse(&["--handle", "nope"]).is_err());
+        assert!(parse(&["--port", "1"]).is_err());
+        assert!(parse(&["a.json", "b.json"]).is_err());
+        assert_eq!(
+            parse(&["--bench", "500"]).unwrap().unwrap().bench(),
+            Some(500)
+        );
+        assert!(parse(&["--bench", "many"]).is_err());
+    }
+}
@@ -0,0 +1,193 @@
+use egui::{Key, KeyboardShortcut, Modifiers, TextEdit};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::export::ExportFormat;
+use crate::{
+    handle::{Accessibility, Handle, Operation},
+    theme::Theme,
+};
+
+/// Opens the command palette.
+const OPEN_PALETTE: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::P);
+
+/// Commands listed at most, best matches first.
+const MAX_SHOWN: usize = 12;
+
+/// Something the command palette can do.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Command {
+    SetTarget(Handle),
+    FetchRoots,
+    /// Fetch the parents of every selected handle in the ancestry tree.
+    FetchSelectedParents,
+    /// Make the profile at this index the active one.
+    SwitchProfile(usize),
+    SetOffline(bool),
+    SetTheme(Theme),
+    SetOperation(Operation),
+    ShowAccessibility(Accessibility, bool),
+    SetSizeNodes(bool),
+    ClearCache,
+    WhatsNew,
+    #[cfg(not(target_arch = "wasm32"))]
+    Export(ExportFormat),
+}
+
+/// A searchable list of commands, opened with Ctrl+P.
+#[derive(Default)]
+pub(crate) struct CommandPalette {
+    open: bool,
+    query: String,
+    /// Index of the highlighted command among those matching.
+    selected: usize,
+}
+
+impl CommandPalette {
+    /// Show the palette if it is open. `commands` lists what can be done
+    /// given the query, each with its label. Returns the command picked, if
+    /// any, which closes the palette.
+    pub(crate) fn show(
+        &mut self,
+        ctx: &egui::Context,
+        commands: impl FnOnce(&str) -> Vec<(String, Command)>,
+    ) -> Option<Command> {
+        if ctx.input_mut(|i| i.consume_shortcut(&OPEN_PALETTE)) {
+            self.open = true;
+            self.query.clear();
+            self.selected = 0;
+        }
+        if !self.open {
+            return None;
+        }
+        // Taken before the query field sees them.
+        let (escape, up, down, enter) = ctx.input_mut(|i| {
+            (
+                i.consume_key(Modifiers::NONE, Key::Escape),
+                i.consume_key(Modifiers::NONE, Key::ArrowUp),
+                i.consume_key(Modifiers::NONE, Key::ArrowDown),
+                i.consume_key(Modifiers::NONE, Key::Enter),
+            )
+        });
+        if escape {
+            self.open = false;
+            return None;
+        }
+
+        let mut matching: Vec<_> = commands(&self.query)
+            .into_iter()
+            .filter_map(|(label, command)| {
+                Some((fuzzy_score(&self.query, &label)?, label, command))
+            })
+            .collect();
+        // Stable, so that equal scores keep the order they were listed in.
+        matching.sort_by_key(|(score, _, _)| -score);
+        matching.truncate(MAX_SHOWN);
+        if down {
+            self.selected += 1;
+        }
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        self.selected = self.selected.min(matching.len().saturating_sub(1));
+
+        let mut picked = enter.then_some(self.selected);
+        egui::Window::new("Commands")
+            .title_bar(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 40.0])
+            .show(ctx, |ui| {
+                let query = TextEdit::singleline(&mut self.query)
+                    .hint_text("Type a command, or a handle to go to")
+                    .desired_width(400.0)
+                    .show(ui)
+                    .response;
+                query.request_focus();
+                if query.changed() {
+                    self.selected = 0;
+                }
+                ui.separator();
+                if matching.is_empty() {
+                    ui.weak("No matching commands.");
+                }
+                for (index, (_, label, _)) in matching.iter().enumerate() {
+                    if ui
+                        .selectable_label(index == self.selected, label.as_str())
+                        .clicked()
+                    {
+                        picked = Some(index);
+                    }
+                }
+            });
+        let (_, _, command) = matching.into_iter().nth(picked?)?;
+        self.open = false;
+        Some(command)
+    }
+}
+
+/// How well `label` matches `query`, higher being better, or None if the
+/// characters of the query do not all appear in the label in order. Case and
+/// spaces in the query are ignored. Characters matched right after the
+/// previous one, or at the start of a word, count for more.
+fn fuzzy_score(query: &str, label: &str) -> Option<i32> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    let label: Vec<char> = label.chars().flat_map(char::to_lowercase).collect();
+    // Matching each character as early as possible finds a match whenever
+    // there is one, but jumping ahead to word starts often scores higher.
+    [false, true]
+        .into_iter()
+        .filter_map(|word_starts| score_matches(&query, &label, word_starts))
+        .max()
+}
+
+fn score_matches(query: &[char], label: &[char], prefer_word_starts: bool) -> Option<i32> {
+    let is_word_start = |index: usize| index == 0 || !label[index - 1].is_alphanumeric();
+    let mut score = 0;
+    let mut from = 0;
+    for &wanted in query {
+        let mut matches = (from..label.len()).filter(|&index| label[index] == wanted);
+        let first = matches.next()?;
+        let index = match prefer_word_starts && first != from {
+            true => std::iter::once(first)
+                .chain(matches)
+                .find(|&index| is_word_start(index))
+                .unwrap_or(first),
+            false => first,
+        };
+        score += 1;
+        // Right after the previous match, which there is once `from` moved.
+        if from > 0 && index == from {
+            score += 4;
+        }
+        if is_word_start(index) {
+            score += 2;
+        }
+        from = index + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_matching() {
+        assert_eq!(fuzzy_score("", "Fetch roots"), Some(0));
+        assert!(fuzzy_score("frt", "Fetch roots").is_some());
+        assert!(fuzzy_score("FR", "fetch roots").is_some());
+        assert!(fuzzy_score("rf", "Fetch roots").is_none());
+        // Word starts and runs beat scattered characters.
+        assert!(
+            fuzzy_score("theme", "Theme: Dark").unwrap()
+                > fuzzy_score("theme", "The mode exit").unwrap()
+        );
+        assert!(
+            fuzzy_score("off", "Go offline").unwrap()
+                > fuzzy_score("off", "Open a fine file").unwrap()
+        );
+    }
+}
@@ -0,0 +1,360 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use egui::{ComboBox, DragValue, RichText, ScrollArea, Ui};
+
+use crate::{
+    cache::ResponseCache,
+    handle::{Handle, Operation},
+    http::{self, DataSource, Envelope, Generation, RequestKind, Response},
+    profile::{Profiles, ServerProfile},
+    simulate::NetworkSimulation,
+};
+
+/// A parent as far as comparing is concerned: restrictions are left out.
+type Parent = (Handle, Operation);
+
+/// How a parent found on one server fares on the other.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Agreement {
+    Both,
+    OnlyHere,
+    /// The other server has not answered for the child yet.
+    Unknown,
+}
+
+/// Whether `parent` is also a parent of the same child on the other server,
+/// given the other server's parents of that child.
+fn agreement(theirs: Option<&Option<Vec<Parent>>>, parent: &Parent) -> Agreement {
+    match theirs {
+        None => Agreement::Unknown,
+        Some(Some(parents)) if parents.contains(parent) => Agreement::Both,
+        Some(_) => Agreement::OnlyHere,
+    }
+}
+
+/// The ancestry of the target as one server tells it.
+struct Side {
+    name: String,
+    url: String,
+    source: Arc<DataSource>,
+    /// Kept apart from the main cache, which holds another server's answers.
+    cache: ResponseCache,
+    tx: Sender<Envelope>,
+    rx: Receiver<Envelope>,
+    /// Parents of each handle once every page has arrived, or None if the
+    /// server knows nothing about the handle.
+    parents: HashMap<Handle, Option<Vec<Parent>>>,
+    /// Pages received so far of lists that are still being fetched.
+    partial: HashMap<Handle, Vec<Parent>>,
+    /// How many generations up from the target each asked for handle is.
+    depth: HashMap<Handle, usize>,
+    errors: Vec<String>,
+    in_flight: usize,
+}
+
+impl Side {
+    fn new(profile: &ServerProfile, simulation: NetworkSimulation, offline: bool) -> Self {
+        let source = DataSource::new(simulation);
+        source.set_server(profile);
+        source.set_offline(offline);
+        let (tx, rx) = unbounded();
+        Self {
+            name: profile.name.clone(),
+            url: profile.url.clone(),
+            source: Arc::new(source),
+            cache: ResponseCache::new(0.0),
+            tx,
+            rx,
+            parents: HashMap::new(),
+            partial: HashMap::new(),
+            depth: HashMap::new(),
+            errors: vec![],
+            in_flight: 0,
+        }
+    }
+
+    /// Ask for the parents of `handle`, `depth` generations up, unless they
+    /// were asked for already.
+    fn ask(&mut self, ctx: &egui::Context, handle: &Handle, depth: usize) {
+        if self.depth.contains_key(handle) {
+            return;
+        }
+        self.depth.insert(handle.clone(), depth);
+        self.request(ctx, handle, None);
+    }
+
+    fn request(&mut self, ctx: &egui::Context, handle: &Handle, after: Option<&str>) {
+        self.in_flight += 1;
+        http::get_parents(
+            self.source.clone(),
+            ctx.clone(),
+            Generation::default(),
+            handle,
+            after,
+            None,
+            self.tx.clone(),
+            &self.url,
+            &self.cache,
+        );
+    }
+
+    /// Take in every response that has arrived, following further pages,
+    /// and asking for the parents of new parents up to `max_depth`.
+    fn receive(&mut self, ctx: &egui::Context, max_depth: usize) {
+        let envelopes: Vec<_> = self.rx.try_iter().collect();
+        for Envelope { request, result } in envelopes {
+            self.in_flight -= 1;
+            let RequestKind::Parents { handle, .. } = request.kind else {
+                continue;
+            };
+            match result {
+                Err(e) => self.errors.push(format!("{}: {e:#}", handle.to_hex())),
+                Ok(Response::Parents(None, _)) => {
+                    self.partial.remove(&handle);
+                    self.parents.insert(handle, None);
+                }
+                Ok(Response::Parents(Some(tasks), next)) => {
+                    self.partial
+                        .entry(handle.clone())
+                        .or_default()
+                        .extend(tasks.into_iter().map(|task| (task.handle, task.operation)));
+                    if let Some(next) = next {
+                        self.request(ctx, &handle, Some(&next.cursor));
+                        continue;
+                    }
+                    let parents = self.partial.remove(&handle).unwrap_or_default();
+                    let depth = self.depth.get(&handle).copied().unwrap_or_default();
+                    if depth + 1 < max_depth {
+                        for (parent, _) in &parents {
+                            self.ask(ctx, parent, depth + 1);
+                        }
+                    }
+                    self.parents.insert(handle, Some(parents));
+                }
+                Ok(response) => log::warn!("Comparison got a {} response", response.kind()),
+            }
+        }
+    }
+
+    /// Number of parents this server lists that the other one does not.
+    fn discrepancies(&self, other: &Side) -> usize {
+        self.parents
+            .iter()
+            .filter_map(|(child, parents)| Some((child, parents.as_ref()?)))
+            .flat_map(|(child, parents)| {
+                parents.iter().filter(|parent| {
+                    agreement(other.parents.get(child), parent) == Agreement::OnlyHere
+                })
+            })
+            .count()
+    }
+
+    fn ui(&self, ui: &mut Ui, other: &Side, target: &Handle) {
+        ui.heading(self.name.as_str())
+            .on_hover_text(self.url.as_str());
+        match self.discrepancies(other) {
+            0 => ui.label(format!("Nothing missing on {}.", other.name)),
+            n => ui.colored_label(
+                ui.visuals().error_fg_color,
+                format!("{n} parents missing on {}.", other.name),
+            ),
+        };
+        if self.in_flight > 0 {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(format!("{} requests in flight", self.in_flight));
+            });
+        }
+        for error in &self.errors {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+        ui.separator();
+        ScrollArea::both().id_source(&self.name).show(ui, |ui| {
+            ui.monospace(target.to_hex());
+            let mut visited = HashSet::from([target.clone()]);
+            self.rows(ui, other, target, 1, &mut visited);
+        });
+    }
+
+    /// The parents of `child`, indented `depth` levels, each followed by its
+    /// own parents. Handles reached again are listed without their parents.
+    fn rows(
+        &self,
+        ui: &mut Ui,
+        other: &Side,
+        child: &Handle,
+        depth: usize,
+        visited: &mut HashSet<Handle>,
+    ) {
+        let indent = depth as f32 * ui.spacing().indent;
+        let parents = match self.parents.get(child) {
+            Some(Some(parents)) => parents,
+            Some(None) => {
+                ui.horizontal(|ui| {
+                    ui.add_space(indent);
+                    ui.weak("unknown to this server");
+                });
+                return;
+            }
+            None => {
+                if self.depth.contains_key(child) {
+                    ui.horizontal(|ui| {
+                        ui.add_space(indent);
+                        ui.weak("…");
+                    });
+                }
+                return;
+            }
+        };
+        for parent in parents {
+            let text = RichText::new(format!("{} {}", parent.1, parent.0.to_hex())).monospace();
+            ui.horizontal(|ui| {
+                ui.add_space(indent);
+                match agreement(other.parents.get(child), parent) {
+                    Agreement::Both => ui.label(text),
+                    Agreement::OnlyHere => ui
+                        .label(text.color(ui.visuals().error_fg_color))
+                        .on_hover_text(format!("Not a parent of this child on {}", other.name)),
+                    Agreement::Unknown => ui
+                        .label(text.weak())
+                        .on_hover_text(format!("{} has not answered yet", other.name)),
+                };
+            });
+            if visited.insert(parent.0.clone()) {
+                self.rows(ui, other, &parent.0, depth + 1, visited);
+            }
+        }
+    }
+}
+
+/// The target's ancestry fetched from two servers at once.
+struct Comparison {
+    target: Handle,
+    depth: usize,
+    sides: [Side; 2],
+}
+
+/// Compare controls for the side panel, and the window with the comparison.
+pub(crate) struct ComparePanel {
+    /// Index of the profile compared against the active one.
+    reference: usize,
+    /// Generations of parents fetched above the target.
+    depth: usize,
+    comparison: Option<Comparison>,
+    open: bool,
+}
+
+impl Default for ComparePanel {
+    fn default() -> Self {
+        Self {
+            reference: 0,
+            depth: 3,
+            comparison: None,
+            open: false,
+        }
+    }
+}
+
+impl ComparePanel {
+    /// Pick the server to compare the active one with, and start comparing
+    /// the ancestry of `target` on both.
+    pub(crate) fn ui(
+        &mut self,
+        ui: &mut Ui,
+        profiles: &Profiles,
+        target: &Handle,
+        simulation: NetworkSimulation,
+        offline: bool,
+    ) {
+        let all = profiles.all();
+        self.reference = self.reference.min(all.len() - 1);
+        ui.horizontal(|ui| {
+            ui.label("With: ");
+            ComboBox::from_id_source("compare_reference")
+                .selected_text(all[self.reference].name.as_str())
+                .show_ui(ui, |ui| {
+                    for (index, profile) in all.iter().enumerate() {
+                        ui.selectable_value(&mut self.reference, index, profile.name.as_str());
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Generations: ");
+            ui.add(DragValue::new(&mut self.depth).clamp_range(1..=20));
+        });
+        let reference = &all[self.reference];
+        if reference == profiles.active() {
+            ui.label("Pick a server other than the active one.");
+            return;
+        }
+        if ui
+            .button("Compare")
+            .on_hover_text("Fetch the target's parents from both servers side by side")
+            .clicked()
+        {
+            let ctx = ui.ctx();
+            let mut sides = [
+                Side::new(profiles.active(), simulation, offline),
+                Side::new(reference, simulation, offline),
+            ];
+            for side in &mut sides {
+                side.ask(ctx, target, 0);
+            }
+            self.comparison = Some(Comparison {
+                target: target.clone(),
+                depth: self.depth,
+                sides,
+            });
+            self.open = true;
+        }
+    }
+
+    /// The comparison window, if one was started. Also takes in responses.
+    pub(crate) fn show(&mut self, ctx: &egui::Context) {
+        let Some(comparison) = self.comparison.as_mut() else {
+            return;
+        };
+        for side in &mut comparison.sides {
+            side.receive(ctx, comparison.depth);
+        }
+        let [active, reference] = &comparison.sides;
+        egui::Window::new("Comparison")
+            .open(&mut self.open)
+            .default_size([640.0, 400.0])
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "Parents of {}, {} generations up. Parents only one server lists are highlighted.",
+                    comparison.target.to_hex(),
+                    comparison.depth
+                ));
+                ui.separator();
+                ui.columns(2, |columns| {
+                    active.ui(&mut columns[0], reference, &comparison.target);
+                    reference.ui(&mut columns[1], active, &comparison.target);
+                });
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn agreement_of_parents() {
+        let parent = (
+            Handle::from_hex("10-0-0-2400000000000000").unwrap(),
+            Operation::Eval,
+        );
+        let other = (parent.0.clone(), Operation::Apply);
+        assert_eq!(agreement(None, &parent), Agreement::Unknown);
+        assert_eq!(agreement(Some(&None), &parent), Agreement::OnlyHere);
+        let theirs = Some(vec![parent.clone()]);
+        assert_eq!(agreement(Some(&theirs), &parent), Agreement::Both);
+        assert_eq!(agreement(Some(&theirs), &other), Agreement::OnlyHere);
+    }
+}
@@ -0,0 +1,186 @@
+use anyhow::{ensure, Context, Result};
+use egui::{ComboBox, DragValue, TextEdit, Ui};
+
+use crate::handle::{Accessibility, Handle, Object};
+
+/// What the typed value of a literal is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum LiteralValue {
+    /// Stored as its 8 little-endian bytes.
+    U64,
+    /// Stored as its UTF-8 bytes.
+    Text,
+}
+
+/// Which kind of handle is being composed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    Literal(LiteralValue),
+    Canonical,
+}
+
+/// A window that builds a handle from its parts, the reverse of parsing one.
+pub(crate) struct ComposePanel {
+    open: bool,
+    kind: Kind,
+    accessibility: Accessibility,
+    value: String,
+    /// Hex of the content hash of a canonical handle.
+    hash: String,
+    size: u64,
+    object: Object,
+}
+
+impl Default for ComposePanel {
+    fn default() -> Self {
+        Self {
+            open: false,
+            kind: Kind::Literal(LiteralValue::U64),
+            accessibility: Accessibility::Strict,
+            value: String::new(),
+            hash: String::new(),
+            size: 0,
+            object: Object::Blob,
+        }
+    }
+}
+
+/// The bytes spelled by `hex`, two digits each, ignoring whitespace.
+fn hex_bytes(hex: &str) -> Result<Vec<u8>> {
+    let digits: Vec<char> = hex.chars().filter(|c| !c.is_whitespace()).collect();
+    ensure!(digits.len() % 2 == 0, "odd number of hex digits");
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let pair: String = pair.iter().collect();
+            u8::from_str_radix(&pair, 16).with_context(|| format!("{pair} is not hex"))
+        })
+        .collect()
+}
+
+impl ComposePanel {
+    pub(crate) fn open(&mut self) {
+        self.open = true;
+    }
+
+    /// The handle the form describes, or why it does not describe one.
+    fn handle(&self) -> Result<Handle> {
+        match self.kind {
+            Kind::Literal(LiteralValue::U64) => {
+                let value: u64 = self.value.trim().parse().context("not a u64")?;
+                Handle::literal(&value.to_le_bytes(), self.accessibility)
+            }
+            Kind::Literal(LiteralValue::Text) => {
+                Handle::literal(self.value.as_bytes(), self.accessibility)
+            }
+            Kind::Canonical => Handle::canonical(
+                &hex_bytes(&self.hash)?,
+                self.size,
+                self.object,
+                self.accessibility,
+            ),
+        }
+    }
+
+    /// Show the window if it is open. Returns the composed handle once it is
+    /// picked as the target.
+    pub(crate) fn show(&mut self, ctx: &egui::Context) -> Option<Handle> {
+        let mut picked = None;
+        let mut open = self.open;
+        egui::Window::new("Compose handle")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| picked = self.ui(ui));
+        self.open = open && picked.is_none();
+        picked
+    }
+
+    fn ui(&mut self, ui: &mut Ui) -> Option<Handle> {
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.kind, Kind::Literal(LiteralValue::U64), "u64");
+            ui.selectable_value(&mut self.kind, Kind::Literal(LiteralValue::Text), "String");
+            ui.selectable_value(&mut self.kind, Kind::Canonical, "Canonical");
+        });
+        ComboBox::from_label("Accessibility")
+            .selected_text(self.accessibility.to_string())
+            .show_ui(ui, |ui| {
+                for accessibility in Accessibility::ALL {
+                    ui.selectable_value(
+                        &mut self.accessibility,
+                        accessibility,
+                        accessibility.to_string(),
+                    );
+                }
+            });
+        match self.kind {
+            Kind::Literal(LiteralValue::U64) => {
+                ui.horizontal(|ui| {
+                    ui.label("Value: ");
+                    ui.text_edit_singleline(&mut self.value);
+                });
+            }
+            Kind::Literal(LiteralValue::Text) => {
+                ui.horizontal(|ui| {
+                    ui.label("Value: ");
+                    ui.text_edit_singleline(&mut self.value);
+                });
+                ui.weak(format!("{} of 31 bytes", self.value.len()));
+            }
+            Kind::Canonical => {
+                ui.horizontal(|ui| {
+                    ui.label("Hash: ");
+                    ui.add(
+                        TextEdit::singleline(&mut self.hash)
+                            .hint_text("hex, at least 27 bytes")
+                            .font(egui::TextStyle::Monospace),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Size: ");
+                    ui.add(DragValue::new(&mut self.size));
+                });
+                ComboBox::from_label("Type")
+                    .selected_text(self.object.to_string())
+                    .show_ui(ui, |ui| {
+                        for object in Object::ALL {
+                            ui.selectable_value(&mut self.object, object, object.to_string());
+                        }
+                    });
+            }
+        }
+        ui.separator();
+        match self.handle() {
+            Ok(handle) => {
+                let hex = handle.to_hex();
+                ui.monospace(hex.as_str());
+                ui.label(handle.to_string());
+                let mut picked = None;
+                ui.horizontal(|ui| {
+                    if ui.button("Copy").clicked() {
+                        ui.output_mut(|o| o.copied_text = hex);
+                    }
+                    if ui.button("Set as target").clicked() {
+                        picked = Some(handle);
+                    }
+                });
+                picked
+            }
+            Err(e) => {
+                ui.colored_label(ui.visuals().error_fg_color, format!("{e:#}"));
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_digits() {
+        assert_eq!(hex_bytes("00ff 1a").unwrap(), vec![0x00, 0xff, 0x1a]);
+        assert!(hex_bytes("abc").is_err());
+        assert!(hex_bytes("zz").is_err());
+    }
+}
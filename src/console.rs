@@ -0,0 +1,182 @@
+use egui::{Color32, Context, RichText, Ui};
+
+use crate::{
+    cache::Endpoint,
+    handle::Handle,
+    workspace::{self, ERRORS},
+};
+
+/// Something that went wrong, kept until the user dismisses it.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ErrorEntry {
+    /// Seconds since the app started.
+    pub(crate) time: f64,
+    /// The request that failed, or `None` if the target could not be parsed.
+    pub(crate) request: Option<(Endpoint, Handle)>,
+    /// The error followed by each of its causes.
+    pub(crate) chain: Vec<String>,
+}
+
+impl ErrorEntry {
+    pub(crate) fn new(
+        time: f64,
+        request: Option<(Endpoint, Handle)>,
+        error: &anyhow::Error,
+    ) -> Self {
+        Self {
+            time,
+            request,
+            chain: error.chain().map(ToString::to_string).collect(),
+        }
+    }
+}
+
+/// Every error since the app started, shown in the "Errors" window.
+#[derive(Default)]
+pub(crate) struct ErrorConsole {
+    entries: Vec<ErrorEntry>,
+    /// Entries recorded since the window was last open.
+    unseen: usize,
+    open: bool,
+}
+
+impl ErrorConsole {
+    pub(crate) fn record(&mut self, entry: ErrorEntry) {
+        log::error!("{}", entry.chain.join(": "));
+        // The target is parsed on every keystroke, so only keep the latest
+        // complaint about it.
+        if entry.request.is_none() && self.entries.last().map_or(false, |e| e.request.is_none()) {
+            *self.entries.last_mut().unwrap() = entry;
+            return;
+        }
+        self.entries.push(entry);
+        if !self.open {
+            self.unseen += 1;
+        }
+    }
+
+    /// Drop the complaint about a target that has since been fixed.
+    pub(crate) fn resolve_target(&mut self) {
+        if self.entries.last().map_or(false, |e| e.request.is_none()) {
+            self.entries.pop();
+            self.unseen = self.unseen.min(self.entries.len());
+        }
+    }
+
+    /// Every entry as plain text, oldest first, as the window shows them.
+    pub(crate) fn text(&self) -> String {
+        let mut text = String::new();
+        for entry in &self.entries {
+            let what = match &entry.request {
+                Some((endpoint, handle)) => format!("{endpoint} of {}", handle.to_hex()),
+                None => "target".to_string(),
+            };
+            text += &format!("{} {what}\n", format_time(entry.time));
+            for (depth, cause) in entry.chain.iter().enumerate() {
+                let prefix = if depth == 0 { "" } else { "caused by: " };
+                text += &format!("  {}{prefix}{cause}\n", "  ".repeat(depth));
+            }
+        }
+        text
+    }
+
+    /// A button that opens the window, badged with the number of new errors.
+    pub(crate) fn badge(&mut self, ui: &mut Ui) {
+        let text = match self.unseen {
+            0 => RichText::new("Errors"),
+            unseen => RichText::new(format!("Errors ({unseen} new)")).color(Color32::RED),
+        };
+        if ui.button(text).clicked() {
+            self.open = true;
+        }
+    }
+
+    /// Show the window while it is open.
+    pub(crate) fn show(&mut self, ctx: &Context) {
+        if !self.open {
+            return;
+        }
+        self.unseen = 0;
+        let entries = &mut self.entries;
+        let window = egui::Window::new(ERRORS)
+            .open(&mut self.open)
+            .resizable(true);
+        let shown = workspace::place(ctx, ERRORS, window).show(ctx, |ui| {
+            if entries.is_empty() {
+                ui.label("No errors.");
+                return;
+            }
+            if ui.button("Dismiss all").clicked() {
+                entries.clear();
+            }
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                // Newest first, each with its own dismiss button.
+                let mut dismissed = None;
+                for (index, entry) in entries.iter().enumerate().rev() {
+                    ui.horizontal(|ui| {
+                        if ui.small_button("✖").on_hover_text("Dismiss").clicked() {
+                            dismissed = Some(index);
+                        }
+                        ui.monospace(format_time(entry.time));
+                        match &entry.request {
+                            Some((endpoint, handle)) => {
+                                ui.label(format!("{endpoint} of {}", handle.to_hex()))
+                            }
+                            None => ui.label("target"),
+                        };
+                    });
+                    for (depth, cause) in entry.chain.iter().enumerate() {
+                        let prefix = if depth == 0 { "" } else { "caused by: " };
+                        ui.label(format!("{}{prefix}{cause}", "  ".repeat(depth)));
+                    }
+                    ui.separator();
+                }
+                if let Some(index) = dismissed {
+                    entries.remove(index);
+                }
+            });
+        });
+        workspace::track(ctx, ERRORS, &shown);
+    }
+}
+
+/// Format seconds since the app started as `hh:mm:ss`.
+pub(crate) fn format_time(seconds: f64) -> String {
+    let seconds = seconds as u64;
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(request: Option<(Endpoint, Handle)>, message: &str) -> ErrorEntry {
+        ErrorEntry::new(0.0, request, &anyhow::anyhow!(message.to_string()))
+    }
+
+    #[test]
+    fn keeps_latest_target_error() {
+        let handle = Handle::from_hex("0-0-0-2400000000000000").unwrap();
+        let mut console = ErrorConsole::default();
+        console.record(entry(Some((Endpoint::Parents, handle)), "timeout"));
+        console.record(entry(None, "bad hex"));
+        console.record(entry(None, "worse hex"));
+        assert_eq!(console.entries.len(), 2);
+        assert_eq!(console.entries[1].chain, ["worse hex"]);
+        assert_eq!(console.unseen, 2);
+
+        console.resolve_target();
+        assert_eq!(console.entries.len(), 1);
+        assert_eq!(console.unseen, 1);
+    }
+
+    #[test]
+    fn formats_time() {
+        assert_eq!(format_time(3723.9), "01:02:03");
+    }
+}
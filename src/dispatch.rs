@@ -0,0 +1,156 @@
+use egui::Ui;
+
+use crate::{
+    console::ErrorEntry,
+    graphs::GraphsContainer,
+    handle::Handle,
+    http::{Envelope, RequestKind, Response},
+};
+
+/// Route a response from the server to the UI action that requested it.
+/// Responses that cannot be applied are logged and otherwise ignored.
+/// Roots go to `roots` whether or not there is a graph.
+/// Returns an entry for the error console if the request failed.
+pub(crate) fn dispatch(
+    ui: &Ui,
+    graph: Option<&mut GraphsContainer>,
+    roots: &mut Option<Vec<Handle>>,
+    Envelope { request, result }: Envelope,
+) -> Option<ErrorEntry> {
+    let response = match result {
+        Ok(response) => response,
+        Err(e) => {
+            let kind = &request.kind;
+            match (kind, graph.filter(|graph| graph.asked_for(kind))) {
+                (RequestKind::Rerun { handle, operation }, Some(graph)) => {
+                    graph.set_rerun(handle.clone(), *operation, None);
+                }
+                (RequestKind::Materialize { handle }, Some(graph)) => {
+                    graph.set_materialized(handle.clone(), false);
+                }
+                (RequestKind::Parents { handle, .. }, Some(graph)) => {
+                    graph.parents_failed(handle);
+                }
+                (RequestKind::Exists { handle }, Some(graph)) => {
+                    graph.probe_failed(handle.clone());
+                }
+                _ => {}
+            }
+            return Some(ErrorEntry::new(
+                ui.input(|i| i.time),
+                kind.handle()
+                    .map(|handle| (kind.endpoint(), handle.clone())),
+                &e.context(format!("request {} failed", request.id)),
+            ));
+        }
+    };
+    if let Response::Roots(found) = response {
+        log::info!("Received {} roots", found.len());
+        *roots = Some(found);
+        return None;
+    }
+    let Some(graph) = graph else {
+        log::warn!(
+            "Dropping {} response to request {} since no graph exists",
+            response.kind(),
+            request.id
+        );
+        return None;
+    };
+    if !graph.asked_for(&request.kind) {
+        log::info!(
+            "Dropping {} response to request {} since its graph was replaced",
+            response.kind(),
+            request.id
+        );
+        return None;
+    }
+    match (request.kind, response) {
+        (RequestKind::Parents { handle, .. }, Response::Parents(Some(tasks), next)) => {
+            log::info!("Received parents {:?}", tasks);
+            graph.set_parents(ui, handle, tasks, next);
+        }
+        (RequestKind::Parents { handle, .. }, Response::Parents(None, _)) => {
+            log::warn!("Server has no parents for {}", handle.to_hex());
+            graph.parents_failed(&handle);
+        }
+        (RequestKind::ParentCount { handle }, Response::ParentCount(Some(count))) => {
+            log::info!("Received parent count {count} of {}", handle.to_hex());
+            graph.set_parent_count(handle, count);
+        }
+        (RequestKind::Child { index, .. }, Response::Child(Some((child, restriction)))) => {
+            log::info!("Received child {}", child.to_hex());
+            graph.set_child(ui, index, child, restriction);
+        }
+        // A missing result is worth telling too.
+        (RequestKind::Outcome { handle, operation }, Response::Child(child)) => {
+            log::info!("Received {operation} outcome of {}", handle.to_hex());
+            graph.set_outcome(ui, handle, operation, child.map(|(child, _)| child));
+        }
+        (RequestKind::Dependees { index, .. }, Response::Dependees(Some(tasks), next)) => {
+            log::info!("Received dependees {:?}", tasks);
+            graph.merge_dependees(ui, index, tasks, next);
+        }
+        (RequestKind::Status { handle, operation }, Response::Status(Some(status))) => {
+            log::info!("Received status {status} of {}", handle.to_hex());
+            graph.set_status(handle, operation, status);
+        }
+        // A refusal is worth telling too.
+        (RequestKind::Rerun { handle, operation }, Response::Rerun(status)) => {
+            log::info!(
+                "Asked to re-run {operation} of {}: {status:?}",
+                handle.to_hex()
+            );
+            graph.set_rerun(handle, operation, status);
+        }
+        (RequestKind::Materialize { handle }, Response::Materialize(resident)) => {
+            log::info!("Asked to load {}: resident {resident}", handle.to_hex());
+            graph.set_materialized(handle, resident);
+        }
+        (RequestKind::TaskTimes { .. }, Response::TaskTimes(Some(task_times))) => {
+            log::info!("Received {} task times", task_times.len());
+            graph.set_task_times(task_times);
+        }
+        (RequestKind::Tag { handle }, Response::Tag(Some(tag))) => {
+            log::info!("Received tag {:?}", tag);
+            graph.set_tag(handle, tag);
+        }
+        (RequestKind::Tree { handle }, Response::Tree(Some(entries))) => {
+            log::info!("Received {} tree entries", entries.len());
+            graph.set_tree(handle, entries);
+        }
+        (RequestKind::Blob { handle }, Response::Blob(Some(contents))) => {
+            log::info!(
+                "Received {} bytes of blob {}",
+                contents.len(),
+                handle.to_hex()
+            );
+            graph.set_blob(handle, contents);
+        }
+        (RequestKind::Exists { handle }, Response::Exists(exists)) => {
+            log::info!("{} exists: {exists}", handle.to_hex());
+            graph.set_exists(handle, exists);
+        }
+        (RequestKind::Canonical { handle }, Response::Canonical(Some(canonical))) => {
+            log::info!("{} is canonically {}", handle.to_hex(), canonical.to_hex());
+            graph.set_canonical(handle, canonical);
+        }
+        // The matching response kinds with data are handled above.
+        (kind, response) if response.answers(&kind) => {
+            log::warn!(
+                "Server has no {} for {}, leaving graph unchanged",
+                response.kind(),
+                kind.handle().map(Handle::to_hex).unwrap_or_default()
+            );
+        }
+        (kind, response) => {
+            log::error!(
+                "Request {} for {:?} was answered with {} response",
+                request.id,
+                kind,
+                response.kind()
+            );
+        }
+    }
+    None
+}
@@ -0,0 +1,562 @@
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use anyhow::{bail, Result};
+use egui::{plot::PlotBounds, Color32, ProgressBar, TextEdit, Ui};
+use serde_json::json;
+
+use crate::handle::{Handle, LiteralView, Operation};
+
+/// Version of the JSON export's layout, written into every file so that other
+/// tools can tell what they are reading. Bump it whenever a field changes
+/// meaning or goes away. Version 1 was unversioned, with only each node's hex
+/// and edges naming their ends by hex.
+const JSON_SCHEMA_VERSION: u32 = 2;
+
+/// File formats a graph can be exported to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum ExportFormat {
+    #[default]
+    Dot,
+    Json,
+    Svg,
+    /// The hex of each handle, one per line, for other fix tools to read.
+    Text,
+    /// Each handle's hex, type, size and accessibility.
+    Csv,
+}
+
+impl ExportFormat {
+    pub(crate) const ALL: [ExportFormat; 5] = [
+        ExportFormat::Dot,
+        ExportFormat::Json,
+        ExportFormat::Svg,
+        ExportFormat::Text,
+        ExportFormat::Csv,
+    ];
+
+    pub(crate) fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Dot => "dot",
+            ExportFormat::Json => "json",
+            ExportFormat::Svg => "svg",
+            ExportFormat::Text => "txt",
+            ExportFormat::Csv => "csv",
+        }
+    }
+}
+
+impl std::fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ExportFormat::Dot => "DOT",
+            ExportFormat::Json => "JSON",
+            ExportFormat::Svg => "SVG",
+            ExportFormat::Text => "Handle list",
+            ExportFormat::Csv => "CSV",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Which handles of the ancestry tree are exported.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum ExportScope {
+    #[default]
+    All,
+    /// The selected handles and the arrows between them.
+    Selected,
+    /// The focused handle and all of its ancestors.
+    Subtree,
+}
+
+impl ExportScope {
+    pub(crate) const ALL: [ExportScope; 3] = [
+        ExportScope::All,
+        ExportScope::Selected,
+        ExportScope::Subtree,
+    ];
+}
+
+impl std::fmt::Display for ExportScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ExportScope::All => "Everything",
+            ExportScope::Selected => "Selected handles",
+            ExportScope::Subtree => "Subtree of the focus",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// A handle in the graph and where it is drawn.
+#[derive(Clone, Debug)]
+pub(crate) struct ExportNode {
+    pub(crate) handle: Handle,
+    pub(crate) bounds: PlotBounds,
+}
+
+/// An arrow from `nodes[from]` to `nodes[to]`.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ExportEdge {
+    pub(crate) from: usize,
+    pub(crate) to: usize,
+    pub(crate) operation: Operation,
+    /// The color the arrow is drawn in.
+    pub(crate) color: Color32,
+}
+
+/// A copy of a graph's structure that can be written out off the UI thread.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct GraphSnapshot {
+    pub(crate) nodes: Vec<ExportNode>,
+    pub(crate) edges: Vec<ExportEdge>,
+}
+
+impl GraphSnapshot {
+    /// The number of items written, for progress reporting.
+    pub(crate) fn len(&self) -> usize {
+        self.nodes.len() + self.edges.len()
+    }
+}
+
+/// Write `snapshot` to `out` one item at a time, so that the output never has
+/// to fit in memory. `progress` counts the items written so far. Stops with an
+/// error as soon as `cancel` is set.
+pub(crate) fn write(
+    snapshot: &GraphSnapshot,
+    format: ExportFormat,
+    out: &mut impl Write,
+    progress: &AtomicUsize,
+    cancel: &AtomicBool,
+) -> Result<()> {
+    let step = || -> Result<()> {
+        if cancel.load(Ordering::Relaxed) {
+            bail!("export cancelled");
+        }
+        progress.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    };
+    match format {
+        ExportFormat::Dot => {
+            writeln!(out, "digraph fix {{")?;
+            for node in &snapshot.nodes {
+                step()?;
+                writeln!(out, "  \"{}\";", node.handle.to_hex())?;
+            }
+            for edge in &snapshot.edges {
+                step()?;
+                writeln!(
+                    out,
+                    "  \"{}\" -> \"{}\" [label=\"{}\", color=\"{}\"];",
+                    snapshot.nodes[edge.from].handle.to_hex(),
+                    snapshot.nodes[edge.to].handle.to_hex(),
+                    edge.operation,
+                    hex_color(edge.color),
+                )?;
+            }
+            writeln!(out, "}}")?;
+        }
+        ExportFormat::Json => {
+            write!(
+                out,
+                "{{\"schema_version\":{JSON_SCHEMA_VERSION},\"nodes\":["
+            )?;
+            for (i, node) in snapshot.nodes.iter().enumerate() {
+                step()?;
+                if i > 0 {
+                    write!(out, ",")?;
+                }
+                serde_json::to_writer(&mut *out, &json_node(i, node))?;
+            }
+            write!(out, "],\"edges\":[")?;
+            for (i, edge) in snapshot.edges.iter().enumerate() {
+                step()?;
+                if i > 0 {
+                    write!(out, ",")?;
+                }
+                // Ends are node ids, since a handle may be drawn more than once.
+                serde_json::to_writer(
+                    &mut *out,
+                    &json!({
+                        "from": edge.from,
+                        "to": edge.to,
+                        "operation": edge.operation.to_string(),
+                    }),
+                )?;
+            }
+            writeln!(out, "]}}")?;
+        }
+        ExportFormat::Svg => {
+            /// Pixels per unit of plot space.
+            const SCALE: f64 = 400.0;
+
+            let mut extent = PlotBounds::NOTHING;
+            for node in &snapshot.nodes {
+                extent.merge(&node.bounds);
+            }
+            if !extent.is_valid() {
+                extent = PlotBounds::from_min_max([0.0, 0.0], [0.0, 0.0]);
+            }
+            // SVG's y axis points down, so flip plot coordinates.
+            let to_svg =
+                |x: f64, y: f64| ((x - extent.min()[0]) * SCALE, (extent.max()[1] - y) * SCALE);
+            writeln!(
+                out,
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\">",
+                extent.width() * SCALE,
+                extent.height() * SCALE,
+            )?;
+            for node in &snapshot.nodes {
+                step()?;
+                let (x, y) = to_svg(node.bounds.min()[0], node.bounds.max()[1]);
+                let (width, height) = (node.bounds.width() * SCALE, node.bounds.height() * SCALE);
+                writeln!(
+                    out,
+                    "  <rect x=\"{x:.1}\" y=\"{y:.1}\" width=\"{width:.1}\" height=\"{height:.1}\" \
+                     fill=\"none\" stroke=\"black\"/>",
+                )?;
+                writeln!(
+                    out,
+                    "  <text x=\"{:.1}\" y=\"{:.1}\" font-size=\"{:.1}\" font-family=\"monospace\" \
+                     text-anchor=\"middle\" dominant-baseline=\"middle\">{}</text>",
+                    x + width / 2.0,
+                    y + height / 2.0,
+                    height / 2.0,
+                    node.handle.to_hex(),
+                )?;
+            }
+            for edge in &snapshot.edges {
+                step()?;
+                let from = snapshot.nodes[edge.from].bounds.center();
+                let to = snapshot.nodes[edge.to].bounds.center();
+                let (x1, y1) = to_svg(from.x, from.y);
+                let (x2, y2) = to_svg(to.x, to.y);
+                writeln!(
+                    out,
+                    "  <line x1=\"{x1:.1}\" y1=\"{y1:.1}\" x2=\"{x2:.1}\" y2=\"{y2:.1}\" \
+                     stroke=\"{}\"/>",
+                    hex_color(edge.color),
+                )?;
+            }
+            writeln!(out, "</svg>")?;
+        }
+        ExportFormat::Text | ExportFormat::Csv => {
+            if format == ExportFormat::Csv {
+                writeln!(out, "hex,type,size,accessibility")?;
+            }
+            for node in &snapshot.nodes {
+                step()?;
+                let handle = &node.handle;
+                match format {
+                    ExportFormat::Csv => writeln!(
+                        out,
+                        "{},{},{},{}",
+                        handle.to_hex(),
+                        handle.object(),
+                        handle.size,
+                        handle.accessibility
+                    )?,
+                    _ => writeln!(out, "{}", handle.to_hex())?,
+                }
+            }
+            // Lists leave the arrows out.
+            progress.fetch_add(snapshot.edges.len(), Ordering::Relaxed);
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// A node of the JSON export: its handle, decoded, and where it is drawn.
+fn json_node(id: usize, node: &ExportNode) -> serde_json::Value {
+    let handle = &node.handle;
+    json!({
+        "id": id,
+        "handle": handle.to_hex(),
+        "accessibility": handle.accessibility.to_string(),
+        "type": handle.object().to_string(),
+        "storage": handle.storage(),
+        "size": handle.size,
+        "literal": handle.literal_as(LiteralView::Hex).ok(),
+        "bounds": {
+            "min": node.bounds.min(),
+            "max": node.bounds.max(),
+        },
+    })
+}
+
+fn hex_color(color: Color32) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b())
+}
+
+/// An export running on a background thread, writing straight to a file.
+pub(crate) struct ExportJob {
+    path: PathBuf,
+    total: usize,
+    progress: Arc<AtomicUsize>,
+    cancel: Arc<AtomicBool>,
+    result: Arc<Mutex<Option<Result<()>>>>,
+}
+
+impl ExportJob {
+    pub(crate) fn start(
+        ctx: egui::Context,
+        snapshot: GraphSnapshot,
+        format: ExportFormat,
+        path: PathBuf,
+    ) -> Self {
+        let job = Self {
+            path: path.clone(),
+            total: snapshot.len(),
+            progress: Arc::new(AtomicUsize::new(0)),
+            cancel: Arc::new(AtomicBool::new(false)),
+            result: Arc::new(Mutex::new(None)),
+        };
+        let (progress, cancel, result) =
+            (job.progress.clone(), job.cancel.clone(), job.result.clone());
+        std::thread::spawn(move || {
+            let written = (|| -> Result<()> {
+                let mut out = BufWriter::new(File::create(&path)?);
+                write(&snapshot, format, &mut out, &progress, &cancel)
+            })();
+            if written.is_err() {
+                // Do not leave a truncated file behind.
+                let _ = fs::remove_file(&path);
+            }
+            *result.lock().unwrap() = Some(written);
+            ctx.request_repaint();
+        });
+        job
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Fraction of items written, in [0, 1].
+    pub(crate) fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            return 1.0;
+        }
+        self.progress.load(Ordering::Relaxed) as f32 / self.total as f32
+    }
+
+    pub(crate) fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    /// The outcome of the export once the background thread is done.
+    pub(crate) fn take_result(&self) -> Option<Result<()>> {
+        self.result.lock().unwrap().take()
+    }
+}
+
+/// Export controls for the side panel, along with the export in progress.
+#[derive(Default)]
+pub(crate) struct ExportPanel {
+    format: ExportFormat,
+    path: String,
+    scope: ExportScope,
+    job: Option<ExportJob>,
+    status: String,
+}
+
+impl ExportPanel {
+    /// `snapshot` is only called when an export is started, and is told
+    /// which handles to include.
+    pub(crate) fn ui(&mut self, ui: &mut Ui, snapshot: impl FnOnce(ExportScope) -> GraphSnapshot) {
+        ui.horizontal(|ui| {
+            for format in ExportFormat::ALL {
+                ui.selectable_value(&mut self.format, format, format.to_string());
+            }
+        });
+        let default_path = format!("graph.{}", self.format.extension());
+        ui.horizontal(|ui| {
+            ui.label("Path: ");
+            TextEdit::singleline(&mut self.path)
+                .hint_text(default_path.as_str())
+                .desired_width(f32::INFINITY)
+                .show(ui);
+        });
+        egui::ComboBox::from_label("Handles")
+            .selected_text(self.scope.to_string())
+            .show_ui(ui, |ui| {
+                for scope in ExportScope::ALL {
+                    ui.selectable_value(&mut self.scope, scope, scope.to_string());
+                }
+            });
+
+        if let Some(result) = self.job.as_ref().and_then(ExportJob::take_result) {
+            let path = self.job.take().unwrap().path().display().to_string();
+            self.status = match result {
+                Ok(()) => format!("Exported to {path}."),
+                Err(e) => format!("Export to {path} stopped: {e:#}."),
+            };
+        }
+        match &self.job {
+            Some(job) => {
+                ui.horizontal(|ui| {
+                    ui.add(ProgressBar::new(job.fraction()).show_percentage());
+                    if ui.button("Cancel").clicked() {
+                        job.cancel();
+                    }
+                });
+                // Keep the progress bar moving while the export runs.
+                ui.ctx().request_repaint_after(Duration::from_millis(100));
+            }
+            None => {
+                if ui.button("Export ancestry").clicked() {
+                    self.export(ui.ctx(), self.format, snapshot);
+                }
+            }
+        }
+        if !self.status.is_empty() {
+            ui.label(self.status.as_str());
+        }
+    }
+
+    /// Start exporting in `format` to the path set in the panel, unless an
+    /// export is running already.
+    pub(crate) fn export(
+        &mut self,
+        ctx: &egui::Context,
+        format: ExportFormat,
+        snapshot: impl FnOnce(ExportScope) -> GraphSnapshot,
+    ) {
+        if self.job.is_some() {
+            return;
+        }
+        self.format = format;
+        let path = if self.path.is_empty() {
+            format!("graph.{}", format.extension())
+        } else {
+            self.path.clone()
+        };
+        self.status.clear();
+        self.job = Some(ExportJob::start(
+            ctx.clone(),
+            snapshot(self.scope),
+            format,
+            path.into(),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> GraphSnapshot {
+        let node = |hex: &str, y: f64| ExportNode {
+            handle: Handle::from_hex(hex).unwrap(),
+            bounds: PlotBounds::from_min_max([0.0, y], [1.0, y + 0.5]),
+        };
+        GraphSnapshot {
+            nodes: vec![
+                node("0-0-0-2400000000000000", 0.0),
+                node("1-0-0-2400000000000000", 1.0),
+            ],
+            edges: vec![ExportEdge {
+                from: 1,
+                to: 0,
+                operation: Operation::Eval,
+                color: Color32::BLUE,
+            }],
+        }
+    }
+
+    #[test]
+    fn writes_dot() {
+        let progress = AtomicUsize::new(0);
+        let mut out = vec![];
+        write(
+            &snapshot(),
+            ExportFormat::Dot,
+            &mut out,
+            &progress,
+            &AtomicBool::new(false),
+        )
+        .unwrap();
+        let dot = String::from_utf8(out).unwrap();
+        assert!(dot.starts_with("digraph fix {"));
+        assert!(dot.contains("\"1-0-0-2400000000000000\" -> \"0-0-0-2400000000000000\""));
+        assert_eq!(progress.load(Ordering::Relaxed), snapshot().len());
+    }
+
+    #[test]
+    fn writes_json() {
+        let mut out = vec![];
+        write(
+            &snapshot(),
+            ExportFormat::Json,
+            &mut out,
+            &AtomicUsize::new(0),
+            &AtomicBool::new(false),
+        )
+        .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(json["schema_version"], JSON_SCHEMA_VERSION);
+        let node = &json["nodes"][1];
+        assert_eq!(node["id"], 1);
+        assert_eq!(node["handle"], "1-0-0-2400000000000000");
+        assert_eq!(node["type"], "Blob");
+        assert_eq!(node["storage"], "literal");
+        assert_eq!(node["size"], 4);
+        assert_eq!(node["literal"], "0x01000000");
+        assert_eq!(node["bounds"]["min"], json!([0.0, 1.0]));
+        assert_eq!(
+            json["edges"][0],
+            json!({"from": 1, "to": 0, "operation": "Eval"})
+        );
+    }
+
+    #[test]
+    fn writes_handle_lists() {
+        let export = |format| {
+            let progress = AtomicUsize::new(0);
+            let mut out = vec![];
+            write(
+                &snapshot(),
+                format,
+                &mut out,
+                &progress,
+                &AtomicBool::new(false),
+            )
+            .unwrap();
+            assert_eq!(progress.load(Ordering::Relaxed), snapshot().len());
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(
+            export(ExportFormat::Text),
+            "0-0-0-2400000000000000\n1-0-0-2400000000000000\n"
+        );
+        let csv = export(ExportFormat::Csv);
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines[0], "hex,type,size,accessibility");
+        assert_eq!(lines[2], "1-0-0-2400000000000000,Blob,4,Strict");
+    }
+
+    #[test]
+    fn stops_when_cancelled() {
+        let progress = AtomicUsize::new(0);
+        let mut out = vec![];
+        let result = write(
+            &snapshot(),
+            ExportFormat::Json,
+            &mut out,
+            &progress,
+            &AtomicBool::new(true),
+        );
+        assert!(result.is_err());
+        assert_eq!(progress.load(Ordering::Relaxed), 0);
+    }
+}
@@ -0,0 +1,184 @@
+//! A Rust source file with a captured ancestry tree and the responses it
+//! was built from, so that regressions in how the tree merges and lays out
+//! responses can be tested against real data.
+
+use std::io::Write;
+
+use anyhow::Result;
+
+use crate::{
+    changelog,
+    export::GraphSnapshot,
+    graphs::Applied,
+    handle::{Handle, Operation},
+    trace::Trace,
+};
+
+/// Everything that goes into a fixture.
+pub(crate) struct Fixture {
+    pub(crate) target: Handle,
+    pub(crate) operation: Operation,
+    /// Every response applied to the graphs, in order.
+    pub(crate) journal: Vec<Applied>,
+    /// The latest bodies the server answered with, before parsing.
+    pub(crate) trace: Trace,
+    /// The ancestry tree built from the journal.
+    pub(crate) graph: GraphSnapshot,
+}
+
+impl Fixture {
+    /// Write the fixture as constants a test can `include!`: the target and
+    /// operation, the journal as JSON, the trace as JSON lines, and the
+    /// tree's nodes and edges.
+    pub(crate) fn write(&self, mut out: impl Write) -> Result<()> {
+        let mut trace = vec![];
+        self.trace.write(&mut trace)?;
+        writeln!(
+            out,
+            "// Captured by fix_viewer {} with Developer > Save test fixture.",
+            changelog::current_version()
+        )?;
+        writeln!(
+            out,
+            "// Rebuild the tree with crate::fixture::rebuild(TARGET, OPERATION, JOURNAL)."
+        )?;
+        writeln!(out)?;
+        writeln!(out, "const TARGET: &str = \"{}\";", self.target.to_hex())?;
+        writeln!(out, "const OPERATION: u8 = {};", self.operation as u8)?;
+        writeln!(out, "/// Every response applied to the graphs, as JSON.")?;
+        writeln!(
+            out,
+            "const JOURNAL: &str = {};",
+            raw_string(&serde_json::to_string(&self.journal)?)
+        )?;
+        writeln!(out, "/// The latest raw responses, as trace JSON lines.")?;
+        writeln!(
+            out,
+            "const TRACE: &str = {};",
+            raw_string(&String::from_utf8(trace)?)
+        )?;
+        writeln!(
+            out,
+            "/// Each handle drawn, with the minimum and maximum x and y it is drawn over."
+        )?;
+        writeln!(out, "const NODES: &[(&str, [f64; 4])] = &[")?;
+        for (hex, bounds) in nodes(&self.graph) {
+            writeln!(out, "    (\"{hex}\", {bounds:?}),")?;
+        }
+        writeln!(out, "];")?;
+        writeln!(
+            out,
+            "/// Each arrow, from and to indices into NODES, and its operation."
+        )?;
+        writeln!(out, "const EDGES: &[(usize, usize, u8)] = &[")?;
+        for edge in &self.graph.edges {
+            writeln!(
+                out,
+                "    ({}, {}, {}),",
+                edge.from, edge.to, edge.operation as u8
+            )?;
+        }
+        writeln!(out, "];")?;
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// The hex of each node of `graph`, and the minimum and maximum x and y it
+/// is drawn over, as a fixture lists them.
+pub(crate) fn nodes(graph: &GraphSnapshot) -> Vec<(String, [f64; 4])> {
+    graph
+        .nodes
+        .iter()
+        .map(|node| {
+            let (min, max) = (node.bounds.min(), node.bounds.max());
+            (node.handle.to_hex(), [min[0], min[1], max[0], max[1]])
+        })
+        .collect()
+}
+
+/// `text` as a raw string literal, with enough `#`s that nothing in it ends
+/// the literal early.
+fn raw_string(text: &str) -> String {
+    let mut longest = 0;
+    let mut run = None;
+    for c in text.chars() {
+        run = match (c, run) {
+            ('"', _) => Some(0),
+            ('#', Some(hashes)) => {
+                longest = longest.max(hashes + 1);
+                Some(hashes + 1)
+            }
+            _ => None,
+        };
+    }
+    let hashes = "#".repeat(longest + 1);
+    format!("r{hashes}\"{text}\"{hashes}")
+}
+
+/// Replay a fixture's journal into new graphs for `target`, in a frame of
+/// its own, and snapshot the ancestry tree it builds.
+#[cfg(test)]
+pub(crate) fn rebuild(target: &str, operation: u8, journal: &str) -> GraphSnapshot {
+    let target = Handle::from_hex(target).expect("fixture target");
+    let operation = Operation::try_from(operation).expect("fixture operation");
+    let journal: Vec<Applied> = serde_json::from_str(journal).expect("fixture journal");
+    let mut snapshot = GraphSnapshot::default();
+    let _ = egui::Context::default().run(egui::RawInput::default(), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let mut graphs = crate::graphs::GraphsContainer::new(ui, target, operation);
+            graphs.replay(ui, journal);
+            snapshot = graphs.export_snapshot(crate::export::ExportScope::All);
+        });
+    });
+    snapshot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handle::Task;
+
+    #[test]
+    fn raw_strings_outlast_their_text() {
+        assert_eq!(raw_string("{}"), "r#\"{}\"#");
+        assert_eq!(raw_string("a\"#b"), "r##\"a\"#b\"##");
+        assert_eq!(raw_string("#\"x\"##"), "r###\"#\"x\"##\"###");
+    }
+
+    #[test]
+    fn rebuilds_the_captured_tree() {
+        let handle = |id: usize| Handle::from_hex(&format!("{id:x}-0-4-100000000000000")).unwrap();
+        let task = |id, operation| Task {
+            handle: handle(id),
+            operation,
+            restriction: None,
+            recorded: None,
+        };
+        let journal = vec![Applied::Parents {
+            handle: handle(1),
+            parents: vec![task(2, Operation::Eval), task(3, Operation::Apply)],
+            next: None,
+        }];
+        let journal = serde_json::to_string(&journal).unwrap();
+        let graph = rebuild(&handle(1).to_hex(), Operation::Eval as u8, &journal);
+        assert_eq!(graph.nodes.len(), 3);
+        assert_eq!(graph.edges.len(), 2);
+
+        let fixture = Fixture {
+            target: handle(1),
+            operation: Operation::Eval,
+            journal: serde_json::from_str(&journal).unwrap(),
+            trace: Trace::default(),
+            graph: graph.clone(),
+        };
+        let mut out = vec![];
+        fixture.write(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains(&format!("const TARGET: &str = \"{}\";", handle(1).to_hex())));
+        assert!(text.contains(&format!("const JOURNAL: &str = r#\"{journal}\"#;")));
+        // The same journal lays out the same tree every time.
+        let again = rebuild(&handle(1).to_hex(), Operation::Eval as u8, &journal);
+        assert_eq!(nodes(&again), nodes(&graph));
+    }
+}
@@ -1,42 +1,520 @@
-use std::sync::mpsc::Sender;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Duration;
 
-use anyhow::Result;
-use egui::plot::Plot;
-use egui::Context;
+use crossbeam_channel::Sender;
+use egui::plot::{Plot, PlotTransform};
 use egui::{plot::items::PlotItem, Ui};
+use egui::{Context, Key, RichText};
 
-use crate::handle::{Operation, Task};
-use crate::http;
-use crate::{handle::Handle, plot::Element};
+use crate::cache::{Endpoint, ResponseCache};
+use crate::handle::{
+    Accessibility, Operation, Restriction, TagEntries, Task, TaskStatus, TaskTime,
+};
+use crate::http::{self, Generation, NextPage, RequestKind};
+use crate::workspace::{self, ANCESTRY, PROGRESS, TIMELINE};
+use crate::{handle::Handle, plot::Element, theme::Palette};
 
 mod ancestors;
+#[cfg(not(target_arch = "wasm32"))]
+mod bench;
+mod duplicates;
+mod element_menu;
+mod fetches;
+mod inspector;
+mod journal;
+mod legend;
+mod node_drag;
+mod path;
+mod placeholder;
+mod preview;
+mod probe;
 mod progress;
+mod pruning;
+mod reach;
+mod routing;
+mod rubber_band;
+mod search;
+mod shared;
+mod spacing;
+mod timeline;
+mod touch;
+mod treemap;
+mod viewport;
+mod wasm;
+mod watch;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use bench::benchmark;
+use duplicates::Duplicates;
+use element_menu::{ElementMenu, MenuAction};
+use fetches::Fetches;
+use inspector::InspectorAction;
+pub(crate) use journal::Applied;
+use probe::Probe;
+pub(crate) use pruning::Pruning;
+pub(crate) use routing::{ArrowStyle, EdgeRouting};
+use search::Search;
+use shared::Shared;
+pub(crate) use spacing::Spacing;
+use touch::Touch;
+use treemap::Treemap;
+use viewport::ViewCommand;
+pub(crate) use viewport::{Navigation, Viewports};
+use watch::{Watch, WatchAction};
 
 #[derive(Clone)]
 pub(crate) struct GraphsContainer {
-    ancestry: ancestors::AncestorGraph,
-    progress: progress::ProgressGraph,
+    ancestry: Shared<ancestors::AncestorGraph>,
+    progress: Shared<progress::ProgressGraph>,
+    timeline: Shared<timeline::TimelineGraph>,
+    inspector: inspector::Inspector,
+    rubber_band: rubber_band::RubberBand,
+    /// Where the graphs are looking, updated every frame.
+    viewports: Viewports,
+    /// Viewports to move the graphs to the next time they are shown.
+    restore: Viewports,
+    navigation: Navigation,
+    /// The handle selected from the timeline, highlighted in every graph.
+    focus: Option<Handle>,
+    show_edge_labels: bool,
+    show_legend: bool,
+    /// Show the recent fetches beside the ancestry tree.
+    show_fetches: bool,
+    merge_shared: bool,
+    lanes: bool,
+    /// Only fetch parents performing this operation, if set.
+    parent_filter: Option<Operation>,
+    /// Every response applied so far, in order.
+    journal: Vec<Applied>,
+    /// Aliases edited in the inspector, keyed by hex, to draw once a Ui is at
+    /// hand.
+    aliases_edited: Option<HashMap<String, String>>,
+    /// The parents fetched lately.
+    fetches: Fetches,
+    /// Fetch the parents of parents too when a handle has at most this many.
+    auto_expand: usize,
+    /// Handles whose parents are to be fetched next frame, by auto-expanding
+    /// or from the command palette.
+    expanding: Vec<Handle>,
+    /// Handles whose parents are to be fetched again next frame, past the
+    /// cache, since they are stale.
+    refreshing: Vec<Handle>,
+    /// Handles whose parents were fetched automatically, which are not
+    /// expanded any further.
+    auto_fetched: HashSet<Handle>,
+    /// Handles whose parents are being fetched, and how many generations
+    /// above them to keep fetching.
+    probing: HashMap<Handle, usize>,
+    /// Local handles in the ancestry tree that are the same object.
+    duplicates: Duplicates,
+    /// Which handles in the ancestry tree the server no longer has.
+    probe: Probe,
+    /// A regex matched against the text of every handle in the tree.
+    search: Search,
+    /// A search hit to move the ancestry plot to next frame.
+    pan_to: Option<Handle>,
+    /// Where the sizes of the handles in the ancestry tree add up.
+    treemap: Treemap,
+    /// Keep fetching the status of every task in the progress tree.
+    monitor: bool,
+    /// When the status of the tasks was last fetched.
+    status_fetched: Option<f64>,
+    /// Tasks asked to run again, and how far that has got.
+    reruns: HashMap<(Handle, Operation), Rerun>,
+    /// Handles whose parents were counted on hover, or are being counted.
+    counted: HashSet<Handle>,
+    /// The Blob or Tree whose users are selected, kept up to date as the
+    /// trees in the way arrive.
+    usages_of: Option<Handle>,
+    /// Trees to fetch next frame to find usages, and those asked for so far.
+    usage_trees: (Vec<Handle>, HashSet<Handle>),
+    watch: Option<Watch>,
+    /// The fingers on a touch screen, if one is used.
+    touch: Touch,
+    /// The menu of the element last right-clicked or held, while open.
+    menu: Option<ElementMenu>,
+    node_drag: node_drag::NodeDrag,
+    target: Handle,
+    /// Which graphs these are, for requests for parents to carry so that
+    /// their answers are dropped once the target is replaced.
+    generation: Generation,
+}
+
+/// Seconds between fetches of task status while monitoring.
+const STATUS_INTERVAL: f64 = 2.0;
+
+/// Parents that arrived longer ago than this, in seconds, are offered to be
+/// fetched again.
+const STALE_AFTER: f64 = 300.0;
+
+/// How far running a task again has got.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Rerun {
+    /// The server has not answered yet.
+    Asked,
+    /// The server took the task back, and its status is being polled.
+    Scheduled,
+    /// The task is done or failed again.
+    Finished,
+    /// The server would not run the task again, or could not be asked.
+    Refused,
 }
 
 impl GraphsContainer {
     pub fn new(ui: &Ui, handle: Handle, operation: Operation) -> Self {
         Self {
-            ancestry: ancestors::AncestorGraph::new(Element::new(ui, handle.clone())),
-            progress: progress::ProgressGraph::new(Element::new(ui, handle), operation),
+            ancestry: Shared::new(ancestors::AncestorGraph::new(Element::new(
+                ui,
+                handle.clone(),
+            ))),
+            progress: Shared::new(progress::ProgressGraph::new(
+                Element::new(ui, handle.clone()),
+                operation,
+            )),
+            timeline: Shared::default(),
+            inspector: inspector::Inspector::default(),
+            rubber_band: rubber_band::RubberBand::default(),
+            viewports: Viewports::default(),
+            restore: Viewports::default(),
+            navigation: Navigation::default(),
+            focus: None,
+            show_edge_labels: false,
+            show_legend: false,
+            show_fetches: false,
+            merge_shared: false,
+            lanes: false,
+            parent_filter: None,
+            journal: vec![],
+            aliases_edited: None,
+            fetches: Fetches::default(),
+            auto_expand: 0,
+            expanding: vec![],
+            refreshing: vec![],
+            auto_fetched: HashSet::new(),
+            probing: HashMap::new(),
+            duplicates: Duplicates::default(),
+            probe: Probe::default(),
+            search: Search::default(),
+            pan_to: None,
+            treemap: Treemap::default(),
+            monitor: false,
+            status_fetched: None,
+            reruns: HashMap::new(),
+            counted: HashSet::new(),
+            usages_of: None,
+            usage_trees: (vec![], HashSet::new()),
+            watch: None,
+            touch: Touch::default(),
+            menu: None,
+            node_drag: node_drag::NodeDrag::default(),
+            target: handle,
+            generation: Generation::next(),
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn view(
-        &self,
+        &mut self,
         ctx: &Context,
-        client: Arc<reqwest::Client>,
+        source: Arc<http::DataSource>,
         url: &str,
-        tx: Sender<(usize, Handle, Result<http::Response>)>,
+        tx: Sender<http::Envelope>,
+        cache: &ResponseCache,
+        notes: &mut HashMap<String, String>,
+        aliases: &mut HashMap<String, String>,
     ) {
-        egui::Window::new("Ancestry Tree").resizable(true).show(ctx, |ui| {
-            let hovered_elem = Plot::new("ancestry_plot")
+        let modifiers = ctx.input(|i| i.modifiers);
+        self.touch.update(ctx);
+        // Holding shift while clicking bypasses the response cache.
+        let force_refresh = modifiers.shift;
+        let (parent_filter, generation) = (self.parent_filter, self.generation);
+        // Handles asked for this frame, to draw placeholders over.
+        let requested = RefCell::new(vec![]);
+        let fetch_parents = |handle: &Handle, after: Option<&str>| {
+            requested.borrow_mut().push(handle.clone());
+            let kind = RequestKind::Parents {
+                generation,
+                handle: handle.clone(),
+                after: after.map(str::to_string),
+                operation: parent_filter,
+            };
+            if !force_refresh && cache.resend(ctx, kind, &tx) {
+                return;
+            }
+            http::get_parents(
+                source.clone(),
+                ctx.clone(),
+                generation,
+                handle,
+                after,
+                parent_filter,
+                tx.clone(),
+                url,
+                cache,
+            );
+        };
+        // Expanding often asks for many handles at once, so those not cached
+        // are asked for in one batch.
+        let expanding: Vec<_> = std::mem::take(&mut self.expanding)
+            .into_iter()
+            .filter(|handle| {
+                let kind = RequestKind::Parents {
+                    generation,
+                    handle: handle.clone(),
+                    after: None,
+                    operation: parent_filter,
+                };
+                force_refresh || !cache.resend(ctx, kind, &tx)
+            })
+            .collect();
+        for handle in &expanding {
+            self.ancestry.set_loading(handle.clone());
+        }
+        if !expanding.is_empty() {
+            http::get_parents_batch(
+                source.clone(),
+                ctx.clone(),
+                generation,
+                expanding,
+                parent_filter,
+                tx.clone(),
+                url,
+                cache,
+            );
+        }
+        let refreshing = std::mem::take(&mut self.refreshing);
+        for handle in &refreshing {
+            self.ancestry.set_loading(handle.clone());
+        }
+        if !refreshing.is_empty() {
+            http::get_parents_batch(
+                source.clone(),
+                ctx.clone(),
+                generation,
+                refreshing,
+                parent_filter,
+                tx.clone(),
+                url,
+                cache,
+            );
+        }
+        for tree in std::mem::take(&mut self.usage_trees.0) {
+            let kind = RequestKind::Tree {
+                handle: tree.clone(),
+            };
+            if force_refresh || !cache.resend(ctx, kind, &tx) {
+                http::get_tree(source.clone(), ctx.clone(), &tree, tx.clone(), url, cache);
+            }
+        }
+        for handle in self.duplicates.take_queued() {
+            let kind = RequestKind::Canonical {
+                handle: handle.clone(),
+            };
+            if force_refresh || !cache.resend(ctx, kind, &tx) {
+                http::get_canonical(source.clone(), ctx.clone(), &handle, tx.clone(), url, cache);
+            }
+        }
+        for handle in self.probe.take_queued() {
+            http::get_exists(source.clone(), ctx.clone(), &handle, tx.clone(), url);
+        }
+        // Tasks running again are polled even when the rest are not.
+        let polled: Vec<_> = if self.monitor {
+            self.progress.tasks()
+        } else {
+            self.reruns
+                .iter()
+                .filter(|(_, rerun)| **rerun == Rerun::Scheduled)
+                .map(|(task, _)| task.clone())
+                .collect()
+        };
+        if !polled.is_empty() {
+            let now = ctx.input(|i| i.time);
+            if self
+                .status_fetched
+                .map_or(true, |at| now - at >= STATUS_INTERVAL)
+            {
+                self.status_fetched = Some(now);
+                for (handle, operation) in polled {
+                    http::get_status(
+                        source.clone(),
+                        ctx.clone(),
+                        &handle,
+                        operation,
+                        tx.clone(),
+                        url,
+                    );
+                }
+            }
+            ctx.request_repaint_after(std::time::Duration::from_secs_f64(STATUS_INTERVAL));
+        } else {
+            self.status_fetched = None;
+        }
+        let operation = self.progress.operation();
+        match &mut self.watch {
+            Some(watch) => {
+                let now = ctx.input(|i| i.time);
+                if watch.due(now) && self.ancestry.contains(&watch.handle) {
+                    let handle = watch.handle.clone();
+                    http::get_parents(
+                        source.clone(),
+                        ctx.clone(),
+                        generation,
+                        &handle,
+                        None,
+                        parent_filter,
+                        tx.clone(),
+                        url,
+                        cache,
+                    );
+                    http::get_outcome(
+                        source.clone(),
+                        ctx.clone(),
+                        &handle,
+                        operation,
+                        tx.clone(),
+                        url,
+                        cache,
+                    );
+                }
+                ctx.request_repaint_after(std::time::Duration::from_secs_f64(watch.interval()));
+                let flash = watch.flash(now);
+                if flash > 0.0 {
+                    ctx.request_repaint();
+                }
+                self.ancestry
+                    .set_watched(Some((watch.handle.clone(), flash, watch.changes())));
+            }
+            None => self.ancestry.set_watched(None),
+        }
+        // Keep repainting while elements move to a new layout, or parents
+        // are awaited.
+        if self.ancestry.advance(ctx.input(|i| i.time)) {
+            ctx.request_repaint();
+        }
+        let window = egui::Window::new(ANCESTRY).resizable(true);
+        let shown = workspace::place(ctx, ANCESTRY, window).show(ctx, |ui| {
+            if let Some(aliases) = self.aliases_edited.take() {
+                self.set_aliases(ui, &aliases);
+            }
+            let mut command = None;
+            ui.horizontal(|ui| {
+                if ui
+                    .checkbox(&mut self.show_edge_labels, "Edge labels")
+                    .on_hover_text("Name the operation on each arrow. Hidden when zoomed far out.")
+                    .changed()
+                {
+                    self.ancestry.set_show_edge_labels(self.show_edge_labels);
+                }
+                if ui
+                    .checkbox(&mut self.show_legend, "Legend")
+                    .on_hover_text(
+                        "Draw the operation colors and how wide each generation is drawn \
+                         on the plot, and in exported images.",
+                    )
+                    .changed()
+                {
+                    self.ancestry.set_legend(self.show_legend);
+                }
+                ui.checkbox(&mut self.show_fetches, "Fetches")
+                    .on_hover_text(
+                        "List the parents fetched lately beside the tree. \
+                         Click one to select them.",
+                    );
+                if ui
+                    .checkbox(&mut self.merge_shared, "Merge shared parents")
+                    .on_hover_text("Center parents shared by several children over all of them.")
+                    .changed()
+                {
+                    self.ancestry.set_merge_shared(ui, self.merge_shared);
+                }
+                if ui
+                    .checkbox(&mut self.lanes, "Lanes by type")
+                    .on_hover_text("Draw Thunks, Trees, Blobs and Tags in lanes of their own.")
+                    .changed()
+                {
+                    self.ancestry.set_lanes(ui, self.lanes);
+                }
+                egui::ComboBox::from_id_source("parent_filter")
+                    .selected_text(match self.parent_filter {
+                        Some(operation) => format!("Fetch {operation} parents"),
+                        None => "Fetch all parents".to_string(),
+                    })
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.parent_filter, None, "All");
+                        for operation in [Operation::Apply, Operation::Eval, Operation::Fill] {
+                            ui.selectable_value(
+                                &mut self.parent_filter,
+                                Some(operation),
+                                operation.to_string(),
+                            );
+                        }
+                    })
+                    .response
+                    .on_hover_text("Only ask the server for parents performing one operation.");
+                ui.separator();
+                for accessibility in Accessibility::ALL {
+                    let outline = match accessibility {
+                        Accessibility::Strict => "solid",
+                        Accessibility::Shallow => "dashed",
+                        Accessibility::Lazy => "dotted",
+                    };
+                    let mut shown = self.ancestry.accessibility_shown(accessibility);
+                    if ui
+                        .toggle_value(&mut shown, accessibility.to_string())
+                        .on_hover_text(format!("Show {accessibility} handles, outlined {outline}."))
+                        .changed()
+                    {
+                        self.ancestry
+                            .set_accessibility_shown(ui, accessibility, shown);
+                    }
+                }
+                let mut shown = self.ancestry.dependees_shown();
+                if ui
+                    .toggle_value(&mut shown, "dependees")
+                    .on_hover_text(
+                        "Link each task to the dependees fetched for the dependency tree, \
+                         finely dashed.",
+                    )
+                    .changed()
+                {
+                    self.ancestry.set_dependees_shown(shown);
+                }
+                command = view_buttons(ui);
+            });
+            self.selection_ui(ui, fetch_parents);
+            self.replay_ui(ui);
+            self.pins_ui(ui);
+            self.fixed_ui(ui);
+            self.stale_ui(ui);
+            self.probe_ui(ui);
+            self.watch_ui(ui);
+            if self.show_fetches {
+                let clicked = egui::SidePanel::right("recent_fetches")
+                    .resizable(true)
+                    .default_width(240.0)
+                    .show_inside(ui, |ui| self.fetches.ui(ui))
+                    .inner;
+                if let Some((handle, parents)) = clicked {
+                    self.ancestry.set_selection(parents);
+                    self.set_focus(Some(handle));
+                }
+            }
+            let pan_to = self.pan_to.take();
+            if let Some(bounds) = pan_to.and_then(|handle| self.ancestry.element_bounds(&handle)) {
+                command = command.or(Some(ViewCommand::CenterOn(bounds.center())));
+            }
+            // Dragging with the command modifier held selects instead, and
+            // with alt held moves the element dragged.
+            let drag_pans = self.navigation.drag_pans(ctx) && !self.touch.pinching();
+            let plot_response = Plot::new("ancestry_plot")
                 .data_aspect(1.0)
+                .allow_double_click_reset(false)
+                .allow_drag(!modifiers.command && !modifiers.alt && drag_pans)
+                .allow_scroll(false)
+                .allow_zoom(false)
                 .auto_bounds_x()
                 .auto_bounds_y()
                 .show_axes([false; 2])
@@ -44,31 +522,189 @@ impl GraphsContainer {
                 .show_y(false)
                 .show(ui, |plot_ui| {
                     let graph = &self.ancestry;
-                    plot_ui.add(graph.clone());
-                    let (Some(coords), true) = (plot_ui.pointer_coordinate(), plot_ui.plot_clicked()) else {
-                        return None
+                    let touch = &self.touch;
+                    plot_ui.add(graph.view());
+                    self.navigation.apply(plot_ui, touch.pinch_center());
+                    if let Some(bounds) = self.restore.ancestry.take() {
+                        plot_ui.set_plot_bounds(bounds.into());
+                    }
+                    let band = self.rubber_band.update(plot_ui);
+                    let hovered_elem = plot_ui
+                        .pointer_coordinate()
+                        .filter(|_| plot_ui.plot_hovered())
+                        .and_then(|coords| {
+                            let closest_elem = graph.find_closest(
+                                plot_ui.screen_from_plot(coords),
+                                plot_ui.transform(),
+                            )?;
+                            // A fingertip beside an element lands on it.
+                            let coords = if touch.reaches(closest_elem.dist_sq) {
+                                graph.snap_to(coords, closest_elem)
+                            } else {
+                                coords
+                            };
+                            Some((coords, closest_elem))
+                        });
+                    let hovered_at = hovered_elem
+                        .and_then(|(coords, closest_elem)| graph.element_at(coords, closest_elem))
+                        .map(|(elem, bounds)| (elem.get_handle().clone(), bounds.center()));
+                    let hovered_center = hovered_at.as_ref().map(|(_, center)| *center);
+                    if let Some(command) =
+                        command.or_else(|| ViewCommand::from_input(plot_ui, hovered_center))
+                    {
+                        let selected = self.focus.as_ref().and_then(|h| graph.element_bounds(h));
+                        command.apply(plot_ui, graph.bounds(), selected);
+                    }
+                    let moved = self.node_drag.update(plot_ui, hovered_at);
+                    let hovered_elem = hovered_elem.map(|(coords, closest_elem)| {
+                        (coords, closest_elem, plot_ui.plot_clicked())
+                    });
+                    (hovered_elem, band, moved)
+                });
+            self.viewports.ancestry = Some((*plot_response.transform.bounds()).into());
+            if let Some((handle, operation)) = self.child_buttons(ctx, &plot_response.transform) {
+                self.set_operation(ui, operation, handle.clone());
+                let generation = self.progress.generation();
+                let kind = RequestKind::Child {
+                    generation,
+                    index: 0,
+                    handle: handle.clone(),
+                    operation,
+                };
+                if force_refresh || !cache.resend(ctx, kind, &tx) {
+                    http::get_child(
+                        source.clone(),
+                        ctx.clone(),
+                        generation,
+                        0,
+                        handle,
+                        operation,
+                        tx.clone(),
+                        url,
+                        cache,
+                    );
+                }
+            }
+            // Number keys over the tree set how many generations above the
+            // selected handle are shown.
+            let depth = (plot_response.response.hovered() && !ctx.wants_keyboard_input())
+                .then(|| depth_pressed(ctx))
+                .flatten();
+            if let (Some(depth), Some(focus)) = (depth, self.focus.clone()) {
+                if self.ancestry.element_bounds(&focus).is_some() {
+                    self.set_depth(ui, focus, depth);
+                }
+            }
+            let (hovered_elem, band, moved) = plot_response.inner;
+            if let Some(area) = band {
+                self.ancestry.select_in(area);
+            }
+            if let Some((handle, center)) = moved {
+                self.ancestry.fix_position(handle, center);
+            }
+            let hovered = hovered_elem
+                .and_then(|(coords, closest, _)| self.ancestry.element_at(coords, closest))
+                .map(|(elem, _)| elem);
+            let held = self.touch.long_press(&plot_response.response);
+            if held || plot_response.response.secondary_clicked() {
+                let pos = ctx.input(|i| i.pointer.interact_pos());
+                if let (Some(elem), Some(pos)) = (hovered, pos) {
+                    let handle = elem.get_handle().clone();
+                    let fixed = self.ancestry.is_fixed(&handle);
+                    self.menu = Some(ElementMenu::new(handle, pos, true, true, fixed));
+                }
+            }
+            if let Some(elem) = hovered {
+                let handle = elem.get_handle();
+                // Counted once on first hover, so that the badge shows how
+                // far the ancestry goes before clicking.
+                if elem.get_restriction().is_none() && self.counted.insert(handle.clone()) {
+                    let kind = RequestKind::ParentCount {
+                        handle: handle.clone(),
                     };
-                    let closest_elem = graph
-                        .find_closest(plot_ui.screen_from_plot(coords), plot_ui.transform())?;
-                    Some((coords, closest_elem))
-                }).inner;
-
-            if let Some((coords, closest_elem)) = hovered_elem {
-                    self.ancestry.handle_nearby_click(ui, coords, closest_elem, |index, handle| {
-                        http::get_parents(
-                            client.clone(),
+                    if !cache.resend(ctx, kind, &tx) {
+                        http::get_parent_count(
+                            source.clone(),
                             ctx.clone(),
-                            index,
                             handle,
                             tx.clone(),
                             url,
+                            cache,
                         );
-                    });
+                    }
+                }
+                let tasks = self.ancestry.tasks_of(handle);
+                let count = self.ancestry.parent_count(handle);
+                plot_response.response.on_hover_ui(|ui| {
+                    elem.details_ui(ui, &tasks);
+                    if let Some(count) = count {
+                        ui.label(format!("{count} parents"));
+                    }
+                });
+            }
+
+            if let Some((coords, closest_elem, true)) = hovered_elem {
+                self.ancestry.handle_nearby_click(
+                    ui,
+                    coords,
+                    closest_elem,
+                    modifiers,
+                    fetch_parents,
+                );
+                // Clicking an element also selects it.
+                let selected = self
+                    .ancestry
+                    .element_at(coords, closest_elem)
+                    .map(|(elem, _)| elem.get_handle().clone());
+                if selected.is_some() {
+                    self.set_focus(selected);
+                }
             }
         });
-        egui::Window::new("Progress Tree").resizable(true).show(ctx, |ui| {
-            let hovered_elem = Plot::new("progress_plot")
+        workspace::track(ctx, ANCESTRY, &shown);
+        for handle in requested.into_inner() {
+            self.ancestry.set_loading(handle);
+        }
+        if let Some(handle) = self.duplicates.show(ctx) {
+            self.set_focus(Some(handle));
+        }
+        if let Some(handle) = self.treemap.show(ctx, &self.ancestry) {
+            self.set_focus(Some(handle));
+        }
+        if let Some(handle) = self.search.show(ctx, &self.ancestry) {
+            self.pan_to = Some(handle.clone());
+            self.set_focus(Some(handle));
+            ctx.request_repaint();
+        }
+        let window = egui::Window::new(PROGRESS).resizable(true);
+        let shown = workspace::place(ctx, PROGRESS, window).show(ctx, |ui| {
+            let command = ui
+                .horizontal(|ui| {
+                    ui.checkbox(&mut self.monitor, "Monitor status")
+                        .on_hover_text(format!(
+                            "Fetch whether each task is pending, running, done or failed \
+                             every {STATUS_INTERVAL} s."
+                        ));
+                    ui.separator();
+                    view_buttons(ui)
+                })
+                .inner;
+            if let Some((handle, operation)) = self.rerun_ui(ui) {
+                http::post_rerun(
+                    source.clone(),
+                    ctx.clone(),
+                    &handle,
+                    operation,
+                    tx.clone(),
+                    url,
+                );
+            }
+            let plot_response = Plot::new("progress_plot")
                 .data_aspect(1.0)
+                .allow_double_click_reset(false)
+                .allow_drag(self.navigation.drag_pans(ctx) && !self.touch.pinching())
+                .allow_scroll(false)
+                .allow_zoom(false)
                 .auto_bounds_x()
                 .auto_bounds_y()
                 .show_axes([false; 2])
@@ -76,83 +712,1293 @@ impl GraphsContainer {
                 .show_y(false)
                 .show(ui, |plot_ui| {
                     let graph = &self.progress;
-                    plot_ui.add(graph.clone());
-                    let (Some(coords), true) = (plot_ui.pointer_coordinate(), plot_ui.plot_clicked()) else {
-                        return None
-                    };
-                    let closest_elem = graph
-                        .find_closest(plot_ui.screen_from_plot(coords), plot_ui.transform())?;
-                    Some((coords, closest_elem))
-                }).inner;
+                    let touch = &self.touch;
+                    plot_ui.add(graph.view());
+                    self.navigation.apply(plot_ui, touch.pinch_center());
+                    if let Some(bounds) = self.restore.progress.take() {
+                        plot_ui.set_plot_bounds(bounds.into());
+                    }
+                    let hovered_elem = plot_ui
+                        .pointer_coordinate()
+                        .filter(|_| plot_ui.plot_hovered())
+                        .and_then(|coords| {
+                            let closest_elem = graph.find_closest(
+                                plot_ui.screen_from_plot(coords),
+                                plot_ui.transform(),
+                            )?;
+                            let coords = if touch.reaches(closest_elem.dist_sq) {
+                                graph.snap_to(coords, closest_elem)
+                            } else {
+                                coords
+                            };
+                            Some((coords, closest_elem))
+                        });
+                    let hovered_center = hovered_elem
+                        .and_then(|(coords, closest_elem)| graph.element_at(coords, closest_elem))
+                        .map(|(_, bounds)| bounds.center());
+                    if let Some(command) =
+                        command.or_else(|| ViewCommand::from_input(plot_ui, hovered_center))
+                    {
+                        let selected = self.focus.as_ref().and_then(|h| graph.element_bounds(h));
+                        command.apply(plot_ui, graph.content_bounds(), selected);
+                    }
+                    hovered_elem.map(|(coords, closest_elem)| {
+                        (coords, closest_elem, plot_ui.plot_clicked())
+                    })
+                });
+            self.viewports.progress = Some((*plot_response.transform.bounds()).into());
+            let hovered_elem = plot_response.inner;
+            let hovered = hovered_elem.and_then(|(coords, closest_elem, _)| {
+                let (elem, _) = self.progress.element_at(coords, closest_elem)?;
+                Some((elem, self.progress.tasks_of(closest_elem, elem)))
+            });
+            let held = self.touch.long_press(&plot_response.response);
+            if held || plot_response.response.secondary_clicked() {
+                let pos = ctx.input(|i| i.pointer.interact_pos());
+                if let (Some((elem, _)), Some(pos)) = (&hovered, pos) {
+                    let handle = elem.get_handle().clone();
+                    let pinnable = self.ancestry.element_bounds(&handle).is_some();
+                    let watchable = self.ancestry.contains(&handle);
+                    let fixed = self.ancestry.is_fixed(&handle);
+                    let menu = ElementMenu::new(handle, pos, pinnable, watchable, fixed);
+                    self.menu = Some(menu);
+                }
+            }
+            if let Some((elem, tasks)) = hovered {
+                plot_response
+                    .response
+                    .on_hover_ui(|ui| elem.details_ui(ui, &tasks));
+            }
 
-            if let Some((coords, closest_elem)) = hovered_elem {
-                    self.progress.handle_nearby_click(ui, coords, closest_elem, |index, handle, operation| {
+            if let Some((coords, closest_elem, true)) = hovered_elem {
+                let generation = self.progress.generation();
+                let fetch_task = |index, handle: Handle, operation, after: Option<String>| {
+                    // With dependees left to load, only load the next page of them.
+                    if after.is_some() {
+                        http::get_dependees(
+                            source.clone(),
+                            ctx.clone(),
+                            generation,
+                            index,
+                            handle,
+                            operation,
+                            after,
+                            tx.clone(),
+                            url,
+                            cache,
+                        );
+                        return;
+                    }
+                    let child = RequestKind::Child {
+                        generation,
+                        index,
+                        handle: handle.clone(),
+                        operation,
+                    };
+                    let child_cached = !force_refresh && cache.resend(ctx, child, &tx);
+                    let dependees = RequestKind::Dependees {
+                        generation,
+                        index,
+                        handle: handle.clone(),
+                        operation,
+                        after: None,
+                    };
+                    let dependees_cached = !force_refresh && cache.resend(ctx, dependees, &tx);
+                    if !child_cached {
                         http::get_child(
-                            client.clone(),
+                            source.clone(),
                             ctx.clone(),
+                            generation,
                             index,
                             handle.clone(),
                             operation,
                             tx.clone(),
                             url,
+                            cache,
                         );
+                    }
+                    if dependees_cached {
+                        return;
+                    }
 
-                        // hack around http web server only handling one request in a short time
-                        let ctx_clone = ctx.clone();
-                        let url_clone = url.to_string();
-                        #[cfg(target_arch = "wasm32")]
-                        {
-                            use wasm_bindgen::JsCast;
-                            let window = web_sys::window().expect("Missing window.");
-                             let _ = window.set_timeout_with_callback_and_timeout_and_arguments_0(
-                                wasm_bindgen::closure::Closure::once_into_js(move ||
-                                 http::get_dependees(
-                                    client.clone(),
+                    // hack around http web server only handling one request in a short time
+                    let ctx_clone = ctx.clone();
+                    let url_clone = url.to_string();
+                    let cache_clone = cache.clone();
+                    #[cfg(target_arch = "wasm32")]
+                    {
+                        use wasm_bindgen::JsCast;
+                        let window = web_sys::window().expect("Missing window.");
+                        let _ = window.set_timeout_with_callback_and_timeout_and_arguments_0(
+                            wasm_bindgen::closure::Closure::once_into_js(move || {
+                                http::get_dependees(
+                                    source.clone(),
                                     ctx_clone,
+                                    generation,
                                     index,
                                     handle,
                                     operation,
+                                    None,
                                     tx.clone(),
                                     &url_clone,
+                                    &cache_clone,
                                 )
-                                ).as_ref().unchecked_ref(),
-                                100,
-                            );
-                        }
-                        #[cfg(not(target_arch = "wasm32"))]
-                        let _ = tokio::spawn(async {
+                            })
+                            .as_ref()
+                            .unchecked_ref(),
+                            100,
+                        );
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    {
+                        let source = source.clone();
+                        let tx = tx.clone();
+                        #[allow(clippy::let_underscore_future)]
+                        let _ = tokio::spawn(async move {
                             tokio::time::sleep(Duration::from_millis(100)).await;
                             http::get_dependees(
-                                client.clone(),
-                                ctx.clone(),
+                                source,
+                                ctx_clone,
+                                generation,
                                 index,
                                 handle,
                                 operation,
-                                tx.clone(),
-                                url,
+                                None,
+                                tx,
+                                &url_clone,
+                                &cache_clone,
                             );
                         });
-                    });
+                    }
+                };
+                self.progress
+                    .handle_nearby_click(ui, coords, closest_elem, fetch_task);
+                // Clicking an element also selects it.
+                let selected = self
+                    .progress
+                    .element_at(coords, closest_elem)
+                    .map(|(elem, _)| elem.get_handle().clone());
+                if selected.is_some() {
+                    self.set_focus(selected);
+                }
             }
         });
+        workspace::track(ctx, PROGRESS, &shown);
+        self.element_menu(ctx);
+        self.view_timeline(ctx, source.clone(), url, tx.clone(), cache, force_refresh);
+        // Without a selection, inspect the target.
+        let inspected = self.focus.as_ref().unwrap_or(&self.target);
+        let hex = inspected.to_hex();
+        let mut note = notes.get(&hex).cloned().unwrap_or_default();
+        let mut alias = aliases.get(&hex).cloned().unwrap_or_default();
+        // Only handles in the ancestry tree have parents to poll.
+        let watchable = self.ancestry.contains(inspected);
+        match self
+            .inspector
+            .show(ctx, inspected, watchable, &mut note, &mut alias)
+        {
+            Some(InspectorAction::FetchTag(handle)) => {
+                let kind = RequestKind::Tag {
+                    handle: handle.clone(),
+                };
+                if force_refresh || !cache.resend(ctx, kind, &tx) {
+                    http::get_tag(source, ctx.clone(), &handle, tx, url, cache);
+                }
+            }
+            Some(InspectorAction::FetchTree(handle)) => {
+                let kind = RequestKind::Tree {
+                    handle: handle.clone(),
+                };
+                if force_refresh || !cache.resend(ctx, kind, &tx) {
+                    http::get_tree(source, ctx.clone(), &handle, tx, url, cache);
+                }
+            }
+            Some(InspectorAction::FetchBlob(handle)) => {
+                let kind = RequestKind::Blob {
+                    handle: handle.clone(),
+                };
+                if force_refresh || !cache.resend(ctx, kind, &tx) {
+                    http::get_blob(source, ctx.clone(), &handle, tx, url, cache);
+                }
+            }
+            Some(InspectorAction::Explain(thunk)) => {
+                self.inspector.explain(thunk.clone());
+                if let Some(tree) = thunk.thunk_tree() {
+                    let kind = RequestKind::Tree {
+                        handle: tree.clone(),
+                    };
+                    if force_refresh || !cache.resend(ctx, kind, &tx) {
+                        http::get_tree(source.clone(), ctx.clone(), &tree, tx.clone(), url, cache);
+                    }
+                }
+                for operation in [Operation::Apply, Operation::Eval] {
+                    let kind = RequestKind::Outcome {
+                        handle: thunk.clone(),
+                        operation,
+                    };
+                    if force_refresh || !cache.resend(ctx, kind, &tx) {
+                        http::get_outcome(
+                            source.clone(),
+                            ctx.clone(),
+                            &thunk,
+                            operation,
+                            tx.clone(),
+                            url,
+                            cache,
+                        );
+                    }
+                }
+            }
+            Some(InspectorAction::Navigate(handle)) => self.set_focus(Some(handle)),
+            Some(InspectorAction::Watch(handle)) => self.watch = Some(Watch::new(handle)),
+            Some(InspectorAction::FindUsages(handle)) => {
+                self.usages_of = Some(handle);
+                self.find_usages();
+            }
+            Some(InspectorAction::Materialize(handle)) => {
+                self.inspector.materializing(handle.clone());
+                http::post_materialize(source, ctx.clone(), &handle, tx, url);
+            }
+            Some(InspectorAction::CopyCommands(handle)) => {
+                let operation = self.progress.operation();
+                let mut queries: Vec<_> = self
+                    .ancestry
+                    .ancestors_of(&handle)
+                    .into_iter()
+                    .map(|ancestor| (Endpoint::Parents, ancestor))
+                    .collect();
+                queries.push((Endpoint::Child(operation), handle.clone()));
+                queries.push((Endpoint::Dependees(operation), handle));
+                let commands = http::curl_commands(url, &source.routes(), &queries);
+                ctx.output_mut(|o| o.copied_text = commands);
+            }
+            Some(InspectorAction::Annotate) => {
+                if note.trim().is_empty() {
+                    notes.remove(&hex);
+                } else {
+                    notes.insert(hex, note);
+                }
+                self.set_notes(notes);
+            }
+            Some(InspectorAction::Alias) => {
+                if alias.trim().is_empty() {
+                    aliases.remove(&hex);
+                } else {
+                    aliases.insert(hex, alias.trim().to_string());
+                }
+                self.aliases_edited = Some(aliases.clone());
+                ctx.request_repaint();
+            }
+            None => {}
+        }
+    }
+
+    fn view_timeline(
+        &mut self,
+        ctx: &Context,
+        source: Arc<http::DataSource>,
+        url: &str,
+        tx: Sender<http::Envelope>,
+        cache: &ResponseCache,
+        force_refresh: bool,
+    ) {
+        let window = egui::Window::new(TIMELINE).resizable(true);
+        let shown = workspace::place(ctx, TIMELINE, window).show(ctx, |ui| {
+            if ui.button("Load task times").clicked() {
+                let kind = RequestKind::TaskTimes {
+                    handle: self.target.clone(),
+                };
+                let cached = !force_refresh && cache.resend(ctx, kind, &tx);
+                if !cached {
+                    http::get_task_times(source, ctx.clone(), &self.target, tx, url, cache);
+                }
+            }
+            if self.timeline.is_empty() {
+                ui.label("No task times loaded.");
+            }
+            Plot::new("timeline_plot")
+                .auto_bounds_x()
+                .auto_bounds_y()
+                .show_axes([true, false])
+                .show_x(true)
+                .show_y(false)
+                .show(ui, |plot_ui| {
+                    let graph = &self.timeline;
+                    plot_ui.add(graph.view());
+                    let (Some(coords), true) =
+                        (plot_ui.pointer_coordinate(), plot_ui.plot_clicked())
+                    else {
+                        return None;
+                    };
+                    let closest_elem = graph
+                        .find_closest(plot_ui.screen_from_plot(coords), plot_ui.transform())?;
+                    graph.handle_at(coords, closest_elem).cloned()
+                })
+                .inner
+        });
+        workspace::track(ctx, TIMELINE, &shown);
+        let clicked = shown.and_then(|response| response.inner.flatten());
+
+        if let Some(handle) = clicked {
+            self.set_focus(Some(handle));
+        }
     }
 
-    /// Set the parents of a specific handle
-    pub fn set_parents(&mut self, ui: &Ui, handle: Handle, parents: Vec<Task>) {
+    /// Set the parents of a specific handle, and where the rest of them
+    /// continue if this was not the last page.
+    pub fn set_parents(
+        &mut self,
+        ui: &Ui,
+        handle: Handle,
+        parents: Vec<Task>,
+        next: Option<NextPage>,
+    ) {
+        if !self.ancestry.contains(&handle) {
+            log::warn!(
+                "Dropping parents of {}, which is not in the ancestry tree",
+                handle.to_hex()
+            );
+            return;
+        }
+        self.ancestry
+            .set_fetched(handle.clone(), ui.input(|i| i.time));
+        // Polls of a watched handle only count when they bring new parents.
+        if let Some(watch) = self.watch.as_mut().filter(|watch| watch.handle == handle) {
+            if parents
+                .iter()
+                .all(|parent| self.ancestry.has_parent(&handle, &parent.handle))
+            {
+                self.ancestry.stop_loading(&handle);
+                return;
+            }
+            if self.ancestry.has_parents(&handle) {
+                watch.changed(ui.input(|i| i.time));
+            }
+        }
+        self.journal.push(Applied::Parents {
+            handle: handle.clone(),
+            parents: parents.clone(),
+            next: next.clone(),
+        });
+        self.fetches.record(
+            handle.clone(),
+            parents.iter().map(|parent| parent.handle.clone()).collect(),
+            ui.input(|i| i.time),
+            next.is_some(),
+        );
+        // Expand one level further on its own when there are few parents,
+        // unless this was such an expansion already.
+        let automatic = self.auto_fetched.remove(&handle);
+        if let Some(depth) = self.probing.remove(&handle).filter(|depth| *depth > 1) {
+            for parent in &parents {
+                self.probing.insert(parent.handle.clone(), depth - 1);
+                self.expanding.push(parent.handle.clone());
+            }
+        }
+        if !automatic && next.is_none() && parents.len() <= self.auto_expand {
+            for parent in &parents {
+                if self.auto_fetched.insert(parent.handle.clone()) {
+                    self.expanding.push(parent.handle.clone());
+                }
+            }
+        }
         // Merge into the ancestry tree.
-        self.ancestry.merge_new_parents(ui, handle, &parents);
+        self.ancestry
+            .merge_new_parents(ui, handle.clone(), &parents);
+        self.ancestry.set_next_page(&handle, next);
+    }
+
+    /// The parents of `handle` could not be fetched.
+    pub fn parents_failed(&mut self, handle: &Handle) {
+        self.ancestry.stop_loading(handle);
+    }
+
+    /// Which graphs these are, for requests for parents made on their behalf.
+    pub(crate) fn generation(&self) -> Generation {
+        self.generation
+    }
+
+    /// Whether `kind` was asked for the trees drawn now, rather than for
+    /// ones since replaced.
+    pub(crate) fn asked_for(&self, kind: &RequestKind) -> bool {
+        match kind {
+            RequestKind::Parents { generation, .. } => *generation == self.generation,
+            RequestKind::Child { generation, .. } | RequestKind::Dependees { generation, .. } => {
+                *generation == self.progress.generation()
+            }
+            _ => true,
+        }
     }
 
-    pub fn set_child(&mut self, ui: &Ui, index: usize, child: Handle) {
-        self.progress.set_child(ui, index, child);
+    pub fn set_child(
+        &mut self,
+        ui: &Ui,
+        index: usize,
+        child: Handle,
+        restriction: Option<Restriction>,
+    ) {
+        self.journal.push(Applied::Child {
+            index,
+            child: child.clone(),
+            restriction: restriction.clone(),
+        });
+        self.progress.set_child(ui, index, child, restriction);
     }
 
-    pub fn merge_dependees(&mut self, ui: &Ui, index: usize, dependees: Vec<Task>) {
-        log::error!("{:?}", dependees);
-        self.progress.merge_dependees(ui, index, dependees);
+    pub fn merge_dependees(
+        &mut self,
+        ui: &Ui,
+        index: usize,
+        dependees: Vec<Task>,
+        next: Option<NextPage>,
+    ) {
+        self.journal.push(Applied::Dependees {
+            index,
+            dependees: dependees.clone(),
+            next: next.clone(),
+        });
+        if let Some(task) = self.progress.task_handle(index).cloned() {
+            self.ancestry.merge_dependees(
+                task,
+                dependees
+                    .iter()
+                    .map(|dependee| (dependee.handle.clone(), dependee.operation)),
+            );
+        }
+        self.progress.merge_dependees(ui, index, dependees, next);
     }
 
     pub(crate) fn set_operation(&mut self, ui: &Ui, operation: Operation, handle: Handle) {
-        self.progress = progress::ProgressGraph::new(Element::new(ui, handle), operation);
+        self.journal.retain(|applied| !applied.is_progress());
+        self.journal.push(Applied::ProgressRoot {
+            handle: handle.clone(),
+            operation,
+        });
+        self.progress
+            .restart(ui, Element::new(ui, handle), operation);
+        self.progress.set_focus(self.focus.clone());
+    }
+
+    pub fn set_status(&mut self, handle: Handle, operation: Operation, status: TaskStatus) {
+        if matches!(status, TaskStatus::Done | TaskStatus::Failed) {
+            if let Some(rerun @ Rerun::Scheduled) =
+                self.reruns.get_mut(&(handle.clone(), operation))
+            {
+                *rerun = Rerun::Finished;
+            }
+        }
+        self.progress.set_status(handle, operation, status);
+    }
+
+    /// What the server said when asked to run a task again: the task's
+    /// status, or `None` if it would not.
+    pub fn set_rerun(&mut self, handle: Handle, operation: Operation, status: Option<TaskStatus>) {
+        let Some(status) = status else {
+            self.reruns.insert((handle, operation), Rerun::Refused);
+            return;
+        };
+        self.reruns
+            .insert((handle.clone(), operation), Rerun::Scheduled);
+        self.set_status(handle, operation, status);
+    }
+
+    pub fn set_parent_count(&mut self, handle: Handle, count: u64) {
+        self.ancestry.set_parent_count(handle, count);
+    }
+
+    pub fn set_task_times(&mut self, task_times: Vec<TaskTime>) {
+        self.journal.push(Applied::TaskTimes(task_times.clone()));
+        self.timeline.set_tasks(task_times);
+    }
+
+    /// Every response applied so far, to be replayed into a new container.
+    pub fn journal(&self) -> &[Applied] {
+        &self.journal
+    }
+
+    /// Apply responses recorded by another container for the same target and
+    /// operation, rebuilding its graphs.
+    pub fn replay(&mut self, ui: &Ui, journal: Vec<Applied>) {
+        for applied in journal {
+            match applied {
+                Applied::Parents {
+                    handle,
+                    parents,
+                    next,
+                } => self.set_parents(ui, handle, parents, next),
+                Applied::Child {
+                    index,
+                    child,
+                    restriction,
+                } => self.set_child(ui, index, child, restriction),
+                Applied::Dependees {
+                    index,
+                    dependees,
+                    next,
+                } => self.merge_dependees(ui, index, dependees, next),
+                Applied::TaskTimes(task_times) => self.set_task_times(task_times),
+                Applied::ProgressRoot { handle, operation } => {
+                    self.set_operation(ui, operation, handle)
+                }
+            }
+        }
+        // The journal holds the expansions' responses already.
+        self.expanding.clear();
+        self.auto_fetched.clear();
+        self.probing.clear();
+    }
+
+    /// Merge the ancestry tree of another session into this one, by handle:
+    /// parents it loaded for handles in the tree are added to theirs, and
+    /// where both loaded some, paging carries on from whichever loaded
+    /// further. Its progress responses are left out, since they belong to
+    /// its own progress tree, as are parents of handles never in this tree.
+    /// Returns how many responses were merged and how many left out.
+    pub fn merge(&mut self, ui: &Ui, journal: Vec<Applied>) -> (usize, usize) {
+        // Expansions the other session made are merged, not asked for again.
+        let pending = (
+            std::mem::take(&mut self.expanding),
+            std::mem::take(&mut self.auto_fetched),
+            std::mem::take(&mut self.probing),
+        );
+        let (mut merged, mut left_out) = (0, 0);
+        for applied in journal {
+            match applied {
+                Applied::Parents {
+                    handle,
+                    parents,
+                    next,
+                } if self.in_ancestry(&handle) => {
+                    let ours = self.ancestry.next_page(&handle).cloned();
+                    let next =
+                        journal::reconcile_next(ours, next, self.ancestry.has_parents(&handle));
+                    self.set_parents(ui, handle, parents, next);
+                    merged += 1;
+                }
+                _ => left_out += 1,
+            }
+        }
+        (self.expanding, self.auto_fetched, self.probing) = pending;
+        (merged, left_out)
+    }
+
+    /// Where the graphs were looking when last shown.
+    pub fn viewports(&self) -> Viewports {
+        self.viewports
+    }
+
+    /// Draw every graph in the colors of a theme.
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.ancestry.set_palette(palette);
+        self.progress.set_palette(palette);
+        self.timeline.set_palette(palette);
+    }
+
+    /// Mark the handles that have a note in `notes`, which are keyed by hex.
+    pub fn set_notes(&mut self, notes: &HashMap<String, String>) {
+        let annotated: HashSet<_> = notes
+            .keys()
+            .filter_map(|hex| Handle::from_hex(hex).ok())
+            .collect();
+        self.ancestry.set_annotated(annotated.clone());
+        self.progress.set_annotated(annotated);
+    }
+
+    /// Draw the aliases in `aliases`, which are keyed by hex, instead of the
+    /// handles they name.
+    pub fn set_aliases(&mut self, ui: &Ui, aliases: &HashMap<String, String>) {
+        let aliases: HashMap<_, _> = aliases
+            .iter()
+            .filter_map(|(hex, alias)| Some((Handle::from_hex(hex).ok()?, alias.clone())))
+            .collect();
+        self.ancestry.set_aliases(ui, aliases.clone());
+        self.progress.set_aliases(ui, aliases);
+    }
+
+    pub fn accessibility_shown(&self, accessibility: Accessibility) -> bool {
+        self.ancestry.accessibility_shown(accessibility)
+    }
+
+    pub fn set_accessibility_shown(&mut self, ui: &Ui, accessibility: Accessibility, shown: bool) {
+        self.ancestry
+            .set_accessibility_shown(ui, accessibility, shown);
+    }
+
+    /// The operation parents are fetched for, or `None` for all of them.
+    pub fn parent_filter(&self) -> Option<Operation> {
+        self.parent_filter
+    }
+
+    pub fn set_parent_filter(&mut self, filter: Option<Operation>) {
+        self.parent_filter = filter;
+    }
+
+    /// Whether the ancestry tree links tasks to the dependees fetched.
+    pub fn dependees_shown(&self) -> bool {
+        self.ancestry.dependees_shown()
+    }
+
+    pub fn set_dependees_shown(&mut self, shown: bool) {
+        self.ancestry.set_dependees_shown(shown);
+    }
+
+    /// Show `depth` generations of parents above `handle` in the ancestry
+    /// tree, fetching those not loaded yet, and hide any beyond them.
+    pub fn set_depth(&mut self, ui: &Ui, handle: Handle, depth: usize) {
+        self.ancestry.limit_depth(ui, &handle, depth);
+        if depth > 0 {
+            self.probing.insert(handle.clone(), depth);
+            self.expanding.push(handle);
+        }
+    }
+
+    /// Fetch the parents of every selected handle next frame.
+    pub fn fetch_selected_parents(&mut self) {
+        let selected: Vec<_> = self
+            .ancestry
+            .selected()
+            .map(|el| el.get_handle().clone())
+            .collect();
+        self.expanding.extend(selected);
+    }
+
+    /// Fetch the parents of each parent as well when a handle has at most
+    /// `limit` parents. Zero turns this off.
+    pub fn set_auto_expand(&mut self, limit: usize) {
+        self.auto_expand = limit;
+    }
+
+    pub fn set_navigation(&mut self, navigation: Navigation) {
+        self.navigation = navigation;
+    }
+
+    /// Draw elements bigger the larger their handle is, or all alike.
+    pub fn set_size_scaling(&mut self, scaled: bool) {
+        self.ancestry.set_size_scaling(scaled);
+        self.progress.set_size_scaling(scaled);
+    }
+
+    /// Cut long hex numbers in element texts to `keep` digits at each end,
+    /// or show them whole if `keep` is 0.
+    pub fn set_abbreviation(&mut self, ui: &Ui, keep: usize) {
+        self.ancestry.set_abbreviation(ui, keep);
+        self.progress.set_abbreviation(ui, keep);
+    }
+
+    pub fn set_spacing(&mut self, ui: &Ui, spacing: Spacing) {
+        self.ancestry.set_spacing(ui, spacing);
+        self.progress.set_padding(spacing.padding);
+    }
+
+    pub fn set_edge_routing(&mut self, edge_routing: EdgeRouting) {
+        self.ancestry.set_edge_routing(edge_routing);
+    }
+
+    pub fn set_pruning(&mut self, ui: &Ui, pruning: Pruning) {
+        self.ancestry.set_pruning(ui, pruning);
+    }
+
+    pub fn set_arrow_style(&mut self, arrow_style: ArrowStyle) {
+        self.ancestry.set_arrow_style(arrow_style);
+        self.progress.set_arrow_style(arrow_style);
+    }
+
+    /// Move the graphs back to where they were looking in an earlier visit.
+    pub fn restore_viewports(&mut self, viewports: Viewports) {
+        self.restore = viewports;
+    }
+
+    pub fn set_tag(&mut self, handle: Handle, tag: TagEntries) {
+        self.inspector.set_tag(handle, tag);
+    }
+
+    pub fn set_tree(&mut self, handle: Handle, entries: Vec<Handle>) {
+        self.inspector.set_tree(handle, entries);
+        self.find_usages();
+    }
+
+    /// Select the Thunks in the ancestry tree that use the handle usages
+    /// are being found of, and queue the trees still needed to tell.
+    fn find_usages(&mut self) {
+        let Some(target) = self.usages_of.clone() else {
+            return;
+        };
+        let handles: Vec<Handle> = self
+            .ancestry
+            .subtrees()
+            .into_iter()
+            .map(|(handle, _)| handle.clone())
+            .collect();
+        let usages = self.inspector.usages(&target, &handles);
+        self.inspector
+            .set_usages(target, usages.users.len(), usages.unfetched.len());
+        self.ancestry.set_selection(usages.users);
+        let (queued, asked) = &mut self.usage_trees;
+        queued.extend(
+            usages
+                .unfetched
+                .into_iter()
+                .filter(|tree| asked.insert(tree.clone())),
+        );
+    }
+
+    /// Whether `handle` is drawn in the ancestry tree, so that its parents
+    /// can be merged in.
+    pub fn in_ancestry(&self, handle: &Handle) -> bool {
+        self.ancestry.element_bounds(handle).is_some()
+    }
+
+    pub fn set_canonical(&mut self, handle: Handle, canonical: Handle) {
+        self.duplicates.set_canonical(handle, canonical);
+        self.ancestry
+            .set_duplicates(self.duplicates.groups().to_vec());
+    }
+
+    pub fn set_exists(&mut self, handle: Handle, exists: bool) {
+        self.probe.set_exists(handle, exists);
+        self.ancestry.set_missing(self.probe.missing().clone());
+    }
+
+    pub fn probe_failed(&mut self, handle: Handle) {
+        self.probe.failed(handle);
+    }
+
+    pub fn set_blob(&mut self, handle: Handle, contents: Vec<u8>) {
+        self.inspector.set_blob(handle, contents);
+    }
+
+    pub fn set_outcome(
+        &mut self,
+        ui: &Ui,
+        handle: Handle,
+        operation: Operation,
+        result: Option<Handle>,
+    ) {
+        if let Some(watch) = self.watch.as_mut().filter(|watch| watch.handle == handle) {
+            if operation == self.progress.operation() {
+                watch.set_outcome(ui.input(|i| i.time), result.clone());
+            }
+        }
+        self.inspector.set_outcome(handle, operation, result);
+    }
+
+    /// Whether the server could load a handle the inspector asked it to.
+    pub fn set_materialized(&mut self, handle: Handle, resident: bool) {
+        self.inspector.set_materialized(handle, resident);
+    }
+
+    /// Copy out the ancestry tree, or only its selected handles, for exporting.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn export_snapshot(
+        &self,
+        scope: crate::export::ExportScope,
+    ) -> crate::export::GraphSnapshot {
+        self.ancestry.snapshot(scope)
+    }
+
+    /// Draw a graph for a canvas of `size` pixels, regardless of the window.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn render_image(
+        &self,
+        ui: &mut Ui,
+        graph: crate::image_export::ImageGraph,
+        size: egui::Vec2,
+    ) -> crate::image_export::Picture {
+        use crate::image_export::{fit_to_canvas, ImageGraph, Picture};
+        use egui::plot::PlotTransform;
+
+        let frame = egui::Rect::from_min_size(egui::Pos2::ZERO, size);
+        let bounds = match graph {
+            ImageGraph::Ancestry => self.ancestry.bounds(),
+            ImageGraph::Progress => self.progress.content_bounds(),
+        };
+        let transform = PlotTransform::new(frame, fit_to_canvas(bounds, size), false, false);
+        let mut shapes = vec![];
+        let labels = match graph {
+            ImageGraph::Ancestry => {
+                self.ancestry.shapes(ui, &transform, &mut shapes);
+                self.ancestry.labels(&transform)
+            }
+            ImageGraph::Progress => {
+                self.progress.shapes(ui, &transform, &mut shapes);
+                self.progress.labels(&transform)
+            }
+        };
+        Picture {
+            size,
+            background: ui.visuals().extreme_bg_color,
+            shapes,
+            labels,
+            text_color: ui.visuals().widgets.active.fg_stroke.color,
+        }
+    }
+
+    /// A button that runs the selected task in the progress tree again, and
+    /// how far that has got. Returns the task to run again, if clicked.
+    fn rerun_ui(&mut self, ui: &mut Ui) -> Option<(Handle, Operation)> {
+        let focus = self.focus.as_ref()?;
+        let task = self
+            .progress
+            .tasks()
+            .into_iter()
+            .find(|(handle, _)| handle == focus)?;
+        let rerun = self.reruns.get(&task).copied();
+        let status = self
+            .progress
+            .status(&task.0, task.1)
+            .map_or("unknown".to_string(), |status| status.to_string());
+        let clicked = ui
+            .horizontal(|ui| {
+                let busy = matches!(rerun, Some(Rerun::Asked | Rerun::Scheduled));
+                let clicked = ui
+                    .add_enabled(!busy, egui::Button::new("Re-run"))
+                    .on_hover_text(format!(
+                        "Ask the server to run {} of the selected handle again, and follow \
+                         its status.",
+                        task.1
+                    ))
+                    .clicked();
+                match rerun {
+                    Some(Rerun::Asked) => {
+                        ui.spinner();
+                        ui.label("Asking the server");
+                    }
+                    Some(Rerun::Scheduled) => {
+                        ui.spinner();
+                        ui.label(format!("Running again: {status}"));
+                    }
+                    Some(Rerun::Finished) => {
+                        ui.label(format!("Ran again: {status}"));
+                    }
+                    Some(Rerun::Refused) => {
+                        ui.colored_label(
+                            ui.visuals().warn_fg_color,
+                            "Not run again; see Errors or the log.",
+                        );
+                    }
+                    None => {}
+                }
+                clicked
+            })
+            .inner;
+        if !clicked {
+            return None;
+        }
+        self.reruns.insert(task.clone(), Rerun::Asked);
+        Some(task)
+    }
+
+    /// A slider over when the arrows' relations were recorded, which leaves
+    /// out those recorded later, if the server said when any were.
+    fn replay_ui(&mut self, ui: &mut Ui) {
+        let Some((first, last)) = self.ancestry.recorded_range() else {
+            return;
+        };
+        ui.horizontal(|ui| {
+            let mut replaying = self.ancestry.recorded_before().is_some();
+            if ui
+                .checkbox(&mut replaying, "Replay")
+                .on_hover_text("Only draw arrows recorded by the time picked.")
+                .changed()
+            {
+                self.ancestry.set_recorded_before(replaying.then_some(last));
+            }
+            let Some(before) = self.ancestry.recorded_before() else {
+                return;
+            };
+            let mut elapsed = before - first;
+            if ui
+                .add(
+                    egui::Slider::new(&mut elapsed, 0.0..=last - first)
+                        .custom_formatter(|seconds, _| format!("+{seconds:.1} s"))
+                        .text("after the first relation"),
+                )
+                .changed()
+            {
+                self.ancestry.set_recorded_before(Some(first + elapsed));
+            }
+        });
+    }
+
+    /// The menu of the element right-clicked or held, while it is open.
+    fn element_menu(&mut self, ctx: &Context) {
+        let Some(menu) = &self.menu else {
+            return;
+        };
+        let (action, open) = menu.show(ctx);
+        if !open {
+            self.menu = None;
+        }
+        match action {
+            Some((handle, MenuAction::Select)) => self.set_focus(Some(handle)),
+            Some((handle, MenuAction::Pin)) => self.ancestry.pin([handle]),
+            Some((handle, MenuAction::Watch)) => self.watch = Some(Watch::new(handle)),
+            Some((handle, MenuAction::Release)) => self.ancestry.release_position(&handle),
+            None => {}
+        }
+    }
+
+    /// The watched handle, if any, with how often it is polled.
+    fn watch_ui(&mut self, ui: &mut Ui) {
+        let Some(watch) = &mut self.watch else {
+            return;
+        };
+        match watch.ui(ui) {
+            Some(WatchAction::Focus) => {
+                let handle = watch.handle.clone();
+                self.set_focus(Some(handle));
+            }
+            Some(WatchAction::Stop) => self.watch = None,
+            None => {}
+        }
+    }
+
+    /// How many handles were dragged to places of their own, with a button
+    /// to let them all go back.
+    fn fixed_ui(&mut self, ui: &mut Ui) {
+        let fixed = self.ancestry.fixed_len();
+        if fixed == 0 {
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.label(match fixed {
+                1 => "📌 1 handle placed by hand".to_string(),
+                fixed => format!("📌 {fixed} handles placed by hand"),
+            });
+            if ui
+                .small_button("Release all")
+                .on_hover_text("Put every handle back where the layout puts it")
+                .clicked()
+            {
+                self.ancestry.release_positions();
+            }
+        });
+    }
+
+    /// How many handles have parents that arrived long ago, with a button to
+    /// fetch them all again.
+    fn stale_ui(&mut self, ui: &mut Ui) {
+        if !self.ancestry.any_fetched() {
+            return;
+        }
+        // The tree grays and handles go stale without any input.
+        ui.ctx()
+            .request_repaint_after(std::time::Duration::from_secs(10));
+        let stale = self.ancestry.stale(STALE_AFTER);
+        if stale.is_empty() {
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.label(match stale.len() {
+                1 => "1 handle's parents are over 5 minutes old".to_string(),
+                stale => format!("{stale} handles' parents are over 5 minutes old"),
+            });
+            if ui
+                .small_button("Refresh stale")
+                .on_hover_text("Fetch their parents again, skipping the cache")
+                .clicked()
+            {
+                self.refreshing = stale;
+            }
+        });
+    }
+
+    /// How the latest probe went, with a button to forget what it found.
+    fn probe_ui(&mut self, ui: &mut Ui) {
+        if self.probe.ui(ui) {
+            self.ancestry.set_missing(HashSet::new());
+        }
+    }
+
+    /// The pinned roots, each in its color, and whether to color the tree by
+    /// which of them reach each handle.
+    fn pins_ui(&mut self, ui: &mut Ui) {
+        if self.ancestry.pinned().is_empty() {
+            return;
+        }
+        let pinned = self.ancestry.pinned().to_vec();
+        ui.horizontal_wrapped(|ui| {
+            let mut color_reach = self.ancestry.color_reach();
+            if ui
+                .checkbox(&mut color_reach, "Color by reach")
+                .on_hover_text(
+                    "Tint each handle by the pinned roots it flows into, mixing the colors of \
+                     roots that share it.",
+                )
+                .changed()
+            {
+                self.ancestry.set_color_reach(color_reach);
+            }
+            for (index, root) in pinned.iter().enumerate() {
+                ui.colored_label(reach::ROOT_COLORS[index], "■");
+                if ui
+                    .link(root.to_hex())
+                    .on_hover_text(root.to_string())
+                    .clicked()
+                {
+                    self.set_focus(Some(root.clone()));
+                }
+                if ui.small_button("×").on_hover_text("Unpin").clicked() {
+                    self.ancestry.unpin(root);
+                }
+            }
+            if color_reach && pinned.len() > 1 {
+                ui.label(format!("{} shared", self.ancestry.shared_reach()));
+            }
+        });
+    }
+
+    /// Actions on every selected handle in the ancestry tree. `fetch_parents`
+    /// requests a page of the parents of one handle.
+    fn selection_ui(&mut self, ui: &mut Ui, fetch_parents: impl Fn(&Handle, Option<&str>)) {
+        let selected = self.ancestry.selection_len();
+        let hidden = self.ancestry.hidden_len();
+        ui.horizontal(|ui| {
+            ui.label(format!("{selected} selected"))
+                .on_hover_text("Ctrl-click or ctrl-drag over handles to select several.");
+            ui.add_enabled_ui(selected > 0, |ui| {
+                if ui.button("Fetch parents").clicked() {
+                    for elem in self.ancestry.selected() {
+                        match elem.get_restriction() {
+                            Some(restriction) => log::info!(
+                                "Not requesting parents of restricted {}, requires {}",
+                                elem.get_handle().to_hex(),
+                                restriction.permission
+                            ),
+                            None => {
+                                let handle = elem.get_handle();
+                                let next = self.ancestry.next_page(handle);
+                                fetch_parents(handle, next.map(|next| next.cursor.as_str()));
+                            }
+                        }
+                    }
+                }
+                if ui.button("Copy hex").clicked() {
+                    let hex: Vec<_> = self
+                        .ancestry
+                        .selected()
+                        .map(|elem| elem.get_handle().to_hex())
+                        .collect();
+                    ui.output_mut(|o| o.copied_text = hex.join("\n"));
+                }
+                if ui.button("Hide").clicked() {
+                    self.ancestry.hide_selected(ui, false);
+                }
+                if ui
+                    .button("Hide with parents")
+                    .on_hover_text("Hide the selected handles and everything above them.")
+                    .clicked()
+                {
+                    self.ancestry.hide_selected(ui, true);
+                }
+                if ui
+                    .button("Pin as roots")
+                    .on_hover_text(format!(
+                        "Pin the selected handles to color the tree by which of them each \
+                         handle flows into, {} at most.",
+                        reach::ROOT_COLORS.len()
+                    ))
+                    .clicked()
+                {
+                    let selected: Vec<_> = self
+                        .ancestry
+                        .selected()
+                        .map(|elem| elem.get_handle().clone())
+                        .collect();
+                    self.ancestry.pin(selected);
+                }
+                if ui.button("Clear").clicked() {
+                    self.ancestry.clear_selection();
+                }
+            });
+            if hidden > 0 && ui.button(format!("Show {hidden} hidden")).clicked() {
+                self.ancestry.show_hidden(ui);
+            }
+            if self.ancestry.is_traced() {
+                if ui.button("Clear path").clicked() {
+                    self.ancestry.clear_trace();
+                }
+            } else if ui
+                .add_enabled(selected == 2, egui::Button::new("Trace path"))
+                .on_hover_text("Draw out the arrows between two selected handles.")
+                .clicked()
+            {
+                let ends: Vec<_> = self
+                    .ancestry
+                    .selected()
+                    .map(|elem| elem.get_handle().clone())
+                    .collect();
+                if !self.ancestry.trace_path(&ends[0], &ends[1]) {
+                    log::info!("Neither selected handle is an ancestor of the other");
+                }
+            }
+            if ui
+                .button("Duplicates")
+                .on_hover_text(
+                    "Ask for the canonical hash of every local handle, and link those that are \
+                     the same object.",
+                )
+                .clicked()
+            {
+                self.duplicates.find(self.ancestry.local_handles());
+            }
+            if ui
+                .button("Probe")
+                .on_hover_text(
+                    "Ask the server whether it still has every handle in the tree, and mark \
+                     those it does not.",
+                )
+                .clicked()
+            {
+                self.probe.start(
+                    self.ancestry
+                        .subtrees()
+                        .into_iter()
+                        .map(|(handle, _)| handle),
+                );
+                self.ancestry.set_missing(HashSet::new());
+            }
+            if ui
+                .button("Search")
+                .on_hover_text(
+                    "Find the handles in the tree whose hex, description or literal contents \
+                     match a regex.",
+                )
+                .clicked()
+            {
+                self.search.open();
+            }
+            if ui
+                .button("Sizes")
+                .on_hover_text(
+                    "Show where the sizes of the handles in the tree add up, by subtree and \
+                     object type.",
+                )
+                .clicked()
+            {
+                self.treemap.open();
+            }
+        });
+    }
+
+    /// Every handle hidden from the ancestry tree, each with a button to show
+    /// it again.
+    pub fn hidden_ui(&mut self, ui: &mut Ui) {
+        if self.ancestry.bin().is_empty() {
+            ui.label("Nothing hidden. Select handles in the ancestry tree to hide them.");
+            return;
+        }
+        let mut restore = None;
+        for (index, hidden) in self.ancestry.bin().iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.small_button("Restore").clicked() {
+                    restore = Some(index);
+                }
+                let hex = hidden.handle.to_hex();
+                ui.monospace(match (hidden.depth, hidden.parents()) {
+                    (None, 0) => hex,
+                    (None, parents) => format!("{hex} and {parents} parents"),
+                    (Some(0), parents) => format!("{parents} parents of {hex}"),
+                    (Some(depth), parents) => {
+                        format!("{parents} parents of {hex} beyond {depth} generations")
+                    }
+                });
+            });
+        }
+        if let Some(index) = restore {
+            self.ancestry.restore(ui, index);
+        }
+        if ui.button("Restore all").clicked() {
+            self.ancestry.show_hidden(ui);
+        }
+    }
+
+    /// A button per operation beside the selected element of the ancestry
+    /// tree, to show what performing it on the element gives in the progress
+    /// tree. Returns the handle and the operation clicked.
+    fn child_buttons(
+        &self,
+        ctx: &Context,
+        transform: &PlotTransform,
+    ) -> Option<(Handle, Operation)> {
+        let focus = self.focus.as_ref()?;
+        let bounds = self.ancestry.element_bounds(focus)?;
+        let rect = transform.rect_from_values(&bounds.min().into(), &bounds.max().into());
+        if !transform.frame().contains(rect.right_top()) {
+            return None;
+        }
+        let palette = self.ancestry.palette();
+        let clicked = egui::Area::new("child_buttons")
+            .order(egui::Order::Foreground)
+            .fixed_pos(rect.right_top() + egui::vec2(4.0, 0.0))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let mut clicked = None;
+                    for operation in [Operation::Apply, Operation::Eval, Operation::Fill] {
+                        let letter = &operation.to_string()[..1];
+                        let text = RichText::new(letter)
+                            .strong()
+                            .color(palette.operation(operation));
+                        if ui
+                            .small_button(text)
+                            .on_hover_text(format!(
+                                "Show the child of {operation} in the progress tree"
+                            ))
+                            .clicked()
+                        {
+                            clicked = Some(operation);
+                        }
+                    }
+                    clicked
+                })
+                .inner
+            })
+            .inner?;
+        Some((focus.clone(), clicked))
+    }
+
+    /// Highlight every element showing `focus`, or clear the highlight.
+    pub fn set_focus(&mut self, focus: Option<Handle>) {
+        self.ancestry.set_focus(focus.clone());
+        self.progress.set_focus(focus.clone());
+        self.timeline.set_focus(focus.clone());
+        self.focus = focus;
+    }
+}
+
+/// The generations asked for with a number key, if one was pressed.
+fn depth_pressed(ctx: &Context) -> Option<usize> {
+    const KEYS: [Key; 10] = [
+        Key::Num0,
+        Key::Num1,
+        Key::Num2,
+        Key::Num3,
+        Key::Num4,
+        Key::Num5,
+        Key::Num6,
+        Key::Num7,
+        Key::Num8,
+        Key::Num9,
+    ];
+    ctx.input(|i| KEYS.iter().position(|key| i.key_pressed(*key)))
+}
+
+/// Buttons that move a graph's viewport.
+fn view_buttons(ui: &mut Ui) -> Option<ViewCommand> {
+    let mut command = None;
+    if ui
+        .button("Fit graph")
+        .on_hover_text("Show the whole graph. Double-click empty space to do the same.")
+        .clicked()
+    {
+        command = Some(ViewCommand::Fit);
+    }
+    if ui
+        .button("Focus selected")
+        .on_hover_text("Zoom to the selected handle. Press F over the graph to do the same.")
+        .clicked()
+    {
+        command = Some(ViewCommand::FocusSelected);
     }
+    command
 }
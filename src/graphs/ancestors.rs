@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 
 use eframe::epaint::{util::FloatOrd, CubicBezierShape};
 use egui::{
@@ -9,12 +9,25 @@ use egui::{
         },
         LabelFormatter, PlotBounds, PlotPoint, PlotTransform,
     },
-    Color32, Pos2, Shape, Stroke, Ui,
+    Align2, Color32, FontId, Modifiers, Pos2, Rect, Shape, Stroke, Ui, Vec2,
 };
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::export::{ExportEdge, ExportNode, ExportScope, GraphSnapshot};
 use crate::{
-    handle::{Handle, Operation, Task},
+    handle::{Accessibility, Handle, Object, Operation, Task},
+    http::NextPage,
     plot::Element,
+    theme::Palette,
+};
+
+use super::{
+    legend, path, placeholder,
+    pruning::Pruning,
+    reach::{self, ROOT_COLORS},
+    routing::{self, ArrowStyle, EdgeRouting},
+    spacing::Spacing,
+    touch,
 };
 
 /// An element and all of its ancestors. This graph is append only.
@@ -27,11 +40,152 @@ pub(super) struct AncestorGraph {
     lineages: HashMap<Handle, (OrderingIndex, Lineage)>,
     /// Defined ordering of Handles. Used to reference from id's
     ordering: Vec<Handle>,
+    /// A handle whose element should stand out.
+    focus: Option<Handle>,
+    /// Whether to write the operation name on each arrow.
+    show_edge_labels: bool,
+    /// Handles picked for bulk actions, highlighted like the focus.
+    selection: HashSet<Handle>,
+    /// Handles left out of drawing, along with the arrows to and from them.
+    hidden: HashSet<Handle>,
+    /// What was hidden, one entry per handle hidden, to restore from.
+    bin: Vec<Hidden>,
+    /// Handles with a note, marked with an icon.
+    annotated: HashSet<Handle>,
+    /// Names given to handles, drawn instead of their descriptions.
+    aliases: HashMap<Handle, String>,
+    /// How many parents each handle has, as counted by the server, for
+    /// handles whose parents are not drawn yet.
+    parent_counts: HashMap<Handle, u64>,
+    /// Whether elements are drawn bigger the larger their handle is.
+    size_scaling: bool,
+    /// Hex digits kept at each end of long hex numbers in element texts.
+    abbreviation: usize,
+    spacing: Spacing,
+    /// Accessibilities left out of drawing like hidden handles.
+    filtered: HashSet<Accessibility>,
+    edge_routing: EdgeRouting,
+    arrow_style: ArrowStyle,
+    palette: Palette,
+    /// Whether parents shared by several children are centered over all of
+    /// them instead of drawn above the first.
+    merge_shared: bool,
+    /// Whether each kind of object is drawn in a lane of its own, stacked in
+    /// the order of `LANES`, instead of all in one tree.
+    lanes: bool,
+    /// How far apart lanes are: the height of the whole tree, and a gap.
+    lane_height: f64,
+    /// Where the layout puts each element, by ordering index. Worked out
+    /// again whenever the layout changes, so that drawing and hit-testing do
+    /// not walk the tree for every element.
+    layout: Vec<(PlotPoint, f64)>,
+    /// The parents of each element, including those drawn elsewhere in the
+    /// tree, as drawn by the arrows.
+    parents_of: HashMap<OrderingIndex, Vec<OrderingIndex>>,
+    /// A chain of arrows to draw out, dimming everything else.
+    traced: Option<TracedPath>,
+    /// Handles pinned as roots, each drawn in its color from `ROOT_COLORS`.
+    pinned: Vec<Handle>,
+    /// Tint every element by which pinned roots it flows into.
+    color_reach: bool,
+    /// Elements still moving to their place after the layout changed.
+    motions: HashMap<OrderingIndex, Motion>,
+    /// Seconds since the app started, as of the current frame.
+    now: f64,
+    /// When the server recorded each arrow's relation, keyed by parent and
+    /// child, for relations it keeps track of.
+    recorded: HashMap<(OrderingIndex, OrderingIndex), f64>,
+    /// Arrows recorded after this time are left out, to replay how the
+    /// tree was built.
+    recorded_before: Option<f64>,
+    /// Groups of local handles that are the same object, linked together.
+    duplicates: Vec<Vec<Handle>>,
+    /// Handles the server was found not to have.
+    missing: HashSet<Handle>,
+    /// The dependees of each task, as fetched for the dependency tree, and
+    /// the operations they perform. Linked to their task when both are drawn.
+    dependees: HashMap<Handle, Vec<(Handle, Operation)>>,
+    /// Whether links to dependees are drawn.
+    dependees_shown: bool,
+    /// Draw the operation colors and the scale of each generation on the
+    /// plot.
+    legend: bool,
+    pruning: Pruning,
+    /// Handles past the pruning limits, left out like hidden ones.
+    pruned: HashSet<Handle>,
+    /// How many ancestors of each element of the last generation drawn were
+    /// pruned.
+    folded: HashMap<Handle, usize>,
+    /// Handles whose parents are being fetched, with placeholders drawn
+    /// where the parents will appear until they arrive.
+    loading: HashSet<Handle>,
+    /// The watched handle, how strongly it flashes, and how many times it
+    /// changed.
+    watched: Option<(Handle, f32, usize)>,
+    /// Handles dragged to a place of their own, with where the centers of
+    /// their elements stay however the rest of the tree is laid out.
+    fixed: HashMap<Handle, PlotPoint>,
+    /// When the parents of each handle last arrived, from the server or the
+    /// cache, in seconds since the app started.
+    fetched: HashMap<Handle, f64>,
 }
 
-#[derive(Clone, Copy, PartialEq, Debug, Eq)]
+#[derive(Clone, Copy, PartialEq, Debug, Eq, Hash)]
 pub struct OrderingIndex(usize);
 
+/// The elements on a traced path and the arrows between them, as
+/// (parent, child) pairs.
+#[derive(Clone, Debug, Default)]
+struct TracedPath {
+    elements: HashSet<OrderingIndex>,
+    arrows: HashSet<(OrderingIndex, OrderingIndex)>,
+}
+
+/// Handles hidden together: a handle, alone or along with its parents, or
+/// its ancestors beyond some generation.
+#[derive(Clone, Debug)]
+pub struct Hidden {
+    pub handle: Handle,
+    /// Every handle hidden, `handle` first unless `depth` is set.
+    handles: Vec<Handle>,
+    /// How many generations above `handle` were left shown, if only its
+    /// ancestors beyond them were hidden.
+    pub depth: Option<usize>,
+}
+
+impl Hidden {
+    /// How many parents were hidden along with the handle, or in its place.
+    pub fn parents(&self) -> usize {
+        match self.depth {
+            Some(_) => self.handles.len(),
+            None => self.handles.len() - 1,
+        }
+    }
+}
+
+/// Where an element was drawn when the layout last changed, and when that was.
+#[derive(Clone, Copy, Debug)]
+struct Motion {
+    from: (PlotPoint, f64),
+    start: f64,
+}
+
+/// How long elements take to move to their place in a new layout.
+const ANIMATION_SECONDS: f64 = 0.2;
+
+/// The lanes of the lanes layout from the bottom up: computations at the
+/// bottom, with the data flowing into them above.
+const LANES: [Object; 4] = [Object::Thunk, Object::Tree, Object::Blob, Object::Tag];
+
+/// Space left between the top of one lane and the bottom of the next.
+const LANE_GAP: f64 = 1.0;
+
+/// Seconds parents are drawn as fresh after they arrive, before they start
+/// to gray.
+const FRESH_FOR: f64 = 60.0;
+/// Seconds after which parents are drawn as gray as they get.
+const GRAYEST_AFTER: f64 = 900.0;
+
 #[derive(Clone, Debug)]
 /// Index positions into the tree of Ancestors.
 struct Lineage(Vec<usize>);
@@ -45,9 +199,54 @@ pub struct Ancestor {
     parents: Vec<Ancestor>,
     /// Children Handles that are pointed to.
     children: Vec<(OrderingIndex, Operation)>,
+    /// Where the parents continue if not all of them have been loaded.
+    more: Option<NextPage>,
+    /// Groups of parents drawn as one summary element each.
+    clusters: Vec<Cluster>,
+    /// Operations whose parents were expanded and are never clustered.
+    expanded: Vec<Operation>,
+    layout: ParentLayout,
+}
+
+/// Parents of one Ancestor that all perform the same operation on it,
+/// summarized by a single element until expanded.
+#[derive(Clone)]
+struct Cluster {
+    operation: Operation,
+    element: Element,
+    /// Indices into the Ancestor's parents, in order.
+    members: Vec<usize>,
+}
+
+/// Where each parent of an Ancestor is drawn in the row above it. Clustered
+/// parents share the place of their cluster.
+#[derive(Clone, Default)]
+struct ParentLayout {
+    /// The place of each parent.
+    slots: Vec<usize>,
+    /// Whether each parent is hidden in a cluster.
+    clustered: Vec<bool>,
+    /// How many places the row has.
+    len: usize,
+}
+
+/// An arrow from a parent, or a cluster of them, to a child.
+struct Edge {
+    /// Where the arrow starts, how far its curve reaches out, and which way.
+    origin: (PlotPoint, f64, Arrow),
+    target: (PlotPoint, f64),
+    target_index: OrderingIndex,
+    /// The generations the arrow runs from and to, the target's being 0.
+    generations: (usize, usize),
+    operation: Operation,
+    color: Color32,
+    /// Whether the arrow goes to a child other than the one the parent is
+    /// drawn above.
+    cross: bool,
 }
 
 /// The direction an outgoing arrow should go.
+#[derive(Clone, Copy, PartialEq)]
 enum Arrow {
     Down,
     Right,
@@ -55,16 +254,144 @@ enum Arrow {
 
 impl PlotItem for AncestorGraph {
     fn shapes(&self, ui: &mut Ui, transform: &PlotTransform, shapes: &mut Vec<Shape>) {
-        for (index, el) in self.iter().enumerate() {
+        let reach = if self.color_reach {
+            self.reach()
+        } else {
+            HashMap::new()
+        };
+        for (index, el) in self.visible() {
+            if !el.is_on_screen(transform, self.get_draw_parameters(index)) {
+                continue;
+            }
+            let handle = el.get_handle();
             el.add_shapes(
                 transform,
                 shapes,
                 self.get_draw_parameters(index),
+                self.focus.as_ref() == Some(handle) || self.selection.contains(handle),
+                ui.visuals(),
+                &self.palette,
+            );
+            let traced = self.traced.as_ref();
+            if traced.map_or(false, |traced| {
+                !traced.elements.contains(&OrderingIndex(index))
+            }) {
+                el.add_tint(transform, self.get_draw_parameters(index), shapes, veil(ui));
+            }
+            if let Some(fetched) = self.fetched.get(handle) {
+                let stale = staleness(self.now - fetched);
+                if stale > 0.0 {
+                    let tint = Color32::GRAY.gamma_multiply(stale * 0.6);
+                    el.add_tint(transform, self.get_draw_parameters(index), shapes, tint);
+                }
+            }
+            if let Some(roots) = reach.get(&OrderingIndex(index)) {
+                let tint = reach::blend(roots).gamma_multiply(0.45);
+                el.add_tint(transform, self.get_draw_parameters(index), shapes, tint);
+            }
+            let loading = self.loading.contains(handle);
+            if loading && self.has_parents(handle) {
+                el.add_loading_note(ui, transform, self.get_draw_parameters(index), shapes);
+            } else if loading {
+                // As many placeholders as the server counted, or one.
+                let count = self
+                    .parent_counts
+                    .get(handle)
+                    .map_or(1, |&count| count as usize);
+                let params = self.get_draw_parameters(index);
+                let bounds = el.bounds(params);
+                let top = PlotPoint::new(params.0.x, bounds.max()[1]);
+                for slot in placeholder::slots(params, count, |scale| self.spacing.rise(scale)) {
+                    placeholder::add_shapes(ui, transform, slot, top, self.now, shapes);
+                }
+            } else if let Some(next) = self.next_page(handle) {
+                el.add_more_note(
+                    ui,
+                    transform,
+                    self.get_draw_parameters(index),
+                    next.remaining,
+                    shapes,
+                );
+            }
+            if let Some(&count) = self.parent_counts.get(handle) {
+                if count > 0 && !self.has_parents(handle) {
+                    el.add_parent_count(
+                        ui,
+                        transform,
+                        self.get_draw_parameters(index),
+                        count,
+                        shapes,
+                    );
+                }
+            }
+            if self.annotated.contains(handle) {
+                el.add_note_icon(ui, transform, self.get_draw_parameters(index), shapes);
+            }
+            if let Some((_, flash, changes)) = self
+                .watched
+                .as_ref()
+                .filter(|(watched, ..)| watched == handle)
+            {
+                let params = self.get_draw_parameters(index);
+                if *flash > 0.0 {
+                    let tint = ui.visuals().warn_fg_color.gamma_multiply(flash * 0.6);
+                    el.add_tint(transform, params, shapes, tint);
+                }
+                el.add_watch_badge(ui, transform, params, *changes, shapes);
+            }
+            if self.fixed.contains_key(handle) {
+                el.add_fixed_badge(ui, transform, self.get_draw_parameters(index), shapes);
+            }
+            if self.missing.contains(handle) {
+                el.add_missing_badge(ui, transform, self.get_draw_parameters(index), shapes);
+            }
+            if let Some(&folded) = self.folded.get(handle) {
+                el.add_parent_count(
+                    ui,
+                    transform,
+                    self.get_draw_parameters(index),
+                    folded as u64,
+                    shapes,
+                );
+            }
+        }
+        for (_, cluster, params) in self.clusters() {
+            if !cluster.element.is_on_screen(transform, params) {
+                continue;
+            }
+            cluster.element.add_shapes(
+                transform,
+                shapes,
+                params,
                 false,
-                ui.visuals().widgets.active.fg_stroke.color,
+                ui.visuals(),
+                &self.palette,
+            );
+            if self.traced.is_some() {
+                cluster
+                    .element
+                    .add_tint(transform, params, shapes, veil(ui));
+            }
+        }
+        self.add_arrows(ui, transform, shapes);
+        self.add_duplicate_links(ui, transform, shapes);
+        if self.dependees_shown {
+            self.add_dependee_links(transform, shapes);
+        }
+        if self.lanes {
+            self.add_lane_markers(ui, transform, shapes);
+        } else {
+            self.add_generation_markers(ui, transform, shapes);
+        }
+        if self.legend {
+            legend::add_shapes(
+                ui,
+                transform,
+                &self.palette,
+                &self.generation_widths(),
+                shapes,
             );
         }
-        self.add_arrows(transform, shapes);
     }
 
     fn initialize(&mut self, _x_range: std::ops::RangeInclusive<f64>) {}
@@ -88,11 +415,18 @@ impl PlotItem for AncestorGraph {
     }
 
     /// Search for the closest element in the graph based on squared distance to bounds.
+    /// Clusters come after every handle in the ordering.
     fn find_closest(&self, point: Pos2, transform: &PlotTransform) -> Option<ClosestElem> {
-        self.iter()
+        let clusters = self
+            .clusters()
             .enumerate()
-            .map(|(index, el)| {
-                let bounds = el.bounds(self.get_draw_parameters(index));
+            .map(|(index, (_, cluster, params))| {
+                (self.ordering.len() + index, cluster.element.bounds(params))
+            });
+        self.visible()
+            .map(|(index, el)| (index, el.bounds(self.get_draw_parameters(index))))
+            .chain(clusters)
+            .map(|(index, bounds)| {
                 let rect = transform.rect_from_values(&bounds.min().into(), &bounds.max().into());
                 ClosestElem {
                     index,
@@ -110,16 +444,35 @@ impl PlotItem for AncestorGraph {
         plot: &PlotConfig<'_>,
         _: &LabelFormatter,
     ) {
+        if let Some((_, cluster, params)) = self.cluster(elem.index) {
+            cluster.element.add_highlight(
+                plot.transform,
+                params,
+                shapes,
+                plot.ui.visuals(),
+                &self.palette,
+            );
+            return;
+        }
         let entry = self.iter().nth(elem.index);
         let Some(entry) = entry else { return };
-        entry.add_highlight(plot.transform, self.get_draw_parameters(elem.index), shapes);
+        entry.add_highlight(
+            plot.transform,
+            self.get_draw_parameters(elem.index),
+            shapes,
+            plot.ui.visuals(),
+            &self.palette,
+        );
     }
 
     fn bounds(&self) -> PlotBounds {
         let mut bounds = PlotBounds::NOTHING;
-        for (index, el) in self.iter().enumerate() {
+        for (index, el) in self.visible() {
             bounds.merge(&el.bounds(self.get_draw_parameters(index)));
         }
+        for (_, cluster, params) in self.clusters() {
+            bounds.merge(&cluster.element.bounds(params));
+        }
         bounds
     }
 }
@@ -132,38 +485,671 @@ impl AncestorGraph {
             element.get_handle().clone(),
             (OrderingIndex(0), Lineage(vec![0])),
         );
-        Self {
+        let mut graph = Self {
             inner: [Ancestor::new(element, vec![])],
             ordering,
             lineages,
+            focus: None,
+            show_edge_labels: false,
+            selection: HashSet::new(),
+            hidden: HashSet::new(),
+            bin: vec![],
+            annotated: HashSet::new(),
+            aliases: HashMap::new(),
+            parent_counts: HashMap::new(),
+            size_scaling: false,
+            abbreviation: 0,
+            spacing: Spacing::default(),
+            filtered: HashSet::new(),
+            edge_routing: EdgeRouting::default(),
+            arrow_style: ArrowStyle::default(),
+            palette: Palette::default(),
+            merge_shared: false,
+            lanes: false,
+            lane_height: 0.0,
+            layout: vec![],
+            parents_of: HashMap::new(),
+            traced: None,
+            pinned: vec![],
+            color_reach: false,
+            motions: HashMap::new(),
+            now: 0.0,
+            recorded: HashMap::new(),
+            recorded_before: None,
+            duplicates: vec![],
+            missing: HashSet::new(),
+            dependees: HashMap::new(),
+            dependees_shown: true,
+            legend: false,
+            pruning: Pruning::default(),
+            pruned: HashSet::new(),
+            folded: HashMap::new(),
+            loading: HashSet::new(),
+            watched: None,
+            fixed: HashMap::new(),
+            fetched: HashMap::new(),
+        };
+        graph.layout = graph.layout_pass();
+        graph
+    }
+
+    /// Move animations forward to `now`. Returns whether any element is
+    /// still moving, or any placeholder spinning.
+    pub fn advance(&mut self, now: f64) -> bool {
+        self.now = now;
+        self.motions
+            .retain(|_, motion| now - motion.start < ANIMATION_SECONDS);
+        !self.motions.is_empty() || !self.loading.is_empty()
+    }
+
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    pub fn set_annotated(&mut self, annotated: HashSet<Handle>) {
+        self.annotated = annotated;
+    }
+
+    pub fn set_aliases(&mut self, ui: &Ui, aliases: HashMap<Handle, String>) {
+        self.inner[0].set_aliases(ui, &aliases);
+        self.aliases = aliases;
+    }
+
+    pub fn set_size_scaling(&mut self, scaled: bool) {
+        self.size_scaling = scaled;
+        self.inner[0].set_size_scaling(scaled);
+    }
+
+    pub fn set_abbreviation(&mut self, ui: &Ui, keep: usize) {
+        self.abbreviation = keep;
+        self.inner[0].set_abbreviation(ui, keep);
+    }
+
+    /// Move every element to where `spacing` places it, and pad it as
+    /// `spacing` says.
+    pub fn set_spacing(&mut self, ui: &Ui, spacing: Spacing) {
+        let before = self.current_layout(ui);
+        self.spacing = spacing;
+        self.inner[0].set_padding(spacing.padding);
+        self.animate_from(before, OrderingIndex(0));
+    }
+
+    pub fn set_edge_routing(&mut self, edge_routing: EdgeRouting) {
+        self.edge_routing = edge_routing;
+    }
+
+    pub fn set_arrow_style(&mut self, arrow_style: ArrowStyle) {
+        self.arrow_style = arrow_style;
+    }
+
+    pub fn set_merge_shared(&mut self, ui: &Ui, merge_shared: bool) {
+        let before = self.current_layout(ui);
+        self.merge_shared = merge_shared;
+        self.animate_from(before, OrderingIndex(0));
+    }
+
+    /// When the first and last relations drawn were recorded, if the server
+    /// said for any of them.
+    pub fn recorded_range(&self) -> Option<(f64, f64)> {
+        let mut times = self.recorded.values().copied();
+        let first = times.next()?;
+        Some(times.fold((first, first), |(min, max), time| {
+            (min.min(time), max.max(time))
+        }))
+    }
+
+    pub fn recorded_before(&self) -> Option<f64> {
+        self.recorded_before
+    }
+
+    /// Leave out arrows recorded after `time`, or none if `None`.
+    pub fn set_recorded_before(&mut self, time: Option<f64>) {
+        self.recorded_before = time;
+    }
+
+    /// Whether the arrow from `parent` to `child` was recorded by the time
+    /// being replayed. Arrows of unknown age are always drawn.
+    fn recorded_yet(&self, parent: OrderingIndex, child: OrderingIndex) -> bool {
+        match (self.recorded_before, self.recorded.get(&(parent, child))) {
+            (Some(before), Some(recorded)) => *recorded <= before,
+            _ => true,
+        }
+    }
+
+    pub fn set_legend(&mut self, legend: bool) {
+        self.legend = legend;
+    }
+
+    /// The width of the widest element shown in each generation, the
+    /// target's first.
+    fn generation_widths(&self) -> Vec<f64> {
+        let mut widths = vec![];
+        for (index, el) in self.visible() {
+            let generation = self.lineages[el.get_handle()].1 .0.len() - 1;
+            if widths.len() <= generation {
+                widths.resize(generation + 1, 0.0);
+            }
+            let width = el.bounds(self.get_draw_parameters(index)).width();
+            widths[generation] = f64::max(widths[generation], width);
         }
+        widths
+    }
+
+    pub fn set_pruning(&mut self, ui: &Ui, pruning: Pruning) {
+        let before = self.current_layout(ui);
+        self.pruning = pruning;
+        self.relayout();
+        self.animate_from(before, OrderingIndex(0));
+    }
+
+    /// Leave out the generations past the pruning limits, counting them on
+    /// the last generation drawn.
+    fn prune(&mut self) {
+        let cutoff = self.pruning.cutoff(
+            self.lineages
+                .values()
+                .map(|(_, lineage)| generation(lineage)),
+        );
+        self.pruned.clear();
+        self.folded.clear();
+        let Some(cutoff) = cutoff else {
+            return;
+        };
+        for (handle, (_, lineage)) in &self.lineages {
+            match generation(lineage) {
+                g if g > cutoff => {
+                    self.pruned.insert(handle.clone());
+                }
+                g if g == cutoff => {
+                    let beyond = self.ancestors_beyond(handle, 0).len();
+                    if beyond > 0 {
+                        self.folded.insert(handle.clone(), beyond);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Every handle in the graph stored under a local id.
+    pub fn local_handles(&self) -> impl Iterator<Item = &Handle> {
+        self.ordering
+            .iter()
+            .filter(|handle| handle.storage() == "local")
+    }
+
+    /// Every handle in the graph, in the order added, with the parent of the
+    /// target whose ancestry it is in, or `None` for the target itself.
+    pub fn subtrees(&self) -> Vec<(&Handle, Option<&Handle>)> {
+        self.ordering
+            .iter()
+            .map(|handle| {
+                let lineage = &self.lineages[handle].1;
+                let root = lineage.0.get(1).map(|&index| {
+                    Self::get_from_lineage(&self.inner, &Lineage(vec![0, index]))
+                        .content
+                        .get_handle()
+                });
+                (handle, root)
+            })
+            .collect()
+    }
+
+    pub fn set_duplicates(&mut self, duplicates: Vec<Vec<Handle>>) {
+        self.duplicates = duplicates;
+    }
+
+    pub fn set_missing(&mut self, missing: HashSet<Handle>) {
+        self.missing = missing;
+    }
+
+    /// Remember `dependees` of the task of `handle`, besides those already
+    /// known.
+    pub fn merge_dependees(
+        &mut self,
+        handle: Handle,
+        dependees: impl IntoIterator<Item = (Handle, Operation)>,
+    ) {
+        let known = self.dependees.entry(handle).or_default();
+        for dependee in dependees {
+            if !known.contains(&dependee) {
+                known.push(dependee);
+            }
+        }
+    }
+
+    pub fn dependees_shown(&self) -> bool {
+        self.dependees_shown
+    }
+
+    pub fn set_dependees_shown(&mut self, shown: bool) {
+        self.dependees_shown = shown;
+    }
+
+    pub fn set_lanes(&mut self, ui: &Ui, lanes: bool) {
+        let before = self.current_layout(ui);
+        self.lanes = lanes;
+        self.animate_from(before, OrderingIndex(0));
+    }
+
+    pub fn set_show_edge_labels(&mut self, show_edge_labels: bool) {
+        self.show_edge_labels = show_edge_labels;
+    }
+
+    pub fn set_focus(&mut self, focus: Option<Handle>) {
+        self.focus = focus;
+    }
+
+    /// `coords` moved onto the element or cluster at `closest_elem`, for
+    /// touches that land beside it.
+    pub fn snap_to(&self, coords: PlotPoint, closest_elem: ClosestElem) -> PlotPoint {
+        let bounds = match self.cluster(closest_elem.index) {
+            Some((_, cluster, params)) => cluster.element.bounds(params),
+            None => match self.iter().nth(closest_elem.index) {
+                Some(elem) => elem.bounds(self.get_draw_parameters(closest_elem.index)),
+                None => return coords,
+            },
+        };
+        touch::clamp(coords, bounds)
+    }
+
+    /// Returns the element under `coords` and where it is drawn, if any.
+    pub fn element_at(
+        &self,
+        coords: PlotPoint,
+        closest_elem: ClosestElem,
+    ) -> Option<(&Element, PlotBounds)> {
+        let elem = self.iter().nth(closest_elem.index)?;
+        let bounds = elem.bounds(self.get_draw_parameters(closest_elem.index));
+        let [min_x, min_y] = bounds.min();
+        let [max_x, max_y] = bounds.max();
+        let p = coords;
+        let elem_contains_p = min_x <= p.x && p.x <= max_x && min_y <= p.y && p.y <= max_y;
+        elem_contains_p.then_some((elem, bounds))
+    }
+
+    /// The operations `handle` is a parent by, each with the child it leads
+    /// to.
+    pub fn tasks_of(&self, handle: &Handle) -> Vec<(Operation, String)> {
+        let Some((_, lineage)) = self.lineages.get(handle) else {
+            return vec![];
+        };
+        Self::get_from_lineage(&self.inner, lineage)
+            .children
+            .iter()
+            .map(|(child, operation)| {
+                (
+                    *operation,
+                    format!("parent of {}", self.ordering[child.0].to_hex()),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns where the element showing `handle` is drawn, if it is in the graph.
+    pub fn element_bounds(&self, handle: &Handle) -> Option<PlotBounds> {
+        let (index, lineage) = self.lineages.get(handle)?;
+        let elem = &Self::get_from_lineage(&self.inner, lineage).content;
+        Some(elem.bounds(self.get_draw_parameters(index.0)))
+    }
+
+    pub fn parent_count(&self, handle: &Handle) -> Option<u64> {
+        self.parent_counts.get(handle).copied()
+    }
+
+    pub fn set_parent_count(&mut self, handle: Handle, count: u64) {
+        self.parent_counts.insert(handle, count);
+    }
+
+    /// Draw placeholders for the parents of `handle` until they arrive.
+    pub fn set_loading(&mut self, handle: Handle) {
+        if self.lineages.contains_key(&handle) {
+            self.loading.insert(handle);
+        }
+    }
+
+    /// Stop drawing placeholders for the parents of `handle`, which are not
+    /// coming.
+    pub fn stop_loading(&mut self, handle: &Handle) {
+        self.loading.remove(handle);
+    }
+
+    pub fn set_watched(&mut self, watched: Option<(Handle, f32, usize)>) {
+        self.watched = watched;
+    }
+
+    /// Draw `handle` centered at `center` from now on, wherever the layout
+    /// puts it.
+    pub fn fix_position(&mut self, handle: Handle, center: PlotPoint) {
+        self.fixed.insert(handle, center);
+    }
+
+    /// Let `handle` go back to where the layout puts it.
+    pub fn release_position(&mut self, handle: &Handle) {
+        self.fixed.remove(handle);
+    }
+
+    pub fn release_positions(&mut self) {
+        self.fixed.clear();
+    }
+
+    /// Whether `handle` is in the tree, drawn or not.
+    pub fn contains(&self, handle: &Handle) -> bool {
+        self.lineages.contains_key(handle)
+    }
+
+    pub fn is_fixed(&self, handle: &Handle) -> bool {
+        self.fixed.contains_key(handle)
+    }
+
+    pub fn set_fetched(&mut self, handle: Handle, time: f64) {
+        self.fetched.insert(handle, time);
+    }
+
+    /// Whether the parents of any handle arrived yet, so that the tree grays
+    /// as time passes.
+    pub fn any_fetched(&self) -> bool {
+        !self.fetched.is_empty()
+    }
+
+    /// Handles in the tree whose parents arrived more than `age` seconds
+    /// ago, in the order added.
+    pub fn stale(&self, age: f64) -> Vec<Handle> {
+        self.ordering
+            .iter()
+            .filter(|handle| {
+                self.fetched
+                    .get(*handle)
+                    .map_or(false, |fetched| self.now - fetched > age)
+            })
+            .cloned()
+            .collect()
+    }
+
+    pub fn fixed_len(&self) -> usize {
+        self.fixed.len()
+    }
+
+    /// Whether `parent` is drawn as a parent of `child`.
+    pub fn has_parent(&self, child: &Handle, parent: &Handle) -> bool {
+        let (Some((child, _)), Some((parent, _))) =
+            (self.lineages.get(child), self.lineages.get(parent))
+        else {
+            return false;
+        };
+        self.parents_of
+            .get(child)
+            .map_or(false, |parents| parents.contains(parent))
+    }
+
+    /// Whether any parents of `handle` have been loaded.
+    pub fn has_parents(&self, handle: &Handle) -> bool {
+        self.lineages.get(handle).map_or(false, |(_, lineage)| {
+            !Self::get_from_lineage(&self.inner, lineage)
+                .parents
+                .is_empty()
+        })
+    }
+
+    /// Where the parents of `handle` continue, if they have not all been loaded.
+    pub fn next_page(&self, handle: &Handle) -> Option<&NextPage> {
+        let (_, lineage) = self.lineages.get(handle)?;
+        Self::get_from_lineage(&self.inner, lineage).more.as_ref()
+    }
+
+    pub fn set_next_page(&mut self, handle: &Handle, next: Option<NextPage>) {
+        if let Some(ancestor) = self.find(handle) {
+            ancestor.more = next;
+        }
+    }
+
+    /// Selected elements, in the order they were added to the graph.
+    pub fn selected(&self) -> impl Iterator<Item = &Element> {
+        self.iter()
+            .filter(|el| self.selection.contains(el.get_handle()))
+    }
+
+    pub fn selection_len(&self) -> usize {
+        self.selection.len()
+    }
+
+    /// Add every visible element that overlaps `area` to the selection.
+    pub fn select_in(&mut self, area: PlotBounds) {
+        let overlapping: Vec<_> = self
+            .visible()
+            .filter(|(index, el)| {
+                let bounds = el.bounds(self.get_draw_parameters(*index));
+                bounds.min()[0] <= area.max()[0]
+                    && area.min()[0] <= bounds.max()[0]
+                    && bounds.min()[1] <= area.max()[1]
+                    && area.min()[1] <= bounds.max()[1]
+            })
+            .map(|(_, el)| el.get_handle().clone())
+            .collect();
+        self.selection.extend(overlapping);
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selection.clear();
+    }
+
+    /// Select exactly `handles`.
+    pub fn set_selection(&mut self, handles: impl IntoIterator<Item = Handle>) {
+        self.selection = handles.into_iter().collect();
+    }
+
+    /// Stop drawing the selected elements, along with all of their parents
+    /// if `with_parents` is set, and deselect them.
+    pub fn hide_selected(&mut self, ui: &Ui, with_parents: bool) {
+        let before = self.current_layout(ui);
+        let selected: Vec<_> = self
+            .ordering
+            .iter()
+            .filter(|handle| self.selection.contains(handle))
+            .cloned()
+            .collect();
+        self.selection.clear();
+        for handle in selected {
+            let handles = match with_parents {
+                true => self.ancestors_of(&handle),
+                false => vec![handle.clone()],
+            };
+            self.bin.push(Hidden {
+                handle,
+                handles,
+                depth: None,
+            });
+        }
+        self.reflow(before);
+    }
+
+    /// Hide every ancestor of `handle` more than `depth` generations above
+    /// it, in place of any depth set for it before.
+    pub fn limit_depth(&mut self, ui: &Ui, handle: &Handle, depth: usize) {
+        let before = self.current_layout(ui);
+        self.bin
+            .retain(|hidden| hidden.depth.is_none() || hidden.handle != *handle);
+        let beyond = self.ancestors_beyond(handle, depth);
+        if !beyond.is_empty() {
+            self.bin.push(Hidden {
+                handle: handle.clone(),
+                handles: beyond,
+                depth: Some(depth),
+            });
+        }
+        self.reflow(before);
+    }
+
+    pub fn hidden_len(&self) -> usize {
+        self.hidden.len()
+    }
+
+    /// Everything hidden, in the order it was hidden.
+    pub fn bin(&self) -> &[Hidden] {
+        &self.bin
+    }
+
+    /// Draw the handles hidden by the bin entry at `index` again.
+    pub fn restore(&mut self, ui: &Ui, index: usize) {
+        let before = self.current_layout(ui);
+        self.bin.remove(index);
+        self.reflow(before);
+    }
+
+    pub fn show_hidden(&mut self, ui: &Ui) {
+        let before = self.current_layout(ui);
+        self.bin.clear();
+        self.reflow(before);
+    }
+
+    /// Catch up with the bin and move elements into the space freed or
+    /// taken by what is no longer or now again shown.
+    fn reflow(&mut self, before: Vec<(PlotPoint, f64)>) {
+        self.hidden = self
+            .bin
+            .iter()
+            .flat_map(|hidden| hidden.handles.iter().cloned())
+            .collect();
+        self.relayout();
+        self.animate_from(before, OrderingIndex(0));
+    }
+
+    /// Lay out the parents of every Ancestor again. Parents with nothing left
+    /// to show, neither themselves nor any of their own parents, give up
+    /// their place.
+    fn relayout(&mut self) {
+        fn lay_out(ancestor: &mut Ancestor, shown: &impl Fn(&Handle) -> bool) -> bool {
+            let occupied: Vec<bool> = ancestor
+                .parents
+                .iter_mut()
+                .map(|parent| lay_out(parent, shown))
+                .collect();
+            ancestor.lay_out(&occupied);
+            shown(ancestor.content.get_handle()) || occupied.contains(&true)
+        }
+        self.prune();
+        let (hidden, filtered, pruned) = (&self.hidden, &self.filtered, &self.pruned);
+        let shown = |handle: &Handle| {
+            !hidden.contains(handle)
+                && !filtered.contains(&handle.accessibility)
+                && !pruned.contains(handle)
+        };
+        lay_out(&mut self.inner[0], &shown);
+    }
+
+    /// Copy out the elements in `scope` and the arrows between them for
+    /// exporting.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn snapshot(&self, scope: ExportScope) -> GraphSnapshot {
+        let subtree: HashSet<Handle> = match (scope, &self.focus) {
+            (ExportScope::Subtree, Some(focus)) => self.ancestors_of(focus).into_iter().collect(),
+            _ => HashSet::new(),
+        };
+        let included = |handle: &Handle| match scope {
+            ExportScope::All => true,
+            ExportScope::Selected => self.selection.contains(handle),
+            ExportScope::Subtree => subtree.contains(handle),
+        };
+        // Where each included element ends up in the snapshot's node list.
+        let mut node_indices = HashMap::new();
+        let mut nodes = vec![];
+        for (index, el) in self.iter().enumerate() {
+            if !included(el.get_handle()) {
+                continue;
+            }
+            node_indices.insert(index, nodes.len());
+            nodes.push(ExportNode {
+                handle: el.get_handle().clone(),
+                bounds: el.bounds(self.get_draw_parameters(index)),
+            });
+        }
+        let mut edges = vec![];
+        for (index, handle) in self.ordering.iter().enumerate() {
+            let Some(&from) = node_indices.get(&index) else {
+                continue;
+            };
+            let lineage = &self.lineages[handle].1;
+            let ancestor = Self::get_from_lineage(&self.inner, lineage);
+            for (to, operation) in &ancestor.children {
+                if let Some(&to) = node_indices.get(&to.0) {
+                    edges.push(ExportEdge {
+                        from,
+                        to,
+                        operation: *operation,
+                        color: self.palette.operation(*operation),
+                    });
+                }
+            }
+        }
+        GraphSnapshot { nodes, edges }
+    }
+
+    /// Every element's text and where it is drawn.
+    pub fn labels(&self, transform: &PlotTransform) -> Vec<(Rect, String)> {
+        let clusters = self
+            .clusters()
+            .map(|(_, cluster, params)| cluster.element.label(transform, params));
+        self.visible()
+            .map(|(index, el)| el.label(transform, self.get_draw_parameters(index)))
+            .chain(clusters)
+            .collect()
     }
 
     /// Handle a click that is near to a ClosestElem. May send an http request
-    /// that is specified by the `request` parameter.
+    /// that is specified by the `request` parameter, which is passed where the
+    /// next page of parents starts if only some have been loaded. Clicking with
+    /// the command modifier held only adds or removes the element from the
+    /// selection.
     pub fn handle_nearby_click(
-        &self,
+        &mut self,
         ui: &Ui,
         coords: PlotPoint,
         closest_elem: ClosestElem,
-        request: impl FnOnce(usize, &Handle),
+        modifiers: Modifiers,
+        request: impl FnOnce(&Handle, Option<&str>),
     ) {
-        let Some(elem) = self.iter().nth(closest_elem.index) else {
+        if let Some((child, cluster, params)) = self.cluster(closest_elem.index) {
+            let [min_x, min_y] = cluster.element.bounds(params).min();
+            let [max_x, max_y] = cluster.element.bounds(params).max();
+            let p = coords;
+            if min_x <= p.x && p.x <= max_x && min_y <= p.y && p.y <= max_y {
+                let (child, operation) = (child.content.get_handle().clone(), cluster.operation);
+                self.expand(ui, &child, operation);
+            }
+            return;
+        }
+        if closest_elem.index >= self.ordering.len() {
             log::error!("Handling a click near to an element whose index no longer exists");
             return;
+        }
+        let Some((elem, _)) = self.element_at(coords, closest_elem) else {
+            return;
         };
-
-        let params = self.get_draw_parameters(closest_elem.index);
-        let [min_x, min_y] = elem.bounds(params).min();
-        let [max_x, max_y] = elem.bounds(params).max();
-        let p = coords;
-        let elem_contains_p = min_x <= p.x && p.x <= max_x && min_y <= p.y && p.y <= max_y;
-        if elem_contains_p {
-            ui.output_mut(|o| o.copied_text = elem.get_handle().to_hex());
-            log::info!("Requesting parents");
-            // TODO use index instead of hashmap
-            request(0, elem.get_handle());
-        };
+        if modifiers.command {
+            let handle = elem.get_handle().clone();
+            if !self.selection.remove(&handle) {
+                self.selection.insert(handle);
+            }
+            return;
+        }
+        ui.output_mut(|o| o.copied_text = elem.get_handle().to_hex());
+        if let Some(restriction) = elem.get_restriction() {
+            log::info!(
+                "Not requesting parents of restricted {}, requires {}",
+                elem.get_handle().to_hex(),
+                restriction.permission
+            );
+            return;
+        }
+        log::info!("Requesting parents");
+        let after = self
+            .next_page(elem.get_handle())
+            .map(|next| next.cursor.as_str());
+        request(elem.get_handle(), after);
     }
 
     fn get_from_lineage<'a>(root_slice: &'a [Ancestor], lineage: &Lineage) -> &'a Ancestor {
@@ -206,52 +1192,405 @@ impl AncestorGraph {
         })
     }
 
+    /// Draw out the chain of arrows from whichever of `a` and `b` is an
+    /// ancestor of the other. Returns false, leaving the graph as it was, if
+    /// neither is.
+    pub fn trace_path(&mut self, a: &Handle, b: &Handle) -> bool {
+        let (Some(&(a, _)), Some(&(b, _))) = (self.lineages.get(a), self.lineages.get(b)) else {
+            return false;
+        };
+        // Paths run from the descendant up through its parents.
+        let Some(path) = path::shortest_path(&self.parents_of, a, b)
+            .or_else(|| path::shortest_path(&self.parents_of, b, a))
+        else {
+            return false;
+        };
+        self.traced = Some(TracedPath {
+            elements: path.iter().copied().collect(),
+            arrows: path.windows(2).map(|pair| (pair[1], pair[0])).collect(),
+        });
+        true
+    }
+
+    /// Pin `handles` as roots, after those pinned already, as long as there
+    /// are colors left for them.
+    pub fn pin(&mut self, handles: impl IntoIterator<Item = Handle>) {
+        for handle in handles {
+            if self.pinned.len() == ROOT_COLORS.len() {
+                break;
+            }
+            if !self.pinned.contains(&handle) {
+                self.pinned.push(handle);
+            }
+        }
+    }
+
+    pub fn unpin(&mut self, handle: &Handle) {
+        self.pinned.retain(|pinned| pinned != handle);
+    }
+
+    pub fn pinned(&self) -> &[Handle] {
+        &self.pinned
+    }
+
+    pub fn color_reach(&self) -> bool {
+        self.color_reach
+    }
+
+    pub fn set_color_reach(&mut self, color_reach: bool) {
+        self.color_reach = color_reach;
+    }
+
+    /// The pinned roots each element flows into, as indices into `pinned`,
+    /// for the elements at least one root reaches.
+    fn reach(&self) -> HashMap<OrderingIndex, Vec<usize>> {
+        let mut reach: HashMap<_, Vec<_>> = HashMap::new();
+        for (root, handle) in self.pinned.iter().enumerate() {
+            let Some(&(index, _)) = self.lineages.get(handle) else {
+                continue;
+            };
+            for reached in path::reachable(&self.parents_of, index) {
+                reach.entry(reached).or_default().push(root);
+            }
+        }
+        reach
+    }
+
+    /// How many elements more than one pinned root reaches.
+    pub fn shared_reach(&self) -> usize {
+        self.reach()
+            .values()
+            .filter(|roots| roots.len() > 1)
+            .count()
+    }
+
+    pub fn is_traced(&self) -> bool {
+        self.traced.is_some()
+    }
+
+    pub fn clear_trace(&mut self) {
+        self.traced = None;
+    }
+
+    /// Arrows off the traced path are faded.
+    fn arrow_color(
+        &self,
+        parent: Option<OrderingIndex>,
+        child: OrderingIndex,
+        color: Color32,
+    ) -> Color32 {
+        match (&self.traced, parent) {
+            (Some(traced), Some(parent)) if traced.arrows.contains(&(parent, child)) => color,
+            (Some(_), _) => color.gamma_multiply(0.2),
+            (None, _) => color,
+        }
+    }
+
+    /// `handle` followed by every ancestor of it in the graph, nearest first.
+    /// Just `handle` if it is not in the graph.
+    pub fn ancestors_of(&self, handle: &Handle) -> Vec<Handle> {
+        let Some((_, lineage)) = self.lineages.get(handle) else {
+            return vec![handle.clone()];
+        };
+        let mut ancestors = vec![];
+        let mut queue = VecDeque::from([Self::get_from_lineage(&self.inner, lineage)]);
+        while let Some(ancestor) = queue.pop_front() {
+            ancestors.push(ancestor.content.get_handle().clone());
+            queue.extend(&ancestor.parents);
+        }
+        ancestors
+    }
+
+    /// Every ancestor of `handle` in the graph more than `depth` generations
+    /// above it.
+    fn ancestors_beyond(&self, handle: &Handle, depth: usize) -> Vec<Handle> {
+        let Some((_, lineage)) = self.lineages.get(handle) else {
+            return vec![];
+        };
+        let mut beyond = vec![];
+        let mut queue = VecDeque::from([(Self::get_from_lineage(&self.inner, lineage), 0)]);
+        while let Some((ancestor, generation)) = queue.pop_front() {
+            if generation > depth {
+                beyond.push(ancestor.content.get_handle().clone());
+            }
+            queue.extend(
+                ancestor
+                    .parents
+                    .iter()
+                    .map(|parent| (parent, generation + 1)),
+            );
+        }
+        beyond
+    }
+
+    pub fn accessibility_shown(&self, accessibility: Accessibility) -> bool {
+        !self.filtered.contains(&accessibility)
+    }
+
+    /// Show or leave out every handle with `accessibility`.
+    pub fn set_accessibility_shown(&mut self, ui: &Ui, accessibility: Accessibility, shown: bool) {
+        let before = self.current_layout(ui);
+        if shown {
+            self.filtered.remove(&accessibility);
+        } else {
+            self.filtered.insert(accessibility);
+        }
+        self.reflow(before);
+    }
+
+    /// Whether `handle` is neither hidden nor filtered out.
+    fn shown(&self, handle: &Handle) -> bool {
+        !self.hidden.contains(handle)
+            && !self.filtered.contains(&handle.accessibility)
+            && !self.pruned.contains(handle)
+    }
+
+    /// Elements that are neither hidden, filtered out nor in a cluster, along with their
+    /// ordering index.
+    fn visible(&self) -> impl Iterator<Item = (usize, &Element)> {
+        self.iter().enumerate().filter(|(_, el)| {
+            let handle = el.get_handle();
+            self.shown(handle) && !self.clustered_away(&self.lineages[handle].1)
+        })
+    }
+
+    /// Whether the Ancestor at `lineage` or any of its descendants on the way
+    /// to the root is folded into a cluster.
+    fn clustered_away(&self, lineage: &Lineage) -> bool {
+        let mut generation = self.inner.as_slice();
+        for (depth, index) in lineage.0.iter().enumerate() {
+            let ancestor = &generation[*index];
+            if let Some(next) = lineage.0.get(depth + 1) {
+                if ancestor.layout.clustered.get(*next) == Some(&true) {
+                    return true;
+                }
+            }
+            generation = ancestor.parents.as_slice();
+        }
+        false
+    }
+
+    /// Every drawn cluster, along with the Ancestor its members are parents
+    /// of and where it is drawn.
+    fn clusters(&self) -> impl Iterator<Item = (&Ancestor, &Cluster, (PlotPoint, f64))> {
+        self.visible().flat_map(move |(_, el)| {
+            let lineage = &self.lineages[el.get_handle()].1;
+            let child = Self::get_from_lineage(&self.inner, lineage);
+            child.clusters.iter().map(move |cluster| {
+                // A cluster takes the place of its first member.
+                let mut member = lineage.0.clone();
+                member.push(cluster.members[0]);
+                (child, cluster, self.lineage_draw_parameters(&member))
+            })
+        })
+    }
+
+    /// The cluster at `index`, counting from the end of the ordering like
+    /// `find_closest` does.
+    fn cluster(&self, index: usize) -> Option<(&Ancestor, &Cluster, (PlotPoint, f64))> {
+        let index = index.checked_sub(self.ordering.len())?;
+        self.clusters().nth(index)
+    }
+
+    /// Draw the parents of `child` that perform `operation` separately again.
+    fn expand(&mut self, ui: &Ui, child: &Handle, operation: Operation) {
+        let Some(&(child_index, _)) = self.lineages.get(child) else {
+            return;
+        };
+        let before = self.current_layout(ui);
+        let Some(ancestor) = self.find(child) else {
+            return;
+        };
+        ancestor.expanded.push(operation);
+        ancestor.regroup(ui, child_index);
+        self.relayout();
+        self.animate_from(before, child_index);
+    }
+
+    /// Where every element is drawn right now, to animate from once the
+    /// layout changes.
+    fn current_layout(&mut self, ui: &Ui) -> Vec<(PlotPoint, f64)> {
+        self.now = ui.input(|i| i.time);
+        (0..self.ordering.len())
+            .map(|index| self.get_draw_parameters(index))
+            .collect()
+    }
+
+    /// Move every element from where it was drawn in `before` to its place in
+    /// the new layout. Elements added since grow out of the one at `source`.
+    fn animate_from(&mut self, before: Vec<(PlotPoint, f64)>, source: OrderingIndex) {
+        // Every change of layout ends here, so the lanes make room for it.
+        self.lane_height = self
+            .lineages
+            .values()
+            .map(|(_, lineage)| self.tree_draw_parameters(&lineage.0).0.y)
+            .fold(0.0, f64::max)
+            + LANE_GAP;
+        self.layout = self.layout_pass();
+        for index in 0..self.ordering.len() {
+            let from = before.get(index).copied().unwrap_or(before[source.0]);
+            let motion = Motion {
+                from,
+                start: self.now,
+            };
+            self.motions.insert(OrderingIndex(index), motion);
+        }
+    }
+
     fn find(&mut self, handle: &Handle) -> Option<&mut Ancestor> {
         let lineage = self.lineages.get(handle)?.clone();
         Some(Self::get_mut_from_lineage(&mut self.inner, &lineage.1))
     }
 
-    fn get_draw_parameters(&self, index: usize) -> (PlotPoint, f64) {
-        const Y_SCALE: f64 = 0.5;
+    /// Where the layout puts every element, by ordering index.
+    fn layout_pass(&self) -> Vec<(PlotPoint, f64)> {
+        self.ordering
+            .iter()
+            .map(|handle| self.lineage_draw_parameters(&self.lineages[handle].1 .0))
+            .collect()
+    }
+
+    /// Where the element at `index` is drawn this frame, part way to its place
+    /// in the layout if the layout changed recently.
+    pub fn get_draw_parameters(&self, index: usize) -> (PlotPoint, f64) {
+        let handle = &self.ordering[index];
+        let (mut target, target_scale) = self.layout[index];
+        // Elements dragged into place keep their size, but not where the
+        // layout puts them.
+        if let Some(&center) = self.fixed.get(handle) {
+            target = center;
+        }
+        let Some(motion) = self.motions.get(&OrderingIndex(index)) else {
+            return (target, target_scale);
+        };
+        let t = ((self.now - motion.start) / ANIMATION_SECONDS).clamp(0.0, 1.0);
+        // Ease out so elements settle gently.
+        let t = 1.0 - (1.0 - t).powi(3);
+        let (from, from_scale) = motion.from;
+        (
+            PlotPoint::new(
+                from.x + (target.x - from.x) * t,
+                from.y + (target.y - from.y) * t,
+            ),
+            from_scale + (target_scale - from_scale) * t,
+        )
+    }
+
+    fn lineage_draw_parameters(&self, lineage: &[usize]) -> (PlotPoint, f64) {
+        let (mut pos, scale) = self.tree_draw_parameters(lineage);
+        if self.merge_shared {
+            pos.x += self.shared_offset(lineage);
+        }
+        if self.lanes {
+            // Each lane is as tall as the tree, so elements keep their place
+            // in it and never land on each other.
+            pos.y += lane(self.object_at(lineage)) as f64 * self.lane_height;
+        }
+        (pos, scale)
+    }
+
+    /// The kind of object of the Ancestor at `lineage`.
+    fn object_at(&self, lineage: &[usize]) -> Object {
+        let mut generation = self.inner.as_slice();
+        let mut object = Object::Thunk;
+        for lineage_index in lineage {
+            let ancestor = &generation[*lineage_index];
+            object = ancestor.content.get_handle().object();
+            generation = ancestor.parents.as_slice();
+        }
+        object
+    }
 
-        let lineage = &self.lineages.get(&self.ordering[index]).unwrap().1 .0;
+    /// How far merging shared parents moves the Ancestor at `lineage`
+    /// sideways. Each shared parent on the way moves to the middle of where
+    /// the tree draws its children, taking its own parents along.
+    fn shared_offset(&self, lineage: &[usize]) -> f64 {
+        let mut offset = 0.0;
+        let mut generation = self.inner.as_slice();
+        for (depth, lineage_index) in lineage.iter().enumerate() {
+            let ancestor = &generation[*lineage_index];
+            let own_index = self.lineages[ancestor.content.get_handle()].0;
+            let children: HashSet<OrderingIndex> = ancestor
+                .children
+                .iter()
+                .map(|(child, _)| *child)
+                .filter(|child| *child != own_index)
+                .collect();
+            if children.len() > 1 {
+                let middle = children
+                    .iter()
+                    .map(|child| {
+                        let lineage = &self.lineages[&self.ordering[child.0]].1;
+                        self.tree_draw_parameters(&lineage.0).0.x
+                    })
+                    .sum::<f64>()
+                    / children.len() as f64;
+                offset += middle - self.tree_draw_parameters(&lineage[..depth]).0.x;
+            }
+            generation = ancestor.parents.as_slice();
+        }
+        offset
+    }
 
+    /// Where the Ancestor at `lineage` is drawn in the tree, with every parent
+    /// above its first child.
+    fn tree_draw_parameters(&self, lineage: &[usize]) -> (PlotPoint, f64) {
         // Set the position to be (0, -pos) so that the first vertical offset puts
         // the main object at (0, 0).
         let mut scale = 1.0;
-        let mut pos = [0.0, -scale * Y_SCALE];
+        let mut pos = [0.0, -self.spacing.rise(scale)];
         let mut current_generation = self.inner.as_slice();
+        // The Ancestor whose parents are the current generation.
+        let mut child: Option<&Ancestor> = None;
         for lineage_index in lineage {
+            // Clusters take up a single place in their generation.
+            let (slot, slots) = match child {
+                Some(child) if child.layout.slots.len() == current_generation.len() => {
+                    (child.layout.slots[*lineage_index], child.layout.len)
+                }
+                _ => (*lineage_index, current_generation.len()),
+            };
             // Scale y for this generation
-            scale /= current_generation.len() as f64;
-            // Increase y (by half relative to the x)
-            pos[1] += scale * Y_SCALE;
+            scale /= slots as f64;
+            // Increase y by the generation's share of its width, and the gap
+            pos[1] += self.spacing.rise(scale);
             // Offset x
             // |   0   |   1   |   2   |
             // |  0  |  1  |  2  |  3  |
             let step_size = scale;
-            let x_step_offset_to_left_edge =
-                *lineage_index as f64 - (current_generation.len() as f64) * 0.5;
+            let x_step_offset_to_left_edge = slot as f64 - (slots as f64) * 0.5;
             let x_step_offset_to_center = x_step_offset_to_left_edge + 0.5;
             pos[0] += step_size * x_step_offset_to_center;
 
-            current_generation = current_generation[*lineage_index].parents.as_slice();
+            let ancestor = &current_generation[*lineage_index];
+            child = Some(ancestor);
+            current_generation = ancestor.parents.as_slice();
         }
 
         (PlotPoint::new(pos[0], pos[1]), scale)
     }
 
     pub fn merge_new_parents(&mut self, ui: &egui::Ui, handle: Handle, incoming_parents: &[Task]) {
-        let (child_index, child_lineage) = self
-            .lineages
-            .get(&handle)
-            .cloned()
-            .expect("the target child for merging new parents must exist");
+        // The handle may have left the tree since its parents were asked for.
+        let Some((child_index, child_lineage)) = self.lineages.get(&handle).cloned() else {
+            return;
+        };
+        self.loading.remove(&handle);
+        let before = self.current_layout(ui);
         for parent in incoming_parents {
             // If the parent already exists, add this handle as a child.
             if let Some(ancestor) = self.find(&parent.handle) {
                 let op = parent.operation;
-                ancestor.add_child(&child_index, op);
+                if ancestor.add_child(&child_index, op) {
+                    let parent_index = self.lineages[&parent.handle].0;
+                    self.parents_of
+                        .entry(child_index)
+                        .or_default()
+                        .push(parent_index);
+                    if let Some(recorded) = parent.recorded {
+                        self.recorded.insert((parent_index, child_index), recorded);
+                    }
+                }
             } else {
                 // Otherwise, add the parent above the child.
                 // Must update the ancestor lineages map, the ancestors ordering
@@ -269,17 +1608,212 @@ impl AncestorGraph {
                     }),
                 );
                 self.ordering.push(parent.handle.clone());
+                self.parents_of
+                    .entry(child_index)
+                    .or_default()
+                    .push(ancestor_index);
+                if let Some(recorded) = parent.recorded {
+                    self.recorded
+                        .insert((ancestor_index, child_index), recorded);
+                }
+                let mut element = Element::with_restriction(
+                    ui,
+                    parent.handle.clone(),
+                    parent.restriction.clone(),
+                );
+                element.set_size_scaling(self.size_scaling);
+                element.set_alias(ui, self.aliases.get(&parent.handle).map(String::as_str));
+                element.set_abbreviation(ui, self.abbreviation);
+                element.set_padding(self.spacing.padding);
                 target_list.push(Ancestor::new(
-                    Element::new(ui, parent.handle.clone()),
+                    element,
                     vec![(child_index, parent.operation)],
                 ));
             }
         }
+        Self::get_mut_from_lineage(&mut self.inner, &child_lineage).regroup(ui, child_index);
+        self.relayout();
+        self.animate_from(before, child_index);
+    }
+
+    /// Mark along the left edge of the plot how far each generation of
+    /// parents is drawn from the target, which is generation 0. A generation
+    /// spreads over several heights once its parents are divided unevenly.
+    fn add_generation_markers(&self, ui: &Ui, transform: &PlotTransform, shapes: &mut Vec<Shape>) {
+        let rows = self.rows(|el| self.lineages[el.get_handle()].1 .0.len() - 1);
+        let rows = rows
+            .into_iter()
+            .map(|(generation, span)| (format!("generation {generation}"), span));
+        Self::add_markers(ui, transform, rows, shapes);
     }
 
-    fn add_arrows(&self, transform: &PlotTransform, shapes: &mut Vec<Shape>) {
+    /// Mark along the left edge of the plot which kind of object each lane
+    /// holds, in the lanes layout.
+    fn add_lane_markers(&self, ui: &Ui, transform: &PlotTransform, shapes: &mut Vec<Shape>) {
+        let rows = self.rows(|el| lane(el.get_handle().object()));
+        let rows = rows
+            .into_iter()
+            .map(|(lane, span)| (format!("{}s", LANES[lane]), span));
+        Self::add_markers(ui, transform, rows, shapes);
+    }
+
+    /// The lowest and highest visible element in each row, with elements put
+    /// into rows by `row`.
+    fn rows(&self, row: impl Fn(&Element) -> usize) -> BTreeMap<usize, (f64, f64)> {
+        let mut rows: BTreeMap<usize, (f64, f64)> = BTreeMap::new();
+        for (index, el) in self.visible() {
+            let y = self.get_draw_parameters(index).0.y;
+            rows.entry(row(el))
+                .and_modify(|(low, high)| {
+                    *low = low.min(y);
+                    *high = high.max(y);
+                })
+                .or_insert((y, y));
+        }
+        rows
+    }
+
+    /// A bracket along the left edge from the low to the high end of each
+    /// row, with the row's name.
+    fn add_markers(
+        ui: &Ui,
+        transform: &PlotTransform,
+        rows: impl Iterator<Item = (String, (f64, f64))>,
+        shapes: &mut Vec<Shape>,
+    ) {
+        let left = transform.frame().left() + 4.0;
+        let stroke = Stroke::new(1.0, ui.visuals().weak_text_color());
+        for (name, (low, high)) in rows {
+            let top = transform.position_from_point(&PlotPoint::new(0.0, high)).y;
+            let bottom = transform.position_from_point(&PlotPoint::new(0.0, low)).y;
+            shapes.push(Shape::line_segment(
+                [Pos2::new(left, top), Pos2::new(left, bottom)],
+                stroke,
+            ));
+            for y in [top, bottom] {
+                shapes.push(Shape::line_segment(
+                    [Pos2::new(left, y), Pos2::new(left + 6.0, y)],
+                    stroke,
+                ));
+            }
+            shapes.push(ui.fonts(|fonts| {
+                Shape::text(
+                    fonts,
+                    Pos2::new(left + 8.0, (top + bottom) / 2.0),
+                    Align2::LEFT_CENTER,
+                    name,
+                    FontId::proportional(11.0),
+                    stroke.color,
+                )
+            }));
+        }
+    }
+
+    /// Dashed lines through the shown members of each group of duplicates.
+    fn add_duplicate_links(&self, ui: &Ui, transform: &PlotTransform, shapes: &mut Vec<Shape>) {
+        let stroke = Stroke::new(1.5, ui.visuals().warn_fg_color);
+        for group in &self.duplicates {
+            let centers: Vec<_> = group
+                .iter()
+                .filter(|handle| self.shown(handle))
+                .filter_map(|handle| self.element_bounds(handle))
+                .map(|bounds| {
+                    let center = bounds.center();
+                    transform.position_from_point(&PlotPoint::new(center.x, center.y))
+                })
+                .collect();
+            if centers.len() > 1 {
+                shapes.extend(Shape::dashed_line(&centers, stroke, 6.0, 4.0));
+            }
+        }
+    }
+
+    /// Finely dashed lines from each shown task to its shown dependees, in
+    /// the color of the operation each dependee performs. Unlike parents,
+    /// dependees do not place elements, so the lines cross the tree freely.
+    fn add_dependee_links(&self, transform: &PlotTransform, shapes: &mut Vec<Shape>) {
+        let center = |handle: &Handle| {
+            let bounds = self.element_bounds(handle).filter(|_| self.shown(handle))?;
+            let center = bounds.center();
+            Some(transform.position_from_point(&PlotPoint::new(center.x, center.y)))
+        };
+        for (task, dependees) in &self.dependees {
+            let Some(from) = center(task) else {
+                continue;
+            };
+            for (dependee, operation) in dependees {
+                if let Some(to) = center(dependee).filter(|to| *to != from) {
+                    let stroke = Stroke::new(1.5, self.palette.operation(*operation));
+                    shapes.extend(Shape::dashed_line(&[from, to], stroke, 3.0, 3.0));
+                }
+            }
+        }
+    }
+
+    fn add_arrows(&self, ui: &Ui, transform: &PlotTransform, shapes: &mut Vec<Shape>) {
+        /// Arrows shorter than this many pixels are too cramped for a label.
+        const MIN_LABEL_PIXELS: f64 = 60.0;
+
+        let edges = self.edges();
+        let midpoints = match self.edge_routing {
+            EdgeRouting::Curved => edges
+                .iter()
+                .map(|edge| self.add_arrow(transform, shapes, edge.origin, edge.target, edge.color))
+                .collect(),
+            EdgeRouting::Orthogonal => self.add_orthogonal_arrows(transform, shapes, &edges),
+            EdgeRouting::Bundled => self.add_bundled_arrows(transform, shapes, &edges),
+        };
+        // Number of "also a parent here" labels at each element.
+        let mut shared_labels: HashMap<OrderingIndex, usize> = HashMap::new();
+        for (edge, midpoint) in edges.iter().zip(midpoints) {
+            // Label the arrow unless zoomed out too far to read it. Edges
+            // reach out a fifth of the element scale.
+            let arrow_pixels =
+                f64::min(edge.origin.1, edge.target.1) * 5.0 * transform.dpos_dvalue_x();
+            if arrow_pixels < MIN_LABEL_PIXELS {
+                continue;
+            }
+            if self.show_edge_labels {
+                shapes.push(ui.fonts(|fonts| {
+                    Shape::text(
+                        fonts,
+                        midpoint,
+                        Align2::LEFT_CENTER,
+                        edge.operation,
+                        FontId::proportional(12.0),
+                        edge.color,
+                    )
+                }));
+            }
+            // A shared parent is only drawn above its first child, so point
+            // it out at the others.
+            if edge.cross && !self.merge_shared {
+                let stacked = shared_labels.entry(edge.target_index).or_default();
+                let anchor = transform.position_from_point(&edge.target.0)
+                    + Vec2::new(4.0, -4.0 - 12.0 * *stacked as f32);
+                *stacked += 1;
+                shapes.push(ui.fonts(|fonts| {
+                    Shape::text(
+                        fonts,
+                        anchor,
+                        Align2::LEFT_BOTTOM,
+                        "also a parent here",
+                        FontId::proportional(11.0),
+                        edge.color,
+                    )
+                }));
+            }
+        }
+    }
+
+    /// Every arrow to draw, from each shown parent and cluster to its children.
+    fn edges(&self) -> Vec<Edge> {
+        let mut edges = vec![];
         // For every handle in this graph,
         for handle in self.ordering.iter() {
+            if !self.shown(handle) {
+                continue;
+            }
             // Obtain its lineage
             let (index, lineage) = self
                 .lineages
@@ -291,6 +1825,11 @@ impl AncestorGraph {
             let o_bbox = container.content.bounds(o_draw_params);
             // Then, for every child of this handle,
             for child in &container.children {
+                // Arrows into hidden elements are hidden with them, as are
+                // arrows recorded after the time being replayed.
+                if !self.shown(&self.ordering[child.0 .0]) || !self.recorded_yet(*index, child.0) {
+                    continue;
+                }
                 // set the origin_point to be the right middle or center bottom of the handle's bounding box
                 // if the origin maps to itself or to another index, respectively
                 let (o_point, direction) = if *index == child.0 {
@@ -298,73 +1837,291 @@ impl AncestorGraph {
                 } else {
                     ([o_bbox.center().x, o_bbox.min()[1]].into(), Arrow::Down)
                 };
-                // and get the target's bounding box.
-                let t_draw_params = self.get_draw_parameters(child.0 .0);
-                let t_bbox = Self::get_from_lineage(
-                    &self.inner,
-                    &self.lineages[&self.ordering[child.0 .0]].1,
-                )
-                .content
-                .bounds(t_draw_params);
-                // Set the target_point to be the center top of the child box.
-                let t_point = PlotPoint::new(t_bbox.center().x, t_bbox.max()[1]);
-                // Draw an arrow from the center bottom of the origin to the center top of the target.
-                Self::add_arrow(
+                edges.push(Edge {
+                    origin: (o_point, o_draw_params.1 / 5.0, direction),
+                    target: self.edge_target(child.0),
+                    target_index: child.0,
+                    generations: (generation(lineage), self.generation_of(child.0)),
+                    operation: child.1,
+                    color: self.arrow_color(Some(*index), child.0, self.palette.operation(child.1)),
+                    cross: child.0 != *index && child.0 != container.children[0].0,
+                });
+            }
+        }
+        // Point each cluster at the Ancestor its members are parents of.
+        for (child, cluster, params) in self.clusters() {
+            let o_bbox = cluster.element.bounds(params);
+            let target_index = self.lineages[child.content.get_handle()].0;
+            edges.push(Edge {
+                origin: (
+                    [o_bbox.center().x, o_bbox.min()[1]].into(),
+                    params.1 / 5.0,
+                    Arrow::Down,
+                ),
+                target: self.edge_target(target_index),
+                target_index,
+                generations: (
+                    self.generation_of(target_index) + 1,
+                    self.generation_of(target_index),
+                ),
+                operation: cluster.operation,
+                color: self.arrow_color(
+                    None,
+                    target_index,
+                    self.palette.operation(cluster.operation),
+                ),
+                cross: false,
+            });
+        }
+        edges
+    }
+
+    /// How many generations above the target the element at `index` is.
+    fn generation_of(&self, index: OrderingIndex) -> usize {
+        generation(&self.lineages[&self.ordering[index.0]].1)
+    }
+
+    /// The center top of the element at `index`, where arrows into it end.
+    fn edge_target(&self, index: OrderingIndex) -> (PlotPoint, f64) {
+        let t_draw_params = self.get_draw_parameters(index.0);
+        let t_bbox = Self::get_from_lineage(&self.inner, &self.lineages[&self.ordering[index.0]].1)
+            .content
+            .bounds(t_draw_params);
+        (
+            PlotPoint::new(t_bbox.center().x, t_bbox.max()[1]),
+            t_draw_params.1 / 5.0,
+        )
+    }
+
+    /// Draws every edge as vertical and horizontal segments. The horizontal
+    /// runs into one element share the gap above it, each on its own track
+    /// when they would overlap. Arrows from an element to itself stay curved.
+    /// Returns the screen position of the middle of each arrow.
+    fn add_orthogonal_arrows(
+        &self,
+        transform: &PlotTransform,
+        shapes: &mut Vec<Shape>,
+        edges: &[Edge],
+    ) -> Vec<Pos2> {
+        let mut midpoints = vec![Pos2::ZERO; edges.len()];
+        let mut by_target: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (index, edge) in edges.iter().enumerate() {
+            if edge.origin.2 == Arrow::Right {
+                midpoints[index] =
+                    self.add_arrow(transform, shapes, edge.origin, edge.target, edge.color);
+            } else {
+                by_target
+                    .entry(edge.target_index.0)
+                    .or_default()
+                    .push(index);
+            }
+        }
+        for members in by_target.values() {
+            let spans: Vec<(f64, f64)> = members
+                .iter()
+                .map(|index| {
+                    let (origin, target) = (edges[*index].origin.0, edges[*index].target.0);
+                    (origin.x.min(target.x), origin.x.max(target.x))
+                })
+                .collect();
+            let tracks = routing::assign_tracks(&spans);
+            let track_count = tracks.iter().max().map_or(1, |max| max + 1);
+            // The gap ends at the lowest origin above the target.
+            let target = edges[members[0]].target.0;
+            let top = members
+                .iter()
+                .map(|index| edges[*index].origin.0.y)
+                .fold(f64::INFINITY, f64::min);
+            for (index, track) in members.iter().zip(tracks) {
+                let fraction = (track + 1) as f64 / (track_count + 1) as f64;
+                let channel = target.y + (top - target.y) * fraction;
+                midpoints[*index] =
+                    self.add_orthogonal_arrow(transform, shapes, &edges[*index], channel);
+            }
+        }
+        midpoints
+    }
+
+    /// Draws the arrows between each two generations as a bundle, their
+    /// curves pulled together between their ends. Arrows from an element to
+    /// itself, and arrows alone between their generations, are drawn as
+    /// usual. Returns the screen position of the middle of each arrow.
+    fn add_bundled_arrows(
+        &self,
+        transform: &PlotTransform,
+        shapes: &mut Vec<Shape>,
+        edges: &[Edge],
+    ) -> Vec<Pos2> {
+        let mut midpoints = vec![Pos2::ZERO; edges.len()];
+        let mut bundles: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for (index, edge) in edges.iter().enumerate() {
+            if edge.origin.2 == Arrow::Right {
+                midpoints[index] =
+                    self.add_arrow(transform, shapes, edge.origin, edge.target, edge.color);
+            } else {
+                bundles.entry(edge.generations).or_default().push(index);
+            }
+        }
+        for members in bundles.values() {
+            if let [index] = members[..] {
+                let edge = &edges[index];
+                midpoints[index] =
+                    self.add_arrow(transform, shapes, edge.origin, edge.target, edge.color);
+                continue;
+            }
+            let ends: Vec<_> = members
+                .iter()
+                .map(|index| (edges[*index].origin.0, edges[*index].target.0))
+                .collect();
+            for (index, controls) in members.iter().zip(routing::bundle_controls(&ends)) {
+                let edge = &edges[*index];
+                let arrow_scale = f64::min(edge.origin.1, edge.target.1);
+                midpoints[*index] = self.add_curve(
                     transform,
                     shapes,
-                    (o_point, o_draw_params.1 / 5.0, direction),
-                    (t_point, t_draw_params.1 / 5.0),
-                    child.1.get_color(),
-                )
+                    [edge.origin.0, controls.0, controls.1, edge.target.0],
+                    arrow_scale,
+                    edge.color,
+                );
             }
         }
+        midpoints
+    }
+
+    /// Draws an arrow that leaves the origin downwards, runs horizontally at
+    /// `channel` and enters the target from above. Returns the screen position
+    /// of the middle of the horizontal run.
+    fn add_orthogonal_arrow(
+        &self,
+        transform: &PlotTransform,
+        shapes: &mut Vec<Shape>,
+        edge: &Edge,
+        channel: f64,
+    ) -> Pos2 {
+        let (origin, target) = (edge.origin.0, edge.target.0);
+        let arrow_scale = f64::min(edge.origin.1, edge.target.1);
+        let stroke = self.arrow_stroke(transform, arrow_scale, edge.color);
+        let points = [
+            origin,
+            PlotPoint::new(origin.x, channel),
+            PlotPoint::new(target.x, channel),
+            target,
+        ];
+        shapes.push(Shape::line(
+            points
+                .iter()
+                .map(|point| transform.position_from_point(point))
+                .collect(),
+            stroke,
+        ));
+        self.add_arrow_head(transform, shapes, &points, arrow_scale, stroke);
+        transform.position_from_point(&PlotPoint::new((origin.x + target.x) / 2.0, channel))
+    }
+
+    fn arrow_stroke(&self, transform: &PlotTransform, arrow_scale: f64, color: Color32) -> Stroke {
+        Stroke::new(
+            self.arrow_style
+                .stroke_width(arrow_scale, transform.dpos_dvalue_x()),
+            color,
+        )
+    }
+
+    /// Draws the head of the arrow along `points` at its target, or at its
+    /// origin when arrows point the way data flows.
+    fn add_arrow_head(
+        &self,
+        transform: &PlotTransform,
+        shapes: &mut Vec<Shape>,
+        points: &[PlotPoint],
+        arrow_scale: f64,
+        stroke: Stroke,
+    ) {
+        let (from, tip) = routing::head_ends(points, self.arrow_style.data_flow);
+        let head = routing::arrow_head(from, tip, self.arrow_style.head_size(arrow_scale));
+        shapes.push(Shape::line(
+            head.iter()
+                .map(|point| transform.position_from_point(point))
+                .collect(),
+            stroke,
+        ));
     }
 
     /// Draws an arrow from the origin to the target using a cubic bezier curve
     /// that weighs the control points according to the scales at each end.
+    /// Returns the screen position of the middle of the curve.
     fn add_arrow(
+        &self,
         transform: &PlotTransform,
         shapes: &mut Vec<Shape>,
         origin: (PlotPoint, f64, Arrow),
         target: (PlotPoint, f64),
         color: Color32,
-    ) {
+    ) -> Pos2 {
         let arrow_scale = f64::min(origin.1, target.1);
-        let tip_scale = arrow_scale / 40.0;
-        let stroke = Stroke::new(
-            (arrow_scale * transform.dpos_dvalue_x() / 100.0) as f32,
-            color,
+        let (reach, target_reach) = (
+            origin.1 * self.arrow_style.curvature,
+            target.1 * self.arrow_style.curvature,
         );
         let origin_control = match origin.2 {
-            Arrow::Down => PlotPoint::new(origin.0.x, origin.0.y - origin.1),
-            Arrow::Right => PlotPoint::new(origin.0.x + origin.1, origin.0.y),
+            Arrow::Down => PlotPoint::new(origin.0.x, origin.0.y - reach),
+            Arrow::Right => PlotPoint::new(origin.0.x + reach, origin.0.y),
         };
-        let target_control = PlotPoint::new(target.0.x, target.0.y + target.1);
-        let head_start = PlotPoint::new(target.0.x - tip_scale, target.0.y + tip_scale);
-        let head_end = PlotPoint::new(target.0.x + tip_scale, target.0.y + tip_scale);
-        let origin = transform.position_from_point(&origin.0);
-        let origin_control = transform.position_from_point(&origin_control);
-        let target_control = transform.position_from_point(&target_control);
-        let target = transform.position_from_point(&target.0);
+        let target_control = PlotPoint::new(target.0.x, target.0.y + target_reach);
+        self.add_curve(
+            transform,
+            shapes,
+            [origin.0, origin_control, target_control, target.0],
+            arrow_scale,
+            color,
+        )
+    }
+
+    /// Draws an arrow along the cubic bezier curve through `points`, from
+    /// the first to the last, or back when arrows point the way data flows.
+    /// Returns the screen position of its middle.
+    fn add_curve(
+        &self,
+        transform: &PlotTransform,
+        shapes: &mut Vec<Shape>,
+        points: [PlotPoint; 4],
+        arrow_scale: f64,
+        color: Color32,
+    ) -> Pos2 {
+        let stroke = self.arrow_stroke(transform, arrow_scale, color);
         let arrow_body = CubicBezierShape::from_points_stroke(
-            [origin, origin_control, target_control, target],
+            points.map(|point| transform.position_from_point(&point)),
             false,
             Color32::TRANSPARENT,
             stroke,
         );
-        let arrow_head = Shape::line(
-            vec![
-                transform.position_from_point(&head_start),
-                target,
-                transform.position_from_point(&head_end),
-            ],
-            stroke,
-        );
+        let midpoint = arrow_body.sample(0.5);
         shapes.push(arrow_body.into());
-        shapes.push(arrow_head);
+        self.add_arrow_head(transform, shapes, &points, arrow_scale, stroke);
+        midpoint
     }
 }
 
+/// Laid over elements off a traced path to fade them into the background.
+/// Which of the `LANES` holds objects of `object`.
+fn lane(object: Object) -> usize {
+    LANES.iter().position(|lane| *lane == object).unwrap()
+}
+
+/// How many generations above the target the Ancestor at `lineage` is.
+fn generation(lineage: &Lineage) -> usize {
+    lineage.0.len() - 1
+}
+
+/// How gray to draw a handle whose parents arrived `age` seconds ago, from
+/// 0 while fresh up to 1 once they are `GRAYEST_AFTER` old.
+fn staleness(age: f64) -> f32 {
+    ((age - FRESH_FOR) / (GRAYEST_AFTER - FRESH_FOR)).clamp(0.0, 1.0) as f32
+}
+
+fn veil(ui: &Ui) -> Color32 {
+    ui.visuals().extreme_bg_color.gamma_multiply(0.75)
+}
+
 impl std::fmt::Debug for Ancestor {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_fmt(format_args!(
@@ -388,17 +2145,120 @@ impl Ancestor {
             content,
             parents: vec![],
             children,
+            more: None,
+            clusters: vec![],
+            expanded: vec![],
+            layout: ParentLayout::default(),
+        }
+    }
+
+    fn set_size_scaling(&mut self, scaled: bool) {
+        self.content.set_size_scaling(scaled);
+        for parent in &mut self.parents {
+            parent.set_size_scaling(scaled);
+        }
+    }
+
+    fn set_aliases(&mut self, ui: &Ui, aliases: &HashMap<Handle, String>) {
+        let alias = aliases.get(self.content.get_handle()).map(String::as_str);
+        self.content.set_alias(ui, alias);
+        for parent in &mut self.parents {
+            parent.set_aliases(ui, aliases);
+        }
+    }
+
+    fn set_abbreviation(&mut self, ui: &Ui, keep: usize) {
+        self.content.set_abbreviation(ui, keep);
+        for parent in &mut self.parents {
+            parent.set_abbreviation(ui, keep);
+        }
+    }
+
+    fn set_padding(&mut self, padding: f64) {
+        self.content.set_padding(padding);
+        for cluster in &mut self.clusters {
+            cluster.element.set_padding(padding);
+        }
+        for parent in &mut self.parents {
+            parent.set_padding(padding);
+        }
+    }
+
+    /// Group parents that perform the same operation on this Ancestor into
+    /// clusters once there are enough of them, and place them in a row.
+    /// `own_index` is this Ancestor's place in the ordering.
+    fn regroup(&mut self, ui: &Ui, own_index: OrderingIndex) {
+        /// At least this many parents with the same operation are clustered.
+        const CLUSTER_SIZE: usize = 8;
+
+        let mut by_operation: Vec<(Operation, Vec<usize>)> = vec![];
+        for (index, parent) in self.parents.iter().enumerate() {
+            let Some(&(_, operation)) = parent.children.iter().find(|(i, _)| *i == own_index)
+            else {
+                continue;
+            };
+            match by_operation.iter_mut().find(|(op, _)| *op == operation) {
+                Some((_, members)) => members.push(index),
+                None => by_operation.push((operation, vec![index])),
+            }
+        }
+        let padding = self.content.padding();
+        self.clusters = by_operation
+            .into_iter()
+            .filter(|(operation, members)| {
+                members.len() >= CLUSTER_SIZE && !self.expanded.contains(operation)
+            })
+            .map(|(operation, members)| {
+                let mut element = Element::cluster(
+                    ui,
+                    self.content.get_handle().clone(),
+                    operation,
+                    members.len(),
+                );
+                element.set_padding(padding);
+                Cluster {
+                    operation,
+                    element,
+                    members,
+                }
+            })
+            .collect();
+    }
+
+    /// Give each parent its place in the row above. Clusters take one place,
+    /// and parents that are not `occupied` share the place before them so
+    /// their row closes up.
+    fn lay_out(&mut self, occupied: &[bool]) {
+        let mut layout = ParentLayout::default();
+        for (index, occupied) in occupied.iter().enumerate() {
+            let cluster = self.clusters.iter().find(|c| c.members.contains(&index));
+            match cluster {
+                Some(cluster) if cluster.members[0] != index => {
+                    layout.slots.push(layout.slots[cluster.members[0]]);
+                }
+                None if !occupied => layout.slots.push(layout.len.saturating_sub(1)),
+                _ => {
+                    layout.slots.push(layout.len);
+                    layout.len += 1;
+                }
+            }
+            layout.clustered.push(cluster.is_some());
         }
+        // A row with nothing left still divides by its length.
+        layout.len = layout.len.max(1);
+        self.layout = layout;
     }
 
-    fn add_child(&mut self, incoming_child: &OrderingIndex, operation: Operation) {
+    /// Returns whether the child was added.
+    fn add_child(&mut self, incoming_child: &OrderingIndex, operation: Operation) -> bool {
         // Linear scan, performance irrelevant for small lists of children.
-        if self
+        let added = self
             .children
             .iter()
-            .all(|(index, op)| (index != incoming_child) && (*op != operation))
-        {
+            .all(|(index, op)| (index != incoming_child) && (*op != operation));
+        if added {
             self.children.push((*incoming_child, operation));
         }
+        added
     }
 }
@@ -0,0 +1,189 @@
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use egui::{
+    plot::{items::PlotItem, PlotTransform},
+    pos2, vec2, Rect, Ui,
+};
+
+use super::{Applied, GraphsContainer};
+use crate::handle::{Handle, Operation, Task};
+
+/// Parents each synthetic handle gets, until there are enough handles.
+const BRANCHING: usize = 3;
+/// Frames measured, each drawing and hit-testing the whole tree once.
+const FRAMES: usize = 60;
+/// Pointer positions hit-tested per frame, spread over the plot.
+const POINTERS: usize = 16;
+/// Size in points of the plot the tree is drawn into.
+const FRAME_SIZE: [f32; 2] = [1600.0, 900.0];
+
+/// Time spent in one part of drawing a frame, over every frame measured.
+#[derive(Default)]
+struct Timing {
+    total: Duration,
+    worst: Duration,
+}
+
+impl Timing {
+    fn time<T>(&mut self, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let value = f();
+        let elapsed = start.elapsed();
+        self.total += elapsed;
+        self.worst = self.worst.max(elapsed);
+        value
+    }
+}
+
+/// How long the ancestry tree took to draw and hit-test.
+struct Report {
+    nodes: usize,
+    build: Duration,
+    shapes: Timing,
+    find_closest: Timing,
+    draw_parameters: Timing,
+    /// Pointers that landed near an element, so that the hit-testing is not
+    /// optimized away.
+    hits: usize,
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        writeln!(
+            f,
+            "{} handles, built in {:.1} ms, {FRAMES} frames",
+            self.nodes,
+            ms(self.build)
+        )?;
+        writeln!(
+            f,
+            "{} of {} pointers near an element",
+            self.hits,
+            FRAMES * POINTERS
+        )?;
+        writeln!(f, "{:<22} {:>12} {:>12}", "", "mean ms", "worst ms")?;
+        for (name, timing) in [
+            ("shapes()", &self.shapes),
+            ("find_closest()", &self.find_closest),
+            ("get_draw_parameters()", &self.draw_parameters),
+        ] {
+            writeln!(
+                f,
+                "{name:<22} {:>12.3} {:>12.3}",
+                ms(timing.total) / FRAMES as f64,
+                ms(timing.worst)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Responses building a tree of `nodes` handles, each with `BRANCHING`
+/// parents, breadth first from the target.
+fn synthetic(nodes: usize) -> (Handle, Vec<Applied>) {
+    let handle = |id: usize| {
+        Handle::from_hex(&format!("{id:x}-0-4-100000000000000")).expect("synthetic handle")
+    };
+    let mut journal = vec![];
+    let mut next = 1;
+    let mut child = 0;
+    while next < nodes {
+        let parents = (next..nodes.min(next + BRANCHING))
+            .map(|id| Task {
+                handle: handle(id),
+                operation: [Operation::Apply, Operation::Eval, Operation::Fill][id % 3],
+                restriction: None,
+                recorded: None,
+            })
+            .collect::<Vec<_>>();
+        next += parents.len();
+        journal.push(Applied::Parents {
+            handle: handle(child),
+            parents,
+            next: None,
+        });
+        child += 1;
+    }
+    (handle(0), journal)
+}
+
+/// Build a synthetic ancestry tree of `nodes` handles and time drawing it,
+/// hit-testing it and placing its elements, the way a frame of the viewer
+/// does. Returns the report to print.
+pub fn benchmark(nodes: usize) -> String {
+    let mut report = String::new();
+    let _ = egui::Context::default().run(egui::RawInput::default(), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            report = measure(ui, nodes.max(1)).to_string();
+        });
+    });
+    report
+}
+
+fn measure(ui: &mut Ui, nodes: usize) -> Report {
+    let (target, journal) = synthetic(nodes);
+    let start = Instant::now();
+    let mut graphs = GraphsContainer::new(ui, target, Operation::Eval);
+    graphs.replay(ui, journal);
+    let build = start.elapsed();
+
+    let graph = &graphs.ancestry;
+    let frame = Rect::from_min_size(pos2(0.0, 0.0), vec2(FRAME_SIZE[0], FRAME_SIZE[1]));
+    let transform = PlotTransform::new(frame, graph.bounds(), false, false);
+    let count = graph.subtrees().len();
+    let mut report = Report {
+        nodes: count,
+        build,
+        shapes: Timing::default(),
+        find_closest: Timing::default(),
+        draw_parameters: Timing::default(),
+        hits: 0,
+    };
+    // Summed so that placing the elements is not optimized away.
+    let mut placed = 0.0;
+    for _ in 0..FRAMES {
+        let mut shapes = vec![];
+        report
+            .shapes
+            .time(|| graph.shapes(ui, &transform, &mut shapes));
+        report.hits += report.find_closest.time(|| {
+            (0..POINTERS)
+                .filter(|i| {
+                    let along = (*i as f32 + 0.5) / POINTERS as f32;
+                    let pointer = frame.lerp_inside(vec2(along, 1.0 - along));
+                    graph.find_closest(pointer, &transform).is_some()
+                })
+                .count()
+        });
+        placed += report.draw_parameters.time(|| {
+            (0..count)
+                .map(|index| graph.get_draw_parameters(index).1)
+                .sum::<f64>()
+        });
+    }
+    log::debug!("Benchmark placed elements at scales summing to {placed}");
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_trees_of_the_size_asked_for() {
+        let (_, journal) = synthetic(10);
+        let parents: usize = journal
+            .iter()
+            .map(|applied| match applied {
+                Applied::Parents { parents, .. } => parents.len(),
+                _ => 0,
+            })
+            .sum();
+        assert_eq!(parents, 9);
+        assert!(benchmark(10).starts_with("10 handles"));
+    }
+}
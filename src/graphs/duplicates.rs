@@ -0,0 +1,139 @@
+use std::collections::{HashMap, HashSet};
+
+use egui::{Context, RichText, ScrollArea};
+
+use crate::handle::{Accessibility, Handle};
+
+/// Local handles that turn out to be the same object once canonicalized,
+/// which hints at work the runtime did more than once.
+#[derive(Clone, Default)]
+pub(super) struct Duplicates {
+    /// The canonical handle of each local handle the server was asked about.
+    canonical: HashMap<Handle, Handle>,
+    /// Local handles asked about, answered or not.
+    asked: HashSet<Handle>,
+    /// Local handles to ask about next frame.
+    queued: Vec<Handle>,
+    groups: Vec<Vec<Handle>>,
+    open: bool,
+}
+
+impl Duplicates {
+    /// Ask about every handle in `local` not asked about before, and show
+    /// the report.
+    pub fn find<'a>(&mut self, local: impl IntoIterator<Item = &'a Handle>) {
+        for handle in local {
+            if self.asked.insert(handle.clone()) {
+                self.queued.push(handle.clone());
+            }
+        }
+        self.open = true;
+    }
+
+    /// Local handles to ask the canonical handle of.
+    pub fn take_queued(&mut self) -> Vec<Handle> {
+        std::mem::take(&mut self.queued)
+    }
+
+    pub fn set_canonical(&mut self, local: Handle, canonical: Handle) {
+        self.canonical.insert(local, canonical);
+        self.groups = groups(&self.canonical);
+    }
+
+    /// Local handles sharing a canonical handle, in groups of two or more.
+    pub fn groups(&self) -> &[Vec<Handle>] {
+        &self.groups
+    }
+
+    /// The report window, while open. Returns a handle to go to, if one was
+    /// clicked.
+    pub fn show(&mut self, ctx: &Context) -> Option<Handle> {
+        let mut target = None;
+        let (asked, answered) = (self.asked.len(), self.canonical.len());
+        let groups = &self.groups;
+        egui::Window::new("Duplicates")
+            .open(&mut self.open)
+            .default_size([420.0, 320.0])
+            .show(ctx, |ui| {
+                if answered < asked {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(format!("{answered} of {asked} local handles canonicalized"));
+                    });
+                }
+                if groups.is_empty() {
+                    ui.label("No two local handles share a canonical hash so far.");
+                    return;
+                }
+                ui.label(format!(
+                    "{} canonical objects are behind more than one local handle. \
+                     Each group is linked in the ancestry tree.",
+                    groups.len()
+                ));
+                ui.separator();
+                ScrollArea::vertical().show(ui, |ui| {
+                    for group in groups {
+                        ui.horizontal_wrapped(|ui| {
+                            ui.label(format!("{}:", group.len()));
+                            for handle in group {
+                                if ui
+                                    .link(RichText::new(handle.to_hex()).monospace())
+                                    .on_hover_text(handle.to_string())
+                                    .clicked()
+                                {
+                                    target = Some(handle.clone());
+                                }
+                            }
+                        });
+                    }
+                });
+            });
+        target
+    }
+}
+
+/// Local handles grouped by the object they canonicalize to, whatever
+/// their accessibility, leaving out objects behind only one of them. Groups
+/// and their members are in hex order.
+fn groups(canonical: &HashMap<Handle, Handle>) -> Vec<Vec<Handle>> {
+    let mut by_object: HashMap<Handle, Vec<Handle>> = HashMap::new();
+    for (local, canonical) in canonical {
+        let mut object = canonical.clone();
+        object.accessibility = Accessibility::Strict;
+        by_object.entry(object).or_default().push(local.clone());
+    }
+    let mut groups: Vec<_> = by_object
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .map(|mut group| {
+            group.sort_by_cached_key(Handle::to_hex);
+            group
+        })
+        .collect();
+    groups.sort_by_cached_key(|group| group[0].to_hex());
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handle::Object;
+
+    #[test]
+    fn groups_by_canonical_object() {
+        let local = |id: &str| Handle::from_hex(&format!("{id}-0-4-100000000000000")).unwrap();
+        let canonical = |byte: u8, accessibility| {
+            Handle::canonical(&[byte; 32], 4, Object::Blob, accessibility).unwrap()
+        };
+        let answers = HashMap::from([
+            (local("a1"), canonical(1, Accessibility::Strict)),
+            (local("a2"), canonical(1, Accessibility::Lazy)),
+            (local("b1"), canonical(2, Accessibility::Strict)),
+            (local("a0"), canonical(1, Accessibility::Strict)),
+        ]);
+        assert_eq!(
+            groups(&answers),
+            [vec![local("a0"), local("a1"), local("a2")]]
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use egui::{Context, Frame, Key, Pos2};
+
+use crate::handle::Handle;
+
+/// What can be done with one element, opened by right-clicking it or, on a
+/// touch screen, holding a finger on it.
+#[derive(Clone, Debug)]
+pub(super) struct ElementMenu {
+    handle: Handle,
+    pos: Pos2,
+    /// Whether the handle is drawn in the ancestry tree, so can be pinned.
+    pinnable: bool,
+    /// Whether the handle is in the ancestry tree, so its parents can be
+    /// watched.
+    watchable: bool,
+    /// Whether the handle was dragged to a place of its own.
+    fixed: bool,
+}
+
+/// What the element menu asked for.
+pub(super) enum MenuAction {
+    Select,
+    Pin,
+    Watch,
+    /// Let the handle go back to where the layout puts it.
+    Release,
+}
+
+impl ElementMenu {
+    pub fn new(handle: Handle, pos: Pos2, pinnable: bool, watchable: bool, fixed: bool) -> Self {
+        Self {
+            handle,
+            pos,
+            pinnable,
+            watchable,
+            fixed,
+        }
+    }
+
+    /// Show the menu. Returns what was chosen, and whether the menu stays
+    /// open, which it does until something is chosen or the pointer is
+    /// pressed elsewhere.
+    pub fn show(&self, ctx: &Context) -> (Option<(Handle, MenuAction)>, bool) {
+        let mut action = None;
+        let mut copied = false;
+        let shown = egui::Area::new("element_menu")
+            .order(egui::Order::Foreground)
+            .fixed_pos(self.pos)
+            .constrain(true)
+            .show(ctx, |ui| {
+                Frame::menu(ui.style()).show(ui, |ui| {
+                    ui.label(self.handle.to_hex());
+                    ui.separator();
+                    if ui.button("Select").clicked() {
+                        action = Some(MenuAction::Select);
+                    }
+                    if ui.button("Copy handle").clicked() {
+                        ui.output_mut(|o| o.copied_text = self.handle.to_hex());
+                        copied = true;
+                    }
+                    if self.pinnable && ui.button("Pin").clicked() {
+                        action = Some(MenuAction::Pin);
+                    }
+                    if self.watchable && ui.button("👁 Watch").clicked() {
+                        action = Some(MenuAction::Watch);
+                    }
+                    if self.fixed
+                        && ui
+                            .button("Release position")
+                            .on_hover_text("Go back to where the layout puts it")
+                            .clicked()
+                    {
+                        action = Some(MenuAction::Release);
+                    }
+                });
+            });
+        let pressed_elsewhere = ctx.input(|i| {
+            i.key_pressed(Key::Escape)
+                || i.pointer.any_pressed()
+                    && i.pointer
+                        .interact_pos()
+                        .map_or(true, |pos| !shown.response.rect.contains(pos))
+        });
+        let open = action.is_none() && !copied && !pressed_elsewhere;
+        (action.map(|action| (self.handle.clone(), action)), open)
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::VecDeque;
+
+use egui::{RichText, ScrollArea, Ui};
+
+use crate::{console::format_time, handle::Handle};
+
+/// Fetches kept in the list before the oldest are dropped.
+const RECENT: usize = 100;
+
+/// One page of parents the server answered with.
+#[derive(Clone)]
+struct Fetch {
+    handle: Handle,
+    parents: Vec<Handle>,
+    /// Seconds since the app started.
+    time: f64,
+    /// Whether more pages of parents were left to fetch.
+    more: bool,
+}
+
+/// The parents fetched lately, newest first, so that what was fetched can
+/// be matched with how the ancestry tree grew.
+#[derive(Clone, Default)]
+pub(super) struct Fetches {
+    recent: VecDeque<Fetch>,
+}
+
+impl Fetches {
+    pub fn record(&mut self, handle: Handle, parents: Vec<Handle>, time: f64, more: bool) {
+        self.recent.push_front(Fetch {
+            handle,
+            parents,
+            time,
+            more,
+        });
+        self.recent.truncate(RECENT);
+    }
+
+    /// The list, newest first. Returns the handle and parents of the fetch
+    /// clicked, if one was.
+    pub fn ui(&self, ui: &mut Ui) -> Option<(Handle, Vec<Handle>)> {
+        ui.heading("Fetches");
+        if self.recent.is_empty() {
+            ui.weak("No parents fetched yet.");
+            return None;
+        }
+        let mut clicked = None;
+        ScrollArea::vertical().show(ui, |ui| {
+            for fetch in &self.recent {
+                let more = if fetch.more { "+" } else { "" };
+                let text = RichText::new(format!(
+                    "{} {} → {}{more} parents",
+                    format_time(fetch.time),
+                    fetch.handle.to_hex(),
+                    fetch.parents.len(),
+                ))
+                .monospace();
+                if ui
+                    .selectable_label(false, text)
+                    .on_hover_text(format!(
+                        "{}\nClick to select the {} parents fetched",
+                        fetch.handle,
+                        fetch.parents.len()
+                    ))
+                    .clicked()
+                {
+                    clicked = Some((fetch.handle.clone(), fetch.parents.clone()));
+                }
+            }
+        });
+        clicked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_newest_fetches() {
+        let handle = |id: usize| Handle::from_hex(&format!("{id:x}-0-4-100000000000000")).unwrap();
+        let mut fetches = Fetches::default();
+        for id in 1..=RECENT + 5 {
+            fetches.record(handle(id), vec![handle(0)], id as f64, false);
+        }
+        assert_eq!(fetches.recent.len(), RECENT);
+        assert_eq!(fetches.recent[0].handle, handle(RECENT + 5));
+        assert_eq!(fetches.recent[RECENT - 1].handle, handle(6));
+    }
+}
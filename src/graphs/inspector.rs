@@ -0,0 +1,716 @@
+use std::collections::{HashMap, HashSet};
+
+use egui::{CollapsingHeader, Context, Grid, ScrollArea, TextEdit, TextureHandle, Ui};
+
+use super::preview::{self, MediaType};
+use super::wasm::Module;
+use crate::handle::{
+    Accessibility, Handle, LiteralView, Object, Operation, TagEntries, ThunkEntries,
+};
+use crate::workspace::{self, INSPECTOR};
+
+/// A window showing the details of a single handle.
+#[derive(Clone, Default)]
+pub(super) struct Inspector {
+    /// How literal blobs are displayed.
+    literal_view: LiteralView,
+    /// Entries of the Tags fetched so far.
+    tags: HashMap<Handle, TagEntries>,
+    /// Entries of the Trees fetched so far.
+    trees: HashMap<Handle, Vec<Handle>>,
+    /// Contents of the Blobs fetched so far.
+    blobs: HashMap<Handle, Vec<u8>>,
+    /// PNG Blobs decoded for display, or why they could not be.
+    thumbnails: HashMap<Handle, Result<TextureHandle, String>>,
+    /// What Thunks gave when forced, or `None` if the server has no result.
+    outcomes: HashMap<(Handle, Operation), Option<Handle>>,
+    /// Thunks to describe in words.
+    explained: HashSet<Handle>,
+    /// Handles the server was asked to load: `None` until it answers, then
+    /// whether their data is resident.
+    materialized: HashMap<Handle, Option<bool>>,
+    /// The Blob or Tree whose usages were last found, how many Thunks use
+    /// it, and how many trees are still awaited to tell.
+    usages: Option<(Handle, usize, usize)>,
+}
+
+/// The Thunks that refer to a handle through their trees.
+#[derive(Debug, Default, PartialEq)]
+pub(super) struct Usages {
+    pub(super) users: Vec<Handle>,
+    /// Trees in the way that have not been fetched, which may hide more.
+    pub(super) unfetched: Vec<Handle>,
+}
+
+/// Something the inspector needs done outside of it.
+pub(super) enum InspectorAction {
+    /// Request the entries of a Tag.
+    FetchTag(Handle),
+    /// Request the entries of a Tree.
+    FetchTree(Handle),
+    /// Request the contents of a Blob.
+    FetchBlob(Handle),
+    /// Inspect and select another handle.
+    Navigate(Handle),
+    /// Select every Thunk in the ancestry tree that refers to a Blob or
+    /// Tree.
+    FindUsages(Handle),
+    /// Request the tree of a Thunk and what it gives when applied and
+    /// evaluated, to describe it in words.
+    Explain(Handle),
+    /// Copy curl commands that query a handle and its ancestors.
+    CopyCommands(Handle),
+    /// Ask the server to load the data of a lazy or shallow handle.
+    Materialize(Handle),
+    /// Poll the parents and result of a handle on their own.
+    Watch(Handle),
+    /// The note on the inspected handle was edited.
+    Annotate,
+    /// The alias of the inspected handle was edited.
+    Alias,
+}
+
+impl Inspector {
+    pub fn set_tag(&mut self, handle: Handle, tag: TagEntries) {
+        self.tags.insert(handle, tag);
+    }
+
+    pub fn set_tree(&mut self, handle: Handle, entries: Vec<Handle>) {
+        self.trees.insert(handle, entries);
+    }
+
+    pub fn set_blob(&mut self, handle: Handle, contents: Vec<u8>) {
+        self.thumbnails.remove(&handle);
+        self.blobs.insert(handle, contents);
+    }
+
+    pub fn set_outcome(&mut self, handle: Handle, operation: Operation, result: Option<Handle>) {
+        self.outcomes.insert((handle, operation), result);
+    }
+
+    /// Wait for the server to load `handle`.
+    pub fn materializing(&mut self, handle: Handle) {
+        self.materialized.insert(handle, None);
+    }
+
+    pub fn set_materialized(&mut self, handle: Handle, resident: bool) {
+        self.materialized.insert(handle, Some(resident));
+    }
+
+    pub fn set_usages(&mut self, handle: Handle, users: usize, unfetched: usize) {
+        self.usages = Some((handle, users, unfetched));
+    }
+
+    /// Which of `thunks` refer to `target` through their trees, or through
+    /// trees in those, as far as the trees fetched so far tell.
+    pub fn usages(&self, target: &Handle, thunks: &[Handle]) -> Usages {
+        let mut usages = Usages::default();
+        let mut unfetched = HashSet::new();
+        for thunk in thunks {
+            let Some(tree) = thunk.thunk_tree() else {
+                continue;
+            };
+            let mut visited = HashSet::new();
+            // A Thunk of a Tree uses that Tree too.
+            let mut used = tree.content == target.content;
+            let mut stack = vec![tree];
+            while let Some(tree) = stack.pop() {
+                if !visited.insert(tree.clone()) {
+                    continue;
+                }
+                let Some(entries) = self.trees.get(&tree) else {
+                    if unfetched.insert(tree.clone()) {
+                        usages.unfetched.push(tree);
+                    }
+                    continue;
+                };
+                // A reference by any accessibility is a use.
+                used |= entries.iter().any(|entry| entry.content == target.content);
+                stack.extend(
+                    entries
+                        .iter()
+                        .filter(|entry| {
+                            entry.object() == Object::Tree && entry.literal_bytes().is_none()
+                        })
+                        .cloned(),
+                );
+            }
+            if used {
+                usages.users.push(thunk.clone());
+            }
+        }
+        usages
+    }
+
+    /// Describe `thunk` in words once what it needs has arrived.
+    pub fn explain(&mut self, thunk: Handle) {
+        self.explained.insert(thunk);
+    }
+
+    /// Show `handle`, along with `note` and `alias` to edit. It can be
+    /// watched if `watchable`.
+    pub fn show(
+        &mut self,
+        ctx: &Context,
+        handle: &Handle,
+        watchable: bool,
+        note: &mut String,
+        alias: &mut String,
+    ) -> Option<InspectorAction> {
+        let window = egui::Window::new(INSPECTOR).resizable(true);
+        let shown = workspace::place(ctx, INSPECTOR, window).show(ctx, |ui| {
+            let header = ui
+                .horizontal(|ui| {
+                    ui.label("Handle: ");
+                    ui.monospace(handle.to_hex());
+                    if ui.small_button("Copy").clicked() {
+                        ui.output_mut(|o| o.copied_text = handle.to_hex());
+                    }
+                    if ui
+                        .small_button("Copy as curl")
+                        .on_hover_text("Copy the queries for this handle and its ancestors")
+                        .clicked()
+                    {
+                        return Some(InspectorAction::CopyCommands(handle.clone()));
+                    }
+                    if !watchable {
+                        return None;
+                    }
+                    let hint = "Keep fetching its parents and result, and flash when they change";
+                    ui.small_button("👁 Watch")
+                        .on_hover_text(hint)
+                        .clicked()
+                        .then(|| InspectorAction::Watch(handle.clone()))
+                })
+                .inner;
+            if header.is_some() {
+                return header;
+            }
+            ui.label(handle.to_string());
+            let aliased = ui
+                .horizontal(|ui| {
+                    ui.label("Alias: ");
+                    TextEdit::singleline(alias)
+                        .hint_text("Drawn instead of the handle in the graphs")
+                        .desired_width(f32::INFINITY)
+                        .show(ui)
+                        .response
+                        .changed()
+                })
+                .inner;
+            if let Some(action) = self.residency_ui(ui, handle) {
+                return Some(action);
+            }
+            if matches!(handle.object(), Object::Blob | Object::Tree) {
+                if let Some(action) = self.usages_ui(ui, handle) {
+                    return Some(action);
+                }
+            }
+            if handle.literal_bytes().is_some() {
+                ui.separator();
+                self.literal_ui(ui, handle);
+            }
+            let action = if handle.object() == Object::Tag {
+                ui.separator();
+                self.tag_ui(ui, handle)
+            } else if let Some(tree) = handle.thunk_tree() {
+                ui.separator();
+                self.thunk_ui(ui, handle, tree)
+            } else if handle.object() == Object::Blob && handle.literal_bytes().is_none() {
+                ui.separator();
+                self.blob_ui(ui, handle)
+            } else {
+                None
+            };
+            ui.separator();
+            ui.label("Note: ");
+            let annotated = TextEdit::multiline(note)
+                .hint_text("Saved with this handle")
+                .desired_rows(2)
+                .desired_width(f32::INFINITY)
+                .show(ui)
+                .response
+                .changed();
+            action
+                .or_else(|| annotated.then_some(InspectorAction::Annotate))
+                .or_else(|| aliased.then_some(InspectorAction::Alias))
+        });
+        workspace::track(ctx, INSPECTOR, &shown);
+        shown.and_then(|response| response.inner.flatten())
+    }
+
+    /// A button to find the Thunks that use `handle`, and how many do.
+    fn usages_ui(&self, ui: &mut Ui, handle: &Handle) -> Option<InspectorAction> {
+        ui.horizontal(|ui| {
+            let clicked = ui
+                .button("Find usages")
+                .on_hover_text("Select every Thunk in the ancestry tree whose tree refers to this, fetching the trees needed to tell")
+                .clicked();
+            match &self.usages {
+                Some((found, users, unfetched)) if found == handle => {
+                    let thunks = if *users == 1 { "Thunk" } else { "Thunks" };
+                    ui.label(format!("Used by {users} {thunks}"));
+                    if *unfetched > 0 {
+                        ui.spinner();
+                        ui.weak(format!("{unfetched} trees to fetch"));
+                    }
+                }
+                _ => {}
+            }
+            clicked.then(|| InspectorAction::FindUsages(handle.clone()))
+        })
+        .inner
+    }
+
+    /// A warning that the data of a lazy or shallow `handle` may not be on
+    /// the server yet, with a way to load it.
+    fn residency_ui(&self, ui: &mut Ui, handle: &Handle) -> Option<InspectorAction> {
+        // Literals carry their data with them.
+        if handle.literal_bytes().is_some() {
+            return None;
+        }
+        let warning = match handle.accessibility {
+            Accessibility::Strict => return None,
+            Accessibility::Shallow => "Shallow: what this refers to may not be resident.",
+            Accessibility::Lazy => "Lazy: its data may not be resident.",
+        };
+        let mut action = None;
+        ui.horizontal_wrapped(|ui| {
+            ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {warning}"));
+            let force_load = |ui: &mut Ui, text: &str| {
+                ui.small_button(text)
+                    .on_hover_text("Ask the server to load the data before fetching from it")
+                    .clicked()
+            };
+            let asked = match self.materialized.get(handle) {
+                None => force_load(ui, "Force load"),
+                Some(None) => {
+                    ui.spinner();
+                    false
+                }
+                Some(Some(true)) => {
+                    ui.label("Loaded.");
+                    false
+                }
+                Some(Some(false)) => {
+                    ui.colored_label(ui.visuals().error_fg_color, "Not loaded.");
+                    force_load(ui, "Retry")
+                }
+            };
+            if asked {
+                action = Some(InspectorAction::Materialize(handle.clone()));
+            }
+        });
+        action
+    }
+
+    fn literal_ui(&mut self, ui: &mut Ui, handle: &Handle) {
+        ui.horizontal(|ui| {
+            ui.label("Read as: ");
+            for view in LiteralView::ALL {
+                ui.selectable_value(&mut self.literal_view, view, view.to_string());
+            }
+        });
+        match handle.literal_as(self.literal_view) {
+            Ok(value) => ui.monospace(value),
+            Err(e) => ui.colored_label(ui.visuals().error_fg_color, format!("{e:#}")),
+        };
+    }
+
+    fn tag_ui(&self, ui: &mut Ui, handle: &Handle) -> Option<InspectorAction> {
+        ui.strong("Tag");
+        let Some(tag) = self.tags.get(handle) else {
+            return ui
+                .button("Fetch tag entries")
+                .clicked()
+                .then(|| InspectorAction::FetchTag(handle.clone()));
+        };
+        let mut action = None;
+        Grid::new("tag_entries").num_columns(2).show(ui, |ui| {
+            ui.label("Tagged: ");
+            if ui
+                .link(tag.tagged.to_hex())
+                .on_hover_text(tag.tagged.to_string())
+                .clicked()
+            {
+                action = Some(InspectorAction::Navigate(tag.tagged.clone()));
+            }
+            ui.end_row();
+            ui.label("Authority: ");
+            ui.monospace(tag.authority.to_hex())
+                .on_hover_text(tag.authority.to_string());
+            ui.end_row();
+            ui.label("Label: ");
+            // Labels are usually short strings stored as literals.
+            let label = tag
+                .label
+                .literal_as(LiteralView::Utf8)
+                .unwrap_or_else(|_| tag.label.to_hex());
+            ui.monospace(label).on_hover_text(tag.label.to_string());
+            ui.end_row();
+        });
+        action
+    }
+
+    /// The contents of the stored Blob `handle`, shown as what they appear
+    /// to hold.
+    fn blob_ui(&mut self, ui: &mut Ui, handle: &Handle) -> Option<InspectorAction> {
+        let Some(contents) = self.blobs.get(handle) else {
+            ui.strong("Blob");
+            return ui
+                .button("Fetch blob contents")
+                .clicked()
+                .then(|| InspectorAction::FetchBlob(handle.clone()));
+        };
+        let media_type = preview::sniff(contents);
+        ui.horizontal(|ui| {
+            ui.strong("Blob");
+            ui.label(format!("{media_type}, {} bytes", contents.len()));
+        });
+        let error = |ui: &mut Ui, e: String| ui.colored_label(ui.visuals().error_fg_color, e);
+        match media_type {
+            MediaType::Text => {
+                ScrollArea::vertical()
+                    .id_source("blob_text")
+                    .max_height(300.0)
+                    .show(ui, |ui| ui.monospace(String::from_utf8_lossy(contents)));
+            }
+            MediaType::Wasm => match Module::parse(contents) {
+                Ok(module) => module_ui(ui, &module),
+                Err(e) => {
+                    error(ui, format!("{e:#}"));
+                }
+            },
+            MediaType::Elf => match preview::elf_summary(contents) {
+                Ok(summary) => {
+                    ui.label(summary);
+                }
+                Err(e) => {
+                    error(ui, format!("{e:#}"));
+                }
+            },
+            MediaType::Png => {
+                let thumbnail = self.thumbnails.entry(handle.clone()).or_insert_with(|| {
+                    preview::decode_png(contents)
+                        .map(|image| {
+                            ui.ctx()
+                                .load_texture(handle.to_hex(), image, Default::default())
+                        })
+                        .map_err(|e| format!("{e:#}"))
+                });
+                match thumbnail {
+                    Ok(texture) => {
+                        let size = texture.size_vec2();
+                        let scale = (256.0 / size.max_elem()).min(1.0);
+                        ui.image(&*texture, size * scale)
+                            .on_hover_text(format!("{} × {} pixels", size.x, size.y));
+                    }
+                    Err(e) => {
+                        error(ui, e.clone());
+                    }
+                }
+            }
+            MediaType::Binary => {}
+        }
+        let shown = contents.len().min(preview::HEX_LIMIT);
+        let title = if shown < contents.len() {
+            format!("Hex (first {shown} bytes)")
+        } else {
+            "Hex".to_string()
+        };
+        CollapsingHeader::new(title)
+            .id_source("blob_hex")
+            .default_open(matches!(media_type, MediaType::Elf | MediaType::Binary))
+            .show(ui, |ui| {
+                ScrollArea::vertical()
+                    .id_source("blob_hex_rows")
+                    .max_height(300.0)
+                    .show(ui, |ui| ui.monospace(preview::hex_dump(&contents[..shown])));
+            });
+        None
+    }
+
+    /// The functions the WebAssembly `procedure` exports, once its contents
+    /// are fetched. Returns whether fetching them was asked for.
+    fn procedure_ui(&self, ui: &mut Ui, procedure: &Handle) -> bool {
+        let Some(contents) = self.blobs.get(procedure) else {
+            return procedure.object() == Object::Blob
+                && procedure.literal_bytes().is_none()
+                && ui
+                    .small_button("Exports")
+                    .on_hover_text("Fetch the procedure and list what it exports")
+                    .clicked();
+        };
+        match Module::parse(contents) {
+            Ok(module) => {
+                let exported: Vec<_> = module.exported_functions().collect();
+                ui.monospace(format!("exports {}", exported.join(", ")));
+            }
+            Err(e) => {
+                ui.colored_label(ui.visuals().error_fg_color, format!("{e:#}"));
+            }
+        }
+        false
+    }
+
+    /// What the Thunk `handle`, whose tree is `tree`, will do when forced.
+    fn thunk_ui(&self, ui: &mut Ui, handle: &Handle, tree: Handle) -> Option<InspectorAction> {
+        let explained = self.explained.contains(handle);
+        let explain = ui
+            .horizontal(|ui| {
+                ui.strong("Thunk");
+                !explained
+                    && ui
+                        .small_button("Explain")
+                        .on_hover_text(
+                            "Fetch what the thunk applies and what it gives, and describe it",
+                        )
+                        .clicked()
+            })
+            .inner;
+        if explain {
+            return Some(InspectorAction::Explain(handle.clone()));
+        }
+        let Some(entries) = self.trees.get(&tree) else {
+            if explained {
+                ui.spinner();
+                return None;
+            }
+            return ui
+                .button("Fetch thunk tree")
+                .clicked()
+                .then_some(InspectorAction::FetchTree(tree));
+        };
+        let thunk = match ThunkEntries::from_tree(entries) {
+            Ok(thunk) => thunk,
+            Err(e) => {
+                ui.colored_label(ui.visuals().error_fg_color, format!("{e:#}"));
+                return None;
+            }
+        };
+        let mut action = None;
+        let mut link = |ui: &mut Ui, handle: &Handle| {
+            if ui
+                .link(handle.to_hex())
+                .on_hover_text(handle.to_string())
+                .clicked()
+            {
+                action = Some(InspectorAction::Navigate(handle.clone()));
+            }
+        };
+        let mut fetch_procedure = false;
+        Grid::new("thunk_entries").num_columns(2).show(ui, |ui| {
+            ui.label("Procedure: ");
+            ui.horizontal(|ui| {
+                link(ui, &thunk.procedure);
+                fetch_procedure = self.procedure_ui(ui, &thunk.procedure);
+            });
+            ui.end_row();
+            ui.label("Limits: ");
+            ui.horizontal(|ui| {
+                link(ui, &thunk.limits);
+                // Small limits are stored as literal numbers.
+                if let Ok(limit) = thunk.limits.literal_as(LiteralView::U64) {
+                    ui.monospace(format!("({limit})"));
+                }
+            });
+            ui.end_row();
+            for (index, argument) in thunk.arguments.iter().enumerate() {
+                ui.label(format!("Argument {index}: "));
+                link(ui, argument);
+                ui.end_row();
+            }
+        });
+        if thunk.arguments.is_empty() {
+            ui.label("No arguments.");
+        }
+        if fetch_procedure {
+            action = Some(InspectorAction::FetchBlob(thunk.procedure.clone()));
+        }
+        if explained {
+            ui.separator();
+            let outcome = |operation| self.outcomes.get(&(handle.clone(), operation));
+            ui.label(narrate(
+                &thunk,
+                outcome(Operation::Apply),
+                outcome(Operation::Eval),
+            ));
+        }
+        action
+    }
+}
+
+/// The sections of a WebAssembly module, and what it imports and exports.
+fn module_ui(ui: &mut Ui, module: &Module) {
+    Grid::new("wasm_sections").num_columns(2).show(ui, |ui| {
+        for section in &module.sections {
+            ui.label(&section.name);
+            ui.monospace(format!("{} bytes", section.size));
+            ui.end_row();
+        }
+    });
+    CollapsingHeader::new(format!("Exports ({})", module.exports.len()))
+        .id_source("wasm_exports")
+        .default_open(true)
+        .show(ui, |ui| {
+            Grid::new("wasm_exports_grid")
+                .num_columns(3)
+                .show(ui, |ui| {
+                    for export in &module.exports {
+                        ui.monospace(&export.name);
+                        ui.label(export.kind.to_string());
+                        ui.monospace(export.signature.as_deref().unwrap_or_default());
+                        ui.end_row();
+                    }
+                });
+        });
+    CollapsingHeader::new(format!("Imports ({})", module.imports.len()))
+        .id_source("wasm_imports")
+        .show(ui, |ui| {
+            Grid::new("wasm_imports_grid")
+                .num_columns(3)
+                .show(ui, |ui| {
+                    for import in &module.imports {
+                        ui.monospace(format!("{}.{}", import.module, import.name));
+                        ui.label(import.kind.to_string());
+                        ui.monospace(import.signature.as_deref().unwrap_or_default());
+                        ui.end_row();
+                    }
+                });
+        });
+}
+
+/// What a Thunk applies, and what it gave when `applied` and `evaluated`:
+/// `None` while still asking, and `Some(None)` if the server has no result.
+fn narrate(
+    thunk: &ThunkEntries,
+    applied: Option<&Option<Handle>>,
+    evaluated: Option<&Option<Handle>>,
+) -> String {
+    let mut text = format!(
+        "This thunk applies procedure {} to {}",
+        thunk.procedure.to_hex(),
+        arguments(&thunk.arguments)
+    );
+    if let Ok(limit) = thunk.limits.literal_as(LiteralView::U64) {
+        text += &format!(", with limits {limit}");
+    }
+    text.push('.');
+    for (doing, outcome) in [("applying", applied), ("evaluating", evaluated)] {
+        text += &match outcome {
+            None => format!(" Still asking what {doing} it gives."),
+            Some(None) => format!(" The server has no result of {doing} it yet."),
+            Some(Some(result)) => format!(
+                " {}{} it produced {}, {}.",
+                doing[..1].to_uppercase(),
+                &doing[1..],
+                result.to_hex(),
+                describe(result)
+            ),
+        };
+    }
+    text
+}
+
+/// How many arguments there are, and of which kinds, as in "3 arguments (2
+/// Blobs and a Tree)".
+fn arguments(arguments: &[Handle]) -> String {
+    let kinds: Vec<_> = Object::ALL
+        .into_iter()
+        .filter_map(
+            |object| match arguments.iter().filter(|a| a.object() == object).count() {
+                0 => None,
+                1 => Some(format!("a {object}")),
+                n => Some(format!("{n} {object}s")),
+            },
+        )
+        .collect();
+    let kinds = match kinds.split_last() {
+        None => return "no arguments".to_string(),
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {last}", rest.join(", ")),
+    };
+    match arguments.len() {
+        1 => format!("1 argument ({kinds})"),
+        n => format!("{n} arguments ({kinds})"),
+    }
+}
+
+/// The kind and size of the object `handle` refers to, as in "a Blob of 8
+/// bytes".
+fn describe(handle: &Handle) -> String {
+    let object = handle.object();
+    let unit = match (object, handle.size) {
+        (Object::Blob, 1) => "byte",
+        (Object::Blob, _) => "bytes",
+        (_, 1) => "entry",
+        _ => "entries",
+    };
+    format!("a {object} of {} {unit}", handle.size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handle::Accessibility;
+
+    #[test]
+    fn narrates_thunk() {
+        let blob = |bytes: &[u8]| Handle::literal(bytes, Accessibility::Strict).unwrap();
+        let tree = Handle::canonical(&[7; 32], 3, Object::Tree, Accessibility::Strict).unwrap();
+        let thunk = ThunkEntries {
+            limits: blob(&1000u64.to_le_bytes()),
+            procedure: blob(b"add"),
+            arguments: vec![blob(b"1"), tree.clone(), blob(b"2")],
+        };
+        let result = blob(b"3");
+        assert_eq!(
+            narrate(&thunk, Some(&None), Some(&Some(result.clone()))),
+            format!(
+                "This thunk applies procedure {} to 3 arguments (2 Blobs and a Tree), with limits 1000. \
+                 The server has no result of applying it yet. \
+                 Evaluating it produced {}, a Blob of 1 byte.",
+                thunk.procedure.to_hex(),
+                result.to_hex()
+            )
+        );
+        assert_eq!(arguments(&[]), "no arguments");
+        assert_eq!(arguments(&[tree]), "1 argument (a Tree)");
+    }
+
+    #[test]
+    fn finds_thunks_using_a_handle() {
+        let canonical = |byte: u8, object| {
+            Handle::canonical(&[byte; 32], 2, object, Accessibility::Strict).unwrap()
+        };
+        let (blob, nested, other) = (
+            canonical(1, Object::Blob),
+            canonical(2, Object::Tree),
+            canonical(3, Object::Blob),
+        );
+        let (user, bystander, unknown) = (
+            canonical(4, Object::Thunk),
+            canonical(5, Object::Thunk),
+            canonical(6, Object::Thunk),
+        );
+        let mut inspector = Inspector::default();
+        // The user refers to the blob inside a nested tree, by another
+        // accessibility.
+        let shallow = Handle {
+            accessibility: Accessibility::Shallow,
+            ..blob.clone()
+        };
+        inspector.set_tree(
+            user.thunk_tree().unwrap(),
+            vec![other.clone(), nested.clone()],
+        );
+        inspector.set_tree(nested, vec![shallow]);
+        inspector.set_tree(bystander.thunk_tree().unwrap(), vec![other]);
+        let usages = inspector.usages(
+            &blob,
+            &[user.clone(), bystander, unknown.clone(), blob.clone()],
+        );
+        assert_eq!(usages.users, [user]);
+        assert_eq!(usages.unfetched, [unknown.thunk_tree().unwrap()]);
+    }
+}
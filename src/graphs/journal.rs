@@ -0,0 +1,193 @@
+use crate::handle::{Handle, Operation, Restriction, Task, TaskTime};
+use crate::http::NextPage;
+
+/// A response that was applied to the graphs. Applying the same responses
+/// again in order rebuilds the graphs, which is how an exploration survives
+/// a restart.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub(crate) enum Applied {
+    Parents {
+        handle: Handle,
+        parents: Vec<Task>,
+        next: Option<NextPage>,
+    },
+    Child {
+        index: usize,
+        child: Handle,
+        restriction: Option<Restriction>,
+    },
+    Dependees {
+        index: usize,
+        dependees: Vec<Task>,
+        next: Option<NextPage>,
+    },
+    TaskTimes(Vec<TaskTime>),
+    /// The progress graph started over from `handle` performing
+    /// `operation`, so that the responses after it go into the right tree.
+    ProgressRoot {
+        handle: Handle,
+        operation: Operation,
+    },
+}
+
+/// Where the parents of a handle continue once the parents another session
+/// loaded for it are merged in: `ours` if `expanded` here, or `theirs`.
+/// Whichever session loaded further wins, and one that loaded them all wins
+/// outright.
+pub(super) fn reconcile_next(
+    ours: Option<NextPage>,
+    theirs: Option<NextPage>,
+    expanded: bool,
+) -> Option<NextPage> {
+    if !expanded {
+        return theirs;
+    }
+    match (ours, theirs) {
+        (Some(ours), Some(theirs)) if theirs.remaining < ours.remaining => Some(theirs),
+        (Some(ours), Some(_)) => Some(ours),
+        _ => None,
+    }
+}
+
+impl Applied {
+    /// Whether this went into the progress graph, which starts over when the
+    /// operation changes.
+    pub(super) fn is_progress(&self) -> bool {
+        matches!(
+            self,
+            Applied::Child { .. } | Applied::Dependees { .. } | Applied::ProgressRoot { .. }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::GraphsContainer;
+    use super::*;
+    use crate::http::RequestKind;
+
+    fn handle(id: usize) -> Handle {
+        Handle::from_hex(&format!("{id:x}-0-4-100000000000000")).unwrap()
+    }
+
+    fn task(id: usize, operation: Operation) -> Task {
+        Task {
+            handle: handle(id),
+            operation,
+            restriction: None,
+            recorded: None,
+        }
+    }
+
+    #[test]
+    fn replaying_rebuilds_the_graphs() {
+        let _ = egui::Context::default().run(egui::RawInput::default(), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                let mut explored = GraphsContainer::new(ui, handle(1), Operation::Eval);
+                let more = NextPage {
+                    cursor: "c".to_string(),
+                    remaining: 3,
+                };
+                let parents = vec![task(2, Operation::Apply), task(3, Operation::Fill)];
+                explored.set_parents(ui, handle(1), parents, Some(more));
+                explored.set_parents(ui, handle(2), vec![task(4, Operation::Eval)], None);
+                explored.set_child(ui, 0, handle(5), None);
+                explored.merge_dependees(ui, 0, vec![task(6, Operation::Eval)], None);
+
+                let mut restored = GraphsContainer::new(ui, handle(1), Operation::Eval);
+                restored.replay(ui, explored.journal().to_vec());
+                assert_eq!(restored.ancestry.subtrees(), explored.ancestry.subtrees());
+                assert_eq!(restored.ancestry.subtrees().len(), 4);
+                assert_eq!(
+                    restored
+                        .ancestry
+                        .next_page(&handle(1))
+                        .map(|next| next.remaining),
+                    Some(3)
+                );
+                assert_eq!(restored.progress.tasks(), explored.progress.tasks());
+                assert_eq!(restored.journal().len(), explored.journal().len());
+            });
+        });
+    }
+
+    #[test]
+    fn drops_answers_for_a_replaced_tree() {
+        let _ = egui::Context::default().run(egui::RawInput::default(), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                let mut graphs = GraphsContainer::new(ui, handle(1), Operation::Eval);
+                let subtrees = graphs.ancestry.subtrees().len();
+                graphs.merge_dependees(ui, 7, vec![task(6, Operation::Eval)], None);
+                graphs.set_child(ui, 7, handle(5), None);
+                assert_eq!(graphs.ancestry.subtrees().len(), subtrees);
+                assert!(graphs.progress.task_handle(7).is_none());
+            });
+        });
+    }
+
+    #[test]
+    fn tells_answers_for_replaced_graphs() {
+        let _ = egui::Context::default().run(egui::RawInput::default(), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                let mut graphs = GraphsContainer::new(ui, handle(1), Operation::Eval);
+                let parents = RequestKind::Parents {
+                    generation: graphs.generation(),
+                    handle: handle(1),
+                    after: None,
+                    operation: None,
+                };
+                let child = RequestKind::Child {
+                    generation: graphs.progress.generation(),
+                    index: 0,
+                    handle: handle(1),
+                    operation: Operation::Eval,
+                };
+                let dependees = RequestKind::Dependees {
+                    generation: graphs.progress.generation(),
+                    index: 0,
+                    handle: handle(1),
+                    operation: Operation::Eval,
+                    after: None,
+                };
+                assert!(graphs.asked_for(&child) && graphs.asked_for(&dependees));
+                graphs.set_operation(ui, Operation::Apply, handle(1));
+                assert!(!graphs.asked_for(&child) && !graphs.asked_for(&dependees));
+                // Restarting the progress tree leaves the ancestry tree be.
+                assert!(graphs.asked_for(&parents));
+                let replaced = GraphsContainer::new(ui, handle(1), Operation::Eval);
+                assert!(!replaced.asked_for(&parents));
+            });
+        });
+    }
+
+    #[test]
+    fn drops_parents_of_handles_outside_the_tree() {
+        let _ = egui::Context::default().run(egui::RawInput::default(), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                let mut graphs = GraphsContainer::new(ui, handle(1), Operation::Eval);
+                graphs.set_parents(ui, handle(9), vec![task(2, Operation::Apply)], None);
+                graphs
+                    .ancestry
+                    .merge_new_parents(ui, handle(9), &[task(3, Operation::Apply)]);
+                assert!(!graphs.ancestry.contains(&handle(2)));
+                assert!(!graphs.ancestry.contains(&handle(3)));
+                assert!(graphs.journal().is_empty());
+            });
+        });
+    }
+
+    #[test]
+    fn keeps_the_page_loaded_furthest() {
+        let page = |remaining: u64| {
+            Some(NextPage {
+                cursor: remaining.to_string(),
+                remaining,
+            })
+        };
+        assert_eq!(reconcile_next(None, page(5), false), page(5));
+        assert_eq!(reconcile_next(page(9), page(5), true), page(5));
+        assert_eq!(reconcile_next(page(5), page(9), true), page(5));
+        assert_eq!(reconcile_next(page(5), None, true), None);
+        assert_eq!(reconcile_next(None, page(5), true), None);
+    }
+}
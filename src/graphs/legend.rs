@@ -0,0 +1,166 @@
+//! A legend drawn on the plot itself, so that images exported from it
+//! explain their own colors and scale.
+
+use egui::{plot::PlotTransform, Align2, FontId, Pos2, Rect, Shape, Stroke, Ui, Vec2};
+
+use crate::{handle::Operation, theme::Palette};
+
+/// Width in points an element needs for its text to be legible.
+const LEGIBLE_WIDTH: f32 = 40.0;
+/// Generations listed before the rest are summed up in one row.
+const SCALE_ROWS: usize = 6;
+/// Longest bar in the scale, in points. Wider elements are cut short.
+const BAR_WIDTH: f32 = 100.0;
+const ROW_HEIGHT: f32 = 16.0;
+const PADDING: f32 = 8.0;
+const WIDTH: f32 = 230.0;
+
+/// How many generations, counting from the target, are drawn wide enough
+/// to read.
+fn legible(widths: &[f32]) -> usize {
+    widths
+        .iter()
+        .take_while(|width| **width >= LEGIBLE_WIDTH)
+        .count()
+}
+
+/// Draw the legend in the bottom left corner of the plot: the arrow color of
+/// each operation, and how wide each generation's elements are at the
+/// current zoom. `widths` holds the width, in plot units, of the widest
+/// element of each generation, the target's first.
+pub(super) fn add_shapes(
+    ui: &Ui,
+    transform: &PlotTransform,
+    palette: &Palette,
+    widths: &[f64],
+    shapes: &mut Vec<Shape>,
+) {
+    let points_per_unit = transform.dpos_dvalue_x().abs() as f32;
+    let widths: Vec<f32> = widths
+        .iter()
+        .map(|width| *width as f32 * points_per_unit)
+        .collect();
+    let operations = [Operation::Apply, Operation::Eval, Operation::Fill];
+    let listed = widths.len().min(SCALE_ROWS);
+    let summed = widths.len() > listed;
+    // The operations, the scale's heading, its rows and the summary.
+    let rows = operations.len() + 1 + listed + usize::from(summed) + 1;
+    let frame = transform.frame();
+    let rect = Rect::from_min_size(
+        Pos2::new(
+            frame.left() + PADDING,
+            frame.bottom() - PADDING - rows as f32 * ROW_HEIGHT - 2.0 * PADDING,
+        ),
+        Vec2::new(WIDTH, rows as f32 * ROW_HEIGHT + 2.0 * PADDING),
+    );
+    let visuals = ui.visuals();
+    shapes.push(Shape::rect_filled(
+        rect,
+        4.0,
+        visuals.extreme_bg_color.gamma_multiply(0.9),
+    ));
+    shapes.push(Shape::rect_stroke(
+        rect,
+        4.0,
+        visuals.widgets.noninteractive.bg_stroke,
+    ));
+
+    let mut row = 0;
+    let mut next_row = || {
+        let top = rect.top() + PADDING + row as f32 * ROW_HEIGHT;
+        row += 1;
+        Rect::from_min_size(
+            Pos2::new(rect.left() + PADDING, top),
+            Vec2::new(WIDTH - 2.0 * PADDING, ROW_HEIGHT),
+        )
+    };
+    let text = |shapes: &mut Vec<Shape>, pos: Pos2, text: String, strong: bool| {
+        let color = if strong {
+            visuals.strong_text_color()
+        } else {
+            visuals.weak_text_color()
+        };
+        shapes.push(ui.fonts(|fonts| {
+            Shape::text(
+                fonts,
+                pos,
+                Align2::LEFT_CENTER,
+                text,
+                FontId::proportional(12.0),
+                color,
+            )
+        }));
+    };
+
+    for operation in operations {
+        let row = next_row();
+        let y = row.center().y;
+        shapes.push(Shape::line_segment(
+            [Pos2::new(row.left(), y), Pos2::new(row.left() + 24.0, y)],
+            Stroke::new(2.5, palette.operation(operation)),
+        ));
+        text(
+            shapes,
+            Pos2::new(row.left() + 32.0, y),
+            operation.to_string(),
+            true,
+        );
+    }
+
+    let legible = legible(&widths);
+    let heading = next_row();
+    text(
+        shapes,
+        heading.left_center(),
+        "Element width by generation".to_string(),
+        true,
+    );
+    for (generation, width) in widths.iter().take(listed).enumerate() {
+        let row = next_row();
+        let bar = Rect::from_min_size(
+            Pos2::new(row.left(), row.center().y - 3.0),
+            Vec2::new(width.clamp(1.0, BAR_WIDTH), 6.0),
+        );
+        let readable = generation < legible;
+        let color = if readable {
+            visuals.widgets.active.fg_stroke.color
+        } else {
+            visuals.weak_text_color()
+        };
+        shapes.push(Shape::rect_filled(bar, 1.0, color));
+        text(
+            shapes,
+            Pos2::new(row.left() + BAR_WIDTH + 8.0, row.center().y),
+            format!("{generation}: {width:.0} pt"),
+            readable,
+        );
+    }
+    if summed {
+        let row = next_row();
+        text(
+            shapes,
+            row.left_center(),
+            format!("… {} more generations", widths.len() - listed),
+            false,
+        );
+    }
+    let summary = next_row();
+    let summary_text = match legible {
+        0 => "Zoom in to read elements".to_string(),
+        n if n == widths.len() => "Every generation is legible".to_string(),
+        n => format!("Generations 0 to {} are legible", n - 1),
+    };
+    text(shapes, summary.left_center(), summary_text, false);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_legible_generations() {
+        assert_eq!(legible(&[300.0, 90.0, 30.0, 45.0]), 2);
+        assert_eq!(legible(&[20.0, 90.0]), 0);
+        assert_eq!(legible(&[]), 0);
+    }
+}
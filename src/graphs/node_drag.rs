@@ -0,0 +1,54 @@
+use egui::{
+    plot::{PlotPoint, PlotUi},
+    Pos2,
+};
+
+use crate::handle::Handle;
+
+/// Dragging less than this many pixels is a click rather than a move.
+const MIN_DRAG_PIXELS: f32 = 4.0;
+
+/// An element of the ancestry tree dragged with alt held, to place it where
+/// it stays whatever the layout. The plot must not pan while alt is held.
+#[derive(Clone, Debug, Default)]
+pub(super) struct NodeDrag {
+    /// The handle grabbed, where the press began, and how far its center
+    /// is from the pointer.
+    grabbed: Option<(Handle, Pos2, [f64; 2])>,
+}
+
+impl NodeDrag {
+    /// Follow the drag. `hovered` is the handle under the pointer and the
+    /// center of its element. Returns the handle dragged and where its center
+    /// is to be, once it has moved far enough to not be a click.
+    pub(super) fn update(
+        &mut self,
+        plot_ui: &PlotUi,
+        hovered: Option<(Handle, PlotPoint)>,
+    ) -> Option<(Handle, PlotPoint)> {
+        let (pointer, pressed, down, alt) = plot_ui.ctx().input(|i| {
+            (
+                i.pointer.hover_pos(),
+                i.pointer.primary_pressed(),
+                i.pointer.primary_down(),
+                i.modifiers.alt,
+            )
+        });
+        let pointer = pointer?;
+        let at = plot_ui.plot_from_screen(pointer);
+        if pressed && alt && plot_ui.plot_hovered() {
+            self.grabbed = hovered
+                .map(|(handle, center)| (handle, pointer, [center.x - at.x, center.y - at.y]));
+        }
+        if !down {
+            self.grabbed = None;
+        }
+        let (handle, origin, offset) = self.grabbed.as_ref()?;
+        (origin.distance(pointer) >= MIN_DRAG_PIXELS).then(|| {
+            (
+                handle.clone(),
+                PlotPoint::new(at.x + offset[0], at.y + offset[1]),
+            )
+        })
+    }
+}
@@ -0,0 +1,74 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    hash::Hash,
+};
+
+/// The shortest chain of steps from `from` to `to`, where `next` lists where
+/// each node can step to. Both ends are included. `None` if `to` cannot be
+/// reached.
+pub(super) fn shortest_path<T: Copy + Eq + Hash>(
+    next: &HashMap<T, Vec<T>>,
+    from: T,
+    to: T,
+) -> Option<Vec<T>> {
+    // Where each reached node was reached from.
+    let mut previous = HashMap::new();
+    let mut reached = HashSet::from([from]);
+    let mut queue = VecDeque::from([from]);
+    while let Some(node) = queue.pop_front() {
+        if node == to {
+            let mut path = vec![to];
+            while let Some(&before) = previous.get(path.last().unwrap()) {
+                path.push(before);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for &step in next.get(&node).into_iter().flatten() {
+            if reached.insert(step) {
+                previous.insert(step, node);
+                queue.push_back(step);
+            }
+        }
+    }
+    None
+}
+
+/// Every node that can be reached from `from`, `from` included, where `next`
+/// lists where each node can step to.
+pub(super) fn reachable<T: Copy + Eq + Hash>(next: &HashMap<T, Vec<T>>, from: T) -> HashSet<T> {
+    let mut reached = HashSet::from([from]);
+    let mut queue = VecDeque::from([from]);
+    while let Some(node) = queue.pop_front() {
+        for &step in next.get(&node).into_iter().flatten() {
+            if reached.insert(step) {
+                queue.push_back(step);
+            }
+        }
+    }
+    reached
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_shortest_path() {
+        // 0 <- 1 <- 2 <- 3, with a shortcut 0 <- 3.
+        let parents = HashMap::from([(0, vec![1, 3]), (1, vec![2]), (2, vec![3])]);
+        assert_eq!(shortest_path(&parents, 0, 3), Some(vec![0, 3]));
+        assert_eq!(shortest_path(&parents, 1, 3), Some(vec![1, 2, 3]));
+        assert_eq!(shortest_path(&parents, 2, 2), Some(vec![2]));
+        assert_eq!(shortest_path(&parents, 3, 0), None);
+    }
+
+    #[test]
+    fn finds_reachable_nodes() {
+        // 0 <- 1 <- 2, and 3 <- 2.
+        let parents = HashMap::from([(0, vec![1]), (1, vec![2]), (3, vec![2])]);
+        assert_eq!(reachable(&parents, 0), HashSet::from([0, 1, 2]));
+        assert_eq!(reachable(&parents, 3), HashSet::from([3, 2]));
+        assert_eq!(reachable(&parents, 2), HashSet::from([2]));
+    }
+}
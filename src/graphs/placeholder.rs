@@ -0,0 +1,114 @@
+use std::f32::consts::TAU;
+
+use egui::{
+    plot::{PlotPoint, PlotTransform},
+    pos2, vec2, Align2, FontId, Rect, Shape, Stroke, Ui,
+};
+
+/// At most this many placeholders stand in for parents being fetched, however
+/// many the server counted.
+pub(super) const MAX_PLACEHOLDERS: usize = 8;
+
+/// Where the placeholders for `count` parents of the element drawn at
+/// `(center, scale)` go: spread over the row above it the way the parents
+/// will be, `rise` being how far above its child a row at a scale is.
+pub(super) fn slots(
+    (center, scale): (PlotPoint, f64),
+    count: usize,
+    rise: impl Fn(f64) -> f64,
+) -> Vec<(PlotPoint, f64)> {
+    let count = count.clamp(1, MAX_PLACEHOLDERS);
+    let scale = scale / count as f64;
+    let y = center.y + rise(scale);
+    (0..count)
+        .map(|slot| {
+            let x = center.x + scale * (slot as f64 - count as f64 * 0.5 + 0.5);
+            (PlotPoint::new(x, y), scale)
+        })
+        .collect()
+}
+
+/// A dashed box with a spinner where a parent of the element whose top is
+/// at `child` will appear, linked to it by a dashed line. `time` turns the
+/// spinner.
+pub(super) fn add_shapes(
+    ui: &Ui,
+    transform: &PlotTransform,
+    (center, scale): (PlotPoint, f64),
+    child: PlotPoint,
+    time: f64,
+    shapes: &mut Vec<Shape>,
+) {
+    let size = vec2(
+        (scale * 0.8 * transform.dpos_dvalue_x()) as f32,
+        (scale * 0.25 * transform.dpos_dvalue_y().abs()) as f32,
+    );
+    let rect = Rect::from_center_size(transform.position_from_point(&center), size);
+    if !rect.intersects(*transform.frame()) || size.y < 2.0 {
+        return;
+    }
+    let color = ui.visuals().weak_text_color();
+    let stroke = Stroke::new(1.0, color);
+    let outline = [
+        rect.left_top(),
+        rect.right_top(),
+        rect.right_bottom(),
+        rect.left_bottom(),
+        rect.left_top(),
+    ];
+    shapes.extend(Shape::dashed_line(&outline, stroke, 4.0, 3.0));
+    shapes.extend(Shape::dashed_line(
+        &[rect.center_bottom(), transform.position_from_point(&child)],
+        stroke,
+        4.0,
+        3.0,
+    ));
+    let radius = (size.y * 0.3).min(8.0);
+    if radius < 2.0 {
+        return;
+    }
+    let spinner = pos2(rect.left() + size.y * 0.5, rect.center().y);
+    let start = (time * 3.0) as f32 % TAU;
+    let arc = (0..=16)
+        .map(|step| {
+            let angle = start + step as f32 / 16.0 * TAU * 0.75;
+            spinner + radius * vec2(angle.cos(), angle.sin())
+        })
+        .collect();
+    shapes.push(Shape::line(arc, stroke));
+    if size.y >= 12.0 {
+        shapes.push(ui.fonts(|fonts| {
+            Shape::text(
+                fonts,
+                pos2(spinner.x + radius * 2.0, rect.center().y),
+                Align2::LEFT_CENTER,
+                "loading…",
+                FontId::proportional((size.y * 0.4).min(14.0)),
+                color,
+            )
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spreads_over_the_row_above() {
+        let rise = |scale: f64| scale * 0.5;
+        let one = slots((PlotPoint::new(0.0, 0.0), 1.0), 0, rise);
+        assert_eq!(one.len(), 1);
+        assert_eq!((one[0].0.x, one[0].0.y, one[0].1), (0.0, 0.5, 1.0));
+
+        let two = slots((PlotPoint::new(1.0, 0.0), 1.0), 2, rise);
+        let xs: Vec<_> = two.iter().map(|(pos, _)| pos.x).collect();
+        assert_eq!(xs, [0.75, 1.25]);
+        assert_eq!(two[0].0.y, 0.25);
+
+        assert_eq!(
+            slots((PlotPoint::new(0.0, 0.0), 1.0), 500, rise).len(),
+            MAX_PLACEHOLDERS
+        );
+    }
+}
@@ -0,0 +1,177 @@
+//! What a blob's bytes look like: text, a WebAssembly module, an ELF
+//! executable, a PNG image, or none of these.
+
+use std::fmt::Display;
+
+use anyhow::{bail, ensure, Context, Result};
+use egui::ColorImage;
+
+use super::wasm;
+
+const ELF_MAGIC: &[u8] = b"\x7fELF";
+const PNG_MAGIC: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// How many bytes the hex view shows before giving up.
+pub(super) const HEX_LIMIT: usize = 4096;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum MediaType {
+    Text,
+    Wasm,
+    Elf,
+    Png,
+    Binary,
+}
+
+impl Display for MediaType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            MediaType::Text => "text",
+            MediaType::Wasm => "WebAssembly module",
+            MediaType::Elf => "ELF executable",
+            MediaType::Png => "PNG image",
+            MediaType::Binary => "binary",
+        })
+    }
+}
+
+/// Guess what `bytes` hold from their leading magic number, or from whether
+/// they read as text.
+pub(super) fn sniff(bytes: &[u8]) -> MediaType {
+    if bytes.starts_with(wasm::MAGIC) {
+        MediaType::Wasm
+    } else if bytes.starts_with(ELF_MAGIC) {
+        MediaType::Elf
+    } else if bytes.starts_with(PNG_MAGIC) {
+        MediaType::Png
+    } else if is_text(bytes) {
+        MediaType::Text
+    } else {
+        MediaType::Binary
+    }
+}
+
+/// Valid UTF-8 without control characters other than whitespace.
+fn is_text(bytes: &[u8]) -> bool {
+    std::str::from_utf8(bytes).map_or(false, |text| {
+        text.chars()
+            .all(|c| !c.is_control() || c.is_ascii_whitespace())
+    })
+}
+
+/// The class, byte order, kind and machine of the ELF file in `bytes`.
+pub(super) fn elf_summary(bytes: &[u8]) -> Result<String> {
+    ensure!(bytes.len() >= 20, "ELF header is truncated");
+    let class = match bytes[4] {
+        1 => "32-bit",
+        2 => "64-bit",
+        class => bail!("unknown ELF class {class}"),
+    };
+    let read_u16 = match bytes[5] {
+        1 => u16::from_le_bytes,
+        2 => u16::from_be_bytes,
+        order => bail!("unknown ELF byte order {order}"),
+    };
+    let kind = match read_u16([bytes[16], bytes[17]]) {
+        1 => "relocatable".to_string(),
+        2 => "executable".to_string(),
+        3 => "shared object".to_string(),
+        4 => "core dump".to_string(),
+        kind => format!("type {kind:#x}"),
+    };
+    let machine = match read_u16([bytes[18], bytes[19]]) {
+        0x03 => "x86".to_string(),
+        0x28 => "ARM".to_string(),
+        0x3e => "x86-64".to_string(),
+        0xb7 => "AArch64".to_string(),
+        0xf3 => "RISC-V".to_string(),
+        machine => format!("machine {machine:#x}"),
+    };
+    let order = if bytes[5] == 1 { "little" } else { "big" };
+    Ok(format!("{class} {order}-endian {kind} for {machine}"))
+}
+
+/// The PNG image in `bytes`, decoded for display.
+pub(super) fn decode_png(bytes: &[u8]) -> Result<ColorImage> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().context("reading PNG header")?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buffer)
+        .context("decoding PNG image")?;
+    let pixels = &buffer[..info.buffer_size()];
+    let size = [info.width as usize, info.height as usize];
+    Ok(match info.color_type {
+        png::ColorType::Rgba => ColorImage::from_rgba_unmultiplied(size, pixels),
+        png::ColorType::Rgb => ColorImage::from_rgb(size, pixels),
+        png::ColorType::GrayscaleAlpha => ColorImage::from_rgba_unmultiplied(
+            size,
+            &pixels
+                .chunks_exact(2)
+                .flat_map(|pixel| [pixel[0], pixel[0], pixel[0], pixel[1]])
+                .collect::<Vec<_>>(),
+        ),
+        png::ColorType::Grayscale => ColorImage::from_rgb(
+            size,
+            &pixels
+                .iter()
+                .flat_map(|gray| [*gray; 3])
+                .collect::<Vec<_>>(),
+        ),
+        png::ColorType::Indexed => bail!("indexed PNG colors were not expanded"),
+    })
+}
+
+/// Rows of 16 bytes of `bytes`, with their offset and printable characters.
+pub(super) fn hex_dump(bytes: &[u8]) -> String {
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(row, chunk)| {
+            let hex: String = chunk.iter().map(|byte| format!("{byte:02x} ")).collect();
+            let printable: String = chunk
+                .iter()
+                .map(|byte| match *byte {
+                    0x20..=0x7e => *byte as char,
+                    _ => '.',
+                })
+                .collect();
+            format!("{:08x}  {hex:<48} {printable}\n", row * 16)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniffs_media_types() {
+        assert_eq!(sniff(b"hello\nworld\t"), MediaType::Text);
+        assert_eq!(sniff(b"\0asm\x01\0\0\0"), MediaType::Wasm);
+        assert_eq!(sniff(b"\x7fELF\x02\x01"), MediaType::Elf);
+        assert_eq!(sniff(PNG_MAGIC), MediaType::Png);
+        assert_eq!(sniff(b"\x01\x02\xff"), MediaType::Binary);
+        assert_eq!(sniff(b"bell\x07"), MediaType::Binary);
+    }
+
+    #[test]
+    fn summarizes_elf() {
+        let mut header = b"\x7fELF\x02\x01\x01".to_vec();
+        header.resize(16, 0);
+        header.extend([2, 0, 0x3e, 0]);
+        assert_eq!(
+            elf_summary(&header).unwrap(),
+            "64-bit little-endian executable for x86-64"
+        );
+    }
+
+    #[test]
+    fn dumps_hex() {
+        assert_eq!(
+            hex_dump(b"fix\n"),
+            format!("00000000  {:<48} fix.\n", "66 69 78 0a ")
+        );
+    }
+}
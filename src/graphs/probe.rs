@@ -0,0 +1,111 @@
+use std::collections::HashSet;
+
+use egui::Ui;
+
+use crate::handle::Handle;
+
+/// Asking the server whether it still has each handle loaded in the
+/// ancestry tree, to find those it lost or never had.
+#[derive(Clone, Debug, Default)]
+pub(super) struct Probe {
+    /// Handles asked about in the latest probe.
+    asked: HashSet<Handle>,
+    /// Handles to ask about next frame.
+    queued: Vec<Handle>,
+    /// Handles the server answered about.
+    answered: HashSet<Handle>,
+    /// Handles the server does not have.
+    missing: HashSet<Handle>,
+}
+
+impl Probe {
+    /// Ask about every handle in `handles` again, forgetting earlier answers.
+    /// Literals carry their data, so are never missing.
+    pub fn start<'a>(&mut self, handles: impl IntoIterator<Item = &'a Handle>) {
+        *self = Self::default();
+        for handle in handles {
+            if handle.storage() != "literal" && self.asked.insert(handle.clone()) {
+                self.queued.push(handle.clone());
+            }
+        }
+    }
+
+    /// Handles to ask about.
+    pub fn take_queued(&mut self) -> Vec<Handle> {
+        std::mem::take(&mut self.queued)
+    }
+
+    pub fn set_exists(&mut self, handle: Handle, exists: bool) {
+        if exists {
+            self.missing.remove(&handle);
+        } else {
+            self.missing.insert(handle.clone());
+        }
+        self.answered.insert(handle);
+    }
+
+    /// Count `handle` as answered when asking about it failed, leaving it
+    /// unmarked.
+    pub fn failed(&mut self, handle: Handle) {
+        self.answered.insert(handle);
+    }
+
+    pub fn missing(&self) -> &HashSet<Handle> {
+        &self.missing
+    }
+
+    /// How far the probe got and what it found, with a button to forget it.
+    /// Returns whether it was forgotten.
+    pub fn ui(&mut self, ui: &mut Ui) -> bool {
+        if self.asked.is_empty() {
+            return false;
+        }
+        let mut cleared = false;
+        let (asked, answered) = (self.asked.len(), self.answered.len());
+        ui.horizontal(|ui| {
+            if answered < asked {
+                ui.spinner();
+                ui.label(format!("Probed {answered} of {asked} handles"));
+            } else {
+                ui.label(format!("Probed {asked} handles"));
+            }
+            match self.missing.len() {
+                0 => ui.label("none missing"),
+                1 => ui.colored_label(ui.visuals().error_fg_color, "1 missing"),
+                missing => {
+                    ui.colored_label(ui.visuals().error_fg_color, format!("{missing} missing"))
+                }
+            };
+            if ui.small_button("Clear").clicked() {
+                *self = Self::default();
+                cleared = true;
+            }
+        });
+        cleared
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marks_missing_until_found() {
+        let local = Handle::from_hex("d9-0-4-100000000000000").unwrap();
+        let literal = Handle::from_hex("0-0-0-2400000000000000").unwrap();
+        let mut probe = Probe::default();
+        probe.start([&local, &literal, &local]);
+        assert_eq!(probe.take_queued(), [local.clone()]);
+        assert!(probe.take_queued().is_empty());
+
+        probe.set_exists(local.clone(), false);
+        assert!(probe.missing().contains(&local));
+        probe.set_exists(local.clone(), true);
+        assert!(probe.missing().is_empty());
+
+        // Probing again forgets what was found.
+        probe.set_exists(local.clone(), false);
+        probe.start([&local]);
+        assert!(probe.missing().is_empty());
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::{HashMap, HashSet};
+
 use eframe::epaint::util::FloatOrd;
 use egui::{
     plot::{
@@ -7,20 +9,42 @@ use egui::{
         },
         LabelFormatter, PlotBounds, PlotPoint, PlotTransform,
     },
-    Color32, Pos2, Shape, Stroke, Ui,
+    Color32, Pos2, Rect, Shape, Stroke, Ui,
 };
 
 use crate::{
-    handle::{Handle, Operation, Task},
+    handle::{Handle, Operation, Restriction, Task, TaskStatus},
+    http::{Generation, NextPage},
     plot::Element,
+    theme::Palette,
 };
 
+use super::{routing::ArrowStyle, touch};
+
 /// Note that there may be duplicate `Progress` if, say, a tree contains two of
 /// the same canonical Object.
 #[derive(Clone)]
 pub(super) struct ProgressGraph {
     inner: Progress,
     ordering: Vec<DependeeStack>,
+    /// A handle whose elements should stand out.
+    focus: Option<Handle>,
+    palette: Palette,
+    arrow_style: ArrowStyle,
+    /// Handles with a note, marked with an icon.
+    annotated: HashSet<Handle>,
+    /// Names given to handles, drawn instead of their descriptions.
+    aliases: HashMap<Handle, String>,
+    /// Whether elements are drawn bigger the larger their handle is.
+    size_scaling: bool,
+    /// Hex digits kept at each end of long hex numbers in element texts.
+    abbreviation: usize,
+    /// Room left around the text of each element.
+    padding: f64,
+    /// Execution status of tasks, as last fetched.
+    status: HashMap<(Handle, Operation), TaskStatus>,
+    /// Which tree this is, new on every restart.
+    generation: Generation,
 }
 
 /// Counterpart of `Lineage` for ancestors.
@@ -49,6 +73,8 @@ struct Progress {
     task: (Element, Operation),
     result: Option<Element>,
     dependees: Vec<Progress>,
+    /// Where the dependees continue if not all of them have been loaded.
+    more: Option<NextPage>,
 }
 
 #[derive(Clone, Copy)]
@@ -68,15 +94,48 @@ impl PlotItem for ProgressGraph {
             let progress = self.get_from_stack(stack);
             let draw_params = self.get_draw_parameters(stack);
             let bounds = progress.task.0.bounds(draw_params.task);
-            let color = ui.visuals().widgets.active.fg_stroke.color;
+            let visuals = ui.visuals();
+            let color = visuals.widgets.active.fg_stroke.color;
+            let is_focus = |el: &Element| self.focus.as_ref() == Some(el.get_handle());
             // Draw task's element.
-            progress
-                .task
-                .0
-                .add_shapes(transform, shapes, draw_params.task, false, color);
-            // Draw result if exists.
-            if let Some(result) = &progress.result {
-                result.add_shapes(transform, shapes, draw_params.result, false, color);
+            let task = &progress.task.0;
+            if task.is_on_screen(transform, draw_params.task) {
+                task.add_shapes(
+                    transform,
+                    shapes,
+                    draw_params.task,
+                    is_focus(task),
+                    visuals,
+                    &self.palette,
+                );
+                if let Some(next) = &progress.more {
+                    task.add_more_note(ui, transform, draw_params.task, next.remaining, shapes);
+                }
+                if self.annotated.contains(task.get_handle()) {
+                    task.add_note_icon(ui, transform, draw_params.task, shapes);
+                }
+                let key = (task.get_handle().clone(), progress.task.1);
+                if let Some(status) = self.status.get(&key) {
+                    task.add_status(ui, transform, draw_params.task, *status, shapes);
+                }
+            }
+            // Draw result if exists and on screen.
+            if let Some(result) = progress
+                .result
+                .as_ref()
+                .filter(|result| result.is_on_screen(transform, draw_params.result))
+            {
+                result.add_shapes(
+                    transform,
+                    shapes,
+                    draw_params.result,
+                    is_focus(result),
+                    visuals,
+                    &self.palette,
+                );
+                if self.annotated.contains(result.get_handle()) {
+                    result.add_note_icon(ui, transform, draw_params.result, shapes);
+                }
             }
             // Draw an arrow to the left.
             // The top level task is not the dependee of others, so only draw an
@@ -87,7 +146,7 @@ impl PlotItem for ProgressGraph {
                 draw_params.task.0 = [bounds.min()[0], bounds.center().y].into();
                 draw_params.result.0 =
                     [bounds.center().x - draw_params.task.1, bounds.center().y].into();
-                Self::add_arrow(transform, shapes, draw_params, Arrow::Left, color);
+                self.add_arrow(transform, shapes, draw_params, Arrow::Left, color);
             }
             // Draw operation arrow
             // Take the bottom center of the task and top center of result if it exists.
@@ -100,12 +159,12 @@ impl PlotItem for ProgressGraph {
                 [bounds.center().x, bounds.center().y - draw_params.task.1]
             }
             .into();
-            Self::add_arrow(
+            self.add_arrow(
                 transform,
                 shapes,
                 draw_params,
                 Arrow::Down,
-                progress.task.1.get_color(),
+                self.palette.operation(progress.task.1),
             );
         }
     }
@@ -168,12 +227,21 @@ impl PlotItem for ProgressGraph {
         let stack = &self.ordering[elem.index];
         let progress = self.get_from_stack(stack);
         let params = self.get_draw_parameters(stack);
-        progress
-            .task
-            .0
-            .add_highlight(plot.transform, params.task, shapes);
+        progress.task.0.add_highlight(
+            plot.transform,
+            params.task,
+            shapes,
+            plot.ui.visuals(),
+            &self.palette,
+        );
         if let Some(result) = &progress.result {
-            result.add_highlight(plot.transform, params.result, shapes);
+            result.add_highlight(
+                plot.transform,
+                params.result,
+                shapes,
+                plot.ui.visuals(),
+                &self.palette,
+            );
         }
     }
 
@@ -190,19 +258,232 @@ impl ProgressGraph {
                 task: (element, operation),
                 result: None,
                 dependees: vec![],
+                more: None,
             },
             ordering,
+            focus: None,
+            palette: Palette::default(),
+            arrow_style: ArrowStyle::default(),
+            annotated: HashSet::new(),
+            aliases: HashMap::new(),
+            size_scaling: false,
+            abbreviation: 0,
+            padding: Element::RECT_EXTENSION,
+            status: HashMap::new(),
+            generation: Generation::next(),
+        }
+    }
+
+    /// Which tree this is, for requests to carry so that their answers are
+    /// dropped once the tree is restarted.
+    pub fn generation(&self) -> Generation {
+        self.generation
+    }
+
+    /// Start over from `element` performing `operation`, drawn as before.
+    pub fn restart(&mut self, ui: &Ui, element: Element, operation: Operation) {
+        let restarted = Self {
+            palette: self.palette,
+            arrow_style: self.arrow_style,
+            annotated: std::mem::take(&mut self.annotated),
+            ..Self::new(element, operation)
+        };
+        let (size_scaling, abbreviation, padding) =
+            (self.size_scaling, self.abbreviation, self.padding);
+        let aliases = std::mem::take(&mut self.aliases);
+        *self = restarted;
+        self.set_aliases(ui, aliases);
+        self.set_size_scaling(size_scaling);
+        self.set_abbreviation(ui, abbreviation);
+        self.set_padding(padding);
+    }
+
+    /// Every task in the tree, once each.
+    pub fn tasks(&self) -> Vec<(Handle, Operation)> {
+        let mut tasks = vec![];
+        for stack in &self.ordering {
+            let (element, operation) = &self.get_from_stack(stack).task;
+            let task = (element.get_handle().clone(), *operation);
+            if !tasks.contains(&task) {
+                tasks.push(task);
+            }
+        }
+        tasks
+    }
+
+    pub fn set_status(&mut self, handle: Handle, operation: Operation, status: TaskStatus) {
+        self.status.insert((handle, operation), status);
+    }
+
+    pub fn status(&self, handle: &Handle, operation: Operation) -> Option<TaskStatus> {
+        self.status.get(&(handle.clone(), operation)).copied()
+    }
+
+    /// The operation performed on the top level task.
+    pub fn operation(&self) -> Operation {
+        self.inner.task.1
+    }
+
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    pub fn set_arrow_style(&mut self, arrow_style: ArrowStyle) {
+        self.arrow_style = arrow_style;
+    }
+
+    pub fn set_annotated(&mut self, annotated: HashSet<Handle>) {
+        self.annotated = annotated;
+    }
+
+    pub fn set_size_scaling(&mut self, scaled: bool) {
+        self.size_scaling = scaled;
+        self.inner.set_size_scaling(scaled);
+    }
+
+    pub fn set_aliases(&mut self, ui: &Ui, aliases: HashMap<Handle, String>) {
+        self.inner.set_aliases(ui, &aliases);
+        self.aliases = aliases;
+    }
+
+    pub fn set_abbreviation(&mut self, ui: &Ui, keep: usize) {
+        self.abbreviation = keep;
+        self.inner.set_abbreviation(ui, keep);
+    }
+
+    pub fn set_padding(&mut self, padding: f64) {
+        self.padding = padding;
+        self.inner.set_padding(padding);
+    }
+
+    pub fn set_focus(&mut self, focus: Option<Handle>) {
+        self.focus = focus;
+    }
+
+    /// `coords` moved onto the task or result at `closest_elem` nearest to
+    /// them, for touches that land beside it.
+    pub fn snap_to(&self, coords: PlotPoint, closest_elem: ClosestElem) -> PlotPoint {
+        let Some(stack) = self.ordering.get(closest_elem.index) else {
+            return coords;
+        };
+        let params = self.get_draw_parameters(stack);
+        let progress = self.get_from_stack(stack);
+        let task = touch::clamp(coords, progress.task.0.bounds(params.task));
+        let result = progress
+            .result
+            .as_ref()
+            .map(|result| touch::clamp(coords, result.bounds(params.result)));
+        let distance = |point: &PlotPoint| (point.x - coords.x).hypot(point.y - coords.y);
+        match result {
+            Some(result) if distance(&result) < distance(&task) => result,
+            _ => task,
+        }
+    }
+
+    /// Returns the element under `coords` and where it is drawn, if any.
+    pub fn element_at(
+        &self,
+        coords: PlotPoint,
+        closest_elem: ClosestElem,
+    ) -> Option<(&Element, PlotBounds)> {
+        let stack = self.ordering.get(closest_elem.index)?;
+        let params = self.get_draw_parameters(stack);
+        let progress = self.get_from_stack(stack);
+
+        let located = |elem: &Element, params: (PlotPoint, f64)| -> Option<PlotBounds> {
+            let bounds = elem.bounds(params);
+            let [min_x, min_y] = bounds.min();
+            let [max_x, max_y] = bounds.max();
+            let p = coords;
+            (min_x <= p.x && p.x <= max_x && min_y <= p.y && p.y <= max_y).then_some(bounds)
+        };
+
+        if let Some(bounds) = located(&progress.task.0, params.task) {
+            Some((&progress.task.0, bounds))
+        } else {
+            let result = progress.result.as_ref()?;
+            Some((result, located(result, params.result)?))
+        }
+    }
+
+    /// The operation of the task `element` at `closest_elem` shows, with the
+    /// task it is a dependee of. Empty if `element` shows a result.
+    pub fn tasks_of(
+        &self,
+        closest_elem: ClosestElem,
+        element: &Element,
+    ) -> Vec<(Operation, String)> {
+        let Some(stack) = self.ordering.get(closest_elem.index) else {
+            return vec![];
+        };
+        let (task, operation) = &self.get_from_stack(stack).task;
+        if !std::ptr::eq(task, element) {
+            return vec![];
+        }
+        let origin = match stack.0.split_last() {
+            Some((_, parent)) => {
+                let parent = self.get_from_stack(&DependeeStack(parent.to_vec()));
+                format!("dependee of {}", parent.task.0.get_handle().to_hex())
+            }
+            None => "the target".to_string(),
+        };
+        vec![(*operation, origin)]
+    }
+
+    /// Returns where the first element showing `handle` is drawn, if any.
+    pub fn element_bounds(&self, handle: &Handle) -> Option<PlotBounds> {
+        self.ordering.iter().find_map(|stack| {
+            let progress = self.get_from_stack(stack);
+            let params = self.get_draw_parameters(stack);
+            if progress.task.0.get_handle() == handle {
+                Some(progress.task.0.bounds(params.task))
+            } else {
+                progress
+                    .result
+                    .as_ref()
+                    .filter(|r| r.get_handle() == handle)
+                    .map(|r| r.bounds(params.result))
+            }
+        })
+    }
+
+    /// Returns the extent of every element in the graph.
+    pub fn content_bounds(&self) -> PlotBounds {
+        let mut bounds = PlotBounds::NOTHING;
+        for stack in self.ordering.iter() {
+            let progress = self.get_from_stack(stack);
+            let params = self.get_draw_parameters(stack);
+            bounds.merge(&progress.task.0.bounds(params.task));
+            if let Some(result) = &progress.result {
+                bounds.merge(&result.bounds(params.result));
+            }
+        }
+        bounds
+    }
+
+    /// Every element's text and where it is drawn.
+    pub fn labels(&self, transform: &PlotTransform) -> Vec<(Rect, String)> {
+        let mut labels = vec![];
+        for stack in self.ordering.iter() {
+            let progress = self.get_from_stack(stack);
+            let params = self.get_draw_parameters(stack);
+            labels.push(progress.task.0.label(transform, params.task));
+            if let Some(result) = &progress.result {
+                labels.push(result.label(transform, params.result));
+            }
         }
+        labels
     }
 
     /// Handle a click that is near to a ClosestElem. May send an http request
-    /// that is specified by the `request` parameter.
+    /// that is specified by the `request` parameter, which is passed where the
+    /// next page of dependees starts if only some have been loaded.
     pub fn handle_nearby_click(
         &self,
         ui: &Ui,
         coords: PlotPoint,
         closest_elem: ClosestElem,
-        request: impl FnOnce(usize, Handle, Operation),
+        request: impl FnOnce(usize, Handle, Operation, Option<String>),
     ) {
         let stack = &self.ordering[closest_elem.index];
         let params = self.get_draw_parameters(stack);
@@ -216,11 +497,21 @@ impl ProgressGraph {
         };
 
         if contains(&progress.task.0, params.task) {
-            ui.output_mut(|o| o.copied_text = progress.task.0.get_handle().to_hex());
+            let task = &progress.task.0;
+            ui.output_mut(|o| o.copied_text = task.get_handle().to_hex());
+            if let Some(restriction) = task.get_restriction() {
+                log::info!(
+                    "Not fetching restricted {}, requires {}",
+                    task.get_handle().to_hex(),
+                    restriction.permission
+                );
+                return;
+            }
             request(
                 closest_elem.index,
-                progress.task.0.get_handle().clone(),
+                task.get_handle().clone(),
                 progress.task.1,
+                progress.more.as_ref().map(|next| next.cursor.clone()),
             )
         } else if progress
             .result
@@ -233,25 +524,69 @@ impl ProgressGraph {
         }
     }
 
-    pub fn set_child(&mut self, ui: &Ui, index: usize, child: Handle) {
-        let ordering = self.ordering[index].clone();
-        self.get_mut_from_stack(&ordering).result = Some(Element::new(ui, child));
+    pub fn set_child(
+        &mut self,
+        ui: &Ui,
+        index: usize,
+        child: Handle,
+        restriction: Option<Restriction>,
+    ) {
+        // The answer may be for a tree that `set_operation` has since replaced.
+        let Some(ordering) = self.ordering.get(index).cloned() else {
+            return;
+        };
+        let alias = self.aliases.get(&child).cloned();
+        let mut result = Element::with_restriction(ui, child, restriction);
+        result.set_alias(ui, alias.as_deref());
+        result.set_size_scaling(self.size_scaling);
+        result.set_abbreviation(ui, self.abbreviation);
+        result.set_padding(self.padding);
+        self.get_mut_from_stack(&ordering).result = Some(result);
     }
 
-    pub fn merge_dependees(&mut self, ui: &Ui, index: usize, dependees: Vec<Task>) {
-        let ordering = self.ordering[index].clone();
+    /// The handle of the task at `index`, if the index is still in the tree.
+    pub fn task_handle(&self, index: usize) -> Option<&Handle> {
+        let stack = self.ordering.get(index)?;
+        Some(self.get_from_stack(stack).task.0.get_handle())
+    }
 
-        let original_list = &mut self.get_mut_from_stack(&ordering).dependees;
+    pub fn merge_dependees(
+        &mut self,
+        ui: &Ui,
+        index: usize,
+        dependees: Vec<Task>,
+        next: Option<NextPage>,
+    ) {
+        // The answer may be for a tree that `set_operation` has since replaced.
+        let Some(ordering) = self.ordering.get(index).cloned() else {
+            return;
+        };
+        let (size_scaling, abbreviation, padding) =
+            (self.size_scaling, self.abbreviation, self.padding);
+        let aliases: Vec<_> = dependees
+            .iter()
+            .map(|dependee| self.aliases.get(&dependee.handle).cloned())
+            .collect();
+
+        let progress = self.get_mut_from_stack(&ordering);
+        progress.more = next;
+        let original_list = &mut progress.dependees;
         let mut new_stacks = vec![];
-        for dependee in dependees {
+        for (dependee, alias) in dependees.into_iter().zip(aliases) {
             if !original_list.iter().any(|p| {
                 p.task.0.get_handle() == &dependee.handle && p.task.1 == dependee.operation
             }) {
                 let new_index = original_list.len();
+                let mut task = Element::with_restriction(ui, dependee.handle, dependee.restriction);
+                task.set_alias(ui, alias.as_deref());
+                task.set_size_scaling(size_scaling);
+                task.set_abbreviation(ui, abbreviation);
+                task.set_padding(padding);
                 original_list.push(Progress {
-                    task: (Element::new(ui, dependee.handle), dependee.operation),
+                    task: (task, dependee.operation),
                     result: None,
                     dependees: vec![],
+                    more: None,
                 });
                 let mut new_stack = ordering.clone();
                 new_stack.0.push(new_index);
@@ -259,7 +594,6 @@ impl ProgressGraph {
             }
         }
         self.ordering.append(&mut new_stacks);
-        log::error!("{:?}", self.ordering.len());
     }
 
     fn get_from_stack(&self, stack: &DependeeStack) -> &Progress {
@@ -305,6 +639,7 @@ impl ProgressGraph {
     }
 
     fn add_arrow(
+        &self,
         transform: &PlotTransform,
         shapes: &mut Vec<Shape>,
         draw_params: DrawParams,
@@ -314,8 +649,12 @@ impl ProgressGraph {
         let scale = draw_params.task.1;
         let origin = draw_params.task.0;
         let target = draw_params.result.0;
-        let tip_scale = scale / 40.0;
-        let stroke = Stroke::new((scale * transform.dpos_dvalue_x() / 100.0) as f32, color);
+        let tip_scale = self.arrow_style.head_size(scale);
+        let stroke = Stroke::new(
+            self.arrow_style
+                .stroke_width(scale, transform.dpos_dvalue_x()),
+            color,
+        );
         let (head_start, head_end) = match direction {
             Arrow::Left => (
                 PlotPoint::from([target.x + tip_scale, target.y + tip_scale]),
@@ -343,3 +682,46 @@ impl ProgressGraph {
         ));
     }
 }
+
+impl Progress {
+    fn set_aliases(&mut self, ui: &Ui, aliases: &HashMap<Handle, String>) {
+        let alias = |element: &Element| aliases.get(element.get_handle()).map(String::as_str);
+        self.task.0.set_alias(ui, alias(&self.task.0));
+        if let Some(result) = &mut self.result {
+            result.set_alias(ui, alias(result));
+        }
+        for dependee in &mut self.dependees {
+            dependee.set_aliases(ui, aliases);
+        }
+    }
+
+    fn set_size_scaling(&mut self, scaled: bool) {
+        self.task.0.set_size_scaling(scaled);
+        if let Some(result) = &mut self.result {
+            result.set_size_scaling(scaled);
+        }
+        for dependee in &mut self.dependees {
+            dependee.set_size_scaling(scaled);
+        }
+    }
+
+    fn set_abbreviation(&mut self, ui: &Ui, keep: usize) {
+        self.task.0.set_abbreviation(ui, keep);
+        if let Some(result) = &mut self.result {
+            result.set_abbreviation(ui, keep);
+        }
+        for dependee in &mut self.dependees {
+            dependee.set_abbreviation(ui, keep);
+        }
+    }
+
+    fn set_padding(&mut self, padding: f64) {
+        self.task.0.set_padding(padding);
+        if let Some(result) = &mut self.result {
+            result.set_padding(padding);
+        }
+        for dependee in &mut self.dependees {
+            dependee.set_padding(padding);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+
+/// Limits on how much of the ancestry tree is drawn. Past them, the
+/// farthest generations are folded into a count on the last one drawn.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct Pruning {
+    pub(crate) enabled: bool,
+    /// Generations drawn above the target.
+    pub(crate) max_depth: usize,
+    /// Elements drawn, counting the target.
+    pub(crate) max_nodes: usize,
+}
+
+impl Default for Pruning {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_depth: 12,
+            max_nodes: 2000,
+        }
+    }
+}
+
+impl Pruning {
+    /// The farthest generation to draw, given the generation of every
+    /// element, or `None` if every element fits. The target, at generation
+    /// 0, is always drawn.
+    pub(crate) fn cutoff(&self, generations: impl Iterator<Item = usize>) -> Option<usize> {
+        if !self.enabled {
+            return None;
+        }
+        let mut per_generation = BTreeMap::new();
+        for generation in generations {
+            *per_generation.entry(generation).or_insert(0usize) += 1;
+        }
+        let farthest = *per_generation.keys().next_back()?;
+        let mut cutoff = self.max_depth.min(farthest);
+        let drawn = |cutoff: usize| {
+            per_generation
+                .range(..=cutoff)
+                .map(|(_, n)| n)
+                .sum::<usize>()
+        };
+        while cutoff > 0 && drawn(cutoff) > self.max_nodes {
+            cutoff -= 1;
+        }
+        (cutoff < farthest).then_some(cutoff)
+    }
+
+    /// Controls for each limit. Returns whether any changed.
+    pub(crate) fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = ui
+            .checkbox(&mut self.enabled, "Fold far generations")
+            .on_hover_text(
+                "Keep huge trees responsive by folding the farthest parents into a count",
+            )
+            .changed();
+        ui.add_enabled_ui(self.enabled, |ui| {
+            egui::Grid::new("pruning").num_columns(2).show(ui, |ui| {
+                ui.label("Deepest: ");
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut self.max_depth)
+                            .clamp_range(1..=1000)
+                            .suffix(" generations"),
+                    )
+                    .changed();
+                ui.end_row();
+                ui.label("Most: ");
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut self.max_nodes)
+                            .clamp_range(1..=1_000_000)
+                            .suffix(" handles"),
+                    )
+                    .changed();
+                ui.end_row();
+            });
+        });
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds_farthest_generations() {
+        // One target, then 2, 4 and 8 parents per generation.
+        let generations = || [0, 1, 1, 2, 2, 2, 2].into_iter().chain([3; 8]);
+        let mut pruning = Pruning {
+            enabled: true,
+            max_depth: 10,
+            max_nodes: 100,
+        };
+        assert_eq!(pruning.cutoff(generations()), None);
+        pruning.max_depth = 2;
+        assert_eq!(pruning.cutoff(generations()), Some(2));
+        pruning.max_nodes = 6;
+        assert_eq!(pruning.cutoff(generations()), Some(1));
+        pruning.max_nodes = 1;
+        assert_eq!(pruning.cutoff(generations()), Some(0));
+        pruning.enabled = false;
+        assert_eq!(pruning.cutoff(generations()), None);
+    }
+}
@@ -0,0 +1,41 @@
+use egui::Color32;
+
+/// The color of each pinned root, in the order pinned. Only this many can
+/// be pinned at once.
+pub(super) const ROOT_COLORS: [Color32; 6] = [
+    Color32::from_rgb(230, 80, 80),
+    Color32::from_rgb(70, 130, 230),
+    Color32::from_rgb(80, 190, 90),
+    Color32::from_rgb(230, 170, 40),
+    Color32::from_rgb(170, 90, 210),
+    Color32::from_rgb(40, 190, 190),
+];
+
+/// The colors of `roots`, indices into `ROOT_COLORS`, mixed evenly, so that
+/// a handle several roots reach stands apart from those only one reaches.
+pub(super) fn blend(roots: &[usize]) -> Color32 {
+    if roots.is_empty() {
+        return Color32::TRANSPARENT;
+    }
+    let mut sum = [0_u32; 3];
+    for &root in roots {
+        let color = ROOT_COLORS[root];
+        for (channel, value) in sum.iter_mut().zip([color.r(), color.g(), color.b()]) {
+            *channel += u32::from(value);
+        }
+    }
+    let [r, g, b] = sum.map(|channel| (channel / roots.len() as u32) as u8);
+    Color32::from_rgb(r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blends_evenly() {
+        assert_eq!(blend(&[1]), ROOT_COLORS[1]);
+        assert_eq!(blend(&[0, 1]), Color32::from_rgb(150, 105, 155));
+        assert_eq!(blend(&[]), Color32::TRANSPARENT);
+    }
+}
@@ -0,0 +1,277 @@
+use std::fmt::Display;
+
+use egui::plot::PlotPoint;
+
+/// How far bundled arrows are pulled toward the spine of their bundle, from
+/// 0 for arrows drawn on their own to 1 for arrows that all meet on it.
+const BUNDLE_STRENGTH: f64 = 0.85;
+
+/// How arrows between elements of the ancestry tree are drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub(crate) enum EdgeRouting {
+    /// A bezier curve straight from parent to child.
+    #[default]
+    Curved,
+    /// Vertical and horizontal segments only, with the horizontal runs into
+    /// the same child spread over separate tracks.
+    Orthogonal,
+    /// Curves pulled together with the others between the same two
+    /// generations, so that many arrows read as a few bundles.
+    Bundled,
+}
+
+impl EdgeRouting {
+    pub(crate) const ALL: [EdgeRouting; 3] = [
+        EdgeRouting::Curved,
+        EdgeRouting::Orthogonal,
+        EdgeRouting::Bundled,
+    ];
+}
+
+impl Display for EdgeRouting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            EdgeRouting::Curved => "Curved",
+            EdgeRouting::Orthogonal => "Orthogonal",
+            EdgeRouting::Bundled => "Bundled",
+        })
+    }
+}
+
+/// How thick arrows are drawn, how big their heads are and how far curved
+/// arrows bend, relative to the defaults, which scale with the zoom.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct ArrowStyle {
+    pub(crate) width: f32,
+    /// Points that arrows never get thinner than, so that they stay visible
+    /// when zoomed far out.
+    pub(crate) min_width: f32,
+    pub(crate) head: f64,
+    pub(crate) curvature: f64,
+    /// Point arrows in the ancestry tree from each child to its parents,
+    /// the way data flows, instead of from parents to the child that
+    /// depends on them.
+    pub(crate) data_flow: bool,
+}
+
+impl Default for ArrowStyle {
+    fn default() -> Self {
+        Self {
+            width: 1.0,
+            min_width: 0.5,
+            head: 1.0,
+            curvature: 1.0,
+            data_flow: false,
+        }
+    }
+}
+
+impl ArrowStyle {
+    /// The stroke width in points of an arrow of `arrow_scale`, drawn at
+    /// `points_per_unit` points to the plot's unit.
+    pub(crate) fn stroke_width(&self, arrow_scale: f64, points_per_unit: f64) -> f32 {
+        let zoomed = (arrow_scale * points_per_unit / 100.0) as f32;
+        (zoomed * self.width).max(self.min_width)
+    }
+
+    /// How far from its tip an arrow of `arrow_scale` has its head reach.
+    pub(crate) fn head_size(&self, arrow_scale: f64) -> f64 {
+        arrow_scale / 40.0 * self.head
+    }
+
+    /// Sliders for each setting. Returns whether any changed.
+    pub(crate) fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        egui::Grid::new("arrow_style")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Width: ");
+                changed |= ui
+                    .add(egui::Slider::new(&mut self.width, 0.25..=4.0).suffix("×"))
+                    .changed();
+                ui.end_row();
+                ui.label("Thinnest: ");
+                changed |= ui
+                    .add(egui::Slider::new(&mut self.min_width, 0.0..=3.0).suffix(" pt"))
+                    .on_hover_text("Arrows stay at least this thick however far out you zoom")
+                    .changed();
+                ui.end_row();
+                ui.label("Heads: ");
+                changed |= ui
+                    .add(egui::Slider::new(&mut self.head, 0.25..=4.0).suffix("×"))
+                    .changed();
+                ui.end_row();
+                ui.label("Curvature: ");
+                changed |= ui
+                    .add(egui::Slider::new(&mut self.curvature, 0.0..=3.0).suffix("×"))
+                    .on_hover_text("How far curved arrows bend out of their ends")
+                    .changed();
+                ui.end_row();
+            });
+        changed |= ui
+            .checkbox(&mut self.data_flow, "Point the way data flows")
+            .on_hover_text(
+                "Draw arrowheads at parents instead of at the children that depend on them",
+            )
+            .changed();
+        if ui.button("Reset arrows").clicked() {
+            *self = Self::default();
+            changed = true;
+        }
+        changed
+    }
+}
+
+/// Assign each horizontal run, given as `(start, end)`, a track such that runs
+/// on the same track do not overlap. Returns the track of every run, in the
+/// order given, and uses as few tracks as it can.
+pub(super) fn assign_tracks(spans: &[(f64, f64)]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..spans.len()).collect();
+    order.sort_by(|a, b| spans[*a].0.total_cmp(&spans[*b].0));
+    // Where the last run placed on each track ends.
+    let mut track_ends: Vec<f64> = vec![];
+    let mut tracks = vec![0; spans.len()];
+    for index in order {
+        let (start, end) = spans[index];
+        let track = match track_ends.iter().position(|track_end| *track_end < start) {
+            Some(track) => track,
+            None => {
+                track_ends.push(f64::NEG_INFINITY);
+                track_ends.len() - 1
+            }
+        };
+        track_ends[track] = end;
+        tracks[index] = track;
+    }
+    tracks
+}
+
+/// The inner control points of the curve of each arrow in a bundle, given
+/// as `(origin, target)`. Each is pulled toward a spine running from the
+/// middle of the origins to the middle of the targets, so that the arrows
+/// leave their origins apart, run together and part again at their targets.
+pub(super) fn bundle_controls(ends: &[(PlotPoint, PlotPoint)]) -> Vec<(PlotPoint, PlotPoint)> {
+    let count = ends.len() as f64;
+    let mean = |end: fn(&(PlotPoint, PlotPoint)) -> PlotPoint| {
+        let (x, y) = ends
+            .iter()
+            .map(end)
+            .fold((0.0, 0.0), |(x, y), point| (x + point.x, y + point.y));
+        PlotPoint::new(x / count, y / count)
+    };
+    let (origins, targets) = (mean(|end| end.0), mean(|end| end.1));
+    // The spine starts and ends a third of the way in from each side.
+    let spine_start = PlotPoint::new(origins.x, origins.y + (targets.y - origins.y) / 3.0);
+    let spine_end = PlotPoint::new(targets.x, targets.y + (origins.y - targets.y) / 3.0);
+    let toward = |from: PlotPoint, to: PlotPoint| {
+        PlotPoint::new(
+            from.x + (to.x - from.x) * BUNDLE_STRENGTH,
+            from.y + (to.y - from.y) * BUNDLE_STRENGTH,
+        )
+    };
+    ends.iter()
+        .map(|(origin, target)| (toward(*origin, spine_start), toward(*target, spine_end)))
+        .collect()
+}
+
+/// The point an arrow along `points`, from its origin to its target, has
+/// its head at, and a point the head points away from. With `data_flow` the
+/// head is at the origin instead. Points on the tip are skipped, so that
+/// curves with no bend still point along their line.
+pub(super) fn head_ends(points: &[PlotPoint], data_flow: bool) -> (PlotPoint, PlotPoint) {
+    let mut points: Vec<PlotPoint> = points.to_vec();
+    if !data_flow {
+        points.reverse();
+    }
+    let tip = points[0];
+    let from = points[1..]
+        .iter()
+        .find(|point| point.x != tip.x || point.y != tip.y)
+        .copied()
+        .unwrap_or(PlotPoint::new(tip.x, tip.y + 1.0));
+    (from, tip)
+}
+
+/// The three points of an arrowhead `size` long with its tip at `tip`,
+/// pointing away from `from`.
+pub(super) fn arrow_head(from: PlotPoint, tip: PlotPoint, size: f64) -> [PlotPoint; 3] {
+    let (dx, dy) = (tip.x - from.x, tip.y - from.y);
+    let length = dx.hypot(dy);
+    let (dx, dy) = if length > 0.0 {
+        (dx / length, dy / length)
+    } else {
+        (0.0, -1.0)
+    };
+    // Back along the arrow, and out to either side of it.
+    let side = |sign: f64| {
+        PlotPoint::new(
+            tip.x + size * (-dx - sign * dy),
+            tip.y + size * (-dy + sign * dx),
+        )
+    };
+    [side(1.0), tip, side(-1.0)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heads_point_along_their_arrow() {
+        let points = [
+            PlotPoint::new(0.0, 2.0),
+            PlotPoint::new(0.0, 1.0),
+            PlotPoint::new(1.0, 0.0),
+            PlotPoint::new(1.0, 0.0),
+        ];
+        let (from, tip) = head_ends(&points, false);
+        assert_eq!((from.x, from.y, tip.x, tip.y), (0.0, 1.0, 1.0, 0.0));
+        let (from, tip) = head_ends(&points, true);
+        assert_eq!((from.x, from.y, tip.x, tip.y), (0.0, 1.0, 0.0, 2.0));
+
+        // Pointing down, the head spreads out above the tip.
+        let [start, tip, end] = arrow_head(PlotPoint::new(0.0, 1.0), PlotPoint::new(0.0, 0.0), 1.0);
+        assert_eq!((start.x, start.y), (1.0, 1.0));
+        assert_eq!((tip.x, tip.y), (0.0, 0.0));
+        assert_eq!((end.x, end.y), (-1.0, 1.0));
+        // Pointing up, below it.
+        let [start, _, end] = arrow_head(PlotPoint::new(0.0, -1.0), PlotPoint::new(0.0, 0.0), 1.0);
+        assert_eq!((start.y, end.y), (-1.0, -1.0));
+    }
+
+    #[test]
+    fn bundles_pull_arrows_together() {
+        let ends = [
+            (PlotPoint::new(-1.0, 3.0), PlotPoint::new(-1.0, 0.0)),
+            (PlotPoint::new(1.0, 3.0), PlotPoint::new(1.0, 0.0)),
+        ];
+        let controls = bundle_controls(&ends);
+        for ((origin, target), (start, end)) in ends.iter().zip(&controls) {
+            assert!(start.x.abs() < origin.x.abs() / 2.0);
+            assert!(end.x.abs() < target.x.abs() / 2.0);
+            assert!(start.y > end.y);
+        }
+        // Mirrored arrows bend alike.
+        assert_eq!(controls[0].0.x, -controls[1].0.x);
+        assert_eq!(controls[0].0.y, controls[1].0.y);
+    }
+
+    #[test]
+    fn overlapping_runs_get_separate_tracks() {
+        let tracks = assign_tracks(&[(0.0, 2.0), (3.0, 4.0), (1.0, 5.0), (4.5, 6.0)]);
+        assert_eq!(tracks, [0, 0, 1, 0]);
+        assert_eq!(assign_tracks(&[]), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn arrows_keep_their_minimum_width() {
+        let style = ArrowStyle {
+            width: 2.0,
+            ..ArrowStyle::default()
+        };
+        assert_eq!(style.stroke_width(1.0, 100.0), 2.0);
+        assert_eq!(style.stroke_width(1.0, 1.0), style.min_width);
+        assert_eq!(ArrowStyle::default().head_size(40.0), 1.0);
+    }
+}
@@ -0,0 +1,57 @@
+use egui::{
+    plot::{PlotBounds, PlotPoint, PlotUi, Polygon},
+    Color32,
+};
+
+/// Dragging less than this many pixels is a click rather than a selection.
+const MIN_DRAG_PIXELS: f32 = 4.0;
+
+/// A rectangle dragged out with the command modifier held to select every
+/// element it touches. The plot must not pan while the modifier is held.
+#[derive(Clone, Copy, Debug, Default)]
+pub(super) struct RubberBand {
+    start: Option<PlotPoint>,
+}
+
+impl RubberBand {
+    /// Track the drag and draw the rectangle. Returns the dragged out area
+    /// once the button is released.
+    pub(super) fn update(&mut self, plot_ui: &mut PlotUi) -> Option<PlotBounds> {
+        let (pointer, pressed, down, command) = plot_ui.ctx().input(|i| {
+            (
+                i.pointer.hover_pos(),
+                i.pointer.primary_pressed(),
+                i.pointer.primary_down(),
+                i.modifiers.command,
+            )
+        });
+        // The plot does not move while dragging, so there is no need to
+        // compensate for the drag like `pointer_coordinate` does.
+        let end = plot_ui.plot_from_screen(pointer?);
+        if pressed && command && plot_ui.plot_hovered() {
+            self.start = Some(end);
+        }
+        let start = self.start?;
+        let area = PlotBounds::from_min_max(
+            [start.x.min(end.x), start.y.min(end.y)],
+            [start.x.max(end.x), start.y.max(end.y)],
+        );
+        if down {
+            let [min_x, min_y] = area.min();
+            let [max_x, max_y] = area.max();
+            plot_ui.polygon(
+                Polygon::new(vec![
+                    [min_x, min_y],
+                    [max_x, min_y],
+                    [max_x, max_y],
+                    [min_x, max_y],
+                ])
+                .color(Color32::LIGHT_BLUE),
+            );
+            return None;
+        }
+        self.start = None;
+        let dragged = plot_ui.screen_from_plot(start) - plot_ui.screen_from_plot(end);
+        (dragged.length() >= MIN_DRAG_PIXELS).then_some(area)
+    }
+}
@@ -0,0 +1,189 @@
+use egui::{Context, Key, RichText, ScrollArea, TextEdit};
+use regex::{Regex, RegexBuilder};
+
+use super::ancestors::AncestorGraph;
+use crate::handle::{Handle, LiteralView};
+
+/// Hits listed at most, so a pattern matching everything stays quick to
+/// draw.
+const MAX_LISTED: usize = 500;
+
+/// A regular expression matched against the text of every handle in the
+/// ancestry tree, with the hits to step through.
+#[derive(Clone, Default)]
+pub(super) struct Search {
+    open: bool,
+    pattern: String,
+    match_case: bool,
+    /// The pattern compiled, or why it does not compile, for the pattern and
+    /// case it was compiled with.
+    compiled: Option<(String, bool, Result<Regex, String>)>,
+    /// Index of the hit last gone to.
+    current: usize,
+}
+
+/// A handle whose text matches, with the text that did.
+#[derive(Debug, PartialEq)]
+struct Hit {
+    handle: Handle,
+    text: String,
+}
+
+impl Search {
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    /// The search window, while open. Returns a hit to go to, if one was
+    /// clicked or stepped to.
+    pub fn show(&mut self, ctx: &Context, ancestry: &AncestorGraph) -> Option<Handle> {
+        if !self.open {
+            return None;
+        }
+        let mut target = None;
+        let mut open = self.open;
+        egui::Window::new("Search")
+            .open(&mut open)
+            .default_size([420.0, 320.0])
+            .show(ctx, |ui| {
+                let (edited, entered) = ui
+                    .horizontal(|ui| {
+                        let field = ui.add(
+                            TextEdit::singleline(&mut self.pattern)
+                                .hint_text("regex")
+                                .desired_width(220.0),
+                        );
+                        ui.checkbox(&mut self.match_case, "Match case");
+                        let entered = field.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+                        (field.changed(), entered)
+                    })
+                    .inner;
+                let regex = match self.regex() {
+                    Ok(regex) => regex,
+                    Err(e) => {
+                        ui.colored_label(ui.visuals().error_fg_color, e);
+                        return;
+                    }
+                };
+                if self.pattern.is_empty() {
+                    ui.weak("Matches hex, the description and the decoded bytes of literals.");
+                    return;
+                }
+                let hits = search(&regex, ancestry.subtrees().into_iter().map(|(h, _)| h));
+                if edited {
+                    self.current = 0;
+                }
+                if hits.is_empty() {
+                    ui.label("No handle in the ancestry tree matches.");
+                    return;
+                }
+                self.current = self.current.min(hits.len() - 1);
+                let step = ui
+                    .horizontal(|ui| {
+                        let previous = ui.button("◀ Previous").clicked();
+                        let next = ui.button("Next ▶").clicked() || entered;
+                        ui.label(format!("{} of {}", self.current + 1, hits.len()));
+                        if previous {
+                            Some(self.current.checked_sub(1).unwrap_or(hits.len() - 1))
+                        } else if next {
+                            Some((self.current + 1) % hits.len())
+                        } else {
+                            None
+                        }
+                    })
+                    .inner;
+                if let Some(index) = step {
+                    self.current = index;
+                    target = Some(hits[index].handle.clone());
+                }
+                ui.separator();
+                ScrollArea::vertical().show(ui, |ui| {
+                    for (index, hit) in hits.iter().enumerate().take(MAX_LISTED) {
+                        let text = RichText::new(&hit.text).monospace();
+                        if ui
+                            .selectable_label(index == self.current, text)
+                            .on_hover_text(hit.handle.to_hex())
+                            .clicked()
+                        {
+                            self.current = index;
+                            target = Some(hit.handle.clone());
+                        }
+                    }
+                    if hits.len() > MAX_LISTED {
+                        ui.weak(format!("and {} more", hits.len() - MAX_LISTED));
+                    }
+                });
+            });
+        self.open = open;
+        target
+    }
+
+    /// The pattern compiled, compiling it again only once it changed.
+    fn regex(&mut self) -> Result<Regex, String> {
+        let stale = self.compiled.as_ref().map_or(true, |(pattern, case, _)| {
+            *pattern != self.pattern || *case != self.match_case
+        });
+        if stale {
+            let regex = RegexBuilder::new(&self.pattern)
+                .case_insensitive(!self.match_case)
+                .build()
+                .map_err(|e| e.to_string());
+            self.compiled = Some((self.pattern.clone(), self.match_case, regex));
+        }
+        self.compiled.as_ref().unwrap().2.clone()
+    }
+}
+
+/// The handles in `handles` whose text `regex` matches, in order, each
+/// once.
+fn search<'a>(regex: &Regex, handles: impl IntoIterator<Item = &'a Handle>) -> Vec<Hit> {
+    handles
+        .into_iter()
+        .filter_map(|handle| {
+            let text = texts(handle).find(|text| regex.is_match(text))?;
+            Some(Hit {
+                handle: handle.clone(),
+                text,
+            })
+        })
+        .collect()
+}
+
+/// Everything a handle is shown as: its hex, its description as drawn in
+/// the graph, and the bytes of a literal read every way they can be.
+fn texts(handle: &Handle) -> impl Iterator<Item = String> + '_ {
+    let views = LiteralView::ALL
+        .into_iter()
+        .filter_map(|view| handle.literal_as(view).ok());
+    [handle.to_hex(), handle.to_string()]
+        .into_iter()
+        .chain(views)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handle::Accessibility;
+
+    #[test]
+    fn matches_hex_descriptions_and_literals() {
+        let local = Handle::from_hex("d9-0-4-100000000000000").unwrap();
+        let word = Handle::literal(b"unused", Accessibility::Strict).unwrap();
+        let number = Handle::literal(&1234u64.to_le_bytes(), Accessibility::Strict).unwrap();
+        let handles = [&local, &word, &number];
+        let regex = |pattern: &str| Regex::new(pattern).unwrap();
+
+        let hits = search(&regex("^d9-"), handles);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].handle, local);
+        assert_eq!(search(&regex("local id"), handles).len(), 1);
+        let hits = search(&regex("\"unu"), handles);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].handle, word);
+        // Decoded as a u64 only by the literal views.
+        let hits = search(&regex("^1234$"), handles);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].text, "1234");
+        assert!(search(&regex("shallow"), handles).is_empty());
+    }
+}
@@ -0,0 +1,117 @@
+use std::ops::{Deref, DerefMut, RangeInclusive};
+use std::sync::Arc;
+
+use egui::{
+    plot::{
+        items::{
+            values::{ClosestElem, PlotGeometry},
+            PlotConfig, PlotItem,
+        },
+        Cursor, LabelFormatter, PlotBounds, PlotTransform,
+    },
+    Color32, Pos2, Shape, Ui,
+};
+
+/// A graph behind an `Arc`, so that handing it to a plot each frame copies a
+/// pointer rather than every element and mesh.
+///
+/// Changes go to the graph in place while nothing else holds it. The plot
+/// drops its view by the end of each frame, so the graph is only copied when
+/// it changes while a view of it is still being drawn.
+#[derive(Clone, Debug, Default)]
+pub(super) struct Shared<T>(Arc<T>);
+
+impl<T> Shared<T> {
+    pub(super) fn new(graph: T) -> Self {
+        Self(Arc::new(graph))
+    }
+
+    /// The graph as it is now, to add to a plot.
+    pub(super) fn view(&self) -> View<T> {
+        View(self.0.clone())
+    }
+}
+
+impl<T> Deref for Shared<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Clone> DerefMut for Shared<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        Arc::make_mut(&mut self.0)
+    }
+}
+
+/// A graph as it was when handed to a plot, which later changes leave alone.
+///
+/// The plot calls `initialize` and `highlight` on it every frame. The graphs
+/// ignore both, so the view does too rather than copy the graph to pass them
+/// on.
+pub(super) struct View<T>(Arc<T>);
+
+impl<T: PlotItem> PlotItem for View<T> {
+    fn shapes(&self, ui: &mut Ui, transform: &PlotTransform, shapes: &mut Vec<Shape>) {
+        self.0.shapes(ui, transform, shapes);
+    }
+
+    fn initialize(&mut self, _x_range: RangeInclusive<f64>) {}
+
+    fn name(&self) -> &str {
+        self.0.name()
+    }
+
+    fn color(&self) -> Color32 {
+        self.0.color()
+    }
+
+    fn highlight(&mut self) {}
+
+    fn highlighted(&self) -> bool {
+        self.0.highlighted()
+    }
+
+    fn geometry(&self) -> PlotGeometry<'_> {
+        self.0.geometry()
+    }
+
+    fn bounds(&self) -> PlotBounds {
+        self.0.bounds()
+    }
+
+    fn find_closest(&self, point: Pos2, transform: &PlotTransform) -> Option<ClosestElem> {
+        self.0.find_closest(point, transform)
+    }
+
+    fn on_hover(
+        &self,
+        elem: ClosestElem,
+        shapes: &mut Vec<Shape>,
+        cursors: &mut Vec<Cursor>,
+        plot: &PlotConfig<'_>,
+        label_formatter: &LabelFormatter,
+    ) {
+        self.0
+            .on_hover(elem, shapes, cursors, plot, label_formatter);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::timeline::TimelineGraph;
+    use super::*;
+
+    #[test]
+    fn drawing_a_view_leaves_one_graph() {
+        let graph: Shared<TimelineGraph> = Shared::default();
+        let mut view = graph.view();
+        view.initialize(0.0..=1.0);
+        view.highlight();
+        assert!(Arc::ptr_eq(&graph.0, &view.0));
+        drop(view);
+        assert_eq!(Arc::strong_count(&graph.0), 1);
+    }
+}
@@ -0,0 +1,95 @@
+use crate::plot::Element;
+
+/// How much room the ancestry tree leaves between and around its elements.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct Spacing {
+    /// How far above its child a generation is drawn, relative to how wide
+    /// the generation's elements are.
+    pub(crate) aspect: f64,
+    /// Height added above every generation, in widths of the target, so
+    /// that deep generations do not flatten into one line.
+    pub(crate) gap: f64,
+    /// Room left around the text of each element, in widths of a full
+    /// sized element.
+    pub(crate) padding: f64,
+}
+
+impl Default for Spacing {
+    fn default() -> Self {
+        Self {
+            aspect: 0.5,
+            gap: 0.0,
+            padding: Element::RECT_EXTENSION,
+        }
+    }
+}
+
+impl Spacing {
+    /// How far above its child a generation drawn at `scale` is placed.
+    pub(crate) fn rise(&self, scale: f64) -> f64 {
+        scale * self.aspect + self.gap
+    }
+
+    /// Controls for each spacing. Returns whether any changed.
+    pub(crate) fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        egui::Grid::new("spacing").num_columns(2).show(ui, |ui| {
+            ui.label("Generation height: ");
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut self.aspect)
+                        .clamp_range(0.1..=4.0)
+                        .speed(0.01)
+                        .suffix(" × width"),
+                )
+                .on_hover_text("How tall each generation is for how wide its elements are")
+                .changed();
+            ui.end_row();
+            ui.label("Generation gap: ");
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut self.gap)
+                        .clamp_range(0.0..=1.0)
+                        .speed(0.005),
+                )
+                .on_hover_text(
+                    "Height added above every generation, so deep trees do not flatten out",
+                )
+                .changed();
+            ui.end_row();
+            ui.label("Padding: ");
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut self.padding)
+                        .clamp_range(0.0..=0.5)
+                        .speed(0.002),
+                )
+                .on_hover_text("Room left around the text of each element")
+                .changed();
+            ui.end_row();
+        });
+        if ui.button("Reset").clicked() && *self != Self::default() {
+            *self = Self::default();
+            changed = true;
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gaps_keep_deep_generations_apart() {
+        let spacing = Spacing::default();
+        assert_eq!(spacing.rise(1.0), 0.5);
+        assert_eq!(spacing.rise(1.0 / 1024.0), 0.5 / 1024.0);
+        let spaced = Spacing {
+            gap: 0.1,
+            ..spacing
+        };
+        assert!(spaced.rise(1.0 / 1024.0) > 0.1);
+    }
+}
@@ -0,0 +1,146 @@
+use eframe::epaint::{util::FloatOrd, RectShape};
+use egui::{
+    plot::{
+        items::{
+            values::{ClosestElem, PlotGeometry},
+            PlotConfig, PlotItem,
+        },
+        LabelFormatter, PlotBounds, PlotPoint, PlotTransform,
+    },
+    Color32, Pos2, Rect, Shape, Stroke, Ui,
+};
+
+use crate::{
+    handle::{Handle, TaskTime},
+    theme::Palette,
+};
+
+/// Tasks laid out along a horizontal time axis, one row per task in the
+/// order they were received. Row `i` is centered at y = -i.
+#[derive(Clone, Default)]
+pub(super) struct TimelineGraph {
+    tasks: Vec<TaskTime>,
+    /// A handle whose bars should stand out.
+    focus: Option<Handle>,
+    palette: Palette,
+}
+
+impl PlotItem for TimelineGraph {
+    fn shapes(&self, ui: &mut Ui, transform: &PlotTransform, shapes: &mut Vec<Shape>) {
+        let fg_stroke_color = ui.visuals().widgets.active.fg_stroke.color;
+        for (row, task_time) in self.tasks.iter().enumerate() {
+            let rect = Self::bar_rect(row, task_time, transform);
+            shapes.push(
+                RectShape::filled(rect, 1.0, self.palette.operation(task_time.task.operation))
+                    .into(),
+            );
+            if self.focus.as_ref() == Some(&task_time.task.handle) {
+                shapes.push(Shape::rect_stroke(
+                    rect,
+                    1.0,
+                    Stroke::new(2.0, fg_stroke_color),
+                ));
+            }
+        }
+    }
+
+    fn initialize(&mut self, _x_range: std::ops::RangeInclusive<f64>) {}
+
+    fn name(&self) -> &str {
+        "Task Timeline"
+    }
+
+    fn color(&self) -> Color32 {
+        Color32::TRANSPARENT
+    }
+
+    fn highlight(&mut self) {}
+
+    fn highlighted(&self) -> bool {
+        false
+    }
+
+    fn geometry(&self) -> PlotGeometry<'_> {
+        PlotGeometry::Rects
+    }
+
+    /// Search for the closest bar based on squared distance to its rect.
+    fn find_closest(&self, point: Pos2, transform: &PlotTransform) -> Option<ClosestElem> {
+        self.tasks
+            .iter()
+            .enumerate()
+            .map(|(index, task_time)| ClosestElem {
+                index,
+                dist_sq: Self::bar_rect(index, task_time, transform).distance_sq_to_pos(point),
+            })
+            .min_by_key(|e| e.dist_sq.ord())
+    }
+
+    fn on_hover(
+        &self,
+        elem: ClosestElem,
+        shapes: &mut Vec<Shape>,
+        _: &mut Vec<egui::plot::Cursor>,
+        plot: &PlotConfig<'_>,
+        _: &LabelFormatter,
+    ) {
+        let Some(task_time) = self.tasks.get(elem.index) else {
+            return;
+        };
+        let rect = Self::bar_rect(elem.index, task_time, plot.transform);
+        shapes
+            .push(RectShape::filled(rect, 1.0, self.palette.highlight.gamma_multiply(0.2)).into());
+    }
+
+    fn bounds(&self) -> PlotBounds {
+        let mut bounds = PlotBounds::NOTHING;
+        for (row, task_time) in self.tasks.iter().enumerate() {
+            bounds.merge(&Self::bar_bounds(row, task_time));
+        }
+        bounds
+    }
+}
+
+impl TimelineGraph {
+    const BAR_HEIGHT: f64 = 0.8;
+
+    pub fn set_tasks(&mut self, tasks: Vec<TaskTime>) {
+        self.tasks = tasks;
+    }
+
+    pub fn set_focus(&mut self, focus: Option<Handle>) {
+        self.focus = focus;
+    }
+
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Returns the handle of the bar under `coords`, if any.
+    pub fn handle_at(&self, coords: PlotPoint, closest_elem: ClosestElem) -> Option<&Handle> {
+        let task_time = self.tasks.get(closest_elem.index)?;
+        let bounds = Self::bar_bounds(closest_elem.index, task_time);
+        let [min_x, min_y] = bounds.min();
+        let [max_x, max_y] = bounds.max();
+        let p = coords;
+        let contains = min_x <= p.x && p.x <= max_x && min_y <= p.y && p.y <= max_y;
+        contains.then_some(&task_time.task.handle)
+    }
+
+    fn bar_bounds(row: usize, task_time: &TaskTime) -> PlotBounds {
+        let center = -(row as f64);
+        PlotBounds::from_min_max(
+            [task_time.start, center - Self::BAR_HEIGHT / 2.0],
+            [task_time.end, center + Self::BAR_HEIGHT / 2.0],
+        )
+    }
+
+    fn bar_rect(row: usize, task_time: &TaskTime, transform: &PlotTransform) -> Rect {
+        let bounds = Self::bar_bounds(row, task_time);
+        transform.rect_from_values(&bounds.min().into(), &bounds.max().into())
+    }
+}
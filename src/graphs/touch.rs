@@ -0,0 +1,184 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use egui::{
+    plot::{PlotBounds, PlotPoint},
+    Context, Event, Pos2, Response, TouchId, TouchPhase, Vec2,
+};
+
+/// How far in points beside an element a touch still lands on it, since a
+/// fingertip covers far more than a mouse pointer.
+const REACH: f32 = 16.0;
+
+/// How long a finger is held still before the element under it opens its
+/// menu. As long as egui waits before a press stops being a click, so that
+/// a long press never clicks as well.
+const LONG_PRESS_SECONDS: f64 = 0.6;
+
+/// How far in points a finger may wander and still be held still.
+const STILL_DISTANCE: f32 = 6.0;
+
+/// Whether the graphs are used with a touch screen or pen, and the fingers
+/// on it.
+#[derive(Clone, Debug, Default)]
+pub(super) struct Touch {
+    /// Whether the latest pointer input came from a touch.
+    active: bool,
+    /// Where each finger on the screen is.
+    fingers: BTreeMap<TouchId, Pos2>,
+    /// When the press that last opened a menu began, so that it opens once.
+    long_pressed: Option<f64>,
+}
+
+impl Touch {
+    /// Follow the fingers on the screen. Called once a frame.
+    pub fn update(&mut self, ctx: &Context) {
+        ctx.input(|i| {
+            let mut moved = false;
+            let mut touched = false;
+            for event in &i.events {
+                match *event {
+                    Event::Touch { id, phase, pos, .. } => {
+                        touched = true;
+                        match phase {
+                            TouchPhase::Start | TouchPhase::Move => {
+                                self.fingers.insert(id, pos);
+                            }
+                            TouchPhase::End | TouchPhase::Cancel => {
+                                self.fingers.remove(&id);
+                            }
+                        }
+                    }
+                    Event::PointerMoved(_) => moved = true,
+                    _ => {}
+                }
+            }
+            // Touches move the pointer too, so only moves without one are
+            // from a mouse.
+            if touched {
+                self.active = true;
+            } else if moved {
+                self.active = false;
+                self.fingers.clear();
+            }
+        });
+    }
+
+    /// Whether an element `dist_sq` squared points from the pointer, as
+    /// `find_closest` measures, is under it.
+    pub fn reaches(&self, dist_sq: f32) -> bool {
+        dist_sq <= 0.0 || self.active && dist_sq <= REACH * REACH
+    }
+
+    /// Whether two or more fingers are on the screen.
+    pub fn pinching(&self) -> bool {
+        self.fingers.len() > 1
+    }
+
+    /// The point between the fingers pinching, to zoom around.
+    pub fn pinch_center(&self) -> Option<Pos2> {
+        if !self.pinching() {
+            return None;
+        }
+        let sum = self
+            .fingers
+            .values()
+            .fold(Vec2::ZERO, |sum, pos| sum + pos.to_vec2());
+        Some((sum / self.fingers.len() as f32).to_pos2())
+    }
+
+    /// Whether a finger has just been held still on `response` for long
+    /// enough to open a menu.
+    pub fn long_press(&mut self, response: &Response) -> bool {
+        if !self.active || self.pinching() || !response.is_pointer_button_down_on() {
+            return false;
+        }
+        let (start, origin, pos, now) = response.ctx.input(|i| {
+            (
+                i.pointer.press_start_time(),
+                i.pointer.press_origin(),
+                i.pointer.interact_pos(),
+                i.time,
+            )
+        });
+        let (Some(start), Some(origin), Some(pos)) = (start, origin, pos) else {
+            return false;
+        };
+        if origin.distance(pos) > STILL_DISTANCE || self.long_pressed == Some(start) {
+            return false;
+        }
+        let held = now - start;
+        if held < LONG_PRESS_SECONDS {
+            response
+                .ctx
+                .request_repaint_after(Duration::from_secs_f64(LONG_PRESS_SECONDS - held));
+            return false;
+        }
+        self.long_pressed = Some(start);
+        true
+    }
+}
+
+/// `coords` moved onto the nearest point of `bounds`, for touches that land
+/// beside an element.
+pub(super) fn clamp(coords: PlotPoint, bounds: PlotBounds) -> PlotPoint {
+    let ([min_x, min_y], [max_x, max_y]) = (bounds.min(), bounds.max());
+    PlotPoint::new(coords.x.clamp(min_x, max_x), coords.y.clamp(min_y, max_y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::{pos2, RawInput, TouchDeviceId};
+
+    fn touch(id: u64, phase: TouchPhase, pos: Pos2) -> Event {
+        Event::Touch {
+            device_id: TouchDeviceId(0),
+            id: TouchId(id),
+            phase,
+            pos,
+            force: 0.0,
+        }
+    }
+
+    #[test]
+    fn follows_fingers_until_a_mouse_moves() {
+        let ctx = Context::default();
+        let mut state = Touch::default();
+        let frame = |state: &mut Touch, events: Vec<Event>| {
+            let input = RawInput {
+                events,
+                ..Default::default()
+            };
+            let _ = ctx.run(input, |ctx| state.update(ctx));
+        };
+        frame(
+            &mut state,
+            vec![
+                touch(1, TouchPhase::Start, pos2(0.0, 0.0)),
+                touch(2, TouchPhase::Start, pos2(10.0, 20.0)),
+            ],
+        );
+        assert!(state.reaches(100.0));
+        assert_eq!(state.pinch_center(), Some(pos2(5.0, 10.0)));
+
+        frame(
+            &mut state,
+            vec![touch(2, TouchPhase::End, pos2(10.0, 20.0))],
+        );
+        assert!(!state.pinching());
+        assert!(!state.reaches(REACH * REACH * 2.0));
+
+        frame(&mut state, vec![Event::PointerMoved(pos2(1.0, 1.0))]);
+        assert!(!state.reaches(100.0));
+        assert!(state.reaches(0.0));
+    }
+
+    #[test]
+    fn clamps_onto_bounds() {
+        let bounds = PlotBounds::from_min_max([0.0, 0.0], [2.0, 1.0]);
+        let clamped = clamp(PlotPoint::new(3.0, -1.0), bounds);
+        assert_eq!((clamped.x, clamped.y), (2.0, 0.0));
+        let inside = clamp(PlotPoint::new(1.0, 0.5), bounds);
+        assert_eq!((inside.x, inside.y), (1.0, 0.5));
+    }
+}
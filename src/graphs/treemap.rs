@@ -0,0 +1,285 @@
+use egui::{pos2, vec2, Align2, Color32, Context, FontId, Rect, Sense, Shape, Stroke, Ui};
+
+use super::ancestors::AncestorGraph;
+use crate::handle::{Handle, Object};
+
+/// Room left around and above each subtree for its name.
+const HEADER: f32 = 16.0;
+const PADDING: f32 = 2.0;
+
+/// The handles of one object type in one subtree.
+#[derive(Clone, Debug, PartialEq)]
+struct Block {
+    object: Object,
+    /// Bytes of Blobs, entries of the rest.
+    size: u64,
+    count: usize,
+}
+
+/// The ancestry of one parent of the target, or the target alone.
+#[derive(Clone, Debug, PartialEq)]
+struct Group {
+    root: Option<Handle>,
+    size: u64,
+    /// Largest first, leaving out types with nothing in them.
+    blocks: Vec<Block>,
+}
+
+/// Sum the size fields of `handles` by the subtree each is in and by
+/// object type, largest first. Subtrees with nothing in them are left out.
+fn aggregate(handles: &[(&Handle, Option<&Handle>)]) -> Vec<Group> {
+    let mut groups: Vec<Group> = vec![];
+    for (handle, root) in handles {
+        let index = match groups.iter().position(|g| g.root.as_ref() == *root) {
+            Some(index) => index,
+            None => {
+                groups.push(Group {
+                    root: root.cloned(),
+                    size: 0,
+                    blocks: Object::ALL
+                        .map(|object| Block {
+                            object,
+                            size: 0,
+                            count: 0,
+                        })
+                        .to_vec(),
+                });
+                groups.len() - 1
+            }
+        };
+        let group = &mut groups[index];
+        let size = handle.size;
+        group.size += size;
+        let block = group
+            .blocks
+            .iter_mut()
+            .find(|block| block.object == handle.object())
+            .expect("every object type has a block");
+        block.size += size;
+        block.count += 1;
+    }
+    groups.retain(|group| group.size > 0);
+    for group in &mut groups {
+        group.blocks.retain(|block| block.size > 0);
+        group
+            .blocks
+            .sort_by_key(|block| std::cmp::Reverse(block.size));
+    }
+    groups.sort_by_key(|group| std::cmp::Reverse(group.size));
+    groups
+}
+
+/// Split `rect` into one rect per weight, in order, with areas in
+/// proportion to the weights and as close to square as the squarified
+/// layout gets them. Weights should be positive and largest first.
+fn squarify(weights: &[f64], rect: Rect) -> Vec<Rect> {
+    let total: f64 = weights.iter().sum();
+    let scale = f64::from(rect.width() * rect.height()) / total;
+    let areas: Vec<f64> = weights.iter().map(|weight| weight * scale).collect();
+    let mut rects = Vec::with_capacity(areas.len());
+    let mut free = rect;
+    let mut start = 0;
+    while start < areas.len() {
+        let side = f64::from(free.width().min(free.height()));
+        // Grow the row while that makes its thinnest rect less thin.
+        let mut end = start + 1;
+        while end < areas.len()
+            && worst_ratio(&areas[start..=end], side) <= worst_ratio(&areas[start..end], side)
+        {
+            end += 1;
+        }
+        let row = &areas[start..end];
+        let thickness = row.iter().sum::<f64>() / side;
+        let mut offset = 0.0;
+        // Rows run along the shorter side of what is left.
+        let vertical = free.width() >= free.height();
+        for area in row {
+            let length = (area / thickness) as f32;
+            rects.push(if vertical {
+                Rect::from_min_size(
+                    pos2(free.left(), free.top() + offset),
+                    vec2(thickness as f32, length),
+                )
+            } else {
+                Rect::from_min_size(
+                    pos2(free.left() + offset, free.top()),
+                    vec2(length, thickness as f32),
+                )
+            });
+            offset += length;
+        }
+        if vertical {
+            free.min.x += thickness as f32;
+        } else {
+            free.min.y += thickness as f32;
+        }
+        start = end;
+    }
+    rects
+}
+
+/// How far from square the thinnest rect of `row` is, laid along `side`.
+fn worst_ratio(row: &[f64], side: f64) -> f64 {
+    let sum: f64 = row.iter().sum();
+    let (min, max) = row
+        .iter()
+        .fold((f64::INFINITY, 0.0_f64), |(min, max), area| {
+            (min.min(*area), max.max(*area))
+        });
+    f64::max(
+        side * side * max / (sum * sum),
+        sum * sum / (side * side * min),
+    )
+}
+
+fn color(object: Object) -> Color32 {
+    match object {
+        Object::Blob => Color32::from_rgb(70, 130, 180),
+        Object::Tree => Color32::from_rgb(60, 160, 110),
+        Object::Thunk => Color32::from_rgb(200, 140, 50),
+        Object::Tag => Color32::from_rgb(150, 100, 190),
+    }
+}
+
+/// A window with the size fields of every handle in the ancestry tree,
+/// summed by the parent of the target they descend from and by object type.
+#[derive(Clone, Default)]
+pub(super) struct Treemap {
+    open: bool,
+}
+
+impl Treemap {
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    /// The window, while open. Returns the root of the subtree clicked.
+    pub fn show(&mut self, ctx: &Context, ancestry: &AncestorGraph) -> Option<Handle> {
+        if !self.open {
+            return None;
+        }
+        let mut clicked = None;
+        egui::Window::new("Sizes")
+            .open(&mut self.open)
+            .default_size([480.0, 360.0])
+            .show(ctx, |ui| {
+                let groups = aggregate(&ancestry.subtrees());
+                ui.horizontal_wrapped(|ui| {
+                    for object in Object::ALL {
+                        ui.colored_label(color(object), format!("■ {object}"));
+                    }
+                    ui.weak("Blobs count bytes, the rest entries.");
+                });
+                if groups.is_empty() {
+                    ui.label("No handle in the ancestry tree has a size yet.");
+                    return;
+                }
+                clicked = treemap_ui(ui, &groups);
+            });
+        clicked
+    }
+}
+
+/// Draw `groups` over the rest of `ui`. Returns the root of the group
+/// clicked.
+fn treemap_ui(ui: &mut Ui, groups: &[Group]) -> Option<Handle> {
+    let (rect, response) = ui.allocate_exact_size(ui.available_size(), Sense::click());
+    let weights: Vec<f64> = groups.iter().map(|group| group.size as f64).collect();
+    let group_rects = squarify(&weights, rect);
+    let visuals = ui.visuals();
+    let mut shapes = vec![];
+    let mut hovered = None;
+    let pointer = response.hover_pos();
+    for (group, group_rect) in groups.iter().zip(&group_rects) {
+        let name = match &group.root {
+            Some(root) => root.to_hex(),
+            None => "target".to_string(),
+        };
+        shapes.push(Shape::rect_stroke(
+            *group_rect,
+            0.0,
+            visuals.widgets.noninteractive.bg_stroke,
+        ));
+        let inner = group_rect.shrink(PADDING);
+        if !inner.is_positive() {
+            continue;
+        }
+        let has_header = inner.height() > 2.0 * HEADER;
+        let blocks_rect = if has_header {
+            // Long names are cut at the edge of their subtree.
+            ui.painter().with_clip_rect(inner).text(
+                inner.left_top(),
+                Align2::LEFT_TOP,
+                format!("{name} ({})", group.size),
+                FontId::proportional(11.0),
+                visuals.text_color(),
+            );
+            Rect::from_min_max(pos2(inner.left(), inner.top() + HEADER), inner.max)
+        } else {
+            inner
+        };
+        let weights: Vec<f64> = group.blocks.iter().map(|block| block.size as f64).collect();
+        for (block, block_rect) in group.blocks.iter().zip(squarify(&weights, blocks_rect)) {
+            let hover = pointer.map_or(false, |pos| block_rect.contains(pos));
+            let fill = if hover {
+                color(block.object)
+            } else {
+                color(block.object).gamma_multiply(0.7)
+            };
+            shapes.push(Shape::rect_filled(block_rect, 0.0, fill));
+            shapes.push(Shape::rect_stroke(
+                block_rect,
+                0.0,
+                Stroke::new(1.0, visuals.extreme_bg_color),
+            ));
+            if hover {
+                hovered = Some((name.clone(), group, block));
+            }
+        }
+    }
+    ui.painter().extend(shapes);
+    let (name, group, block) = hovered?;
+    let response = response.on_hover_text(format!(
+        "{name}: {} {}s, size {} of {}",
+        block.count, block.object, block.size, group.size
+    ));
+    response.clicked().then(|| group.root.clone()).flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handle::Accessibility;
+
+    #[test]
+    fn squarifies_in_proportion() {
+        let rect = Rect::from_min_size(pos2(0.0, 0.0), vec2(6.0, 4.0));
+        let weights = [6.0, 6.0, 4.0, 3.0, 2.0, 2.0, 1.0];
+        let rects = squarify(&weights, rect);
+        assert_eq!(rects.len(), weights.len());
+        for (weight, rect) in weights.iter().zip(&rects) {
+            assert!((f64::from(rect.area()) - weight).abs() < 1e-3);
+            assert!(Rect::from_min_size(pos2(0.0, 0.0), vec2(6.0, 4.0))
+                .expand(1e-3)
+                .contains_rect(*rect));
+        }
+        // The first row holds the two largest, side by side on the left.
+        assert_eq!(rects[0], Rect::from_min_max(pos2(0.0, 0.0), pos2(3.0, 2.0)));
+        assert_eq!(rects[1], Rect::from_min_max(pos2(0.0, 2.0), pos2(3.0, 4.0)));
+    }
+
+    #[test]
+    fn sums_by_subtree_and_object() {
+        let blob = |byte: u8, size| {
+            Handle::canonical(&[byte; 32], size, Object::Blob, Accessibility::Strict).unwrap()
+        };
+        let (a, b, c) = (blob(1, 3), blob(2, 5), blob(3, 9));
+        let groups = aggregate(&[(&a, None), (&b, Some(&a)), (&c, Some(&a))]);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].root, Some(a.clone()));
+        assert_eq!(groups[0].size, 14);
+        assert_eq!(groups[0].blocks.len(), 1);
+        assert_eq!(groups[0].blocks[0].count, 2);
+        assert_eq!(groups[1].root, None);
+    }
+}
@@ -0,0 +1,276 @@
+use egui::{
+    plot::{PlotBounds, PlotPoint, PlotUi},
+    Context, Key, PointerButton, Pos2, Ui, Vec2,
+};
+
+/// A change to a plot's viewport, applied while the plot is being shown.
+#[derive(Clone, Copy, Debug)]
+pub(super) enum ViewCommand {
+    /// Show the whole graph.
+    Fit,
+    /// Keep the zoom level and move the view so `point` is in the center.
+    CenterOn(PlotPoint),
+    /// Zoom so the selected element fills about `FOCUS_FRACTION` of the view.
+    FocusSelected,
+}
+
+/// Fraction of the view the selected element takes up after focusing it.
+const FOCUS_FRACTION: f64 = 0.3;
+/// Space left around the graph when fitting it, relative to its size.
+const FIT_MARGIN: f64 = 0.05;
+
+impl ViewCommand {
+    /// Commands triggered by input on the plot: double-clicking centers on
+    /// the element under the pointer or fits the graph if there is none, and
+    /// `F` focuses the selected element.
+    pub(super) fn from_input(plot_ui: &PlotUi, element_center: Option<PlotPoint>) -> Option<Self> {
+        if !plot_ui.plot_hovered() {
+            return None;
+        }
+        let (double_clicked, focus_pressed) = plot_ui.ctx().input(|i| {
+            (
+                i.pointer.button_double_clicked(PointerButton::Primary),
+                i.key_pressed(Key::F),
+            )
+        });
+        if double_clicked {
+            Some(element_center.map_or(Self::Fit, Self::CenterOn))
+        } else if focus_pressed {
+            Some(Self::FocusSelected)
+        } else {
+            None
+        }
+    }
+
+    /// Move the viewport of `plot_ui`. `graph` is the extent of everything in
+    /// the plot and `selected` that of the selected element, if any.
+    pub(super) fn apply(
+        self,
+        plot_ui: &mut PlotUi,
+        graph: PlotBounds,
+        selected: Option<PlotBounds>,
+    ) {
+        let bounds = match self {
+            ViewCommand::Fit => {
+                if !graph.is_valid() {
+                    return;
+                }
+                let margin = [graph.width() * FIT_MARGIN, graph.height() * FIT_MARGIN];
+                PlotBounds::from_min_max(
+                    [graph.min()[0] - margin[0], graph.min()[1] - margin[1]],
+                    [graph.max()[0] + margin[0], graph.max()[1] + margin[1]],
+                )
+            }
+            ViewCommand::CenterOn(point) => {
+                let current = plot_ui.plot_bounds();
+                centered(point, current.width(), current.height())
+            }
+            ViewCommand::FocusSelected => {
+                let Some(selected) = selected.filter(PlotBounds::is_valid) else {
+                    log::info!("Nothing selected to focus");
+                    return;
+                };
+                centered(
+                    selected.center(),
+                    selected.width() / FOCUS_FRACTION,
+                    selected.height() / FOCUS_FRACTION,
+                )
+            }
+        };
+        plot_ui.set_plot_bounds(bounds);
+    }
+}
+
+/// Plot bounds that can be saved between sessions.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub(crate) struct SavedBounds {
+    min: [f64; 2],
+    max: [f64; 2],
+}
+
+impl From<PlotBounds> for SavedBounds {
+    fn from(bounds: PlotBounds) -> Self {
+        Self {
+            min: bounds.min(),
+            max: bounds.max(),
+        }
+    }
+}
+
+impl From<SavedBounds> for PlotBounds {
+    fn from(bounds: SavedBounds) -> Self {
+        PlotBounds::from_min_max(bounds.min, bounds.max)
+    }
+}
+
+/// Where each graph rooted at one target was last looking.
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct Viewports {
+    pub(super) ancestry: Option<SavedBounds>,
+    pub(super) progress: Option<SavedBounds>,
+}
+
+/// What the mouse wheel does over a graph.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub(crate) enum WheelAction {
+    #[default]
+    Pan,
+    Zoom,
+}
+
+/// How the graphs are panned and zoomed, since the plot's own bindings are
+/// awkward for wide, shallow trees.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct Navigation {
+    pub(crate) wheel: WheelAction,
+    /// Scrolling with shift held pans sideways, for mice with one wheel.
+    pub(crate) shift_pans_sideways: bool,
+    /// Pinching a trackpad, or scrolling with the command key held, zooms.
+    pub(crate) pinch_zoom: bool,
+    /// Dragging only pans while space is held.
+    pub(crate) space_drag: bool,
+}
+
+impl Default for Navigation {
+    fn default() -> Self {
+        Self {
+            wheel: WheelAction::Pan,
+            shift_pans_sideways: true,
+            pinch_zoom: true,
+            space_drag: false,
+        }
+    }
+}
+
+/// How much one point of scrolling zooms when the wheel zooms, as egui
+/// itself does for scrolling with the command key held.
+const WHEEL_ZOOM_SPEED: f32 = 1.0 / 200.0;
+
+impl Navigation {
+    /// Whether dragging a graph pans it this frame.
+    pub(super) fn drag_pans(&self, ctx: &Context) -> bool {
+        !self.space_drag || ctx.input(|i| i.key_down(Key::Space))
+    }
+
+    /// Pan and zoom `plot_ui` from the wheel, trackpad and touch screen, in
+    /// place of the plot's own scrolling and zooming, which must be turned
+    /// off. Pinches zoom around `pinch_center`, else around the pointer.
+    pub(super) fn apply(&self, plot_ui: &mut PlotUi, pinch_center: Option<Pos2>) {
+        if !plot_ui.plot_hovered() {
+            return;
+        }
+        let (scroll, zoom, shift) = plot_ui
+            .ctx()
+            .input(|i| (i.scroll_delta, i.zoom_delta(), i.modifiers.shift));
+        let (pan, zoom) = self.pan_and_zoom(scroll, zoom, shift);
+        if zoom != 1.0 {
+            let center = pinch_center
+                .map(|pos| plot_ui.plot_from_screen(pos))
+                .or_else(|| plot_ui.pointer_coordinate());
+            if let Some(center) = center {
+                plot_ui.set_plot_bounds(zoomed(plot_ui.plot_bounds(), center, zoom));
+            }
+        }
+        if pan != Vec2::ZERO {
+            plot_ui.translate_bounds(-pan);
+        }
+    }
+
+    /// How far in points to pan and by what factor to zoom for `scroll`
+    /// points of scrolling and a `zoom` factor from pinching.
+    fn pan_and_zoom(&self, mut scroll: Vec2, zoom: f32, shift: bool) -> (Vec2, f32) {
+        let mut zoom = if self.pinch_zoom { zoom } else { 1.0 };
+        if shift && self.shift_pans_sideways {
+            scroll = Vec2::new(scroll.x + scroll.y, 0.0);
+        }
+        match self.wheel {
+            WheelAction::Pan => (scroll, zoom),
+            WheelAction::Zoom => {
+                zoom *= (scroll.y * WHEEL_ZOOM_SPEED).exp();
+                (Vec2::new(scroll.x, 0.0), zoom)
+            }
+        }
+    }
+
+    /// Controls for each binding. Returns whether any changed.
+    pub(crate) fn ui(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label("Wheel: ");
+            changed |= ui
+                .selectable_value(&mut self.wheel, WheelAction::Pan, "Pans")
+                .changed();
+            changed |= ui
+                .selectable_value(&mut self.wheel, WheelAction::Zoom, "Zooms")
+                .changed();
+        });
+        changed |= ui
+            .checkbox(&mut self.shift_pans_sideways, "Shift+wheel pans sideways")
+            .changed();
+        changed |= ui
+            .checkbox(&mut self.pinch_zoom, "Pinch zooms")
+            .on_hover_text("Pinching a trackpad, or scrolling with Ctrl held, zooms")
+            .changed();
+        changed |= ui
+            .checkbox(&mut self.space_drag, "Drag pans only with space held")
+            .changed();
+        changed
+    }
+}
+
+/// `bounds` zoomed by `factor` around `center`, which stays put.
+fn zoomed(bounds: PlotBounds, center: PlotPoint, factor: f32) -> PlotBounds {
+    let factor = factor as f64;
+    centered(
+        PlotPoint::new(
+            center.x + (bounds.center().x - center.x) / factor,
+            center.y + (bounds.center().y - center.y) / factor,
+        ),
+        bounds.width() / factor,
+        bounds.height() / factor,
+    )
+}
+
+fn centered(center: PlotPoint, width: f64, height: f64) -> PlotBounds {
+    PlotBounds::from_min_max(
+        [center.x - width / 2.0, center.y - height / 2.0],
+        [center.x + width / 2.0, center.y + height / 2.0],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wheel_pans_or_zooms() {
+        let mut navigation = Navigation::default();
+        let down = Vec2::new(0.0, 40.0);
+        assert_eq!(navigation.pan_and_zoom(down, 1.0, false), (down, 1.0));
+        assert_eq!(
+            navigation.pan_and_zoom(down, 1.5, true),
+            (Vec2::new(40.0, 0.0), 1.5)
+        );
+        navigation.pinch_zoom = false;
+        assert_eq!(navigation.pan_and_zoom(Vec2::ZERO, 1.5, false).1, 1.0);
+        navigation.wheel = WheelAction::Zoom;
+        let (pan, zoom) = navigation.pan_and_zoom(down, 1.0, false);
+        assert_eq!(pan, Vec2::ZERO);
+        assert!(zoom > 1.0);
+        // Shift still pans sideways instead of zooming.
+        assert_eq!(
+            navigation.pan_and_zoom(down, 1.0, true),
+            (Vec2::new(40.0, 0.0), 1.0)
+        );
+    }
+
+    #[test]
+    fn zooms_around_a_point() {
+        let bounds = PlotBounds::from_min_max([0.0, 0.0], [4.0, 2.0]);
+        let zoomed = zoomed(bounds, PlotPoint::new(0.0, 0.0), 2.0);
+        assert_eq!(zoomed.min(), [0.0, 0.0]);
+        assert_eq!(zoomed.max(), [2.0, 1.0]);
+    }
+}
@@ -0,0 +1,374 @@
+//! Just enough of the WebAssembly binary format to tell procedures apart:
+//! the size of each section, what a module imports and what it exports.
+
+use std::fmt::Display;
+
+use anyhow::{bail, ensure, Context, Result};
+
+pub(super) const MAGIC: &[u8] = b"\0asm";
+
+/// One section of a module.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct Section {
+    pub(super) name: String,
+    /// Size of the section's contents in bytes.
+    pub(super) size: usize,
+}
+
+/// What kind of thing is imported or exported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum External {
+    Function,
+    Table,
+    Memory,
+    Global,
+}
+
+impl Display for External {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            External::Function => "function",
+            External::Table => "table",
+            External::Memory => "memory",
+            External::Global => "global",
+        })
+    }
+}
+
+/// Something a module needs from its host.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct Import {
+    pub(super) module: String,
+    pub(super) name: String,
+    pub(super) kind: External,
+    /// The type of an imported function, as in "(i32, i64) -> i32".
+    pub(super) signature: Option<String>,
+}
+
+/// Something a module offers its host.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct Export {
+    pub(super) name: String,
+    pub(super) kind: External,
+    /// The type of an exported function, if its type could be found.
+    pub(super) signature: Option<String>,
+}
+
+/// The parts of a module the inspector shows.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(super) struct Module {
+    pub(super) sections: Vec<Section>,
+    pub(super) imports: Vec<Import>,
+    pub(super) exports: Vec<Export>,
+}
+
+/// Reads the parts of a module in order.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.offset >= self.bytes.len()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .with_context(|| format!("{len} bytes at offset {} run past the end", self.offset))?;
+        let taken = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// An unsigned LEB128 number of up to 64 bits.
+    fn u64(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..70).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7f)
+                .checked_shl(shift)
+                .context("number too large")?;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        bail!("number too long")
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        u32::try_from(self.u64()?).context("number too large")
+    }
+
+    fn len(&mut self) -> Result<usize> {
+        Ok(self.u32()? as usize)
+    }
+
+    fn name(&mut self) -> Result<String> {
+        let len = self.len()?;
+        let name = self.take(len)?;
+        Ok(String::from_utf8_lossy(name).into_owned())
+    }
+
+    /// The minimum and optional maximum of a table or memory.
+    fn skip_limits(&mut self) -> Result<()> {
+        let flags = self.byte()?;
+        self.u64()?;
+        if flags & 1 != 0 {
+            self.u64()?;
+        }
+        Ok(())
+    }
+
+    fn value_type(&mut self) -> Result<&'static str> {
+        Ok(match self.byte()? {
+            0x7f => "i32",
+            0x7e => "i64",
+            0x7d => "f32",
+            0x7c => "f64",
+            0x7b => "v128",
+            0x70 => "funcref",
+            0x6f => "externref",
+            other => bail!("unknown value type {other:#x}"),
+        })
+    }
+
+    fn value_types(&mut self) -> Result<Vec<&'static str>> {
+        (0..self.len()?).map(|_| self.value_type()).collect()
+    }
+
+    /// A function type, as in "(i32, i64) -> i32".
+    fn signature(&mut self) -> Result<String> {
+        ensure!(self.byte()? == 0x60, "malformed function type");
+        let params = self.value_types()?.join(", ");
+        let results = self.value_types()?;
+        Ok(match results.as_slice() {
+            [] => format!("({params})"),
+            [result] => format!("({params}) -> {result}"),
+            results => format!("({params}) -> ({})", results.join(", ")),
+        })
+    }
+
+    fn import(&mut self, signatures: &[String]) -> Result<Import> {
+        let module = self.name()?;
+        let name = self.name()?;
+        let kind = self.external()?;
+        let signature = match kind {
+            External::Function => signatures.get(self.len()?).cloned(),
+            External::Table => {
+                self.value_type()?;
+                self.skip_limits()?;
+                None
+            }
+            External::Memory => {
+                self.skip_limits()?;
+                None
+            }
+            External::Global => {
+                self.value_type()?;
+                self.byte()?;
+                None
+            }
+        };
+        Ok(Import {
+            module,
+            name,
+            kind,
+            signature,
+        })
+    }
+
+    fn external(&mut self) -> Result<External> {
+        Ok(match self.byte()? {
+            0 => External::Function,
+            1 => External::Table,
+            2 => External::Memory,
+            3 => External::Global,
+            other => bail!("unknown external kind {other:#x}"),
+        })
+    }
+}
+
+fn section_name(id: u8) -> Result<&'static str> {
+    Ok(match id {
+        1 => "type",
+        2 => "import",
+        3 => "function",
+        4 => "table",
+        5 => "memory",
+        6 => "global",
+        7 => "export",
+        8 => "start",
+        9 => "element",
+        10 => "code",
+        11 => "data",
+        12 => "data count",
+        id => bail!("unknown section id {id}"),
+    })
+}
+
+impl Module {
+    /// Read the module in `bytes`. Custom sections are named after the name
+    /// they carry.
+    pub(super) fn parse(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(bytes);
+        ensure!(reader.take(4)? == MAGIC, "not a WebAssembly module");
+        let version = reader.take(4)?;
+        ensure!(
+            version == [1, 0, 0, 0],
+            "unsupported WebAssembly version {version:?}"
+        );
+        let mut module = Module::default();
+        let mut signatures = vec![];
+        // The type of each function, imported ones first.
+        let mut functions = vec![];
+        let mut exports = vec![];
+        while !reader.is_empty() {
+            let id = reader.byte()?;
+            let size = reader.len()?;
+            let mut contents = Reader::new(reader.take(size)?);
+            let context = || format!("reading section {id}");
+            let name = match id {
+                0 => format!("custom \"{}\"", contents.name()?),
+                1 => {
+                    signatures = (0..contents.len()?)
+                        .map(|_| contents.signature())
+                        .collect::<Result<_>>()
+                        .with_context(context)?;
+                    section_name(id)?.to_string()
+                }
+                2 => {
+                    for _ in 0..contents.len()? {
+                        let import = contents.import(&signatures).with_context(context)?;
+                        if import.kind == External::Function {
+                            functions.push(import.signature.clone());
+                        }
+                        module.imports.push(import);
+                    }
+                    section_name(id)?.to_string()
+                }
+                3 => {
+                    for _ in 0..contents.len()? {
+                        let index = contents.len()?;
+                        functions.push(signatures.get(index).cloned());
+                    }
+                    section_name(id)?.to_string()
+                }
+                7 => {
+                    for _ in 0..contents.len()? {
+                        let name = contents.name()?;
+                        let kind = contents.external()?;
+                        exports.push((name, kind, contents.len()?));
+                    }
+                    section_name(id)?.to_string()
+                }
+                id => section_name(id)?.to_string(),
+            };
+            module.sections.push(Section { name, size });
+        }
+        module.exports = exports
+            .into_iter()
+            .map(|(name, kind, index)| Export {
+                signature: match kind {
+                    External::Function => functions.get(index).cloned().flatten(),
+                    _ => None,
+                },
+                name,
+                kind,
+            })
+            .collect();
+        Ok(module)
+    }
+
+    /// The exported functions' names.
+    pub(super) fn exported_functions(&self) -> impl Iterator<Item = &str> {
+        self.exports
+            .iter()
+            .filter(|export| export.kind == External::Function)
+            .map(|export| export.name.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A section with `id` holding `contents`, which must be short.
+    fn section(id: u8, contents: &[u8]) -> Vec<u8> {
+        let mut section = vec![id, contents.len() as u8];
+        section.extend(contents);
+        section
+    }
+
+    #[test]
+    fn reads_imports_and_exports() {
+        let mut bytes = b"\0asm\x01\0\0\0".to_vec();
+        // Types: (i32) -> i64, and ().
+        bytes.extend(section(1, &[2, 0x60, 1, 0x7f, 1, 0x7e, 0x60, 0, 0]));
+        // Imports "fix" "len" as a function of type 0, and "fix" "mem" as a
+        // memory of at least one page.
+        let mut imports = vec![2];
+        imports.extend(b"\x03fix\x03len\x00\x00");
+        imports.extend(b"\x03fix\x03mem\x02\x00\x01");
+        bytes.extend(section(2, &imports));
+        // One function of type 1.
+        bytes.extend(section(3, &[1, 1]));
+        // Exports "_fixpoint_apply" as function 1, and "len" as function 0.
+        let mut exports = vec![2, 15];
+        exports.extend(b"_fixpoint_apply\x00\x01");
+        exports.extend(b"\x03len\x00\x00");
+        bytes.extend(section(7, &exports));
+        bytes.extend(section(10, &[1, 2, 0, 0x0b]));
+
+        let module = Module::parse(&bytes).unwrap();
+        let names: Vec<_> = module.sections.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["type", "import", "function", "export", "code"]);
+        assert_eq!(module.sections[4].size, 4);
+        assert_eq!(module.imports.len(), 2);
+        assert_eq!(module.imports[0].signature.as_deref(), Some("(i32) -> i64"));
+        assert_eq!(module.imports[1].kind, External::Memory);
+        assert_eq!(
+            module.exports,
+            [
+                Export {
+                    name: "_fixpoint_apply".to_string(),
+                    kind: External::Function,
+                    signature: Some("()".to_string()),
+                },
+                Export {
+                    name: "len".to_string(),
+                    kind: External::Function,
+                    signature: Some("(i32) -> i64".to_string()),
+                },
+            ]
+        );
+        assert_eq!(
+            module.exported_functions().collect::<Vec<_>>(),
+            ["_fixpoint_apply", "len"]
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_modules() {
+        let mut bytes = b"\0asm\x01\0\0\0".to_vec();
+        bytes.extend([0, 6, 4]);
+        bytes.extend(b"name\0");
+        let module = Module::parse(&bytes).unwrap();
+        assert_eq!(module.sections[0].name, "custom \"name\"");
+        assert_eq!(module.sections[0].size, 6);
+        bytes.push(10);
+        assert!(Module::parse(&bytes).is_err());
+        assert!(Module::parse(b"\0asm\x02\0\0\0").is_err());
+    }
+}
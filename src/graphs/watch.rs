@@ -0,0 +1,135 @@
+use egui::{DragValue, Ui};
+
+use crate::handle::Handle;
+
+/// How long a watched handle flashes after something about it changed.
+const FLASH_SECONDS: f64 = 1.5;
+
+/// One handle whose parents and result are polled on their own, to follow a
+/// long-running task without polling the whole graph.
+#[derive(Clone, Debug)]
+pub(super) struct Watch {
+    pub(super) handle: Handle,
+    /// Seconds between polls.
+    interval: f64,
+    /// When the handle was last polled.
+    polled: Option<f64>,
+    /// How many times its parents or result changed since watching began.
+    changes: usize,
+    changed_at: Option<f64>,
+    /// The result of the last poll, once one arrived.
+    outcome: Option<Option<Handle>>,
+}
+
+/// What the watch bar asked for.
+pub(super) enum WatchAction {
+    Focus,
+    Stop,
+}
+
+impl Watch {
+    pub fn new(handle: Handle) -> Self {
+        Self {
+            handle,
+            interval: 2.0,
+            polled: None,
+            changes: 0,
+            changed_at: None,
+            outcome: None,
+        }
+    }
+
+    /// Whether the handle is to be polled at `now`, marking it polled if so.
+    pub fn due(&mut self, now: f64) -> bool {
+        if self.polled.map_or(false, |at| now - at < self.interval) {
+            return false;
+        }
+        self.polled = Some(now);
+        true
+    }
+
+    pub fn interval(&self) -> f64 {
+        self.interval
+    }
+
+    pub fn changes(&self) -> usize {
+        self.changes
+    }
+
+    pub fn changed(&mut self, now: f64) {
+        self.changes += 1;
+        self.changed_at = Some(now);
+    }
+
+    /// Take in a polled result, noting a change if it differs from the last.
+    pub fn set_outcome(&mut self, now: f64, outcome: Option<Handle>) {
+        if self.outcome.as_ref().map_or(false, |last| *last != outcome) {
+            self.changed(now);
+        }
+        self.outcome = Some(outcome);
+    }
+
+    /// How strongly the handle flashes at `now`, from 1 right after a change
+    /// down to 0.
+    pub fn flash(&self, now: f64) -> f32 {
+        self.changed_at.map_or(0.0, |at| {
+            (1.0 - (now - at) / FLASH_SECONDS).clamp(0.0, 1.0) as f32
+        })
+    }
+
+    /// A bar naming the handle, with its interval and how often it changed.
+    pub fn ui(&mut self, ui: &mut Ui) -> Option<WatchAction> {
+        let mut action = None;
+        ui.horizontal(|ui| {
+            ui.label("👁 Watching");
+            if ui
+                .link(self.handle.to_hex())
+                .on_hover_text(self.handle.to_string())
+                .clicked()
+            {
+                action = Some(WatchAction::Focus);
+            }
+            ui.label("every");
+            ui.add(
+                DragValue::new(&mut self.interval)
+                    .clamp_range(0.5..=60.0)
+                    .speed(0.1)
+                    .suffix(" s"),
+            )
+            .on_hover_text("How often its parents and result are fetched again");
+            ui.label(match self.changes {
+                0 => "no changes yet".to_string(),
+                1 => "1 change".to_string(),
+                changes => format!("{changes} changes"),
+            });
+            if ui.small_button("Stop").clicked() {
+                action = Some(WatchAction::Stop);
+            }
+        });
+        action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polls_on_interval_and_flashes_on_change() {
+        let handle = Handle::from_hex("d9-0-4-100000000000000").unwrap();
+        let mut watch = Watch::new(handle.clone());
+        assert!(watch.due(10.0));
+        assert!(!watch.due(11.0));
+        assert!(watch.due(12.0));
+
+        // The first result is only remembered.
+        watch.set_outcome(12.0, None);
+        watch.set_outcome(13.0, None);
+        assert_eq!(watch.changes(), 0);
+        assert_eq!(watch.flash(13.0), 0.0);
+        watch.set_outcome(14.0, Some(handle));
+        assert_eq!(watch.changes(), 1);
+        assert_eq!(watch.flash(14.0), 1.0);
+        assert_eq!(watch.flash(14.0 + FLASH_SECONDS), 0.0);
+    }
+}
@@ -0,0 +1,350 @@
+//! Queries over gRPC, for servers that do not speak JSON over HTTP. Replies
+//! are turned into the JSON the HTTP server would send for the same query.
+//!
+//! The service, with messages defined by hand below instead of generated:
+//!
+//! ```proto
+//! package fix;
+//!
+//! service Relations {
+//!   rpc Parents(ListRequest) returns (TaskList);
+//!   rpc ParentCount(HandleRequest) returns (CountReply);
+//!   rpc Dependees(ListRequest) returns (TaskList);
+//!   rpc Child(HandleRequest) returns (ChildReply);
+//!   rpc Status(HandleRequest) returns (StatusReply);
+//!   rpc TaskTimes(HandleRequest) returns (TaskTimesReply);
+//!   rpc Tag(HandleRequest) returns (TagReply);
+//!   rpc Tree(HandleRequest) returns (TreeReply);
+//!   rpc Blob(HandleRequest) returns (BlobReply);
+//!   rpc Canonical(HandleRequest) returns (CanonicalReply);
+//!   rpc Rerun(HandleRequest) returns (StatusReply);
+//!   rpc Materialize(HandleRequest) returns (MaterializeReply);
+//!   rpc Roots(RootsRequest) returns (RootsReply);
+//! }
+//! ```
+
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::{json, Value};
+use tonic::{
+    client::Grpc,
+    codec::ProstCodec,
+    codegen::http::uri::PathAndQuery,
+    transport::{Channel, Endpoint as ServerEndpoint},
+};
+
+use crate::{
+    cache::Endpoint,
+    handle::Handle,
+    http::{RequestKind, PAGE_SIZE},
+    profile::Timeouts,
+};
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct HandleRequest {
+    #[prost(string, tag = "1")]
+    handle: String,
+    #[prost(uint32, tag = "2")]
+    operation: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct ListRequest {
+    #[prost(string, tag = "1")]
+    handle: String,
+    #[prost(uint32, tag = "2")]
+    operation: u32,
+    #[prost(uint32, tag = "3")]
+    limit: u32,
+    #[prost(string, optional, tag = "4")]
+    after: Option<String>,
+    /// Only parents performing this operation, if set.
+    #[prost(uint32, optional, tag = "5")]
+    only: Option<u32>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Task {
+    #[prost(string, tag = "1")]
+    handle: String,
+    #[prost(uint32, tag = "2")]
+    operation: u32,
+    #[prost(bool, tag = "3")]
+    restricted: bool,
+    #[prost(string, optional, tag = "4")]
+    permission: Option<String>,
+    /// Seconds since the Unix epoch when the relation was recorded.
+    #[prost(double, optional, tag = "5")]
+    recorded: Option<f64>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct TaskList {
+    /// False if the server knows nothing about the handle.
+    #[prost(bool, tag = "1")]
+    found: bool,
+    #[prost(message, repeated, tag = "2")]
+    tasks: Vec<Task>,
+    #[prost(string, optional, tag = "3")]
+    next: Option<String>,
+    #[prost(uint64, tag = "4")]
+    remaining: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct CountReply {
+    /// False if the server knows nothing about the handle.
+    #[prost(bool, tag = "1")]
+    found: bool,
+    #[prost(uint64, tag = "2")]
+    count: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct ChildReply {
+    #[prost(string, optional, tag = "1")]
+    handle: Option<String>,
+    #[prost(bool, tag = "2")]
+    restricted: bool,
+    #[prost(string, optional, tag = "3")]
+    permission: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct StatusReply {
+    /// One of pending, running, done or failed, if the runtime knows.
+    #[prost(string, optional, tag = "1")]
+    status: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct TaskTime {
+    #[prost(message, optional, tag = "1")]
+    task: Option<Task>,
+    #[prost(double, tag = "2")]
+    start: f64,
+    #[prost(double, tag = "3")]
+    end: f64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct TaskTimesReply {
+    #[prost(bool, tag = "1")]
+    found: bool,
+    #[prost(message, repeated, tag = "2")]
+    tasks: Vec<TaskTime>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct TagReply {
+    #[prost(string, optional, tag = "1")]
+    tagged: Option<String>,
+    #[prost(string, optional, tag = "2")]
+    authority: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    label: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct TreeReply {
+    #[prost(bool, tag = "1")]
+    found: bool,
+    #[prost(string, repeated, tag = "2")]
+    entries: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct BlobReply {
+    #[prost(bool, tag = "1")]
+    found: bool,
+    #[prost(bytes = "vec", tag = "2")]
+    contents: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct CanonicalReply {
+    #[prost(string, optional, tag = "1")]
+    handle: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct MaterializeReply {
+    /// Whether the handle's data is resident now.
+    #[prost(bool, tag = "1")]
+    resident: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct RootsRequest {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct RootsReply {
+    #[prost(string, repeated, tag = "1")]
+    roots: Vec<String>,
+}
+
+/// Ask the server at `url_base` the query of `kind`, and return the reply as
+/// the JSON body the HTTP server would have sent.
+pub(crate) async fn fetch(
+    url_base: &str,
+    kind: &RequestKind,
+    timeouts: Timeouts,
+) -> Result<String> {
+    let channel = ServerEndpoint::from_shared(format!("http://{url_base}"))
+        .context("request failed: building url")?
+        .connect_timeout(timeouts.connect())
+        .timeout(timeouts.read())
+        .connect()
+        .await
+        .map_err(|e| anyhow!("request failed: connecting: {e}"))?;
+    let mut client = Grpc::new(channel);
+
+    let handle = kind.handle().map(Handle::to_hex).unwrap_or_default();
+    let operation = match kind.endpoint() {
+        Endpoint::Child(operation)
+        | Endpoint::Dependees(operation)
+        | Endpoint::Status(operation)
+        | Endpoint::Rerun(operation) => operation as u32,
+        _ => 0,
+    };
+    let only = match kind.endpoint() {
+        Endpoint::FilteredParents(operation) => Some(operation as u32),
+        _ => None,
+    };
+    let list = || ListRequest {
+        handle: handle.clone(),
+        operation,
+        limit: PAGE_SIZE as u32,
+        after: kind.after().map(str::to_string),
+        only,
+    };
+    let single = || HandleRequest {
+        handle: handle.clone(),
+        operation,
+    };
+    let body = match kind.endpoint() {
+        Endpoint::Parents | Endpoint::FilteredParents(_) => {
+            task_list("parents", call(&mut client, "Parents", list()).await?)
+        }
+        Endpoint::ParentCount => {
+            let reply: CountReply = call(&mut client, "ParentCount", single()).await?;
+            json!({ "count": reply.found.then_some(reply.count) })
+        }
+        Endpoint::Dependees(_) => {
+            task_list("dependees", call(&mut client, "Dependees", list()).await?)
+        }
+        Endpoint::Child(_) => {
+            let reply: ChildReply = call(&mut client, "Child", single()).await?;
+            json!({
+                "handle": reply.handle,
+                "restricted": reply.restricted,
+                "permission": reply.permission,
+            })
+        }
+        Endpoint::Status(_) => {
+            let reply: StatusReply = call(&mut client, "Status", single()).await?;
+            json!({ "status": reply.status })
+        }
+        Endpoint::TaskTimes => {
+            let reply: TaskTimesReply = call(&mut client, "TaskTimes", single()).await?;
+            let tasks = reply.found.then(|| {
+                reply
+                    .tasks
+                    .iter()
+                    .map(|time| {
+                        let mut task = task(&time.task.clone().unwrap_or_default());
+                        task["start"] = json!(time.start);
+                        task["end"] = json!(time.end);
+                        task
+                    })
+                    .collect::<Vec<_>>()
+            });
+            json!({ "tasks": tasks })
+        }
+        Endpoint::Tag => {
+            let reply: TagReply = call(&mut client, "Tag", single()).await?;
+            json!({
+                "tagged": reply.tagged,
+                "authority": reply.authority,
+                "label": reply.label,
+            })
+        }
+        Endpoint::Tree => {
+            let reply: TreeReply = call(&mut client, "Tree", single()).await?;
+            json!({ "entries": reply.found.then_some(reply.entries) })
+        }
+        Endpoint::Blob => {
+            let reply: BlobReply = call(&mut client, "Blob", single()).await?;
+            let contents = reply.found.then(|| STANDARD.encode(reply.contents));
+            json!({ "contents": contents })
+        }
+        Endpoint::Rerun(_) => {
+            let reply: StatusReply = call(&mut client, "Rerun", single()).await?;
+            json!({ "status": reply.status })
+        }
+        Endpoint::Materialize => {
+            let reply: MaterializeReply = call(&mut client, "Materialize", single()).await?;
+            json!({ "resident": reply.resident })
+        }
+        Endpoint::Canonical => {
+            let reply: CanonicalReply = call(&mut client, "Canonical", single()).await?;
+            json!({ "handle": reply.handle })
+        }
+        Endpoint::Roots => {
+            let reply: RootsReply = call(&mut client, "Roots", RootsRequest {}).await?;
+            json!({ "roots": reply.roots })
+        }
+        Endpoint::Exists => {
+            return Err(anyhow!(
+                "request failed: the gRPC service cannot be probed for objects"
+            ))
+        }
+    };
+    Ok(body.to_string())
+}
+
+/// Make one unary call to `method` of the Relations service.
+async fn call<Request, Reply>(
+    client: &mut Grpc<Channel>,
+    method: &str,
+    request: Request,
+) -> Result<Reply>
+where
+    Request: prost::Message + Send + 'static,
+    Reply: prost::Message + Default + Send + 'static,
+{
+    client
+        .ready()
+        .await
+        .map_err(|e| anyhow!("request failed: connecting: {e}"))?;
+    let path = PathAndQuery::try_from(format!("/fix.Relations/{method}"))
+        .context("request failed: building path")?;
+    let reply = client
+        .unary(tonic::Request::new(request), path, ProstCodec::default())
+        .await
+        .map_err(|status| anyhow!("request failed: {}", status.message()))?;
+    Ok(reply.into_inner())
+}
+
+/// A list of parents or dependees under `field`, with its page fields.
+fn task_list(field: &str, list: TaskList) -> Value {
+    let tasks = list
+        .found
+        .then(|| list.tasks.iter().map(task).collect::<Vec<_>>());
+    json!({
+        field: tasks,
+        "next": list.next,
+        "remaining": list.remaining,
+    })
+}
+
+fn task(task: &Task) -> Value {
+    json!({
+        "handle": task.handle,
+        // The HTTP server sends the operation as a string.
+        "operation": task.operation.to_string(),
+        "restricted": task.restricted,
+        "permission": task.permission,
+        "recorded": task.recorded,
+    })
+}
@@ -1,6 +1,7 @@
-use std::{convert::TryFrom, fmt::Display};
+use std::{convert::TryFrom, fmt::Display, ops::Range};
 
-use anyhow::{bail, ensure, Context, Result};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
 
 /// Number of bytes
 const METADATA_LENGTH: usize = 1;
@@ -15,9 +16,83 @@ const CANONICAL_HASH_LENGTH: usize = HANDLE_LENGTH - UINT64_LENGTH - METADATA_LE
 pub(crate) struct Task {
     pub(crate) handle: Handle,
     pub(crate) operation: Operation,
+    pub(crate) restriction: Option<Restriction>,
+    /// When the server recorded the relation, in seconds since the Unix
+    /// epoch, if it keeps track.
+    #[serde(default)]
+    pub(crate) recorded: Option<f64>,
 }
 
-#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize, Clone, Copy)]
+/// Set by the server on handles whose contents are only served to clients
+/// holding `permission`.
+#[derive(Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize, Clone)]
+pub(crate) struct Restriction {
+    pub(crate) permission: String,
+}
+
+/// A task along with when it started and finished running, in seconds.
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize, Clone)]
+pub(crate) struct TaskTime {
+    pub(crate) task: Task,
+    pub(crate) start: f64,
+    pub(crate) end: f64,
+}
+
+/// Where a task is in its execution, as reported by the runtime.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum TaskStatus {
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+impl Display for TaskStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            TaskStatus::Pending => "pending",
+            TaskStatus::Running => "running",
+            TaskStatus::Done => "done",
+            TaskStatus::Failed => "failed",
+        })
+    }
+}
+
+/// The three entries of a Tag: `authority` vouches that `tagged` has `label`.
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize, Clone)]
+pub(crate) struct TagEntries {
+    pub(crate) tagged: Handle,
+    pub(crate) authority: Handle,
+    pub(crate) label: Handle,
+}
+
+/// The entries of the tree a Thunk applies: resource limits, then the
+/// procedure, then the arguments passed to it.
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct ThunkEntries {
+    pub(crate) limits: Handle,
+    pub(crate) procedure: Handle,
+    pub(crate) arguments: Vec<Handle>,
+}
+
+impl ThunkEntries {
+    pub(crate) fn from_tree(entries: &[Handle]) -> Result<Self> {
+        let [limits, procedure, arguments @ ..] = entries else {
+            bail!(
+                "a thunk's tree needs resource limits and a procedure, found {} entries",
+                entries.len()
+            );
+        };
+        Ok(Self {
+            limits: limits.clone(),
+            procedure: procedure.clone(),
+            arguments: arguments.to_vec(),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize, Clone, Copy)]
 pub(crate) enum Operation {
     Apply,
     Eval,
@@ -53,6 +128,24 @@ pub(crate) enum Accessibility {
     Lazy,
 }
 
+impl Accessibility {
+    pub(crate) const ALL: [Accessibility; 3] = [
+        Accessibility::Strict,
+        Accessibility::Shallow,
+        Accessibility::Lazy,
+    ];
+}
+
+impl Display for Accessibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Accessibility::Strict => "Strict",
+            Accessibility::Shallow => "Shallow",
+            Accessibility::Lazy => "Lazy",
+        })
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
 pub(crate) enum Object {
     Blob,
@@ -61,21 +154,110 @@ pub(crate) enum Object {
     Tag,
 }
 
+impl Object {
+    pub(crate) const ALL: [Object; 4] = [Object::Blob, Object::Tree, Object::Thunk, Object::Tag];
+}
+
+/// Why a string is not a handle, and where in the string the problem is.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct HandleDiagnostic {
+    pub(crate) problem: HandleProblem,
+    /// Byte range of the offending part of the input, if there is one.
+    pub(crate) span: Option<Range<usize>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum HandleProblem {
+    /// There must be exactly 4 parts.
+    PartCount(usize),
+    /// A part is empty, has a non-hex character or does not fit in 64 bits.
+    NotHex { part: usize },
+    /// The top two metadata bits do not name an accessibility.
+    Accessibility(u8),
+    /// A non-literal's unused metadata bits, between the literal flag and the
+    /// canonical flag, are set.
+    ObjectBits(u8),
+}
+
+impl Display for HandleDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.problem {
+            HandleProblem::PartCount(count) => {
+                write!(f, "expected 4 parts separated by - or |, found {count}")
+            }
+            HandleProblem::NotHex { part } => {
+                write!(f, "part {} is not a 64 bit hex number", part + 1)
+            }
+            HandleProblem::Accessibility(bits) => write!(
+                f,
+                "accessibility bits {bits:#04b} in the metadata byte are not strict, shallow or lazy"
+            ),
+            HandleProblem::ObjectBits(bits) => write!(
+                f,
+                "unused object bits {bits:#04b} in the metadata byte must be zero"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for HandleDiagnostic {}
+
 impl Handle {
+    /// Check that `input` is a handle, returning its four parts if so.
+    /// The literal size needs no check: its 5 bits can not exceed the 31
+    /// bytes a literal holds.
+    pub(crate) fn validate(input: &str) -> Result<[u64; 4], HandleDiagnostic> {
+        let mut parts = vec![];
+        let mut start = 0;
+        for part in input.split(|c| c == '-' || c == '|') {
+            let span = start..start + part.len();
+            start = span.end + 1;
+            match u64::from_str_radix(part, 16) {
+                // from_str_radix takes a leading +, which is not hex.
+                Ok(value) if !part.starts_with('+') => parts.push((value, span)),
+                _ => {
+                    return Err(HandleDiagnostic {
+                        problem: HandleProblem::NotHex { part: parts.len() },
+                        span: Some(span),
+                    })
+                }
+            }
+            if parts.len() > 4 {
+                break;
+            }
+        }
+        if parts.len() != 4 {
+            return Err(HandleDiagnostic {
+                problem: HandleProblem::PartCount(input.split(|c| c == '-' || c == '|').count()),
+                span: None,
+            });
+        }
+        // The metadata byte is the top byte of the last part.
+        let (last, last_span) = parts[3].clone();
+        let metadata = (last >> 56) as u8;
+        let metadata_span = Some(last_span);
+        if Accessibility::try_from(metadata >> 6).is_err() {
+            return Err(HandleDiagnostic {
+                problem: HandleProblem::Accessibility(metadata >> 6),
+                span: metadata_span,
+            });
+        }
+        let is_literal = metadata & 0b10_0000 != 0;
+        let unused = (metadata >> 3) & 0b11;
+        if !is_literal && unused != 0 {
+            return Err(HandleDiagnostic {
+                problem: HandleProblem::ObjectBits(unused),
+                span: metadata_span,
+            });
+        }
+        Ok([parts[0].0, parts[1].0, parts[2].0, parts[3].0])
+    }
+
     /// Parses a handle in format [unsigned 64 bit number as hex]-[u64 as hex]-[u64 as hex]-[u64 as hex].
     /// For example, d9-0-4-0 or 10-0-0-2400000000000000.
     /// Also takes d9|0|4|0 for compatibility with fixpoint handle formatting.
     pub(crate) fn from_hex(input: &str) -> Result<Self> {
-        let handle_content = input
-            .split(|c| c == '-' || c == '|')
-            .map(|i| u64::from_str_radix(i, 16))
-            .collect::<Result<Vec<_>, _>>()
-            .context("Failed to parse handle from hex")?;
-        ensure!(
-            handle_content.len() == 4,
-            "Expected handle with exactly 4 parts when parsing from hex"
-        );
-        let handle_content: [u64; 4] = handle_content.try_into().unwrap();
+        let handle_content = Self::validate(input).context("Failed to parse handle from hex")?;
         let handle_content: [u8; HANDLE_LENGTH] = handle_content
             .into_iter()
             .flat_map(|i| i.to_le_bytes().into_iter())
@@ -138,6 +320,70 @@ impl Handle {
         })
     }
 
+    /// Parses a handle from the base64 of its 32 bytes, as some tools print
+    /// it. The bytes are checked the same way as a hex handle.
+    pub(crate) fn from_base64(input: &str) -> Result<Self> {
+        let bytes = STANDARD
+            .decode(input)
+            .context("Failed to decode handle from base64")?;
+        let bytes: [u8; HANDLE_LENGTH] = bytes.try_into().map_err(|bytes: Vec<u8>| {
+            anyhow!("expected {HANDLE_LENGTH} bytes, found {}", bytes.len())
+        })?;
+        let hex = bytes
+            .chunks_exact(UINT64_LENGTH)
+            .map(|s: &[u8]| format!("{:x}", u64::from_le_bytes(s.try_into().unwrap())))
+            .collect::<Vec<_>>()
+            .join("-");
+        Self::from_hex(&hex)
+    }
+
+    /// A literal blob holding `bytes`, which must fit in the handle itself.
+    pub(crate) fn literal(bytes: &[u8], accessibility: Accessibility) -> Result<Self> {
+        ensure!(
+            bytes.len() <= LITERAL_CONTENT_LENGTH,
+            "a literal holds at most {LITERAL_CONTENT_LENGTH} bytes, not {}",
+            bytes.len()
+        );
+        let mut content = [0; LITERAL_CONTENT_LENGTH];
+        content[..bytes.len()].copy_from_slice(bytes);
+        Ok(Self {
+            size: bytes.len() as u64,
+            accessibility,
+            content: Content::Literal(content),
+        })
+    }
+
+    /// A canonical handle to the object of `object_type` and `size` whose
+    /// contents hash to `hash`. Only the first 27 bytes of the hash fit in a
+    /// handle, so a longer one, such as a whole SHA-256, is cut short.
+    pub(crate) fn canonical(
+        hash: &[u8],
+        size: u64,
+        object_type: Object,
+        accessibility: Accessibility,
+    ) -> Result<Self> {
+        ensure!(
+            hash.len() >= CANONICAL_HASH_LENGTH,
+            "a canonical hash needs at least {CANONICAL_HASH_LENGTH} bytes, not {}",
+            hash.len()
+        );
+        Ok(Self {
+            size,
+            accessibility,
+            content: Content::Other {
+                object_type,
+                data: Nonliteral::Canonical(hash[..CANONICAL_HASH_LENGTH].try_into().unwrap()),
+            },
+        })
+    }
+
+    /// Parses a handle in hex or base64, ignoring surrounding whitespace.
+    /// Reports why the hex failed if neither works.
+    pub(crate) fn parse(input: &str) -> Result<Self> {
+        let input = input.trim();
+        Self::from_hex(input).or_else(|e| Self::from_base64(input).map_err(|_| e))
+    }
+
     /// Reconstructs the hex string version of a Handle
     pub(crate) fn to_hex(&self) -> String {
         self.to_buffer()
@@ -216,16 +462,6 @@ impl Display for Operation {
     }
 }
 
-impl Operation {
-    pub fn get_color(&self) -> egui::Color32 {
-        match self {
-            Operation::Apply => egui::Color32::GREEN,
-            Operation::Eval => egui::Color32::from_rgb(20, 20, 255),
-            Operation::Fill => egui::Color32::RED,
-        }
-    }
-}
-
 impl Display for Handle {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         // strict Tree, 3 entries, local id d9
@@ -238,10 +474,7 @@ impl Display for Handle {
             Accessibility::Shallow => "shallow",
             Accessibility::Lazy => "lazy",
         };
-        let content_type = match self.content {
-            Content::Other { object_type, .. } => object_type,
-            Content::Literal(_) => Object::Blob,
-        };
+        let content_type = self.object();
         let size = self.size;
         let size_desc = match content_type {
             Object::Blob => "bytes",
@@ -312,6 +545,146 @@ impl Display for Handle {
     }
 }
 
+/// Ways to read the bytes of a literal blob.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub(crate) enum LiteralView {
+    #[default]
+    Hex,
+    /// Little-endian unsigned integer of up to 8 bytes.
+    U64,
+    /// Little-endian signed integer of up to 8 bytes, sign extended.
+    I64,
+    Utf8,
+    /// Little-endian IEEE 754 double of exactly 8 bytes.
+    F64,
+}
+
+impl LiteralView {
+    pub(crate) const ALL: [LiteralView; 5] = [
+        LiteralView::Hex,
+        LiteralView::U64,
+        LiteralView::I64,
+        LiteralView::Utf8,
+        LiteralView::F64,
+    ];
+}
+
+impl Display for LiteralView {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            LiteralView::Hex => "hex",
+            LiteralView::U64 => "u64",
+            LiteralView::I64 => "i64",
+            LiteralView::Utf8 => "UTF-8",
+            LiteralView::F64 => "f64",
+        })
+    }
+}
+
+impl Handle {
+    /// The kind of object the handle refers to. Literals are always Blobs.
+    pub(crate) fn object(&self) -> Object {
+        match self.content {
+            Content::Other { object_type, .. } => object_type,
+            Content::Literal(_) => Object::Blob,
+        }
+    }
+
+    /// Where the handle's contents live: in the handle itself, in the
+    /// server's local storage, or anywhere under a content hash.
+    pub(crate) fn storage(&self) -> &'static str {
+        match &self.content {
+            Content::Literal(_) => "literal",
+            Content::Other {
+                data: Nonliteral::Local(_),
+                ..
+            } => "local",
+            Content::Other {
+                data: Nonliteral::Canonical(_),
+                ..
+            } => "canonical",
+        }
+    }
+
+    /// The tree a Thunk applies, which shares the Thunk's contents, or `None`
+    /// if the handle is not a Thunk.
+    pub(crate) fn thunk_tree(&self) -> Option<Handle> {
+        match &self.content {
+            Content::Other {
+                object_type: Object::Thunk,
+                data,
+            } => Some(Handle {
+                size: self.size,
+                accessibility: self.accessibility,
+                content: Content::Other {
+                    object_type: Object::Tree,
+                    data: data.clone(),
+                },
+            }),
+            _ => None,
+        }
+    }
+
+    /// The bytes of a literal blob, or `None` if the handle is not a literal.
+    pub(crate) fn literal_bytes(&self) -> Option<&[u8]> {
+        match &self.content {
+            Content::Literal(content) => Some(&content[..(self.size as usize).min(content.len())]),
+            Content::Other { .. } => None,
+        }
+    }
+
+    /// Read a literal blob as `view`. Fails if the handle is not a literal or
+    /// its bytes do not fit the view.
+    pub(crate) fn literal_as(&self, view: LiteralView) -> Result<String> {
+        let Some(bytes) = self.literal_bytes() else {
+            bail!("not a literal");
+        };
+        let widened = || -> Result<[u8; UINT64_LENGTH]> {
+            ensure!(
+                bytes.len() <= UINT64_LENGTH,
+                "{} bytes do not fit in {view}",
+                bytes.len()
+            );
+            let mut array = [0; UINT64_LENGTH];
+            array[..bytes.len()].copy_from_slice(bytes);
+            Ok(array)
+        };
+        Ok(match view {
+            LiteralView::Hex => format!(
+                "0x{}",
+                bytes
+                    .iter()
+                    .map(|byte| format!("{:0>2x}", byte))
+                    .collect::<String>()
+            ),
+            LiteralView::U64 => u64::from_le_bytes(widened()?).to_string(),
+            LiteralView::I64 => {
+                let unsigned = u64::from_le_bytes(widened()?);
+                // Sign extend from the highest byte present.
+                let shift = (UINT64_LENGTH - bytes.len()) as u32 * 8;
+                let signed = if shift == 64 {
+                    0
+                } else {
+                    ((unsigned << shift) as i64) >> shift
+                };
+                signed.to_string()
+            }
+            LiteralView::Utf8 => format!(
+                "\"{}\"",
+                std::str::from_utf8(bytes)
+                    .context("not valid UTF-8")?
+                    .escape_debug()
+            ),
+            LiteralView::F64 => {
+                let array: [u8; UINT64_LENGTH] = bytes.try_into().map_err(|_| {
+                    anyhow::anyhow!("f64 needs exactly 8 bytes, not {}", bytes.len())
+                })?;
+                f64::from_le_bytes(array).to_string()
+            }
+        })
+    }
+}
+
 impl TryFrom<u8> for Accessibility {
     type Error = anyhow::Error;
 
@@ -391,6 +764,111 @@ mod tests {
         assert_eq!(handle_string, handle.to_hex());
     }
 
+    #[test]
+    fn base64() {
+        let handle = Handle::from_hex("10-0-0-2400000000000000").unwrap();
+        let encoded = STANDARD.encode(handle.to_buffer());
+        assert_eq!(Handle::from_base64(&encoded).unwrap(), handle);
+        assert_eq!(Handle::parse(&format!(" {encoded}\n")).unwrap(), handle);
+        assert_eq!(Handle::parse("10|0|0|2400000000000000").unwrap(), handle);
+        assert!(Handle::parse(&STANDARD.encode([0u8; 31])).is_err());
+    }
+
+    #[test]
+    fn composed() {
+        let text = Handle::literal(b"hi", Accessibility::Strict).unwrap();
+        assert_eq!(text.to_hex(), "6968-0-0-2200000000000000");
+        let number = Handle::literal(&16u64.to_le_bytes(), Accessibility::Strict).unwrap();
+        assert_eq!(number.to_hex(), "10-0-0-2800000000000000");
+        assert!(Handle::literal(&[0; 32], Accessibility::Strict).is_err());
+
+        let tag = Handle::from_hex("862fcba5ecaade2c-4b24159ac7c28a29-3-715eb1e41f37d42").unwrap();
+        let Content::Other {
+            data: Nonliteral::Canonical(hash),
+            ..
+        } = &tag.content
+        else {
+            unreachable!()
+        };
+        let mut longer = hash.to_vec();
+        longer.extend([0xff; 5]);
+        assert_eq!(
+            Handle::canonical(&longer, 3, Object::Tag, Accessibility::Strict).unwrap(),
+            tag
+        );
+        assert!(Handle::canonical(&hash[1..], 3, Object::Tag, Accessibility::Strict).is_err());
+    }
+
+    #[test]
+    fn literal_views() {
+        // A 2 byte literal holding 0xfffe.
+        let handle = Handle::from_hex("fffe-0-0-2200000000000000").unwrap();
+        assert_eq!(handle.literal_as(LiteralView::Hex).unwrap(), "0xfeff");
+        assert_eq!(handle.literal_as(LiteralView::U64).unwrap(), "65534");
+        assert_eq!(handle.literal_as(LiteralView::I64).unwrap(), "-2");
+        assert!(handle.literal_as(LiteralView::Utf8).is_err());
+        assert!(handle.literal_as(LiteralView::F64).is_err());
+
+        let float =
+            Handle::from_hex(&format!("{:x}-0-0-2800000000000000", 1.5f64.to_bits())).unwrap();
+        assert_eq!(float.literal_as(LiteralView::F64).unwrap(), "1.5");
+
+        let text = Handle::from_hex("6968-0-0-2200000000000000").unwrap();
+        assert_eq!(text.literal_as(LiteralView::Utf8).unwrap(), "\"hi\"");
+
+        let thunk = Handle::from_hex("d9-0-4-100000000000000").unwrap();
+        assert!(thunk.literal_as(LiteralView::Hex).is_err());
+    }
+
+    #[test]
+    fn diagnostics() {
+        let problem = |input: &str| {
+            let diagnostic = Handle::validate(input).unwrap_err();
+            (diagnostic.problem, diagnostic.span)
+        };
+        assert_eq!(problem("1-2-3"), (HandleProblem::PartCount(3), None));
+        assert_eq!(problem("1-2-3-4-5"), (HandleProblem::PartCount(5), None));
+        assert_eq!(
+            problem("1-2g-3-4"),
+            (HandleProblem::NotHex { part: 1 }, Some(2..4))
+        );
+        assert_eq!(
+            problem("1||3-4"),
+            (HandleProblem::NotHex { part: 1 }, Some(2..2))
+        );
+        assert_eq!(
+            problem("0-0-0-c000000000000000"),
+            (HandleProblem::Accessibility(0b11), Some(6..22))
+        );
+        assert_eq!(
+            problem("0-0-0-0800000000000000"),
+            (HandleProblem::ObjectBits(0b01), Some(6..22))
+        );
+        // Literals use those bits for their size.
+        assert!(Handle::validate("0-0-0-2800000000000000").is_ok());
+    }
+
+    #[test]
+    fn thunk_entries() {
+        let thunk = Handle::from_hex("d9-0-4-100000000000000").unwrap();
+        let tree = thunk.thunk_tree().unwrap();
+        assert_eq!(tree.object(), Object::Tree);
+        assert_eq!(tree.size, thunk.size);
+        assert!(tree.thunk_tree().is_none());
+
+        let limits = Handle::from_hex("10-0-0-2400000000000000").unwrap();
+        let entries = [limits.clone(), tree.clone(), thunk.clone()];
+        assert_eq!(
+            ThunkEntries::from_tree(&entries).unwrap(),
+            ThunkEntries {
+                limits,
+                procedure: tree,
+                arguments: vec![thunk],
+            }
+        );
+        assert!(ThunkEntries::from_tree(&entries[..1]).is_err());
+    }
+
     #[test]
     fn thunk() {
         // &strict Thunk(d9|0|4|100000000000000)
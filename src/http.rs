@@ -1,57 +1,717 @@
-use std::sync::{mpsc::Sender, Arc};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
 
 use anyhow::{Context, Result};
-use reqwest::Client;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use crossbeam_channel::{Receiver, Sender};
+use reqwest::{Client, StatusCode};
 use serde::de::DeserializeOwned;
 
-use crate::handle::{Handle, Operation, Task};
+#[cfg(all(feature = "grpc", not(target_arch = "wasm32")))]
+use crate::grpc;
+use crate::metrics::{self, Latencies};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::trace::Tracer;
+use crate::{
+    api::{self, ServerVersion},
+    cache::{Endpoint, ResponseCache},
+    handle::{Handle, Operation, Restriction, TagEntries, Task, TaskStatus, TaskTime},
+    profile::{Routes, ServerProfile, Timeouts, Transport},
+    simulate::{self, NetworkSimulation},
+};
 
+/// Where responses come from: an HTTP client, or a gRPC one when built with
+/// the `grpc` feature, optionally behind simulated network conditions. On
+/// native, requests can also be recorded to a trace or answered from one.
+pub(crate) struct DataSource {
+    client: Mutex<Client>,
+    transport: Mutex<Transport>,
+    timeouts: Mutex<Timeouts>,
+    routes: Mutex<Routes>,
+    /// How long each request that reached a server took.
+    latencies: Mutex<Latencies>,
+    /// Send nothing, so only the cache and a replayed trace answer.
+    offline: AtomicBool,
+    /// Set once the server fails a batch of parents, so that parents are
+    /// asked for one handle at a time until the server changes.
+    batch_failed: AtomicBool,
+    /// What the server said of its version, once it has.
+    version: Mutex<Option<ServerVersion>>,
+    simulation: Mutex<NetworkSimulation>,
+    #[cfg(not(target_arch = "wasm32"))]
+    tracer: Tracer,
+}
+
+impl DataSource {
+    pub(crate) fn new(simulation: NetworkSimulation) -> Self {
+        Self {
+            client: Mutex::new(Client::new()),
+            transport: Mutex::new(Transport::default()),
+            timeouts: Mutex::new(Timeouts::default()),
+            routes: Mutex::new(Routes::default()),
+            latencies: Mutex::new(Latencies::default()),
+            offline: AtomicBool::new(false),
+            batch_failed: AtomicBool::new(false),
+            version: Mutex::new(None),
+            simulation: Mutex::new(simulation),
+            #[cfg(not(target_arch = "wasm32"))]
+            tracer: Tracer::default(),
+        }
+    }
+
+    /// Reach servers the way `profile` says to.
+    pub(crate) fn set_server(&self, profile: &ServerProfile) {
+        *self.transport.lock().unwrap() = profile.transport;
+        *self.routes.lock().unwrap() = profile.routes.clone();
+        self.batch_failed.store(false, Ordering::Relaxed);
+        *self.version.lock().unwrap() = None;
+        let mut timeouts = self.timeouts.lock().unwrap();
+        if *timeouts != profile.timeouts {
+            *timeouts = profile.timeouts;
+            *self.client.lock().unwrap() = build_client(*timeouts);
+        }
+    }
+
+    pub(crate) fn set_offline(&self, offline: bool) {
+        self.offline.store(offline, Ordering::Relaxed);
+    }
+
+    pub(crate) fn set_simulation(&self, simulation: NetworkSimulation) {
+        *self.simulation.lock().unwrap() = simulation;
+    }
+
+    /// Where the queries live on the server.
+    pub(crate) fn routes(&self) -> Routes {
+        self.routes.lock().unwrap().clone()
+    }
+
+    /// The version of the server's answers, once it has been asked.
+    pub(crate) fn server_version(&self) -> Option<ServerVersion> {
+        self.version.lock().unwrap().clone()
+    }
+
+    /// Which version of answers the server at `url_base` gives, asked once
+    /// per server. Servers without the version route give the first, as do
+    /// gRPC ones, whose replies are converted to it already.
+    async fn version(&self, url_base: &str) -> ServerVersion {
+        if let Some(version) = self.version.lock().unwrap().clone() {
+            return version;
+        }
+        let transport = *self.transport.lock().unwrap();
+        let path = self.routes.lock().unwrap().version.clone();
+        if transport != Transport::Http || path.is_empty() {
+            return ServerVersion::default();
+        }
+        let url = format!("{}://{url_base}{path}", transport.scheme());
+        #[cfg(not(target_arch = "wasm32"))]
+        let replayed = self.tracer.replay().map(|replay| replay.respond(&url));
+        #[cfg(target_arch = "wasm32")]
+        let replayed = None;
+        let body = match replayed {
+            Some(body) => body,
+            None if self.offline.load(Ordering::Relaxed) => return ServerVersion::default(),
+            None => self.fetch_http(&url, false).await,
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        self.tracer.record(&url, &body);
+        // Servers that cannot be reached are asked again with the next
+        // request.
+        let Ok(body) = body else {
+            return ServerVersion::default();
+        };
+        let version = ServerVersion::parse(&body).unwrap_or_else(|e| {
+            log::info!("Assuming the server gives the first version of answers: {e:#}");
+            ServerVersion::default()
+        });
+        *self.version.lock().unwrap() = Some(version.clone());
+        version
+    }
+
+    /// `body` in the layout the parsers read, whichever version of answers
+    /// the server at `url_base` gives.
+    async fn normalize(&self, url_base: &str, body: String) -> Result<String> {
+        self.version(url_base).await.api.normalize(body)
+    }
+
+    pub(crate) fn latencies(&self) -> &Mutex<Latencies> {
+        &self.latencies
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn tracer(&self) -> &Tracer {
+        &self.tracer
+    }
+
+    /// Fetch the JSON body answering `kind` from the server at `url_base`, or
+    /// from the replayed trace if there is one. gRPC replies are converted to
+    /// the JSON the HTTP server would send, so both are parsed, cached and
+    /// traced alike.
+    async fn fetch(&self, url_base: &str, kind: &RequestKind) -> Result<String> {
+        let transport = *self.transport.lock().unwrap();
+        let path = kind.path(&self.routes.lock().unwrap());
+        let url = format!("{}://{url_base}{path}", transport.scheme());
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(replay) = self.tracer.replay() {
+            let body = replay.respond(&url);
+            self.tracer.record(&url, &body);
+            return body;
+        }
+        if self.offline.load(Ordering::Relaxed) {
+            return Err(anyhow::anyhow!(
+                "request failed: offline, and {} is not cached",
+                kind.endpoint()
+            ));
+        }
+        let started = metrics::now();
+        let body = match transport {
+            Transport::Http => match kind {
+                RequestKind::Exists { .. } => self.fetch_exists(&url).await,
+                _ => self.fetch_http(&url, kind.is_command()).await,
+            },
+            #[cfg(all(feature = "grpc", not(target_arch = "wasm32")))]
+            Transport::Grpc => {
+                let timeouts = *self.timeouts.lock().unwrap();
+                grpc::fetch(url_base, kind, timeouts).await
+            }
+            #[cfg(not(all(feature = "grpc", not(target_arch = "wasm32"))))]
+            Transport::Grpc => Err(anyhow::anyhow!(
+                "request failed: this build has no gRPC support"
+            )),
+        };
+        self.latencies.lock().unwrap().record(
+            kind.endpoint(),
+            metrics::now() - started,
+            body.is_ok(),
+        );
+        #[cfg(not(target_arch = "wasm32"))]
+        self.tracer.record(&url, &body);
+        body
+    }
+
+    /// Whether parents can be asked for in batches: over HTTP, from a server
+    /// with a batch route that has not failed one, and neither from or into
+    /// a trace, which replays requests one path at a time, nor through a
+    /// simulated network, which delays and fails each request on its own.
+    fn batches(&self) -> bool {
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.tracer.replay().is_some() || self.tracer.recorded().is_some() {
+            return false;
+        }
+        *self.transport.lock().unwrap() == Transport::Http
+            && !self.routes.lock().unwrap().parents_batch.is_empty()
+            && !self.batch_failed.load(Ordering::Relaxed)
+            && !self.offline.load(Ordering::Relaxed)
+            && !self.simulation.lock().unwrap().enabled
+    }
+
+    /// POST `handles` to the batch route for the first page of parents of
+    /// each, only those performing `operation` if set.
+    async fn fetch_batch(
+        &self,
+        url_base: &str,
+        handles: &[Handle],
+        operation: Option<Operation>,
+    ) -> Result<String> {
+        let path = self.routes.lock().unwrap().parents_batch.clone();
+        let url = format!("{}://{url_base}{path}", Transport::Http.scheme());
+        let mut batch = serde_json::json!({
+            "handles": handles.iter().map(Handle::to_hex).collect::<Vec<_>>(),
+            "limit": PAGE_SIZE,
+        });
+        if let Some(operation) = operation {
+            batch["operation"] = (operation as u8).into();
+        }
+        let client = self.client.lock().unwrap().clone();
+        let started = metrics::now();
+        let body = async {
+            let response = client.post(&url).json(&batch).send().await?;
+            response.error_for_status()?.text().await
+        }
+        .await
+        .map_err(|e| anyhow::anyhow!("request failed: batch of parents: {e}"));
+        self.latencies.lock().unwrap().record(
+            Endpoint::Parents,
+            metrics::now() - started,
+            body.is_ok(),
+        );
+        #[cfg(not(target_arch = "wasm32"))]
+        self.tracer.record(&url, &body);
+        body
+    }
+
+    /// HEAD `url`, answering whether the object exists in the JSON the
+    /// parsers read, so that probes are traced and replayed like the rest.
+    async fn fetch_exists(&self, url: &str) -> Result<String> {
+        let client = self.client.lock().unwrap().clone();
+        let response = client
+            .head(url)
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("request failed: probing: {e}"))?;
+        let exists = match response.status() {
+            StatusCode::NOT_FOUND => false,
+            status if status.is_success() => true,
+            status => anyhow::bail!("request failed: probing: status {status}"),
+        };
+        Ok(serde_json::json!({ "exists": exists }).to_string())
+    }
+
+    /// GET `url`, or POST to it for a `command`.
+    async fn fetch_http(&self, url: &str, command: bool) -> Result<String> {
+        let client = self.client.lock().unwrap().clone();
+        let request = if command {
+            client.post(url)
+        } else {
+            client.get(url)
+        };
+        let body = match request.send().await {
+            Ok(response) => response.text().await,
+            Err(e) => Err(e),
+        };
+        let body = body.map_err(|e| {
+            anyhow::anyhow!(
+                "request failed: {} error",
+                match () {
+                    () if e.is_builder() => "building url",
+                    () if e.is_request() => "request",
+                    () if e.is_redirect() => "redirect",
+                    () if e.is_status() => "status code",
+                    () if e.is_body() => "body",
+                    () if e.is_decode() => "decode",
+                    () if e.is_timeout() => "timeout",
+                    () => "unknown",
+                }
+            )
+        });
+        body
+    }
+}
+
+/// A client that gives up after `timeouts`. Browsers pick their own
+/// timeouts, so they are ignored on the web.
+fn build_client(timeouts: Timeouts) -> Client {
+    #[cfg(not(target_arch = "wasm32"))]
+    let client = Client::builder()
+        .connect_timeout(timeouts.connect())
+        .timeout(timeouts.read())
+        .build()
+        .unwrap_or_default();
+    #[cfg(target_arch = "wasm32")]
+    let client = {
+        let _ = timeouts;
+        Client::new()
+    };
+    client
+}
+
+/// How many parents or dependees are asked for at once.
+pub(crate) const PAGE_SIZE: usize = 100;
+
+/// Where the rest of a list of parents or dependees continues.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub(crate) struct NextPage {
+    /// Passed back to the server as `after` to get the next page.
+    pub(crate) cursor: String,
+    /// How many entries are left after this page.
+    pub(crate) remaining: u64,
+}
+
+#[derive(Clone)]
 pub(crate) enum Response {
-    Parents(Option<Vec<Task>>),
-    Child(Option<Handle>),
-    Dependees(Option<Vec<Task>>),
+    Parents(Option<Vec<Task>>, Option<NextPage>),
+    ParentCount(Option<u64>),
+    Child(Option<(Handle, Option<Restriction>)>),
+    Dependees(Option<Vec<Task>>, Option<NextPage>),
+    Status(Option<TaskStatus>),
+    TaskTimes(Option<Vec<TaskTime>>),
+    Tag(Option<TagEntries>),
+    Tree(Option<Vec<Handle>>),
+    Blob(Option<Vec<u8>>),
+    Canonical(Option<Handle>),
+    /// The status of the task once asked to run again, or `None` if the
+    /// server would not.
+    Rerun(Option<TaskStatus>),
+    /// Whether the handle's data is resident once asked to load it.
+    Materialize(bool),
+    Roots(Vec<Handle>),
+    /// Whether the server has the handle's object.
+    Exists(bool),
+}
+
+/// Identifies a single request for the lifetime of the app.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct RequestId(u64);
+
+/// Which graph or tree a request was made for, so that answers arriving
+/// after it was replaced can be dropped. The default is never handed out,
+/// and is for requests made for no graph.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub(crate) struct Generation(u64);
+
+impl Generation {
+    /// A generation no tree has had before.
+    pub(crate) fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// The UI action a request was made for, along with what the dispatcher needs
+/// to apply its response.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum RequestKind {
+    /// A page of parents of a handle in the ancestry tree of `generation`,
+    /// starting after the `after` cursor or at the first page.
+    Parents {
+        generation: Generation,
+        handle: Handle,
+        after: Option<String>,
+        /// Only parents performing this operation, if set.
+        operation: Option<Operation>,
+    },
+    /// How many parents a handle in the ancestry tree has, shown before
+    /// they are fetched.
+    ParentCount { handle: Handle },
+    /// Result of the task at `index` in the ordering of the progress tree of
+    /// `generation`.
+    Child {
+        generation: Generation,
+        index: usize,
+        handle: Handle,
+        operation: Operation,
+    },
+    /// A page of dependees of the task at `index` in the ordering of the
+    /// progress tree of `generation`, starting after the `after` cursor or at
+    /// the first page.
+    Dependees {
+        generation: Generation,
+        index: usize,
+        handle: Handle,
+        operation: Operation,
+        after: Option<String>,
+    },
+    /// Execution status of a task in the progress tree.
+    Status {
+        handle: Handle,
+        operation: Operation,
+    },
+    /// Start and end times for the timeline.
+    TaskTimes { handle: Handle },
+    /// What a Thunk gives when forced by `operation`, for the inspector's
+    /// explanation of it.
+    Outcome {
+        handle: Handle,
+        operation: Operation,
+    },
+    /// Entries of a Tag for the inspector.
+    Tag { handle: Handle },
+    /// Entries of a Tree for the inspector.
+    Tree { handle: Handle },
+    /// Contents of a Blob for the inspector to preview.
+    Blob { handle: Handle },
+    /// The canonical handle of a local handle, to find local handles that
+    /// are the same object.
+    Canonical { handle: Handle },
+    /// Run a task in the progress tree again.
+    Rerun {
+        handle: Handle,
+        operation: Operation,
+    },
+    /// Load the data of a lazy or shallow handle, from the inspector.
+    Materialize { handle: Handle },
+    /// Top-level targets the server knows about, for the side panel.
+    Roots,
+    /// Whether the server has a handle in the ancestry tree, for probing.
+    Exists { handle: Handle },
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Request {
+    pub(crate) id: RequestId,
+    pub(crate) kind: RequestKind,
+}
+
+/// What is sent back over the response channel.
+pub(crate) struct Envelope {
+    pub(crate) request: Request,
+    pub(crate) result: Result<Response>,
+}
+
+impl Request {
+    pub(crate) fn new(kind: RequestKind) -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        Self {
+            id: RequestId(NEXT_ID.fetch_add(1, Ordering::Relaxed)),
+            kind,
+        }
+    }
+}
+
+impl RequestKind {
+    /// The handle the request is about, if it is about one.
+    pub(crate) fn handle(&self) -> Option<&Handle> {
+        match self {
+            RequestKind::Parents { handle, .. }
+            | RequestKind::ParentCount { handle }
+            | RequestKind::Child { handle, .. }
+            | RequestKind::Outcome { handle, .. }
+            | RequestKind::Dependees { handle, .. }
+            | RequestKind::Status { handle, .. }
+            | RequestKind::TaskTimes { handle }
+            | RequestKind::Tag { handle }
+            | RequestKind::Tree { handle }
+            | RequestKind::Blob { handle }
+            | RequestKind::Canonical { handle }
+            | RequestKind::Rerun { handle, .. }
+            | RequestKind::Materialize { handle }
+            | RequestKind::Exists { handle } => Some(handle),
+            RequestKind::Roots => None,
+        }
+    }
+
+    pub(crate) fn endpoint(&self) -> Endpoint {
+        match self {
+            RequestKind::Parents {
+                operation: None, ..
+            } => Endpoint::Parents,
+            RequestKind::Parents {
+                operation: Some(operation),
+                ..
+            } => Endpoint::FilteredParents(*operation),
+            RequestKind::ParentCount { .. } => Endpoint::ParentCount,
+            RequestKind::Child { operation, .. } | RequestKind::Outcome { operation, .. } => {
+                Endpoint::Child(*operation)
+            }
+            RequestKind::Dependees { operation, .. } => Endpoint::Dependees(*operation),
+            RequestKind::Status { operation, .. } => Endpoint::Status(*operation),
+            RequestKind::TaskTimes { .. } => Endpoint::TaskTimes,
+            RequestKind::Tag { .. } => Endpoint::Tag,
+            RequestKind::Tree { .. } => Endpoint::Tree,
+            RequestKind::Blob { .. } => Endpoint::Blob,
+            RequestKind::Canonical { .. } => Endpoint::Canonical,
+            RequestKind::Rerun { operation, .. } => Endpoint::Rerun(*operation),
+            RequestKind::Materialize { .. } => Endpoint::Materialize,
+            RequestKind::Roots => Endpoint::Roots,
+            RequestKind::Exists { .. } => Endpoint::Exists,
+        }
+    }
+
+    /// The cursor of the page asked for, if not the first.
+    pub(crate) fn after(&self) -> Option<&str> {
+        match self {
+            RequestKind::Parents { after, .. } | RequestKind::Dependees { after, .. } => {
+                after.as_deref()
+            }
+            _ => None,
+        }
+    }
+
+    /// Path and query of the request on an HTTP server with `routes`.
+    pub(crate) fn path(&self, routes: &Routes) -> String {
+        match self.handle() {
+            Some(handle) => endpoint_path(routes, self.endpoint(), handle, self.after()),
+            None => routes.roots.clone(),
+        }
+    }
+
+    /// Whether the request tells the server to do something instead of
+    /// asking about what it did. These are sent as POSTs.
+    pub(crate) fn is_command(&self) -> bool {
+        matches!(
+            self,
+            RequestKind::Rerun { .. } | RequestKind::Materialize { .. }
+        )
+    }
+
+    /// Whether the answer is kept in the response cache: answers about a
+    /// handle, but not to commands, about objects, which come and go, or
+    /// about statuses, which are polled.
+    pub(crate) fn is_cached(&self) -> bool {
+        !self.is_command()
+            && !matches!(
+                self,
+                RequestKind::Exists { .. } | RequestKind::Status { .. }
+            )
+    }
+
+    /// Later pages are not cached, since the cache keeps one response per
+    /// handle and endpoint.
+    pub(crate) fn is_first_page(&self) -> bool {
+        match self {
+            RequestKind::Parents { after, .. } | RequestKind::Dependees { after, .. } => {
+                after.is_none()
+            }
+            _ => true,
+        }
+    }
 }
 
-pub(crate) fn get<T, S, F>(
-    client: Arc<Client>,
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+impl Response {
+    /// A short name for the kind of response, used in logs.
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Response::Parents(..) => "parents",
+            Response::ParentCount(_) => "parent count",
+            Response::Child(_) => "child",
+            Response::Dependees(..) => "dependees",
+            Response::Status(_) => "status",
+            Response::TaskTimes(_) => "task times",
+            Response::Tag(_) => "tag",
+            Response::Tree(_) => "tree",
+            Response::Blob(_) => "blob",
+            Response::Canonical(_) => "canonical",
+            Response::Rerun(_) => "re-run",
+            Response::Materialize(_) => "materialize",
+            Response::Roots(_) => "roots",
+            Response::Exists(_) => "exists",
+        }
+    }
+
+    /// Every handle the response names.
+    pub(crate) fn handles(&self) -> Vec<Handle> {
+        let tasks = |tasks: &Option<Vec<Task>>| {
+            tasks
+                .iter()
+                .flatten()
+                .map(|task| task.handle.clone())
+                .collect()
+        };
+        match self {
+            Response::Parents(parents, _) => tasks(parents),
+            Response::Dependees(dependees, _) => tasks(dependees),
+            Response::Child(child) => child.iter().map(|(handle, _)| handle.clone()).collect(),
+            Response::TaskTimes(times) => times
+                .iter()
+                .flatten()
+                .map(|time| time.task.handle.clone())
+                .collect(),
+            Response::Tag(tag) => tag
+                .iter()
+                .flat_map(|tag| [tag.tagged.clone(), tag.authority.clone(), tag.label.clone()])
+                .collect(),
+            Response::Tree(entries) => entries.iter().flatten().cloned().collect(),
+            Response::Canonical(canonical) => canonical.iter().cloned().collect(),
+            Response::Roots(roots) => roots.clone(),
+            Response::ParentCount(_)
+            | Response::Status(_)
+            | Response::Blob(_)
+            | Response::Rerun(_)
+            | Response::Materialize(_)
+            | Response::Exists(_) => vec![],
+        }
+    }
+
+    /// Whether this is the kind of response a request of `kind` expects.
+    pub(crate) fn answers(&self, kind: &RequestKind) -> bool {
+        matches!(
+            (kind, self),
+            (RequestKind::Parents { .. }, Response::Parents(..))
+                | (RequestKind::ParentCount { .. }, Response::ParentCount(_))
+                | (RequestKind::Child { .. }, Response::Child(_))
+                | (RequestKind::Outcome { .. }, Response::Child(_))
+                | (RequestKind::Dependees { .. }, Response::Dependees(..))
+                | (RequestKind::Status { .. }, Response::Status(_))
+                | (RequestKind::TaskTimes { .. }, Response::TaskTimes(_))
+                | (RequestKind::Tag { .. }, Response::Tag(_))
+                | (RequestKind::Tree { .. }, Response::Tree(_))
+                | (RequestKind::Blob { .. }, Response::Blob(_))
+                | (RequestKind::Canonical { .. }, Response::Canonical(_))
+                | (RequestKind::Rerun { .. }, Response::Rerun(_))
+                | (RequestKind::Materialize { .. }, Response::Materialize(_))
+                | (RequestKind::Roots, Response::Roots(_))
+                | (RequestKind::Exists { .. }, Response::Exists(_))
+        )
+    }
+}
+
+/// Whether a repaint has been asked for that has not drained the responses
+/// yet. Responses that arrive in the meantime are picked up by that repaint.
+static REPAINT_PENDING: AtomicBool = AtomicBool::new(false);
+
+/// Send `envelope` to the UI, waking it only if no repaint is on its way
+/// already, so that a burst of responses is handled in one frame.
+pub(crate) fn deliver(ctx: &egui::Context, tx: &Sender<Envelope>, envelope: Envelope) {
+    let _ = tx.send(envelope);
+    if !REPAINT_PENDING.swap(true, Ordering::AcqRel) {
+        ctx.request_repaint();
+    }
+}
+
+/// Every response that has arrived, oldest first.
+pub(crate) fn drain(rx: &Receiver<Envelope>) -> impl Iterator<Item = Envelope> + '_ {
+    // Cleared before draining, so a response sent after the drain asks for
+    // another repaint.
+    REPAINT_PENDING.store(false, Ordering::Release);
+    rx.try_iter()
+}
+
+pub(crate) fn get<T, F>(
+    source: Arc<DataSource>,
     ctx: egui::Context,
-    index: usize,
-    handle: Handle,
-    url: String,
+    request: Request,
+    url_base: String,
     map: F,
-    tx: Sender<(usize, Handle, Result<S>)>,
+    tx: Sender<Envelope>,
 ) where
     T: DeserializeOwned + Send,
-    S: Send + 'static,
-    F: FnOnce(T) -> Result<S> + Send + 'static,
+    F: FnOnce(T) -> Result<Response> + Send + 'static,
 {
+    let simulation = *source.simulation.lock().unwrap();
     let task = async move {
-        let result = client.get(url).send().await;
-        match result {
-            Ok(ok) => {
-                let json = ok.json::<T>().await;
-                let _ = tx.send((index, handle, json.context("parsing json").and_then(map)));
-            }
-            Err(e) => {
-                let _ = tx.send((
-                    index,
-                    handle,
-                    Err(anyhow::anyhow!(format!(
-                        "request failed: {} error",
-                        match () {
-                            () if e.is_builder() => "building url",
-                            () if e.is_request() => "request",
-                            () if e.is_redirect() => "redirect",
-                            () if e.is_status() => "status code",
-                            () if e.is_body() => "body",
-                            () if e.is_decode() => "decode",
-                            () if e.is_timeout() => "timeout",
-                            () => "unknown",
-                        }
-                    ))),
-                ));
+        if simulation.enabled {
+            let (delay, fail) = simulation.sample(request.id.0);
+            simulate::sleep(delay).await;
+            if fail {
+                let result = Err(anyhow::anyhow!("request failed: simulated network failure"));
+                deliver(&ctx, &tx, Envelope { request, result });
+                return;
             }
         }
+        let result = match source.fetch(&url_base, &request.kind).await {
+            Ok(body) => source.normalize(&url_base, body).await,
+            Err(e) => Err(e),
+        }
+        .and_then(|body| {
+            serde_json::from_str::<T>(&body)
+                .context("parsing json")
+                .and_then(map)
+        });
+        deliver(&ctx, &tx, Envelope { request, result });
+    };
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_futures::spawn_local(task);
+    #[cfg(not(target_arch = "wasm32"))]
+    #[allow(clippy::let_underscore_future)]
+    let _ = tokio::spawn(task);
+}
+
+/// Fetch the body answering `request` without parsing or caching it, for
+/// the query console. The body is in the layout the parsers read, so that
+/// `parse_response` can apply it as the viewer would.
+pub(crate) fn get_raw(
+    source: Arc<DataSource>,
+    ctx: egui::Context,
+    request: Request,
+    url_base: String,
+    tx: Sender<(Request, Result<String>)>,
+) {
+    let task = async move {
+        let result = match source.fetch(&url_base, &request.kind).await {
+            Ok(body) => source.normalize(&url_base, body).await,
+            Err(e) => Err(e),
+        };
+        let _ = tx.send((request, result));
         ctx.request_repaint();
     };
     #[cfg(target_arch = "wasm32")]
@@ -64,133 +724,1085 @@ pub(crate) fn get<T, S, F>(
 #[derive(serde::Deserialize)]
 struct JsonTask {
     handle: String,
-    operation: String,
+    /// A number, a number in a string, or a name.
+    operation: serde_json::Value,
+    #[serde(flatten)]
+    restriction: JsonRestriction,
+    #[serde(default)]
+    recorded: Option<f64>,
 }
 
-pub(crate) fn get_parents(
-    client: Arc<Client>,
-    ctx: egui::Context,
-    index: usize,
+/// Optional fields the server adds to handles it restricts access to.
+#[derive(serde::Deserialize)]
+struct JsonRestriction {
+    #[serde(default)]
+    restricted: bool,
+    #[serde(default)]
+    permission: Option<String>,
+}
+
+impl JsonTask {
+    fn parse(&self) -> Result<Task> {
+        Ok(Task {
+            handle: Handle::from_hex(&self.handle).context("parsing handle")?,
+            operation: api::parse_operation(&self.operation)?,
+            restriction: self.restriction.parse(),
+            recorded: self.recorded,
+        })
+    }
+}
+
+/// Fields the server adds to a page of a list that does not fit in one.
+#[derive(serde::Deserialize)]
+struct JsonPage {
+    #[serde(default)]
+    next: Option<String>,
+    #[serde(default)]
+    remaining: u64,
+}
+
+impl JsonPage {
+    fn parse(self) -> Option<NextPage> {
+        self.next.map(|cursor| NextPage {
+            cursor,
+            remaining: self.remaining,
+        })
+    }
+}
+
+/// The handle parameter, and the pagination parameters for a list endpoint.
+fn page_query(routes: &Routes, handle: &Handle, after: Option<&str>) -> String {
+    let mut query = format!(
+        "?{}={}&{}={PAGE_SIZE}",
+        routes.handle,
+        handle.to_hex(),
+        routes.limit
+    );
+    if let Some(after) = after {
+        query.push_str(&format!("&{}={after}", routes.after));
+    }
+    query
+}
+
+/// Path and query of the request for `endpoint` of `handle` on a server with
+/// `routes`. `after` picks the page of a list endpoint and is ignored by the
+/// others.
+pub(crate) fn endpoint_path(
+    routes: &Routes,
+    endpoint: Endpoint,
     handle: &Handle,
-    tx: Sender<(usize, Handle, Result<Response>)>,
+    after: Option<&str>,
+) -> String {
+    let single = |path: &str| format!("{path}?{}={}", routes.handle, handle.to_hex());
+    let with_operation = |query: String, operation: Operation| {
+        format!("{query}&{}={}", routes.operation, operation as u8)
+    };
+    match endpoint {
+        Endpoint::Parents => format!("{}{}", routes.parents, page_query(routes, handle, after)),
+        Endpoint::FilteredParents(operation) => with_operation(
+            format!("{}{}", routes.parents, page_query(routes, handle, after)),
+            operation,
+        ),
+        Endpoint::ParentCount => single(&routes.parent_count),
+        Endpoint::Dependees(operation) => with_operation(
+            format!("{}{}", routes.dependees, page_query(routes, handle, after)),
+            operation,
+        ),
+        Endpoint::Child(operation) => with_operation(single(&routes.child), operation),
+        Endpoint::Status(operation) => with_operation(single(&routes.status), operation),
+        Endpoint::Rerun(operation) => with_operation(single(&routes.rerun), operation),
+        Endpoint::Materialize => single(&routes.materialize),
+        Endpoint::TaskTimes => single(&routes.task_times),
+        Endpoint::Tag => single(&routes.tag),
+        Endpoint::Tree => single(&routes.tree),
+        Endpoint::Blob => single(&routes.blob),
+        Endpoint::Canonical => single(&routes.canonical),
+        Endpoint::Roots => routes.roots.clone(),
+        Endpoint::Exists => single(&routes.object),
+    }
+}
+
+/// A shell script of curl commands that make the first request of each query,
+/// one per line, for debugging outside the viewer.
+pub(crate) fn curl_commands(
     url_base: &str,
-) {
-    #[derive(serde::Deserialize)]
-    struct JsonResponse {
-        parents: Option<Vec<JsonTask>>,
+    routes: &Routes,
+    queries: &[(Endpoint, Handle)],
+) -> String {
+    queries
+        .iter()
+        .map(|(endpoint, handle)| {
+            format!(
+                "curl -s 'http://{url_base}{}'\n",
+                endpoint_path(routes, *endpoint, handle, None)
+            )
+        })
+        .collect()
+}
+
+impl JsonRestriction {
+    fn parse(&self) -> Option<Restriction> {
+        self.restricted.then(|| Restriction {
+            permission: self
+                .permission
+                .clone()
+                .unwrap_or_else(|| "unknown".to_string()),
+        })
     }
+}
 
-    get(
-        client,
-        ctx,
-        index,
-        handle.clone(),
-        format!("http://{url_base}/parents?handle={}", handle.to_hex()),
-        |json: JsonResponse| {
-            let Some(json_parents )= json.parents else {
-                return Ok(Response::Parents(None));
-            };
-            Ok(Response::Parents(Some(
+/// A page of parents, as the parents route and each entry of the batch
+/// route answer it.
+#[derive(serde::Deserialize)]
+struct JsonParents {
+    parents: Option<Vec<JsonTask>>,
+    #[serde(flatten)]
+    page: JsonPage,
+}
+
+impl JsonParents {
+    fn parse(self) -> Result<Response> {
+        let next = self.page.parse();
+        let Some(json_parents) = self.parents else {
+            return Ok(Response::Parents(None, next));
+        };
+        Ok(Response::Parents(
+            Some(
                 json_parents
                     .iter()
-                    .map(|json_task| {
-                        Ok::<Task, anyhow::Error>(Task {
-                            handle: Handle::from_hex(&json_task.handle)
-                                .context("parsing handle")?,
-                            operation: json_task
-                                .operation
-                                .parse::<u8>()
-                                .context("parsing operation as u8")?
-                                .try_into()
-                                .context("casting u8 to operation")?,
-                        })
-                    })
+                    .map(JsonTask::parse)
                     .collect::<Result<Vec<_>>>()?,
-            )))
-        },
+            ),
+            next,
+        ))
+    }
+}
+
+/// Fetch a page of the parents of `handle`, starting after `after` or at the
+/// first page. With `operation` set, only parents performing it are fetched.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn get_parents(
+    source: Arc<DataSource>,
+    ctx: egui::Context,
+    generation: Generation,
+    handle: &Handle,
+    after: Option<&str>,
+    operation: Option<Operation>,
+    tx: Sender<Envelope>,
+    url_base: &str,
+    cache: &ResponseCache,
+) -> RequestId {
+    let request = Request::new(RequestKind::Parents {
+        generation,
+        handle: handle.clone(),
+        after: after.map(str::to_string),
+        operation,
+    });
+    let id = request.id;
+    get(
+        source,
+        ctx.clone(),
+        request.clone(),
+        url_base.to_string(),
+        cache.inserting(ctx, &request.kind, JsonParents::parse),
         tx,
     );
+    id
 }
 
-pub(crate) fn get_dependees(
-    client: Arc<Client>,
+/// Fetch the first page of parents of every handle in `handles` with one
+/// POST to the batch route, answering each over `tx` as if it had been asked
+/// for alone. Asks for each alone instead if the server cannot batch.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn get_parents_batch(
+    source: Arc<DataSource>,
     ctx: egui::Context,
-    index: usize,
-    handle: Handle,
-    operation: Operation,
-    tx: Sender<(usize, Handle, Result<Response>)>,
+    generation: Generation,
+    handles: Vec<Handle>,
+    operation: Option<Operation>,
+    tx: Sender<Envelope>,
     url_base: &str,
+    cache: &ResponseCache,
 ) {
-    #[derive(serde::Deserialize)]
-    struct JsonResponse {
-        dependees: Option<Vec<JsonTask>>,
+    let one_by_one = move |source: &Arc<DataSource>,
+                           ctx: &egui::Context,
+                           handles: &[Handle],
+                           tx: &Sender<Envelope>,
+                           url_base: &str,
+                           cache: &ResponseCache| {
+        for handle in handles {
+            get_parents(
+                source.clone(),
+                ctx.clone(),
+                generation,
+                handle,
+                None,
+                operation,
+                tx.clone(),
+                url_base,
+                cache,
+            );
+        }
+    };
+    if handles.len() < 2 || !source.batches() {
+        one_by_one(&source, &ctx, &handles, &tx, url_base, cache);
+        return;
     }
+    let requests: Vec<_> = handles
+        .iter()
+        .map(|handle| {
+            Request::new(RequestKind::Parents {
+                generation,
+                handle: handle.clone(),
+                after: None,
+                operation,
+            })
+        })
+        .collect();
+    let (url_base, cache) = (url_base.to_string(), cache.clone());
+    let task = async move {
+        let answered = match source.fetch_batch(&url_base, &handles, operation).await {
+            Ok(body) => source.normalize(&url_base, body).await,
+            Err(e) => Err(e),
+        }
+        .and_then(|body| demultiplex(&body, &handles));
+        let results = match answered {
+            Ok(results) => results,
+            Err(e) => {
+                log::warn!("Asking for parents one handle at a time: {e:#}");
+                source.batch_failed.store(true, Ordering::Relaxed);
+                one_by_one(&source, &ctx, &handles, &tx, &url_base, &cache);
+                return;
+            }
+        };
+        for (request, result) in requests.into_iter().zip(results) {
+            let result = result
+                .and_then(|response| cache.inserting(ctx.clone(), &request.kind, Ok)(response));
+            deliver(&ctx, &tx, Envelope { request, result });
+        }
+    };
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_futures::spawn_local(task);
+    #[cfg(not(target_arch = "wasm32"))]
+    #[allow(clippy::let_underscore_future)]
+    let _ = tokio::spawn(task);
+}
 
+/// The answer of the parent count route.
+#[derive(serde::Deserialize)]
+struct JsonParentCount {
+    count: Option<u64>,
+}
+
+impl JsonParentCount {
+    fn parse(self) -> Result<Response> {
+        Ok(Response::ParentCount(self.count))
+    }
+}
+
+/// The answer of the batch route: a page of parents for each handle.
+#[derive(serde::Deserialize)]
+struct JsonBatch {
+    results: Vec<JsonBatchEntry>,
+}
+
+#[derive(serde::Deserialize)]
+struct JsonBatchEntry {
+    handle: String,
+    #[serde(flatten)]
+    parents: JsonParents,
+}
+
+/// The parents of each of `handles`, in order, from `body`, an answer of the
+/// batch route. Handles the answer has nothing for, or nothing readable,
+/// fail on their own. Fails as a whole if the answer cannot be read at all,
+/// for the handles to be asked for one at a time instead.
+fn demultiplex(body: &str, handles: &[Handle]) -> Result<Vec<Result<Response>>> {
+    let json: JsonBatch = serde_json::from_str(body).context("parsing json")?;
+    let mut results: HashMap<Handle, JsonParents> = json
+        .results
+        .into_iter()
+        .filter_map(|entry| Some((Handle::from_hex(&entry.handle).ok()?, entry.parents)))
+        .collect();
+    Ok(handles
+        .iter()
+        .map(|handle| match results.remove(handle) {
+            Some(parents) => parents.parse(),
+            None => Err(anyhow::anyhow!(
+                "request failed: the batch answer has nothing for {}",
+                handle.to_hex()
+            )),
+        })
+        .collect())
+}
+
+/// Fetch how many parents `handle` has, which is cheaper than fetching them.
+pub(crate) fn get_parent_count(
+    source: Arc<DataSource>,
+    ctx: egui::Context,
+    handle: &Handle,
+    tx: Sender<Envelope>,
+    url_base: &str,
+    cache: &ResponseCache,
+) -> RequestId {
+    let request = Request::new(RequestKind::ParentCount {
+        handle: handle.clone(),
+    });
+    let id = request.id;
     get(
-        client,
-        ctx,
-        index,
-        handle.clone(),
-        format!(
-            "http://{url_base}/dependees?handle={}&op={}",
-            handle.to_hex(),
-            operation as u8
-        ),
-        |json: JsonResponse| {
-            let Some(dependees) = json.dependees else {
-                return Ok(Response::Dependees(None));
-            };
-            Ok(Response::Dependees(Some(
+        source,
+        ctx.clone(),
+        request.clone(),
+        url_base.to_string(),
+        cache.inserting(ctx, &request.kind, JsonParentCount::parse),
+        tx,
+    );
+    id
+}
+
+/// A page of dependees, as the dependees route answers it.
+#[derive(serde::Deserialize)]
+struct JsonDependees {
+    dependees: Option<Vec<JsonTask>>,
+    #[serde(flatten)]
+    page: JsonPage,
+}
+
+impl JsonDependees {
+    fn parse(self) -> Result<Response> {
+        let next = self.page.parse();
+        let Some(dependees) = self.dependees else {
+            return Ok(Response::Dependees(None, next));
+        };
+        Ok(Response::Dependees(
+            Some(
                 dependees
                     .iter()
-                    .map(|json_task| {
-                        Ok::<Task, anyhow::Error>(Task {
-                            handle: Handle::from_hex(&json_task.handle)
-                                .context("parsing handle")?,
-                            operation: json_task
-                                .operation
-                                .parse::<u8>()
-                                .context("parsing operation as u8")?
-                                .try_into()
-                                .context("casting u8 to operation")?,
-                        })
-                    })
+                    .map(JsonTask::parse)
                     .collect::<Result<Vec<_>>>()?,
-            )))
-        },
+            ),
+            next,
+        ))
+    }
+}
+
+/// Fetch a page of the dependees of the task at `index`, starting after
+/// `after` or at the first page.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn get_dependees(
+    source: Arc<DataSource>,
+    ctx: egui::Context,
+    generation: Generation,
+    index: usize,
+    handle: Handle,
+    operation: Operation,
+    after: Option<String>,
+    tx: Sender<Envelope>,
+    url_base: &str,
+    cache: &ResponseCache,
+) -> RequestId {
+    let request = Request::new(RequestKind::Dependees {
+        generation,
+        index,
+        handle,
+        operation,
+        after,
+    });
+    let id = request.id;
+    get(
+        source,
+        ctx.clone(),
+        request.clone(),
+        url_base.to_string(),
+        cache.inserting(ctx, &request.kind, JsonDependees::parse),
         tx,
     );
+    id
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn get_child(
-    client: Arc<Client>,
+    source: Arc<DataSource>,
     ctx: egui::Context,
+    generation: Generation,
     index: usize,
     handle: Handle,
     operation: Operation,
-    tx: Sender<(usize, Handle, Result<Response>)>,
+    tx: Sender<Envelope>,
     url_base: &str,
-) {
-    #[derive(serde::Deserialize)]
-    struct JsonResponse {
-        handle: Option<String>,
+    cache: &ResponseCache,
+) -> RequestId {
+    let request = Request::new(RequestKind::Child {
+        generation,
+        index,
+        handle,
+        operation,
+    });
+    fetch_child(source, ctx, request, tx, url_base, cache)
+}
+
+/// Fetch what the Thunk `handle` gives when forced by `operation`, for the
+/// inspector rather than the progress tree.
+pub(crate) fn get_outcome(
+    source: Arc<DataSource>,
+    ctx: egui::Context,
+    handle: &Handle,
+    operation: Operation,
+    tx: Sender<Envelope>,
+    url_base: &str,
+    cache: &ResponseCache,
+) -> RequestId {
+    let request = Request::new(RequestKind::Outcome {
+        handle: handle.clone(),
+        operation,
+    });
+    fetch_child(source, ctx, request, tx, url_base, cache)
+}
+
+/// The answer of the child route, for a child or an outcome.
+#[derive(serde::Deserialize)]
+struct JsonChild {
+    handle: Option<String>,
+    #[serde(flatten)]
+    restriction: JsonRestriction,
+}
+
+impl JsonChild {
+    fn parse(self) -> Result<Response> {
+        let restriction = self.restriction.parse();
+        Ok(Response::Child(self.handle.and_then(|handle| {
+            Handle::from_hex(&handle)
+                .context("parsing handle")
+                .ok()
+                .map(|handle| (handle, restriction))
+        })))
+    }
+}
+
+/// Fetch the result of the task `request` is about, for either a child in
+/// the progress tree or an outcome.
+fn fetch_child(
+    source: Arc<DataSource>,
+    ctx: egui::Context,
+    request: Request,
+    tx: Sender<Envelope>,
+    url_base: &str,
+    cache: &ResponseCache,
+) -> RequestId {
+    let id = request.id;
+    get(
+        source,
+        ctx.clone(),
+        request.clone(),
+        url_base.to_string(),
+        cache.inserting(ctx, &request.kind, JsonChild::parse),
+        tx,
+    );
+    id
+}
+
+/// The answer of the status route.
+#[derive(serde::Deserialize)]
+struct JsonStatus {
+    status: Option<TaskStatus>,
+}
+
+impl JsonStatus {
+    fn parse(self) -> Result<Response> {
+        Ok(Response::Status(self.status))
     }
+}
 
+/// Fetch whether the task performing `operation` on `handle` is pending,
+/// running, done or failed, if the runtime knows. Statuses are polled, so
+/// are never cached.
+pub(crate) fn get_status(
+    source: Arc<DataSource>,
+    ctx: egui::Context,
+    handle: &Handle,
+    operation: Operation,
+    tx: Sender<Envelope>,
+    url_base: &str,
+) -> RequestId {
+    let request = Request::new(RequestKind::Status {
+        handle: handle.clone(),
+        operation,
+    });
+    let id = request.id;
     get(
-        client,
+        source,
         ctx,
-        index,
-        handle.clone(),
-        format!(
-            "http://{url_base}/child?handle={}&op={}",
-            handle.to_hex(),
-            operation as u8
-        ),
-        |json: JsonResponse| {
-            Ok(Response::Child(json.handle.and_then(|handle| {
-                Handle::from_hex(&handle).context("parsing handle").ok()
-            })))
-        },
+        request,
+        url_base.to_string(),
+        JsonStatus::parse,
+        tx,
+    );
+    id
+}
+
+/// The answer of the re-run route.
+#[derive(serde::Deserialize)]
+struct JsonRerun {
+    status: Option<TaskStatus>,
+}
+
+impl JsonRerun {
+    fn parse(self) -> Result<Response> {
+        Ok(Response::Rerun(self.status))
+    }
+}
+
+/// Ask the server to run the task performing `operation` on `handle` again.
+/// The answer is never cached, since it is not a fact about the task.
+pub(crate) fn post_rerun(
+    source: Arc<DataSource>,
+    ctx: egui::Context,
+    handle: &Handle,
+    operation: Operation,
+    tx: Sender<Envelope>,
+    url_base: &str,
+) -> RequestId {
+    let request = Request::new(RequestKind::Rerun {
+        handle: handle.clone(),
+        operation,
+    });
+    let id = request.id;
+    get(
+        source,
+        ctx,
+        request,
+        url_base.to_string(),
+        JsonRerun::parse,
+        tx,
+    );
+    id
+}
+
+/// The answer of the materialize route.
+#[derive(serde::Deserialize)]
+struct JsonMaterialize {
+    resident: bool,
+}
+
+impl JsonMaterialize {
+    fn parse(self) -> Result<Response> {
+        Ok(Response::Materialize(self.resident))
+    }
+}
+
+/// Ask the server to load the data of the lazy or shallow `handle`. Like a
+/// re-run, the answer is never cached.
+pub(crate) fn post_materialize(
+    source: Arc<DataSource>,
+    ctx: egui::Context,
+    handle: &Handle,
+    tx: Sender<Envelope>,
+    url_base: &str,
+) -> RequestId {
+    let request = Request::new(RequestKind::Materialize {
+        handle: handle.clone(),
+    });
+    let id = request.id;
+    get(
+        source,
+        ctx,
+        request,
+        url_base.to_string(),
+        JsonMaterialize::parse,
+        tx,
+    );
+    id
+}
+
+/// Whether an object exists, as `fetch_exists` answers it.
+#[derive(serde::Deserialize)]
+struct JsonExists {
+    exists: bool,
+}
+
+impl JsonExists {
+    fn parse(self) -> Result<Response> {
+        Ok(Response::Exists(self.exists))
+    }
+}
+
+/// Ask whether the server has the object of `handle`. Objects come and go,
+/// so the answer is never cached.
+pub(crate) fn get_exists(
+    source: Arc<DataSource>,
+    ctx: egui::Context,
+    handle: &Handle,
+    tx: Sender<Envelope>,
+    url_base: &str,
+) -> RequestId {
+    let request = Request::new(RequestKind::Exists {
+        handle: handle.clone(),
+    });
+    let id = request.id;
+    get(
+        source,
+        ctx,
+        request,
+        url_base.to_string(),
+        JsonExists::parse,
+        tx,
+    );
+    id
+}
+
+/// One task of the task times route, with when it ran.
+#[derive(serde::Deserialize)]
+struct JsonTaskTime {
+    #[serde(flatten)]
+    task: JsonTask,
+    start: f64,
+    end: f64,
+}
+
+/// The answer of the task times route.
+#[derive(serde::Deserialize)]
+struct JsonTaskTimes {
+    tasks: Option<Vec<JsonTaskTime>>,
+}
+
+impl JsonTaskTimes {
+    fn parse(self) -> Result<Response> {
+        let Some(tasks) = self.tasks else {
+            return Ok(Response::TaskTimes(None));
+        };
+        Ok(Response::TaskTimes(Some(
+            tasks
+                .iter()
+                .map(|json_task| {
+                    Ok::<TaskTime, anyhow::Error>(TaskTime {
+                        task: json_task.task.parse()?,
+                        start: json_task.start,
+                        end: json_task.end,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?,
+        )))
+    }
+}
+
+/// Fetch start and end times of the tasks involved in evaluating `handle`.
+pub(crate) fn get_task_times(
+    source: Arc<DataSource>,
+    ctx: egui::Context,
+    handle: &Handle,
+    tx: Sender<Envelope>,
+    url_base: &str,
+    cache: &ResponseCache,
+) -> RequestId {
+    let request = Request::new(RequestKind::TaskTimes {
+        handle: handle.clone(),
+    });
+    let id = request.id;
+    get(
+        source,
+        ctx.clone(),
+        request.clone(),
+        url_base.to_string(),
+        cache.inserting(ctx, &request.kind, JsonTaskTimes::parse),
+        tx,
+    );
+    id
+}
+
+/// The answer of the tag route.
+#[derive(serde::Deserialize)]
+struct JsonTag {
+    tagged: Option<String>,
+    authority: Option<String>,
+    label: Option<String>,
+}
+
+impl JsonTag {
+    fn parse(self) -> Result<Response> {
+        let (Some(tagged), Some(authority), Some(label)) =
+            (self.tagged, self.authority, self.label)
+        else {
+            return Ok(Response::Tag(None));
+        };
+        Ok(Response::Tag(Some(TagEntries {
+            tagged: Handle::from_hex(&tagged).context("parsing tagged handle")?,
+            authority: Handle::from_hex(&authority).context("parsing authority handle")?,
+            label: Handle::from_hex(&label).context("parsing label handle")?,
+        })))
+    }
+}
+
+/// Fetch the three entries of the Tag `handle`.
+pub(crate) fn get_tag(
+    source: Arc<DataSource>,
+    ctx: egui::Context,
+    handle: &Handle,
+    tx: Sender<Envelope>,
+    url_base: &str,
+    cache: &ResponseCache,
+) -> RequestId {
+    let request = Request::new(RequestKind::Tag {
+        handle: handle.clone(),
+    });
+    let id = request.id;
+    get(
+        source,
+        ctx.clone(),
+        request.clone(),
+        url_base.to_string(),
+        cache.inserting(ctx, &request.kind, JsonTag::parse),
+        tx,
+    );
+    id
+}
+
+/// The answer of the tree route.
+#[derive(serde::Deserialize)]
+struct JsonTree {
+    entries: Option<Vec<String>>,
+}
+
+impl JsonTree {
+    fn parse(self) -> Result<Response> {
+        let Some(entries) = self.entries else {
+            return Ok(Response::Tree(None));
+        };
+        Ok(Response::Tree(Some(
+            entries
+                .iter()
+                .map(|entry| Handle::from_hex(entry).context("parsing tree entry"))
+                .collect::<Result<Vec<_>>>()?,
+        )))
+    }
+}
+
+/// Fetch the entries of the Tree `handle`.
+pub(crate) fn get_tree(
+    source: Arc<DataSource>,
+    ctx: egui::Context,
+    handle: &Handle,
+    tx: Sender<Envelope>,
+    url_base: &str,
+    cache: &ResponseCache,
+) -> RequestId {
+    let request = Request::new(RequestKind::Tree {
+        handle: handle.clone(),
+    });
+    let id = request.id;
+    get(
+        source,
+        ctx.clone(),
+        request.clone(),
+        url_base.to_string(),
+        cache.inserting(ctx, &request.kind, JsonTree::parse),
         tx,
     );
+    id
+}
+
+/// The answer of the blob route, with the contents in base64.
+#[derive(serde::Deserialize)]
+struct JsonBlob {
+    contents: Option<String>,
+}
+
+impl JsonBlob {
+    fn parse(self) -> Result<Response> {
+        let Some(contents) = self.contents else {
+            return Ok(Response::Blob(None));
+        };
+        Ok(Response::Blob(Some(
+            STANDARD
+                .decode(contents)
+                .context("decoding blob contents")?,
+        )))
+    }
+}
+
+/// Fetch the contents of the Blob `handle`, which the server sends in base64.
+pub(crate) fn get_blob(
+    source: Arc<DataSource>,
+    ctx: egui::Context,
+    handle: &Handle,
+    tx: Sender<Envelope>,
+    url_base: &str,
+    cache: &ResponseCache,
+) -> RequestId {
+    let request = Request::new(RequestKind::Blob {
+        handle: handle.clone(),
+    });
+    let id = request.id;
+    get(
+        source,
+        ctx.clone(),
+        request.clone(),
+        url_base.to_string(),
+        cache.inserting(ctx, &request.kind, JsonBlob::parse),
+        tx,
+    );
+    id
+}
+
+/// The answer of the canonical route.
+#[derive(serde::Deserialize)]
+struct JsonCanonical {
+    handle: Option<String>,
+}
+
+impl JsonCanonical {
+    fn parse(self) -> Result<Response> {
+        let Some(canonical) = self.handle else {
+            return Ok(Response::Canonical(None));
+        };
+        Ok(Response::Canonical(Some(
+            Handle::from_hex(&canonical).context("parsing canonical handle")?,
+        )))
+    }
+}
+
+/// Fetch the canonical handle of the local handle `handle`.
+pub(crate) fn get_canonical(
+    source: Arc<DataSource>,
+    ctx: egui::Context,
+    handle: &Handle,
+    tx: Sender<Envelope>,
+    url_base: &str,
+    cache: &ResponseCache,
+) -> RequestId {
+    let request = Request::new(RequestKind::Canonical {
+        handle: handle.clone(),
+    });
+    let id = request.id;
+    get(
+        source,
+        ctx.clone(),
+        request.clone(),
+        url_base.to_string(),
+        cache.inserting(ctx, &request.kind, JsonCanonical::parse),
+        tx,
+    );
+    id
+}
+
+/// The answer of the roots route.
+#[derive(serde::Deserialize)]
+struct JsonRoots {
+    roots: Vec<String>,
+}
+
+impl JsonRoots {
+    fn parse(self) -> Result<Response> {
+        Ok(Response::Roots(
+            self.roots
+                .iter()
+                .map(|root| Handle::from_hex(root).context("parsing root"))
+                .collect::<Result<Vec<_>>>()?,
+        ))
+    }
+}
+
+/// Fetch the top-level targets the server knows about.
+pub(crate) fn get_roots(
+    source: Arc<DataSource>,
+    ctx: egui::Context,
+    tx: Sender<Envelope>,
+    url_base: &str,
+) -> RequestId {
+    let request = Request::new(RequestKind::Roots);
+    let id = request.id;
+    get(
+        source,
+        ctx,
+        request,
+        url_base.to_string(),
+        JsonRoots::parse,
+        tx,
+    );
+    id
+}
+
+/// Parse `body`, fetched by `get_raw`, into the response to `kind`, as the
+/// functions above would.
+pub(crate) fn parse_response(kind: &RequestKind, body: &str) -> Result<Response> {
+    fn parse<T: DeserializeOwned>(
+        body: &str,
+        map: impl FnOnce(T) -> Result<Response>,
+    ) -> Result<Response> {
+        serde_json::from_str::<T>(body)
+            .context("parsing json")
+            .and_then(map)
+    }
+
+    match kind {
+        RequestKind::Parents { .. } => parse(body, JsonParents::parse),
+        RequestKind::ParentCount { .. } => parse(body, JsonParentCount::parse),
+        RequestKind::Dependees { .. } => parse(body, JsonDependees::parse),
+        RequestKind::Child { .. } | RequestKind::Outcome { .. } => parse(body, JsonChild::parse),
+        RequestKind::Status { .. } => parse(body, JsonStatus::parse),
+        RequestKind::Rerun { .. } => parse(body, JsonRerun::parse),
+        RequestKind::Materialize { .. } => parse(body, JsonMaterialize::parse),
+        RequestKind::Exists { .. } => parse(body, JsonExists::parse),
+        RequestKind::TaskTimes { .. } => parse(body, JsonTaskTimes::parse),
+        RequestKind::Tag { .. } => parse(body, JsonTag::parse),
+        RequestKind::Tree { .. } => parse(body, JsonTree::parse),
+        RequestKind::Blob { .. } => parse(body, JsonBlob::parse),
+        RequestKind::Canonical { .. } => parse(body, JsonCanonical::parse),
+        RequestKind::Roots => parse(body, JsonRoots::parse),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curl_commands_match_requests() {
+        let handle = Handle::from_hex("0-0-0-2400000000000000").unwrap();
+        let hex = handle.to_hex();
+        let script = curl_commands(
+            "127.0.0.1:9090",
+            &Routes::default(),
+            &[
+                (Endpoint::Parents, handle.clone()),
+                (Endpoint::Child(Operation::Eval), handle),
+            ],
+        );
+        assert_eq!(
+            script,
+            format!(
+                "curl -s 'http://127.0.0.1:9090/parents?handle={hex}&limit={PAGE_SIZE}'\n\
+                 curl -s 'http://127.0.0.1:9090/child?handle={hex}&op={}'\n",
+                Operation::Eval as u8
+            )
+        );
+    }
+
+    #[test]
+    fn custom_routes() {
+        let handle = Handle::from_hex("0-0-0-2400000000000000").unwrap();
+        let hex = handle.to_hex();
+        let routes = Routes {
+            dependees: "/api/v2/dependees".to_string(),
+            handle: "h".to_string(),
+            operation: "operation".to_string(),
+            after: "cursor".to_string(),
+            ..Routes::default()
+        };
+        assert_eq!(
+            endpoint_path(
+                &routes,
+                Endpoint::Dependees(Operation::Eval),
+                &handle,
+                Some("7")
+            ),
+            format!(
+                "/api/v2/dependees?h={hex}&limit={PAGE_SIZE}&cursor=7&operation={}",
+                Operation::Eval as u8
+            )
+        );
+        assert_eq!(
+            endpoint_path(&routes, Endpoint::Roots, &handle, None),
+            "/roots"
+        );
+        let rerun = RequestKind::Rerun {
+            handle: handle.clone(),
+            operation: Operation::Apply,
+        };
+        assert!(rerun.is_command());
+        assert_eq!(rerun.path(&routes), format!("/rerun?h={hex}&operation=0"));
+        let materialize = RequestKind::Materialize {
+            handle: handle.clone(),
+        };
+        assert!(materialize.is_command());
+        assert_eq!(materialize.path(&routes), format!("/materialize?h={hex}"));
+        let exists = RequestKind::Exists {
+            handle: handle.clone(),
+        };
+        assert!(!exists.is_command());
+        assert_eq!(exists.path(&routes), format!("/object?h={hex}"));
+    }
+
+    #[test]
+    fn recorded_time_is_optional() {
+        let hex = "0-0-0-2400000000000000";
+        let parse = |json: String| {
+            serde_json::from_str::<JsonTask>(&json)
+                .unwrap()
+                .parse()
+                .unwrap()
+        };
+        let task = parse(format!(r#"{{"handle": "{hex}", "operation": "1"}}"#));
+        assert_eq!(task.recorded, None);
+        let task = parse(format!(
+            r#"{{"handle": "{hex}", "operation": "1", "recorded": 1700000000.5}}"#
+        ));
+        assert_eq!(task.recorded, Some(1_700_000_000.5));
+    }
+
+    #[test]
+    fn parses_bodies_the_console_fetched() {
+        let handle = Handle::from_hex("0-0-0-2400000000000000").unwrap();
+        let parents = RequestKind::Parents {
+            generation: Generation::default(),
+            handle: handle.clone(),
+            after: None,
+            operation: None,
+        };
+        let body = format!(
+            r#"{{"parents": [{{"handle": "{}", "operation": 2}}], "next": "c", "remaining": 4}}"#,
+            handle.to_hex()
+        );
+        let Response::Parents(Some(tasks), Some(next)) = parse_response(&parents, &body).unwrap()
+        else {
+            panic!("not a page of parents");
+        };
+        assert_eq!(tasks[0].handle, handle);
+        assert_eq!(next.remaining, 4);
+        assert!(parents.is_cached());
+
+        let status = RequestKind::Status {
+            handle: handle.clone(),
+            operation: Operation::Eval,
+        };
+        assert!(!status.is_cached());
+        let exists = RequestKind::Exists { handle };
+        assert!(!exists.is_cached());
+        assert!(matches!(
+            parse_response(&exists, r#"{"exists": false}"#).unwrap(),
+            Response::Exists(false)
+        ));
+        assert!(parse_response(&RequestKind::Roots, "{}").is_err());
+    }
+
+    #[test]
+    fn demultiplexes_batches() {
+        let [a, b, c] = [
+            "1-0-4-100000000000000",
+            "2-0-4-100000000000000",
+            "3-0-4-100000000000000",
+        ]
+        .map(|hex| Handle::from_hex(hex).unwrap());
+        let body = format!(
+            r#"{{"results": [
+                {{"handle": "{b}", "parents": [{{"handle": "{a}", "operation": 1}}]}},
+                {{"handle": "not hex", "parents": []}},
+                {{"handle": "{a}", "parents": null, "next": "n", "remaining": 2}}
+            ]}}"#,
+            a = a.to_hex(),
+            b = b.to_hex()
+        );
+        let results = demultiplex(&body, &[a.clone(), b, c]).unwrap();
+        assert!(matches!(
+            &results[0],
+            Ok(Response::Parents(None, Some(next))) if next.remaining == 2
+        ));
+        assert!(matches!(
+            &results[1],
+            Ok(Response::Parents(Some(tasks), None)) if tasks[0].handle == a
+        ));
+        // Nothing for the third handle, and the unreadable entry is dropped.
+        assert!(matches!(&results[2], Err(error) if error.to_string().contains("nothing for")));
+        // An answer that cannot be read at all is asked for one by one.
+        assert!(demultiplex("<html>", &[a]).is_err());
+    }
 }
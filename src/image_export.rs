@@ -0,0 +1,642 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont};
+use anyhow::{Context, Result};
+use eframe::epaint::{
+    tessellator::{TessellationOptions, Tessellator},
+    Mesh, TextShape, WHITE_UV,
+};
+use egui::{
+    plot::PlotBounds, Color32, DragValue, FontDefinitions, FontFamily, Pos2, Rect, Shape, TextEdit,
+    Ui, Vec2,
+};
+
+/// Which graph to render to an image.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum ImageGraph {
+    #[default]
+    Ancestry,
+    Progress,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum ImageFormat {
+    #[default]
+    Svg,
+    Png,
+}
+
+impl ImageFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Svg => "svg",
+            ImageFormat::Png => "png",
+        }
+    }
+}
+
+/// A graph drawn for a canvas of `size` pixels, independent of any window.
+pub(crate) struct Picture {
+    pub(crate) size: Vec2,
+    pub(crate) background: Color32,
+    pub(crate) shapes: Vec<Shape>,
+    /// Element text and where it goes. Element meshes sample egui's font
+    /// texture, which is not readable from here, so their text is drawn from
+    /// these instead.
+    pub(crate) labels: Vec<(Rect, String)>,
+    pub(crate) text_color: Color32,
+}
+
+/// Bounds with the aspect ratio of `size` that contain `bounds`, so the graph
+/// is not stretched.
+pub(crate) fn fit_to_canvas(bounds: PlotBounds, size: Vec2) -> PlotBounds {
+    let (width, height) = (size.x as f64, size.y as f64);
+    // Plot units per pixel, with a small margin.
+    let scale = (bounds.width() / width).max(bounds.height() / height) * 1.05;
+    let center = bounds.center();
+    PlotBounds::from_min_max(
+        [
+            center.x - width * scale / 2.0,
+            center.y - height * scale / 2.0,
+        ],
+        [
+            center.x + width * scale / 2.0,
+            center.y + height * scale / 2.0,
+        ],
+    )
+}
+
+impl Picture {
+    pub(crate) fn write(&self, format: ImageFormat, path: &Path) -> Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.encode(format, &mut out)?;
+        out.flush()?;
+        Ok(())
+    }
+
+    pub(crate) fn encode(&self, format: ImageFormat, out: &mut impl Write) -> Result<()> {
+        match format {
+            ImageFormat::Svg => self.write_svg(out),
+            ImageFormat::Png => self.write_png(out),
+        }
+    }
+
+    fn write_svg(&self, out: &mut impl Write) -> Result<()> {
+        writeln!(
+            out,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\">",
+            self.size.x, self.size.y,
+        )?;
+        writeln!(
+            out,
+            "  <rect width=\"100%\" height=\"100%\"{}/>",
+            svg_paint("fill", self.background),
+        )?;
+        for shape in &self.shapes {
+            write_svg_shape(out, shape)?;
+        }
+        for (rect, text) in &self.labels {
+            // Squeeze the text into the element's box.
+            writeln!(
+                out,
+                "  <text x=\"{:.1}\" y=\"{:.1}\" font-family=\"monospace\" font-size=\"{:.1}\" \
+                 text-anchor=\"middle\" dominant-baseline=\"central\" textLength=\"{:.1}\" \
+                 lengthAdjust=\"spacingAndGlyphs\"{}>{}</text>",
+                rect.center().x,
+                rect.center().y,
+                rect.height() * 0.5,
+                rect.width() * 0.9,
+                svg_paint("fill", self.text_color),
+                escape_xml(text),
+            )?;
+        }
+        writeln!(out, "</svg>")?;
+        Ok(())
+    }
+
+    fn write_png(&self, out: &mut impl Write) -> Result<()> {
+        let mut canvas = Canvas::new(self.size, self.background);
+        let mut tessellator = Tessellator::new(1.0, TessellationOptions::default(), [1, 1], vec![]);
+        let mut texts = vec![];
+        for shape in flatten(&self.shapes) {
+            match shape {
+                Shape::Text(text) => texts.push(text),
+                Shape::Mesh(mesh) if is_textured(mesh) => {}
+                shape => {
+                    let mut mesh = Mesh::default();
+                    tessellator.tessellate_shape(shape.clone(), &mut mesh);
+                    canvas.fill_mesh(&mesh);
+                }
+            }
+        }
+
+        let font = monospace_font()?;
+        let font = FontRef::try_from_slice(&font).context("loading monospace font")?;
+        for text in texts {
+            let Some(section) = text.galley.job.sections.first() else {
+                continue;
+            };
+            let color = text.override_text_color.unwrap_or(section.format.color);
+            canvas.draw_text(
+                &font,
+                text.galley.text(),
+                text.pos,
+                section.format.font_id.size,
+                color,
+            );
+        }
+        for (rect, text) in &self.labels {
+            // Fit the text inside the element's box, like the app does.
+            let unit_width = text_width(&font, text, 1.0);
+            let size = (rect.height() * 0.6).min(rect.width() * 0.9 / unit_width.max(1e-3));
+            let origin = rect.center() - Vec2::new(text_width(&font, text, size), size) / 2.0;
+            canvas.draw_text(&font, text, origin, size, self.text_color);
+        }
+
+        let mut encoder = png::Encoder::new(out, canvas.width as u32, canvas.height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()?
+            .write_image_data(&canvas.to_rgba())?;
+        Ok(())
+    }
+}
+
+/// All shapes with nested `Shape::Vec`s expanded.
+fn flatten(shapes: &[Shape]) -> Vec<&Shape> {
+    shapes
+        .iter()
+        .flat_map(|shape| match shape {
+            Shape::Vec(shapes) => flatten(shapes),
+            shape => vec![shape],
+        })
+        .collect()
+}
+
+/// Whether the mesh samples a texture, i.e. is rendered text.
+fn is_textured(mesh: &Mesh) -> bool {
+    mesh.vertices.iter().any(|v| v.uv != WHITE_UV)
+}
+
+fn write_svg_shape(out: &mut impl Write, shape: &Shape) -> Result<()> {
+    let points = |points: &[Pos2]| {
+        points
+            .iter()
+            .map(|p| format!("{:.1},{:.1}", p.x, p.y))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    match shape {
+        Shape::Noop | Shape::Callback(_) => {}
+        Shape::Vec(shapes) => {
+            for shape in shapes {
+                write_svg_shape(out, shape)?;
+            }
+        }
+        Shape::Circle(circle) => writeln!(
+            out,
+            "  <circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\"{}{}/>",
+            circle.center.x,
+            circle.center.y,
+            circle.radius,
+            svg_paint("fill", circle.fill),
+            svg_stroke(circle.stroke),
+        )?,
+        Shape::LineSegment {
+            points: [a, b],
+            stroke,
+        } => writeln!(
+            out,
+            "  <line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\"{}/>",
+            a.x,
+            a.y,
+            b.x,
+            b.y,
+            svg_stroke(*stroke),
+        )?,
+        Shape::Path(path) => writeln!(
+            out,
+            "  <{} points=\"{}\"{}{}/>",
+            if path.closed { "polygon" } else { "polyline" },
+            points(&path.points),
+            svg_paint(
+                "fill",
+                if path.closed {
+                    path.fill
+                } else {
+                    Color32::TRANSPARENT
+                }
+            ),
+            svg_stroke(path.stroke),
+        )?,
+        Shape::Rect(rect) => writeln!(
+            out,
+            "  <rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" rx=\"{:.1}\"{}{}/>",
+            rect.rect.min.x,
+            rect.rect.min.y,
+            rect.rect.width(),
+            rect.rect.height(),
+            rect.rounding.nw,
+            svg_paint("fill", rect.fill),
+            svg_stroke(rect.stroke),
+        )?,
+        Shape::Text(text) => write_svg_text(out, text)?,
+        // Text meshes are drawn from labels instead.
+        Shape::Mesh(mesh) if is_textured(mesh) => {}
+        Shape::Mesh(mesh) => {
+            for triangle in mesh.indices.chunks_exact(3) {
+                let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices[triangle[i] as usize]);
+                writeln!(
+                    out,
+                    "  <polygon points=\"{}\"{}/>",
+                    points(&[a.pos, b.pos, c.pos]),
+                    svg_paint("fill", a.color),
+                )?;
+            }
+        }
+        Shape::QuadraticBezier(bezier) => {
+            let [a, b, c] = bezier.points;
+            writeln!(
+                out,
+                "  <path d=\"M {:.1} {:.1} Q {:.1} {:.1} {:.1} {:.1}{}\"{}{}/>",
+                a.x,
+                a.y,
+                b.x,
+                b.y,
+                c.x,
+                c.y,
+                if bezier.closed { " Z" } else { "" },
+                svg_paint("fill", bezier.fill),
+                svg_stroke(bezier.stroke),
+            )?;
+        }
+        Shape::CubicBezier(bezier) => {
+            let [a, b, c, d] = bezier.points;
+            writeln!(
+                out,
+                "  <path d=\"M {:.1} {:.1} C {:.1} {:.1} {:.1} {:.1} {:.1} {:.1}{}\"{}{}/>",
+                a.x,
+                a.y,
+                b.x,
+                b.y,
+                c.x,
+                c.y,
+                d.x,
+                d.y,
+                if bezier.closed { " Z" } else { "" },
+                svg_paint("fill", bezier.fill),
+                svg_stroke(bezier.stroke),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn write_svg_text(out: &mut impl Write, text: &TextShape) -> Result<()> {
+    let Some(section) = text.galley.job.sections.first() else {
+        return Ok(());
+    };
+    writeln!(
+        out,
+        "  <text x=\"{:.1}\" y=\"{:.1}\" font-family=\"sans-serif\" font-size=\"{:.1}\" \
+         dominant-baseline=\"hanging\"{}>{}</text>",
+        text.pos.x,
+        text.pos.y,
+        section.format.font_id.size,
+        svg_paint(
+            "fill",
+            text.override_text_color.unwrap_or(section.format.color)
+        ),
+        escape_xml(text.galley.text()),
+    )?;
+    Ok(())
+}
+
+/// A paint attribute such as ` fill="#rrggbb" fill-opacity="0.50"`.
+fn svg_paint(attribute: &str, color: Color32) -> String {
+    if color == Color32::TRANSPARENT {
+        return format!(" {attribute}=\"none\"");
+    }
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    format!(
+        " {attribute}=\"#{r:02x}{g:02x}{b:02x}\" {attribute}-opacity=\"{:.2}\"",
+        a as f32 / 255.0
+    )
+}
+
+fn svg_stroke(stroke: egui::Stroke) -> String {
+    if stroke.width <= 0.0 {
+        return String::new();
+    }
+    format!(
+        "{} stroke-width=\"{:.1}\"",
+        svg_paint("stroke", stroke.color),
+        stroke.width
+    )
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The bytes of the font egui uses for monospace text.
+fn monospace_font() -> Result<Vec<u8>> {
+    let definitions = FontDefinitions::default();
+    let name = definitions
+        .families
+        .get(&FontFamily::Monospace)
+        .and_then(|names| names.first())
+        .context("no monospace font")?;
+    let data = definitions
+        .font_data
+        .get(name)
+        .context("missing monospace font data")?;
+    Ok(data.font.to_vec())
+}
+
+fn text_width(font: &FontRef<'_>, text: &str, size: f32) -> f32 {
+    let font = font.as_scaled(PxScale::from(size));
+    text.chars().map(|c| font.h_advance(font.glyph_id(c))).sum()
+}
+
+/// Largest width or height of an exported image, keeping the PNG canvas under
+/// 512 MiB.
+const MAX_SIDE: u32 = 8192;
+
+/// A premultiplied RGBA image that triangles and text are blended onto.
+/// Channels are kept as 16 bits, enough that blending many translucent
+/// shapes does not band.
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<[u16; 4]>,
+}
+
+impl Canvas {
+    fn new(size: Vec2, background: Color32) -> Self {
+        let (width, height) = (size.x.max(1.0) as usize, size.y.max(1.0) as usize);
+        Self {
+            width,
+            height,
+            pixels: vec![stored(premultiplied(background)); width * height],
+        }
+    }
+
+    /// Blend `color`, scaled by `coverage`, over the pixel at (x, y).
+    fn blend(&mut self, x: i64, y: i64, color: [f32; 4], coverage: f32) {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return;
+        }
+        let pixel = &mut self.pixels[y as usize * self.width + x as usize];
+        let alpha = color[3] * coverage;
+        let below = unstored(*pixel);
+        let mut blended = [0.0; 4];
+        for i in 0..4 {
+            blended[i] = color[i] * coverage + below[i] * (1.0 - alpha);
+        }
+        *pixel = stored(blended);
+    }
+
+    /// Fill the mesh's triangles, interpolating vertex colors. Anti-aliasing
+    /// comes from the tessellator's feathering.
+    fn fill_mesh(&mut self, mesh: &Mesh) {
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices[triangle[i] as usize]);
+            let area = edge(a.pos, b.pos, c.pos);
+            if area.abs() < f32::EPSILON {
+                continue;
+            }
+            // Only the part of the triangle's bounds on the canvas.
+            let min = a.pos.min(b.pos).min(c.pos).floor().max(Pos2::ZERO);
+            let max = a
+                .pos
+                .max(b.pos)
+                .max(c.pos)
+                .ceil()
+                .min(Pos2::new(self.width as f32 - 1.0, self.height as f32 - 1.0));
+            let colors = [a.color, b.color, c.color].map(premultiplied);
+            for y in min.y as i64..=max.y as i64 {
+                for x in min.x as i64..=max.x as i64 {
+                    let p = Pos2::new(x as f32 + 0.5, y as f32 + 0.5);
+                    let weights = [
+                        edge(b.pos, c.pos, p) / area,
+                        edge(c.pos, a.pos, p) / area,
+                        edge(a.pos, b.pos, p) / area,
+                    ];
+                    if weights.iter().any(|w| *w < 0.0) {
+                        continue;
+                    }
+                    let mut color = [0.0; 4];
+                    for (weight, vertex_color) in weights.iter().zip(colors) {
+                        for i in 0..4 {
+                            color[i] += weight * vertex_color[i];
+                        }
+                    }
+                    self.blend(x, y, color, 1.0);
+                }
+            }
+        }
+    }
+
+    /// Draw a single line of text with its top left corner at `origin`.
+    fn draw_text(
+        &mut self,
+        font: &FontRef<'_>,
+        text: &str,
+        origin: Pos2,
+        size: f32,
+        color: Color32,
+    ) {
+        let scaled = font.as_scaled(PxScale::from(size));
+        let color = premultiplied(color);
+        let mut x = origin.x;
+        let baseline = origin.y + scaled.ascent();
+        for c in text.chars() {
+            let id = scaled.glyph_id(c);
+            let glyph = id.with_scale_and_position(size, ab_glyph::point(x, baseline));
+            x += scaled.h_advance(id);
+            let Some(outline) = font.outline_glyph(glyph) else {
+                continue;
+            };
+            let bounds = outline.px_bounds();
+            outline.draw(|gx, gy, coverage| {
+                self.blend(
+                    bounds.min.x as i64 + gx as i64,
+                    bounds.min.y as i64 + gy as i64,
+                    color,
+                    coverage,
+                );
+            });
+        }
+    }
+
+    fn to_rgba(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .map(|pixel| unstored(*pixel))
+            .flat_map(|[r, g, b, a]| {
+                let unmultiply = |c: f32| {
+                    if a > 0.0 {
+                        (c / a * 255.0).round().clamp(0.0, 255.0) as u8
+                    } else {
+                        0
+                    }
+                };
+                [
+                    unmultiply(r),
+                    unmultiply(g),
+                    unmultiply(b),
+                    (a * 255.0).round() as u8,
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Twice the signed area of the triangle (a, b, p).
+fn edge(a: Pos2, b: Pos2, p: Pos2) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+fn premultiplied(color: Color32) -> [f32; 4] {
+    color.to_array().map(|c| c as f32 / 255.0)
+}
+
+/// A color as kept on the canvas.
+fn stored(color: [f32; 4]) -> [u16; 4] {
+    color.map(|c| (c.clamp(0.0, 1.0) * f32::from(u16::MAX)).round() as u16)
+}
+
+fn unstored(pixel: [u16; 4]) -> [f32; 4] {
+    pixel.map(|c| f32::from(c) / f32::from(u16::MAX))
+}
+
+/// Image export controls for the side panel.
+pub(crate) struct ImagePanel {
+    graph: ImageGraph,
+    format: ImageFormat,
+    width: u32,
+    height: u32,
+    path: String,
+    status: String,
+}
+
+impl Default for ImagePanel {
+    fn default() -> Self {
+        Self {
+            graph: ImageGraph::default(),
+            format: ImageFormat::default(),
+            width: 1920,
+            height: 1080,
+            path: String::new(),
+            status: String::new(),
+        }
+    }
+}
+
+impl ImagePanel {
+    /// `render` draws the chosen graph for a canvas of the given size.
+    pub(crate) fn ui(
+        &mut self,
+        ui: &mut Ui,
+        render: impl FnOnce(&mut Ui, ImageGraph, Vec2) -> Option<Picture>,
+    ) {
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.graph, ImageGraph::Ancestry, "Ancestry");
+            ui.selectable_value(&mut self.graph, ImageGraph::Progress, "Progress");
+        });
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.format, ImageFormat::Svg, "SVG");
+            ui.selectable_value(&mut self.format, ImageFormat::Png, "PNG");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Size: ");
+            ui.add(DragValue::new(&mut self.width).clamp_range(1..=MAX_SIDE));
+            ui.label("×");
+            ui.add(DragValue::new(&mut self.height).clamp_range(1..=MAX_SIDE));
+            ui.label("px");
+        });
+        let default_path = format!("graph.{}", self.format.extension());
+        ui.horizontal(|ui| {
+            ui.label("Path: ");
+            TextEdit::singleline(&mut self.path)
+                .hint_text(default_path.as_str())
+                .desired_width(f32::INFINITY)
+                .show(ui);
+        });
+        if ui.button("Export image").clicked() {
+            let path = if self.path.is_empty() {
+                default_path
+            } else {
+                self.path.clone()
+            };
+            let size = Vec2::new(self.width as f32, self.height as f32);
+            self.status = match render(ui, self.graph, size) {
+                None => "Nothing to export.".to_string(),
+                Some(picture) => match picture.write(self.format, Path::new(&path)) {
+                    Ok(()) => format!("Exported image to {path}."),
+                    Err(e) => format!("Exporting image to {path} failed: {e:#}."),
+                },
+            };
+        }
+        if !self.status.is_empty() {
+            ui.label(self.status.as_str());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn picture() -> Picture {
+        let rect = Rect::from_min_size(Pos2::new(10.0, 10.0), Vec2::new(40.0, 20.0));
+        Picture {
+            size: Vec2::new(64.0, 48.0),
+            background: Color32::BLACK,
+            shapes: vec![Shape::rect_stroke(
+                rect,
+                1.0,
+                egui::Stroke::new(2.0, Color32::WHITE),
+            )],
+            labels: vec![(rect, "a < b".to_string())],
+            text_color: Color32::WHITE,
+        }
+    }
+
+    #[test]
+    fn writes_svg() {
+        let mut out = vec![];
+        picture().write_svg(&mut out).unwrap();
+        let svg = String::from_utf8(out).unwrap();
+        assert!(svg.contains("<rect x=\"10.0\" y=\"10.0\""));
+        assert!(svg.contains(">a &lt; b</text>"));
+    }
+
+    #[test]
+    fn fills_triangles_reaching_far_off_the_canvas() {
+        let mut canvas = Canvas::new(Vec2::new(4.0, 4.0), Color32::TRANSPARENT);
+        let mut mesh = Mesh::default();
+        for pos in [(-1e5, -1e5), (3e5, -1e5), (-1e5, 3e5)] {
+            mesh.colored_vertex(Pos2::new(pos.0, pos.1), Color32::WHITE);
+        }
+        mesh.add_triangle(0, 1, 2);
+        canvas.fill_mesh(&mesh);
+        assert!(canvas.to_rgba().iter().all(|channel| *channel == 255));
+    }
+
+    #[test]
+    fn writes_png() {
+        let mut out = vec![];
+        picture().write_png(&mut out).unwrap();
+        assert!(out.starts_with(b"\x89PNG"));
+    }
+}
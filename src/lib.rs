@@ -1,7 +1,46 @@
+mod api;
 mod app;
+mod cache;
+mod changelog;
+#[cfg(not(target_arch = "wasm32"))]
+mod cli;
+mod commands;
+mod compare;
+mod compose;
+mod console;
+mod dispatch;
+// The web app has no file system to export to.
+#[cfg(not(target_arch = "wasm32"))]
+mod export;
+#[cfg(not(target_arch = "wasm32"))]
+mod fixture;
 mod graphs;
+#[cfg(all(feature = "grpc", not(target_arch = "wasm32")))]
+mod grpc;
 mod handle;
 mod http;
+#[cfg(not(target_arch = "wasm32"))]
+mod image_export;
+// Links are only read and written on the web; the command line fills one in.
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+mod link;
+mod logs;
+mod metrics;
+mod orphans;
 mod plot;
+mod profile;
+mod query;
+#[cfg(not(target_arch = "wasm32"))]
+mod report;
+mod simulate;
+mod theme;
+#[cfg(not(target_arch = "wasm32"))]
+mod trace;
+mod workspace;
 
 pub use app::App;
+#[cfg(not(target_arch = "wasm32"))]
+pub use cli::{Launch, USAGE};
+#[cfg(not(target_arch = "wasm32"))]
+pub use graphs::benchmark;
+pub use logs::init_logging;
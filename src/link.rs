@@ -0,0 +1,140 @@
+//! Links that open the viewer on a server and target, written into the page
+//! address on the web as `#server=127.0.0.1:9090&handle=d9-0-4-0`.
+
+use anyhow::{bail, Context, Result};
+
+use crate::handle::Handle;
+
+/// Where a link says to start. Either part may be left out.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Link {
+    pub(crate) server: Option<String>,
+    pub(crate) handle: Option<Handle>,
+}
+
+impl Link {
+    /// Read the `key=value` pairs of a fragment or query string, with or
+    /// without its leading `#` or `?`. Unknown keys are ignored, so that
+    /// links can carry more in the future.
+    pub(crate) fn parse(fragment: &str) -> Result<Self> {
+        let fragment = fragment.trim_start_matches(&['#', '?'][..]);
+        let mut link = Self::default();
+        for pair in fragment.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = decode(value).with_context(|| format!("decoding {key}"))?;
+            match key {
+                "server" => link.server = Some(value),
+                "handle" => link.handle = Some(Handle::parse(&value)?),
+                _ => log::warn!("Ignoring unknown link parameter {key}"),
+            }
+        }
+        Ok(link)
+    }
+
+    /// The fragment that opens on this link, without the leading `#`.
+    pub(crate) fn fragment(&self) -> String {
+        let server = self
+            .server
+            .as_deref()
+            .map(|s| format!("server={}", encode(s)));
+        let handle = self
+            .handle
+            .as_ref()
+            .map(|h| format!("handle={}", h.to_hex()));
+        server
+            .into_iter()
+            .chain(handle)
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+}
+
+/// Escape every byte of `text` that could end a value or be mangled by the
+/// browser.
+fn encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b':' | b'/' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+fn decode(text: &str) -> Result<String> {
+    let mut bytes = vec![];
+    let mut rest = text.as_bytes();
+    while let [first, tail @ ..] = rest {
+        match (first, tail) {
+            (b'%', [high, low, tail @ ..]) => {
+                let hex = std::str::from_utf8(&[*high, *low])?.to_string();
+                bytes.push(u8::from_str_radix(&hex, 16).with_context(|| format!("%{hex}"))?);
+                rest = tail;
+            }
+            (b'%', _) => bail!("% is not followed by two hex digits"),
+            (b'+', _) => {
+                bytes.push(b' ');
+                rest = tail;
+            }
+            _ => {
+                bytes.push(*first);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8(bytes).context("not UTF-8")
+}
+
+/// The link the page was opened with, from its fragment or else its query
+/// string.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn read() -> Option<Link> {
+    let location = web_sys::window()?.location();
+    let hash = location.hash().ok().filter(|hash| hash.len() > 1);
+    let address = hash.or_else(|| location.search().ok())?;
+    Link::parse(&address)
+        .map_err(|e| log::error!("Ignoring link {address}: {e:#}"))
+        .ok()
+}
+
+/// Show `link` in the page address, so that it can be copied and shared,
+/// without adding to the history.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn write(link: &Link) {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    if let Err(e) = window.location().replace(&format!("#{}", link.fragment())) {
+        log::error!("Setting the page address failed: {e:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let link = Link {
+            server: Some("fix.example.com:9090/api v2".to_string()),
+            handle: Some(Handle::from_hex("d9-0-4-100000000000000").unwrap()),
+        };
+        let fragment = link.fragment();
+        assert_eq!(
+            fragment,
+            "server=fix.example.com:9090/api%20v2&handle=d9-0-4-100000000000000"
+        );
+        assert_eq!(Link::parse(&format!("#{fragment}")).unwrap(), link);
+        assert_eq!(
+            Link::parse("?handle=d9|0|4|100000000000000&zoom=2").unwrap(),
+            Link {
+                server: None,
+                handle: link.handle,
+            }
+        );
+        assert_eq!(Link::parse("").unwrap(), Link::default());
+        assert!(Link::parse("server=%zz").is_err());
+        assert!(Link::parse("handle=nope").is_err());
+    }
+}
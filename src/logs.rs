@@ -0,0 +1,185 @@
+//! Log messages kept in memory for the "Log" window, since on the web the
+//! browser console is otherwise the only place they go.
+
+use std::sync::Mutex;
+
+use egui::{ComboBox, Context, RichText, ScrollArea, TextEdit, TextStyle, Ui};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use crate::workspace::{self, LOG};
+
+/// Messages up to this level are kept, whatever the other logger shows.
+const CAPTURED: LevelFilter = LevelFilter::Info;
+
+/// Messages kept at most. The oldest half is dropped once there are more.
+const MAX_ENTRIES: usize = 10_000;
+
+static ENTRIES: Mutex<Vec<LogEntry>> = Mutex::new(Vec::new());
+
+#[derive(Clone, Debug, PartialEq)]
+struct LogEntry {
+    level: Level,
+    /// The module that logged the message.
+    target: String,
+    message: String,
+}
+
+impl LogEntry {
+    /// Whether the entry is at or above `level` and mentions `search`,
+    /// ignoring case, in its message or module.
+    fn matches(&self, level: LevelFilter, search: &str) -> bool {
+        let search = search.to_lowercase();
+        self.level <= level
+            && (self.message.to_lowercase().contains(&search)
+                || self.target.to_lowercase().contains(&search))
+    }
+}
+
+/// Keeps every message for the Log window and passes them on to `inner`.
+struct CapturingLogger {
+    inner: Box<dyn Log>,
+}
+
+impl Log for CapturingLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= CAPTURED || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if record.level() <= CAPTURED {
+            let mut entries = ENTRIES.lock().unwrap();
+            if entries.len() >= MAX_ENTRIES {
+                entries.drain(..MAX_ENTRIES / 2);
+            }
+            entries.push(LogEntry {
+                level: record.level(),
+                target: record.target().to_string(),
+                message: record.args().to_string(),
+            });
+        }
+        if self.inner.enabled(record.metadata()) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Install `inner`, which shows messages up to `inner_level`, as the logger,
+/// keeping messages for the Log window as they pass through.
+pub fn init_logging(inner: Box<dyn Log>, inner_level: LevelFilter) {
+    match log::set_boxed_logger(Box::new(CapturingLogger { inner })) {
+        Ok(()) => log::set_max_level(inner_level.max(CAPTURED)),
+        Err(e) => eprintln!("Logging is already set up: {e}"),
+    }
+}
+
+/// The "Log" window, filtering the messages kept by level and text.
+pub(crate) struct LogWindow {
+    open: bool,
+    level: LevelFilter,
+    search: String,
+}
+
+impl Default for LogWindow {
+    fn default() -> Self {
+        Self {
+            open: false,
+            level: CAPTURED,
+            search: String::new(),
+        }
+    }
+}
+
+impl LogWindow {
+    /// A button that opens the window.
+    pub(crate) fn button(&mut self, ui: &mut Ui) {
+        if ui.button("Log").clicked() {
+            self.open = true;
+        }
+    }
+
+    /// Show the window while it is open.
+    pub(crate) fn show(&mut self, ctx: &Context) {
+        if !self.open {
+            return;
+        }
+        let (level, search) = (&mut self.level, &mut self.search);
+        let window = egui::Window::new(LOG)
+            .open(&mut self.open)
+            .resizable(true)
+            .default_size([600.0, 300.0]);
+        let shown = workspace::place(ctx, LOG, window).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ComboBox::from_id_source("log_level")
+                    .selected_text(level.to_string())
+                    .show_ui(ui, |ui| {
+                        let choices = LevelFilter::iter()
+                            .filter(|choice| *choice != LevelFilter::Off && *choice <= CAPTURED);
+                        for choice in choices {
+                            ui.selectable_value(level, choice, choice.to_string());
+                        }
+                    });
+                TextEdit::singleline(search)
+                    .hint_text("Search")
+                    .desired_width(200.0)
+                    .show(ui);
+                if ui.button("Clear").clicked() {
+                    ENTRIES.lock().unwrap().clear();
+                }
+            });
+            ui.separator();
+            let entries: Vec<LogEntry> = ENTRIES
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|entry| entry.matches(*level, search))
+                .cloned()
+                .collect();
+            let row_height = ui.text_style_height(&TextStyle::Monospace);
+            ScrollArea::vertical()
+                .auto_shrink([false; 2])
+                .stick_to_bottom(true)
+                .show_rows(ui, row_height, entries.len(), |ui, rows| {
+                    for entry in &entries[rows] {
+                        let color = match entry.level {
+                            Level::Error => ui.visuals().error_fg_color,
+                            Level::Warn => ui.visuals().warn_fg_color,
+                            _ => ui.visuals().text_color(),
+                        };
+                        ui.label(
+                            RichText::new(format!(
+                                "{:5} {}: {}",
+                                entry.level, entry.target, entry.message
+                            ))
+                            .monospace()
+                            .color(color),
+                        )
+                        .on_hover_text(entry.message.as_str());
+                    }
+                });
+        });
+        workspace::track(ctx, LOG, &shown);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filtering() {
+        let entry = LogEntry {
+            level: Level::Warn,
+            target: "fix_viewer::http".to_string(),
+            message: "Request #3 timed out".to_string(),
+        };
+        assert!(entry.matches(LevelFilter::Info, ""));
+        assert!(entry.matches(LevelFilter::Warn, "TIMED"));
+        assert!(entry.matches(LevelFilter::Warn, "http"));
+        assert!(!entry.matches(LevelFilter::Error, ""));
+        assert!(!entry.matches(LevelFilter::Info, "parents"));
+    }
+}
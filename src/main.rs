@@ -4,21 +4,50 @@
 // When compiling natively:
 #[cfg(not(target_arch = "wasm32"))]
 fn main() -> eframe::Result<()> {
-    env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
+    // Log to stderr (if you run with `RUST_LOG=debug`), and to the Log window.
+    let logger = env_logger::Builder::from_default_env().build();
+    let level = logger.filter();
+    fix_viewer::init_logging(Box::new(logger), level);
+
+    let launch = match fix_viewer::Launch::from_args(std::env::args().skip(1)) {
+        Ok(Some(launch)) => launch,
+        Ok(None) => {
+            println!("{}", fix_viewer::USAGE);
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("{e:#}\n\n{}", fix_viewer::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if let Some(nodes) = launch.bench() {
+        print!("{}", fix_viewer::benchmark(nodes));
+        return Ok(());
+    }
+
+    // Requests are spawned onto a tokio runtime driven by a background thread.
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("failed to build tokio runtime");
+    let _guard = runtime.enter();
+    std::thread::spawn(move || runtime.block_on(std::future::pending::<()>()));
 
     let native_options = eframe::NativeOptions::default();
     eframe::run_native(
         "Fix Viewer",
         native_options,
-        Box::new(|cc| Box::new(fix_viewer::App::new(cc))),
+        Box::new(|cc| Box::new(fix_viewer::App::launch(cc, launch))),
     )
 }
 
 // When compiling to web using trunk:
 #[cfg(target_arch = "wasm32")]
 fn main() {
-    // Redirect `log` message to `console.log` and friends:
-    eframe::WebLogger::init(log::LevelFilter::Trace).ok();
+    // Redirect `log` message to `console.log` and friends, and to the Log window:
+    let level = log::LevelFilter::Trace;
+    fix_viewer::init_logging(Box::new(eframe::WebLogger::new(level)), level);
 
     let web_options = eframe::WebOptions::default();
 
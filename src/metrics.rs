@@ -0,0 +1,195 @@
+//! How long the server takes to answer, per endpoint, for the Latency window.
+
+use std::collections::{HashMap, VecDeque};
+
+use egui::{Context, Grid, Rect, Sense, Shape, Stroke, Ui, Vec2};
+
+use crate::{
+    cache::Endpoint,
+    workspace::{self, LATENCY},
+};
+
+/// Latencies kept per endpoint; older ones are dropped.
+const MAX_SAMPLES: usize = 500;
+
+/// Upper edges of the histogram's buckets in seconds. The last bucket holds
+/// everything slower.
+const BUCKETS: [f64; 9] = [0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+/// Seconds since some fixed point in the past, on both native and web.
+pub(crate) fn now() -> f64 {
+    #[cfg(target_arch = "wasm32")]
+    return js_sys::Date::now() / 1000.0;
+    #[cfg(not(target_arch = "wasm32"))]
+    return std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64();
+}
+
+/// One call to the server.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Sample {
+    seconds: f64,
+    ok: bool,
+}
+
+/// The most recent latencies of each endpoint.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Latencies {
+    samples: HashMap<Endpoint, VecDeque<Sample>>,
+}
+
+impl Latencies {
+    pub(crate) fn record(&mut self, endpoint: Endpoint, seconds: f64, ok: bool) {
+        let samples = self.samples.entry(endpoint).or_default();
+        if samples.len() == MAX_SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back(Sample { seconds, ok });
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.samples.clear();
+    }
+}
+
+/// How many of `seconds` fall into each of the `BUCKETS`, and past the last.
+fn histogram(seconds: impl Iterator<Item = f64>) -> [usize; BUCKETS.len() + 1] {
+    let mut counts = [0; BUCKETS.len() + 1];
+    for seconds in seconds {
+        let bucket = BUCKETS.iter().position(|edge| seconds <= *edge);
+        counts[bucket.unwrap_or(BUCKETS.len())] += 1;
+    }
+    counts
+}
+
+/// The `fraction` quantile of `sorted`, which must not be empty.
+fn quantile(sorted: &[f64], fraction: f64) -> f64 {
+    let index = ((sorted.len() - 1) as f64 * fraction).round() as usize;
+    sorted[index]
+}
+
+fn format_seconds(seconds: f64) -> String {
+    match seconds {
+        s if s < 1.0 => format!("{:.0} ms", s * 1000.0),
+        s => format!("{s:.1} s"),
+    }
+}
+
+/// The window with a row per endpoint: how many calls were made, how fast
+/// they were, and a histogram of their latencies.
+#[derive(Default)]
+pub(crate) struct LatencyWindow {
+    open: bool,
+}
+
+impl LatencyWindow {
+    pub(crate) fn button(&mut self, ui: &mut Ui) {
+        if ui
+            .button("Request latency")
+            .on_hover_text("How long the server takes to answer each kind of request")
+            .clicked()
+        {
+            self.open = true;
+        }
+    }
+
+    /// Show the window while it is open. Returns whether the latencies are
+    /// to be cleared.
+    pub(crate) fn show(&mut self, ctx: &Context, latencies: &Latencies) -> bool {
+        let mut clear = false;
+        let window = egui::Window::new(LATENCY)
+            .open(&mut self.open)
+            .resizable(false);
+        let shown = workspace::place(ctx, LATENCY, window).show(ctx, |ui| {
+            if latencies.samples.is_empty() {
+                ui.label("No requests have reached a server yet.");
+                return;
+            }
+            let mut endpoints: Vec<_> = latencies.samples.iter().collect();
+            endpoints.sort_by_key(|(endpoint, _)| endpoint.to_string());
+            Grid::new("latencies").striped(true).show(ui, |ui| {
+                for heading in ["Endpoint", "Calls", "Failed", "Median", "95th", ""] {
+                    ui.strong(heading);
+                }
+                ui.end_row();
+                for (endpoint, samples) in endpoints {
+                    let mut sorted: Vec<f64> = samples.iter().map(|s| s.seconds).collect();
+                    sorted.sort_by(f64::total_cmp);
+                    ui.label(endpoint.to_string());
+                    ui.label(samples.len().to_string());
+                    ui.label(samples.iter().filter(|s| !s.ok).count().to_string());
+                    ui.label(format_seconds(quantile(&sorted, 0.5)));
+                    ui.label(format_seconds(quantile(&sorted, 0.95)));
+                    histogram_ui(ui, &histogram(sorted.into_iter()));
+                    ui.end_row();
+                }
+            });
+            clear = ui.button("Clear").clicked();
+        });
+        workspace::track(ctx, LATENCY, &shown);
+        clear
+    }
+}
+
+/// A bar per bucket, as tall as its share of the calls, with the bucket's
+/// range on hover.
+fn histogram_ui(ui: &mut Ui, counts: &[usize]) {
+    const BAR_WIDTH: f32 = 10.0;
+    let size = Vec2::new(BAR_WIDTH * counts.len() as f32, 24.0);
+    let (rect, response) = ui.allocate_exact_size(size, Sense::hover());
+    let most = counts.iter().copied().max().unwrap_or_default().max(1);
+    let color = ui.visuals().selection.bg_fill;
+    let painter = ui.painter_at(rect);
+    for (index, count) in counts.iter().enumerate() {
+        let height = rect.height() * *count as f32 / most as f32;
+        let left = rect.left() + index as f32 * BAR_WIDTH;
+        let bar = Rect::from_min_max(
+            egui::pos2(left + 1.0, rect.bottom() - height),
+            egui::pos2(left + BAR_WIDTH - 1.0, rect.bottom()),
+        );
+        painter.add(Shape::rect_filled(bar, 0.0, color));
+    }
+    painter.add(Shape::line_segment(
+        [rect.left_bottom(), rect.right_bottom()],
+        Stroke::new(1.0, ui.visuals().weak_text_color()),
+    ));
+    response.on_hover_ui(|ui| {
+        for (index, count) in counts.iter().enumerate() {
+            let range = match index {
+                0 => format!("up to {}", format_seconds(BUCKETS[0])),
+                i if i == BUCKETS.len() => format!("over {}", format_seconds(BUCKETS[i - 1])),
+                i => format!(
+                    "{} to {}",
+                    format_seconds(BUCKETS[i - 1]),
+                    format_seconds(BUCKETS[i])
+                ),
+            };
+            ui.label(format!("{range}: {count}"));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_and_quantiles() {
+        let seconds = [0.005, 0.01, 0.02, 0.3, 7.0];
+        assert_eq!(
+            histogram(seconds.into_iter()),
+            [2, 1, 0, 0, 0, 1, 0, 0, 0, 1]
+        );
+        assert_eq!(quantile(&seconds, 0.5), 0.02);
+        assert_eq!(quantile(&seconds, 0.95), 7.0);
+        assert_eq!(quantile(&[1.5], 0.95), 1.5);
+
+        let mut latencies = Latencies::default();
+        for _ in 0..MAX_SAMPLES + 3 {
+            latencies.record(Endpoint::Tag, 0.1, true);
+        }
+        assert_eq!(latencies.samples[&Endpoint::Tag].len(), MAX_SAMPLES);
+    }
+}
@@ -0,0 +1,265 @@
+use std::{collections::HashSet, sync::Arc};
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use egui::{DragValue, ScrollArea, Ui};
+
+use crate::{
+    cache::ResponseCache,
+    handle::{Accessibility, Handle, Object},
+    http::{self, DataSource, Envelope, RequestKind, Response},
+};
+
+/// The object whose storage `handle` keeps alive, whatever the handle's
+/// accessibility, or `None` for a literal, which lives in the handle itself.
+/// A Thunk keeps the tree it applies alive.
+fn stored(handle: &Handle) -> Option<Handle> {
+    if handle.literal_bytes().is_some() {
+        return None;
+    }
+    let mut stored = handle.thunk_tree().unwrap_or_else(|| handle.clone());
+    stored.accessibility = Accessibility::Strict;
+    Some(stored)
+}
+
+/// Stored objects among `known` that are not `reachable`, in hex order.
+fn collectable<'a>(
+    known: impl IntoIterator<Item = &'a Handle>,
+    reachable: &HashSet<Handle>,
+) -> Vec<Handle> {
+    let mut orphans: Vec<_> = known
+        .into_iter()
+        .filter_map(stored)
+        .filter(|handle| !reachable.contains(handle))
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    orphans.sort_by_cached_key(Handle::to_hex);
+    orphans
+}
+
+/// Everything reachable from the server's roots: each root, the entries of
+/// every reachable Tree and Tag, and the tree of every reachable Thunk.
+struct Walk {
+    tx: Sender<Envelope>,
+    rx: Receiver<Envelope>,
+    /// Stored objects reached so far.
+    reachable: HashSet<Handle>,
+    /// Stop asking for more entries once this many objects are reached.
+    limit: usize,
+    /// Whether the walk stopped at the limit, so that more may be reachable.
+    truncated: bool,
+    errors: Vec<String>,
+    in_flight: usize,
+    /// The handles seen so far that are not reachable, once the walk is done.
+    orphans: Option<Vec<Handle>>,
+}
+
+impl Walk {
+    /// Mark `handle` reachable, and ask for its entries if it has any and
+    /// was not reached before.
+    fn visit(&mut self, handle: &Handle, fetch: &impl Fn(RequestKind)) {
+        let Some(stored) = stored(handle) else {
+            return;
+        };
+        if self.reachable.contains(&stored) {
+            return;
+        }
+        if self.reachable.len() >= self.limit {
+            self.truncated = true;
+            return;
+        }
+        self.reachable.insert(stored.clone());
+        let kind = match stored.object() {
+            Object::Tree => RequestKind::Tree { handle: stored },
+            Object::Tag => RequestKind::Tag { handle: stored },
+            Object::Blob | Object::Thunk => return,
+        };
+        self.in_flight += 1;
+        fetch(kind);
+    }
+
+    /// Take in every response that has arrived, visiting what it refers to.
+    fn receive(&mut self, fetch: &impl Fn(RequestKind)) {
+        let envelopes: Vec<_> = self.rx.try_iter().collect();
+        for Envelope { request, result } in envelopes {
+            self.in_flight -= 1;
+            let referred = match result {
+                Err(e) => {
+                    self.errors
+                        .push(format!("{}: {e:#}", request.kind.endpoint()));
+                    continue;
+                }
+                Ok(Response::Roots(roots)) => roots,
+                Ok(Response::Tree(entries)) => entries.unwrap_or_default(),
+                Ok(Response::Tag(entries)) => entries
+                    .map(|tag| vec![tag.tagged, tag.authority, tag.label])
+                    .unwrap_or_default(),
+                Ok(response) => {
+                    log::warn!("Orphan search got a {} response", response.kind());
+                    continue;
+                }
+            };
+            for handle in &referred {
+                self.visit(handle, fetch);
+            }
+        }
+    }
+}
+
+/// Orphan search controls for the side panel, and the window listing what
+/// was found.
+pub(crate) struct OrphanPanel {
+    limit: usize,
+    walk: Option<Walk>,
+    open: bool,
+}
+
+impl Default for OrphanPanel {
+    fn default() -> Self {
+        Self {
+            limit: 10_000,
+            walk: None,
+            open: false,
+        }
+    }
+}
+
+impl OrphanPanel {
+    /// Start walking from the roots of the server at `url`.
+    pub(crate) fn ui(&mut self, ui: &mut Ui, source: &Arc<DataSource>, url: &str) {
+        ui.horizontal(|ui| {
+            ui.label("Walk at most: ");
+            ui.add(
+                DragValue::new(&mut self.limit)
+                    .clamp_range(1..=1_000_000)
+                    .suffix(" objects"),
+            );
+        });
+        if ui
+            .button("Find orphans")
+            .on_hover_text(
+                "Walk everything the server's roots refer to, and list the handles seen so far that none of them reach",
+            )
+            .clicked()
+        {
+            let (tx, rx) = unbounded();
+            http::get_roots(source.clone(), ui.ctx().clone(), tx.clone(), url);
+            self.walk = Some(Walk {
+                tx,
+                rx,
+                reachable: HashSet::new(),
+                limit: self.limit,
+                truncated: false,
+                errors: vec![],
+                in_flight: 1,
+                orphans: None,
+            });
+            self.open = true;
+        }
+    }
+
+    /// The window with the handles no root reaches, once a search was
+    /// started. Also takes in responses. Returns a handle to go to, if one
+    /// was clicked.
+    pub(crate) fn show(
+        &mut self,
+        ctx: &egui::Context,
+        source: &Arc<DataSource>,
+        url: &str,
+        cache: &ResponseCache,
+    ) -> Option<Handle> {
+        let walk = self.walk.as_mut()?;
+        let tx = walk.tx.clone();
+        let fetch = |kind: RequestKind| {
+            if cache.resend(ctx, kind.clone(), &tx) {
+                return;
+            }
+            let (source, ctx, tx) = (source.clone(), ctx.clone(), tx.clone());
+            match &kind {
+                RequestKind::Tree { handle } => http::get_tree(source, ctx, handle, tx, url, cache),
+                RequestKind::Tag { handle } => http::get_tag(source, ctx, handle, tx, url, cache),
+                _ => unreachable!("only trees and tags are walked"),
+            };
+        };
+        walk.receive(&fetch);
+        if walk.in_flight == 0 && walk.orphans.is_none() {
+            walk.orphans = Some(collectable(&cache.handles(), &walk.reachable));
+        }
+        let mut target = None;
+        let walk = &*walk;
+        egui::Window::new("Orphans")
+            .open(&mut self.open)
+            .default_size([480.0, 400.0])
+            .show(ctx, |ui| {
+                let Some(orphans) = &walk.orphans else {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(format!(
+                            "{} objects reached, {} requests in flight",
+                            walk.reachable.len(),
+                            walk.in_flight
+                        ));
+                    });
+                    return;
+                };
+                for error in &walk.errors {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                if walk.truncated {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!(
+                            "Stopped after {} objects, so some of these may be reachable.",
+                            walk.limit
+                        ),
+                    );
+                }
+                ui.label(format!(
+                    "{} objects are reachable from the roots. Of the handles seen so far, {} are not, and could be collected.",
+                    walk.reachable.len(),
+                    orphans.len()
+                ));
+                ui.separator();
+                ScrollArea::vertical().show_rows(
+                    ui,
+                    ui.text_style_height(&egui::TextStyle::Monospace),
+                    orphans.len(),
+                    |ui, rows| {
+                        for handle in &orphans[rows] {
+                            if ui
+                                .link(egui::RichText::new(handle.to_hex()).monospace())
+                                .on_hover_text(handle.to_string())
+                                .clicked()
+                            {
+                                target = Some(handle.clone());
+                            }
+                        }
+                    },
+                );
+            });
+        target
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unreachable_stored_objects() {
+        let tree = Handle::from_hex("d9-0-4-100000000000000").unwrap();
+        let literal = Handle::literal(b"fix", Accessibility::Strict).unwrap();
+        let mut lazy = tree.clone();
+        lazy.accessibility = Accessibility::Lazy;
+        assert_eq!(stored(&literal), None);
+        assert_eq!(stored(&lazy), stored(&tree));
+
+        let known = [tree.clone(), lazy, literal];
+        assert_eq!(
+            collectable(&known, &HashSet::new()),
+            [stored(&tree).unwrap()]
+        );
+        let reachable = HashSet::from([stored(&tree).unwrap()]);
+        assert!(collectable(&known, &reachable).is_empty());
+    }
+}
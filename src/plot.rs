@@ -1,16 +1,52 @@
+use std::{collections::HashMap, sync::Arc};
+
 use eframe::epaint::{ClippedShape, Primitive, RectShape, TextShape};
 use egui::{
     plot::{PlotBounds, PlotPoint, PlotTransform},
-    Color32, Mesh, Pos2, Rect, RichText, Shape, Stroke, TextStyle, Ui, WidgetText,
+    Align2, Color32, FontId, Grid, Id, Mesh, Pos2, Rect, Rgba, RichText, Shape, Stroke, TextStyle,
+    Ui, Vec2, Visuals, WidgetText,
+};
+
+use crate::{
+    handle::{Accessibility, Handle, Object, Operation, Restriction, TaskStatus},
+    theme::Palette,
 };
 
-use crate::handle::Handle;
+/// Tessellated element texts, keyed by the text and the pixels per point it
+/// was tessellated at. Kept in the context's memory, so that elements with
+/// the same text share one mesh.
+#[derive(Clone, Default)]
+struct TextMeshes(HashMap<(String, u32), (Arc<Mesh>, Rect)>);
+
+/// Texts kept in `TextMeshes` before it is emptied and starts over.
+const MAX_TEXT_MESHES: usize = 10_000;
 
 #[derive(Clone)]
 pub(crate) struct Element {
     content: Handle,
-    mesh: Mesh,
+    /// The full text, to abbreviate again when the abbreviation changes.
+    text: Arc<str>,
+    /// Shared, so that cloning the graph into the plot every frame is cheap.
+    mesh: Arc<Mesh>,
     mesh_bounds: Rect,
+    /// The text with its long hex numbers cut short, drawn instead of the
+    /// full text unless the element is selected or hovered. `None` while
+    /// there is nothing to cut.
+    abbreviated: Option<(Arc<Mesh>, Rect)>,
+    /// Hex digits kept at each end of long hex numbers, or 0 to keep them
+    /// whole.
+    abbreviation: usize,
+    /// Restricted elements are drawn locked and are not fetched from.
+    restriction: Option<Restriction>,
+    /// Text shown instead of the handle by an element that stands for a
+    /// group of handles.
+    summary: Option<String>,
+    /// Name given to the handle, drawn instead of its description.
+    alias: Option<String>,
+    /// How much bigger than normal the element is drawn.
+    size_scale: f64,
+    /// Room left around the text, in graph units.
+    padding: f64,
 }
 
 impl PartialEq for Element {
@@ -21,16 +57,93 @@ impl PartialEq for Element {
 
 impl Element {
     const TEXT_RENDER_SCALE: f64 = 30.0;
-    const RECT_EXTENSION: f64 = 0.02;
+    /// Room left around the text unless the spacing settings say otherwise.
+    pub(crate) const RECT_EXTENSION: f64 = 0.02;
     /// The number of pixels just a full rendered handle takes.
     /// Used to scale the text.
     const TEXT_PIXEL_SCALE: f64 = 40.0;
+    /// Elements shorter than this many pixels on screen are drawn as plain
+    /// rects, since their text could not be read anyway.
+    const MIN_TEXT_HEIGHT: f32 = 4.0;
 
     pub(crate) fn new(ui: &Ui, content: Handle) -> Self {
-        let rich_text = RichText::new(content.to_string())
+        Self::with_restriction(ui, content, None)
+    }
+
+    pub(crate) fn with_restriction(
+        ui: &Ui,
+        content: Handle,
+        restriction: Option<Restriction>,
+    ) -> Self {
+        let text = Self::describe(&content, restriction.as_ref(), None);
+        Self::with_text(ui, content, text, restriction, None)
+    }
+
+    /// The text drawn for `content`: `alias` if it has one, and locked if
+    /// restricted.
+    fn describe(
+        content: &Handle,
+        restriction: Option<&Restriction>,
+        alias: Option<&str>,
+    ) -> String {
+        let text = alias.map_or_else(|| content.to_string(), str::to_string);
+        match restriction {
+            Some(_) => format!("🔒 {text}"),
+            None => text,
+        }
+    }
+
+    /// An element summarizing `count` parents of `child` that all perform
+    /// `operation` on it.
+    pub(crate) fn cluster(ui: &Ui, child: Handle, operation: Operation, count: usize) -> Self {
+        let summary = format!("{count} {operation} parents");
+        Self::with_text(ui, child, format!("▶ {summary}"), None, Some(summary))
+    }
+
+    fn with_text(
+        ui: &Ui,
+        content: Handle,
+        text: String,
+        restriction: Option<Restriction>,
+        summary: Option<String>,
+    ) -> Self {
+        let (mesh, mesh_bounds) = Self::text_mesh(ui, text.clone());
+        Self {
+            content,
+            text: text.into(),
+            mesh,
+            mesh_bounds,
+            abbreviated: None,
+            abbreviation: 0,
+            restriction,
+            summary,
+            alias: None,
+            size_scale: 1.0,
+            padding: Self::RECT_EXTENSION,
+        }
+    }
+
+    /// `text` tessellated, centered on the origin and scaled to graph units,
+    /// along with its bounds. Reused from the context's memory if any element
+    /// showed the same text before. The text is white, and colored to stand
+    /// out from what is behind it when drawn, so that it need not be made
+    /// again when the theme changes.
+    fn text_mesh(ui: &Ui, text: String) -> (Arc<Mesh>, Rect) {
+        let key = (text, ui.ctx().pixels_per_point().to_bits());
+        let cached = ui.ctx().data_mut(|data| {
+            data.get_temp_mut_or_default::<TextMeshes>(Id::null())
+                .0
+                .get(&key)
+                .cloned()
+        });
+        if let Some(cached) = cached {
+            return cached;
+        }
+
+        let rich_text = RichText::new(key.0.as_str())
             .size(Self::TEXT_RENDER_SCALE as f32)
             .monospace()
-            .color(ui.visuals().widgets.active.fg_stroke.color);
+            .color(Color32::WHITE);
         let galley = WidgetText::RichText(rich_text).into_galley(
             ui,
             Some(false),
@@ -56,16 +169,75 @@ impl Element {
                     (v.pos.y as f64 / Self::TEXT_RENDER_SCALE / Self::TEXT_PIXEL_SCALE) as f32,
                 );
             });
-            Self {
-                content,
-                mesh_bounds: mesh.calc_bounds().expand(Self::RECT_EXTENSION as f32),
-                mesh,
-            }
+            let bounds = mesh.calc_bounds();
+            let entry = (Arc::new(mesh), bounds);
+            ui.ctx().data_mut(|data| {
+                let meshes = &mut data.get_temp_mut_or_default::<TextMeshes>(Id::null()).0;
+                if meshes.len() >= MAX_TEXT_MESHES {
+                    meshes.clear();
+                }
+                meshes.insert(key, entry.clone());
+            });
+            entry
         } else {
             panic!("Tessellated text should be a mesh")
         }
     }
 
+    /// Draw the element bigger the larger its handle is if `scaled` is set,
+    /// or at its normal size otherwise.
+    pub(crate) fn set_size_scaling(&mut self, scaled: bool) {
+        self.size_scale = match (scaled, &self.summary) {
+            (true, None) => {
+                // The area grows with the logarithm of the size, from 0.4
+                // times the normal area for empty handles to twice it from
+                // 2^40 on.
+                let t = ((self.content.size as f64 + 1.0).log2() / 40.0).min(1.0);
+                (0.4 + 1.6 * t).sqrt()
+            }
+            _ => 1.0,
+        };
+    }
+
+    /// Cut hex numbers in the text longer than twice `keep` down to their
+    /// first and last `keep` digits, or draw them whole if `keep` is 0.
+    pub(crate) fn set_abbreviation(&mut self, ui: &Ui, keep: usize) {
+        self.abbreviation = keep;
+        let abbreviated = abbreviate(&self.text, keep);
+        self.abbreviated = (abbreviated != *self.text).then(|| Self::text_mesh(ui, abbreviated));
+    }
+
+    /// Draw `alias` instead of the description of the handle, or the
+    /// description again if `None`. Elements standing for a group of handles
+    /// keep their summary.
+    pub(crate) fn set_alias(&mut self, ui: &Ui, alias: Option<&str>) {
+        if self.summary.is_some() || self.alias.as_deref() == alias {
+            return;
+        }
+        self.alias = alias.map(str::to_string);
+        let text = Self::describe(&self.content, self.restriction.as_ref(), alias);
+        (self.mesh, self.mesh_bounds) = Self::text_mesh(ui, text.clone());
+        self.text = text.into();
+        self.set_abbreviation(ui, self.abbreviation);
+    }
+
+    pub(crate) fn set_padding(&mut self, padding: f64) {
+        self.padding = padding;
+    }
+
+    pub(crate) fn padding(&self) -> f64 {
+        self.padding
+    }
+
+    /// The mesh drawn and its padded bounds, abbreviated unless `expanded`.
+    fn drawn(&self, expanded: bool) -> (&Arc<Mesh>, Rect) {
+        let (mesh, bounds) = match &self.abbreviated {
+            Some((mesh, bounds)) if !expanded => (mesh, *bounds),
+            _ => (&self.mesh, self.mesh_bounds),
+        };
+        (mesh, bounds.expand(self.padding as f32))
+    }
+
     fn graph_pos_to_screen_pos(
         position: PlotPoint,
         transform: &PlotTransform,
@@ -85,38 +257,146 @@ impl Element {
         shapes: &mut Vec<Shape>,
         (center, zoom): (PlotPoint, f64),
         highlight: bool,
-        fg_stroke_color: Color32,
+        visuals: &Visuals,
+        palette: &Palette,
     ) {
+        let fg_stroke_color = visuals.widgets.active.fg_stroke.color;
+        let zoom = zoom * self.size_scale;
         let transform = |pos: PlotPoint| -> Pos2 {
             Self::graph_pos_to_screen_pos(pos, transform, zoom, center)
         };
 
-        let mut mesh = self.mesh.clone();
+        // Selected elements show their full text.
+        let (drawn, mut mesh_bounds) = self.drawn(highlight);
+        mesh_bounds.min = transform(PlotPoint::new(mesh_bounds.min.x, mesh_bounds.min.y));
+        mesh_bounds.max = transform(PlotPoint::new(mesh_bounds.max.x, mesh_bounds.max.y));
+
+        // Locked elements are outlined in their own color. The outline is
+        // solid for strict handles, dashed for shallow and dotted for lazy.
+        let color = match self.restriction {
+            Some(_) => palette.restricted,
+            None => fg_stroke_color,
+        };
+        if mesh_bounds.height().abs() < Self::MIN_TEXT_HEIGHT {
+            let opacity = if highlight { 0.8 } else { 0.4 };
+            shapes.push(Shape::rect_filled(
+                mesh_bounds,
+                0.0,
+                color.gamma_multiply(opacity),
+            ));
+            return;
+        }
+
+        // Selected elements are filled under their text, which is colored to
+        // stand out from the fill.
+        let mut background = visuals.extreme_bg_color;
+        if highlight {
+            shapes.push(Shape::rect_filled(mesh_bounds, 1.0, fg_stroke_color));
+            background = over(background, fg_stroke_color);
+        }
+        let text = contrasting_text(background);
+        let mut mesh = Mesh::clone(drawn);
         mesh.vertices.iter_mut().for_each(|v| {
             v.pos = transform(PlotPoint::new(v.pos.x, v.pos.y));
+            v.color = text;
         });
+        shapes.push(Shape::Mesh(mesh));
+        let accessibility = match self.summary {
+            Some(_) => Accessibility::Strict,
+            None => self.content.accessibility,
+        };
+        let r = mesh_bounds;
+        let outline = [
+            r.left_top(),
+            r.right_top(),
+            r.right_bottom(),
+            r.left_bottom(),
+            r.left_top(),
+        ];
+        match accessibility {
+            Accessibility::Strict => {
+                shapes.push(Shape::rect_stroke(r, 1.0, Stroke::new(2.0, color)));
+            }
+            Accessibility::Shallow => {
+                shapes.extend(Shape::dashed_line(
+                    &outline,
+                    Stroke::new(2.0, color),
+                    6.0,
+                    4.0,
+                ));
+            }
+            Accessibility::Lazy => shapes.extend(Shape::dotted_line(&outline, color, 5.0, 1.5)),
+        }
+    }
+
+    pub(crate) fn add_highlight(
+        &self,
+        transform: &PlotTransform,
+        (center, zoom): (PlotPoint, f64),
+        shapes: &mut Vec<Shape>,
+        visuals: &Visuals,
+        palette: &Palette,
+    ) {
+        self.add_tint(
+            transform,
+            (center, zoom),
+            shapes,
+            palette.highlight.gamma_multiply(0.2),
+        );
+        self.add_full_text(transform, (center, zoom), shapes, visuals);
+    }
 
-        let mut mesh_bounds = self.mesh_bounds;
+    /// Draw the full text over an abbreviated element, on a backdrop that
+    /// hides its neighbors.
+    fn add_full_text(
+        &self,
+        transform: &PlotTransform,
+        (center, zoom): (PlotPoint, f64),
+        shapes: &mut Vec<Shape>,
+        visuals: &Visuals,
+    ) {
+        if self.abbreviated.is_none() {
+            return;
+        }
+        let zoom = zoom * self.size_scale;
+        let transform = |pos: PlotPoint| -> Pos2 {
+            Self::graph_pos_to_screen_pos(pos, transform, zoom, center)
+        };
+        let (_, mut mesh_bounds) = self.drawn(true);
         mesh_bounds.min = transform(PlotPoint::new(mesh_bounds.min.x, mesh_bounds.min.y));
         mesh_bounds.max = transform(PlotPoint::new(mesh_bounds.max.x, mesh_bounds.max.y));
-
-        shapes.push(Shape::Mesh(mesh.clone()));
-        shapes.push(Shape::rect_stroke(
-            mesh_bounds,
+        if mesh_bounds.height().abs() < Self::MIN_TEXT_HEIGHT {
+            return;
+        }
+        // A backdrop in contrast with the text, whatever the theme.
+        let text = contrasting_text(visuals.extreme_bg_color);
+        let backdrop = if text == Color32::WHITE {
+            Color32::from_black_alpha(220)
+        } else {
+            Color32::from_white_alpha(220)
+        };
+        shapes.push(Shape::rect_filled(
+            Rect::from_two_pos(mesh_bounds.min, mesh_bounds.max),
             1.0,
-            Stroke::new(2.0, fg_stroke_color),
+            backdrop,
         ));
-        if highlight {
-            shapes.push(Shape::rect_filled(mesh_bounds, 1.0, fg_stroke_color));
-        }
+        let mut mesh = Mesh::clone(&self.mesh);
+        mesh.vertices.iter_mut().for_each(|v| {
+            v.pos = transform(PlotPoint::new(v.pos.x, v.pos.y));
+            v.color = text;
+        });
+        shapes.push(Shape::Mesh(mesh));
     }
 
-    pub(crate) fn add_highlight(
+    /// Cover the element with `color`, which should be translucent.
+    pub(crate) fn add_tint(
         &self,
         transform: &PlotTransform,
         (center, zoom): (PlotPoint, f64),
         shapes: &mut Vec<Shape>,
+        color: Color32,
     ) {
+        let zoom = zoom * self.size_scale;
         let transform = transform;
         let scale_transform = |pos: Pos2| -> Pos2 {
             Pos2::new(
@@ -125,16 +405,17 @@ impl Element {
             )
         };
         let translation = transform.position_from_point(&center).to_vec2();
-        let mut mesh_bounds = self.mesh_bounds;
+        let (_, mut mesh_bounds) = self.drawn(false);
         mesh_bounds.min = scale_transform(mesh_bounds.min);
         mesh_bounds.max = scale_transform(mesh_bounds.max);
         mesh_bounds = mesh_bounds.translate(translation);
 
-        shapes.push(RectShape::filled(mesh_bounds, 1.0, Color32::BLUE.gamma_multiply(0.2)).into())
+        shapes.push(RectShape::filled(mesh_bounds, 1.0, color).into())
     }
 
     pub(crate) fn bounds(&self, (center, zoom): (PlotPoint, f64)) -> PlotBounds {
-        let rect = self.mesh_bounds;
+        let zoom = zoom * self.size_scale;
+        let (_, rect) = self.drawn(false);
 
         assert!(rect.center() == Pos2::ZERO);
         let left = rect.min.x as f64 * zoom + center.x;
@@ -146,6 +427,14 @@ impl Element {
         PlotBounds::from_min_max([left, bottom], [right, top])
     }
 
+    /// Whether any part of the element is inside the plot's frame.
+    pub(crate) fn is_on_screen(&self, transform: &PlotTransform, params: (PlotPoint, f64)) -> bool {
+        let bounds = self.bounds(params);
+        transform
+            .rect_from_values(&bounds.min().into(), &bounds.max().into())
+            .intersects(*transform.frame())
+    }
+
     pub(crate) fn get_text(&self) -> String {
         self.content.to_string()
     }
@@ -153,4 +442,339 @@ impl Element {
     pub(crate) fn get_handle(&self) -> &Handle {
         &self.content
     }
+
+    /// The element's text and where it is drawn on screen, for renderers
+    /// that cannot use the text mesh.
+    pub(crate) fn label(
+        &self,
+        transform: &PlotTransform,
+        params: (PlotPoint, f64),
+    ) -> (Rect, String) {
+        let bounds = self.bounds(params);
+        let rect = transform.rect_from_values(&bounds.min().into(), &bounds.max().into());
+        let text = match (&self.summary, &self.alias) {
+            (Some(summary), _) => summary.clone(),
+            (None, Some(alias)) => alias.clone(),
+            (None, None) => abbreviate(&self.content.to_string(), self.abbreviation),
+        };
+        (rect, text)
+    }
+
+    /// Note under the element that clicking it loads `remaining` more parents
+    /// or dependees.
+    pub(crate) fn add_more_note(
+        &self,
+        ui: &Ui,
+        transform: &PlotTransform,
+        params: (PlotPoint, f64),
+        remaining: u64,
+        shapes: &mut Vec<Shape>,
+    ) {
+        let (rect, _) = self.label(transform, params);
+        shapes.push(ui.fonts(|fonts| {
+            Shape::text(
+                fonts,
+                rect.left_bottom() + Vec2::new(0.0, 2.0),
+                Align2::LEFT_TOP,
+                format!("load more ({remaining} remaining)"),
+                FontId::proportional(12.0),
+                ui.visuals().hyperlink_color,
+            )
+        }));
+    }
+
+    /// Note under the element, where `add_more_note` was, while the next
+    /// page is being fetched.
+    pub(crate) fn add_loading_note(
+        &self,
+        ui: &Ui,
+        transform: &PlotTransform,
+        params: (PlotPoint, f64),
+        shapes: &mut Vec<Shape>,
+    ) {
+        let (rect, _) = self.label(transform, params);
+        shapes.push(ui.fonts(|fonts| {
+            Shape::text(
+                fonts,
+                rect.left_bottom() + Vec2::new(0.0, 2.0),
+                Align2::LEFT_TOP,
+                "loading more…",
+                FontId::proportional(12.0),
+                ui.visuals().weak_text_color(),
+            )
+        }));
+    }
+
+    /// Badge above the element with how many parents it has that are not
+    /// drawn yet.
+    pub(crate) fn add_parent_count(
+        &self,
+        ui: &Ui,
+        transform: &PlotTransform,
+        params: (PlotPoint, f64),
+        count: u64,
+        shapes: &mut Vec<Shape>,
+    ) {
+        let (rect, _) = self.label(transform, params);
+        shapes.push(ui.fonts(|fonts| {
+            Shape::text(
+                fonts,
+                rect.center_top() - Vec2::new(0.0, 2.0),
+                Align2::CENTER_BOTTOM,
+                format!("▲ {count}"),
+                FontId::proportional(12.0),
+                ui.visuals().weak_text_color(),
+            )
+        }));
+    }
+
+    /// Icon at the top right of the element showing it has a note.
+    pub(crate) fn add_note_icon(
+        &self,
+        ui: &Ui,
+        transform: &PlotTransform,
+        params: (PlotPoint, f64),
+        shapes: &mut Vec<Shape>,
+    ) {
+        let (rect, _) = self.label(transform, params);
+        shapes.push(ui.fonts(|fonts| {
+            Shape::text(
+                fonts,
+                rect.right_top(),
+                Align2::CENTER_CENTER,
+                "📝",
+                FontId::proportional(12.0),
+                ui.visuals().warn_fg_color,
+            )
+        }));
+    }
+
+    /// Eye at the bottom right of a watched element, with how many times it
+    /// changed.
+    pub(crate) fn add_watch_badge(
+        &self,
+        ui: &Ui,
+        transform: &PlotTransform,
+        params: (PlotPoint, f64),
+        changes: usize,
+        shapes: &mut Vec<Shape>,
+    ) {
+        let (rect, _) = self.label(transform, params);
+        let text = match changes {
+            0 => "👁".to_string(),
+            changes => format!("👁 {changes}"),
+        };
+        shapes.push(ui.fonts(|fonts| {
+            Shape::text(
+                fonts,
+                rect.right_bottom(),
+                Align2::CENTER_CENTER,
+                text,
+                FontId::proportional(12.0),
+                ui.visuals().hyperlink_color,
+            )
+        }));
+    }
+
+    /// Pushpin at the top left of an element dragged to a place of its own.
+    pub(crate) fn add_fixed_badge(
+        &self,
+        ui: &Ui,
+        transform: &PlotTransform,
+        params: (PlotPoint, f64),
+        shapes: &mut Vec<Shape>,
+    ) {
+        let (rect, _) = self.label(transform, params);
+        shapes.push(ui.fonts(|fonts| {
+            Shape::text(
+                fonts,
+                rect.left_top(),
+                Align2::CENTER_CENTER,
+                "📌",
+                FontId::proportional(12.0),
+                ui.visuals().text_color(),
+            )
+        }));
+    }
+
+    /// Red cross at the top of an element whose object the server does not
+    /// have.
+    pub(crate) fn add_missing_badge(
+        &self,
+        ui: &Ui,
+        transform: &PlotTransform,
+        params: (PlotPoint, f64),
+        shapes: &mut Vec<Shape>,
+    ) {
+        let (rect, _) = self.label(transform, params);
+        shapes.push(ui.fonts(|fonts| {
+            Shape::text(
+                fonts,
+                rect.center_top(),
+                Align2::CENTER_CENTER,
+                "❌",
+                FontId::proportional(12.0),
+                ui.visuals().error_fg_color,
+            )
+        }));
+    }
+
+    /// Icon at the top left of the element for the status of its task, over
+    /// a tint in the same color.
+    pub(crate) fn add_status(
+        &self,
+        ui: &Ui,
+        transform: &PlotTransform,
+        params: (PlotPoint, f64),
+        status: TaskStatus,
+        shapes: &mut Vec<Shape>,
+    ) {
+        let (icon, color) = match status {
+            TaskStatus::Pending => ("⏳", ui.visuals().weak_text_color()),
+            TaskStatus::Running => ("▶", ui.visuals().warn_fg_color),
+            TaskStatus::Done => ("✔", Color32::from_rgb(80, 180, 80)),
+            TaskStatus::Failed => ("✖", ui.visuals().error_fg_color),
+        };
+        self.add_tint(transform, params, shapes, color.gamma_multiply(0.15));
+        let (rect, _) = self.label(transform, params);
+        shapes.push(ui.fonts(|fonts| {
+            Shape::text(
+                fonts,
+                rect.left_top(),
+                Align2::CENTER_CENTER,
+                icon,
+                FontId::proportional(12.0),
+                color,
+            )
+        }));
+    }
+
+    pub(crate) fn get_restriction(&self) -> Option<&Restriction> {
+        self.restriction.as_ref()
+    }
+
+    /// What the handle decodes to, for a tooltip. `tasks` are the operations
+    /// the element is a task of, each with where it was reached from.
+    pub(crate) fn details_ui(&self, ui: &mut Ui, tasks: &[(Operation, String)]) {
+        if let Some(summary) = &self.summary {
+            ui.label(summary);
+            return;
+        }
+        let handle = &self.content;
+        if let Some(alias) = &self.alias {
+            ui.strong(alias);
+        }
+        ui.monospace(handle.to_hex());
+        Grid::new("element_details").num_columns(2).show(ui, |ui| {
+            ui.label("Type");
+            ui.label(handle.object().to_string());
+            ui.end_row();
+            ui.label("Size");
+            ui.label(match handle.object() {
+                Object::Blob => format!("{} bytes", handle.size),
+                _ => format!("{} entries", handle.size),
+            });
+            ui.end_row();
+            ui.label("Accessibility");
+            ui.label(handle.accessibility.to_string());
+            ui.end_row();
+            ui.label("Storage");
+            ui.label(handle.storage());
+            ui.end_row();
+            if let Some(restriction) = &self.restriction {
+                ui.label("Restricted");
+                ui.label(format!("requires {}", restriction.permission));
+                ui.end_row();
+            }
+            for (operation, origin) in tasks {
+                ui.label("Task");
+                ui.label(format!("{operation}, {origin}"));
+                ui.end_row();
+            }
+        });
+    }
+}
+
+/// `text` with every hex number after a `0x` longer than twice `keep`
+/// digits cut to its first and last `keep`, as in `0x0123ab…89cdef`. A
+/// `keep` of 0 leaves the text whole.
+pub(crate) fn abbreviate(text: &str, keep: usize) -> String {
+    if keep == 0 {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("0x") {
+        let (before, number) = rest.split_at(start + 2);
+        out.push_str(before);
+        let digits = number
+            .find(|c: char| !c.is_ascii_hexdigit())
+            .unwrap_or(number.len());
+        // Cutting one digit would not save any room.
+        if digits > 2 * keep + 1 {
+            out.push_str(&number[..keep]);
+            out.push('…');
+            out.push_str(&number[digits - keep..digits]);
+        } else {
+            out.push_str(&number[..digits]);
+        }
+        rest = &number[digits..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Black or white, whichever is easier to read on `background`.
+pub(crate) fn contrasting_text(background: Color32) -> Color32 {
+    // Past this relative luminance, black stands out from the background
+    // more than white does.
+    const MIDDLE_LUMINANCE: f32 = 0.179;
+    let linear = Rgba::from(background);
+    let luminance = 0.2126 * linear.r() + 0.7152 * linear.g() + 0.0722 * linear.b();
+    if luminance > MIDDLE_LUMINANCE {
+        Color32::BLACK
+    } else {
+        Color32::WHITE
+    }
+}
+
+/// The color seen where `top` is drawn over the opaque `base`.
+fn over(base: Color32, top: Color32) -> Color32 {
+    let top = Rgba::from(top);
+    (top + Rgba::from(base) * (1.0 - top.a())).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_stands_out_from_its_background() {
+        for (background, text) in [
+            (Visuals::dark().extreme_bg_color, Color32::WHITE),
+            (Visuals::light().extreme_bg_color, Color32::BLACK),
+            (Color32::YELLOW, Color32::BLACK),
+            (Color32::BLUE, Color32::WHITE),
+        ] {
+            assert_eq!(contrasting_text(background), text, "on {background:?}");
+        }
+        // A light fill over a dark background can call for dark text.
+        let filled = over(Color32::BLACK, Color32::WHITE.gamma_multiply(0.9));
+        assert_eq!(contrasting_text(filled), Color32::BLACK);
+        assert_eq!(over(Color32::BLACK, Color32::TRANSPARENT), Color32::BLACK);
+    }
+
+    #[test]
+    fn abbreviates_long_hex_numbers() {
+        let text = "strict Blob, 30 bytes, content 0x0123456789abcdef0123456789";
+        assert_eq!(
+            abbreviate(text, 6),
+            "strict Blob, 30 bytes, content 0x012345…456789"
+        );
+        assert_eq!(abbreviate(text, 0), text);
+        // Numbers short enough, and hex letters outside numbers, stay.
+        let local = "shallow Tree, 3 entries, local id 0xdeadbeef";
+        assert_eq!(abbreviate(local, 4), local);
+        assert_eq!(abbreviate("0xabcdefabc and 0x", 2), "0xab…bc and 0x");
+    }
 }
@@ -0,0 +1,329 @@
+use std::fmt::Display;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Duration;
+
+use egui::{ComboBox, DragValue, Grid, TextEdit, Ui};
+
+/// How requests reach a server.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub(crate) enum Transport {
+    /// JSON over HTTP.
+    #[default]
+    Http,
+    /// The same queries over gRPC. Only works in builds with the `grpc`
+    /// feature.
+    Grpc,
+}
+
+impl Transport {
+    pub(crate) const ALL: [Transport; 2] = [Transport::Http, Transport::Grpc];
+
+    /// Scheme of the URLs recorded in traces.
+    pub(crate) fn scheme(self) -> &'static str {
+        match self {
+            Transport::Http => "http",
+            Transport::Grpc => "grpc",
+        }
+    }
+}
+
+impl Display for Transport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Transport::Http => "HTTP",
+            Transport::Grpc => "gRPC",
+        })
+    }
+}
+
+/// How long to wait on a server before giving up, in seconds.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct Timeouts {
+    /// Until the connection is made.
+    pub(crate) connect: f64,
+    /// Until the whole response has arrived once connected.
+    pub(crate) read: f64,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            connect: 10.0,
+            read: 30.0,
+        }
+    }
+}
+
+impl Timeouts {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn connect(&self) -> Duration {
+        Duration::from_secs_f64(self.connect)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn read(&self) -> Duration {
+        Duration::from_secs_f64(self.read)
+    }
+}
+
+/// Where each query lives on an HTTP server, for servers that name their
+/// routes differently. gRPC servers ignore these.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct Routes {
+    pub(crate) parents: String,
+    pub(crate) parent_count: String,
+    pub(crate) dependees: String,
+    pub(crate) child: String,
+    pub(crate) status: String,
+    pub(crate) task_times: String,
+    pub(crate) tag: String,
+    pub(crate) tree: String,
+    pub(crate) blob: String,
+    pub(crate) canonical: String,
+    /// Asked with a POST, unlike the others.
+    pub(crate) rerun: String,
+    /// Asked with a POST too.
+    pub(crate) materialize: String,
+    /// Asked with a POST of many handles for the first page of parents of
+    /// each. Empty for servers without it.
+    pub(crate) parents_batch: String,
+    pub(crate) roots: String,
+    /// Asked with a HEAD, whose status tells whether the object exists.
+    pub(crate) object: String,
+    /// Asked once per server for the version of its answers. Empty for
+    /// servers without it, which give the first.
+    pub(crate) version: String,
+    /// Name of the query parameter holding the handle.
+    pub(crate) handle: String,
+    pub(crate) operation: String,
+    /// Names of the pagination parameters: the page size and the cursor to
+    /// continue after.
+    pub(crate) limit: String,
+    pub(crate) after: String,
+}
+
+impl Default for Routes {
+    fn default() -> Self {
+        Self {
+            parents: "/parents".to_string(),
+            parent_count: "/parents_count".to_string(),
+            dependees: "/dependees".to_string(),
+            child: "/child".to_string(),
+            status: "/status".to_string(),
+            task_times: "/task_times".to_string(),
+            tag: "/tag".to_string(),
+            tree: "/tree".to_string(),
+            blob: "/blob".to_string(),
+            canonical: "/canonical".to_string(),
+            rerun: "/rerun".to_string(),
+            materialize: "/materialize".to_string(),
+            parents_batch: "/parents_batch".to_string(),
+            roots: "/roots".to_string(),
+            object: "/object".to_string(),
+            version: "/version".to_string(),
+            handle: "handle".to_string(),
+            operation: "op".to_string(),
+            limit: "limit".to_string(),
+            after: "after".to_string(),
+        }
+    }
+}
+
+impl Routes {
+    fn ui(&mut self, ui: &mut Ui) {
+        let fields = [
+            ("Parents", &mut self.parents),
+            ("Parent count", &mut self.parent_count),
+            ("Dependees", &mut self.dependees),
+            ("Child", &mut self.child),
+            ("Status", &mut self.status),
+            ("Task times", &mut self.task_times),
+            ("Tag", &mut self.tag),
+            ("Tree", &mut self.tree),
+            ("Blob", &mut self.blob),
+            ("Canonical", &mut self.canonical),
+            ("Re-run", &mut self.rerun),
+            ("Materialize", &mut self.materialize),
+            ("Parents batch", &mut self.parents_batch),
+            ("Roots", &mut self.roots),
+            ("Object", &mut self.object),
+            ("Version", &mut self.version),
+            ("Handle parameter", &mut self.handle),
+            ("Operation parameter", &mut self.operation),
+            ("Limit parameter", &mut self.limit),
+            ("After parameter", &mut self.after),
+        ];
+        Grid::new("routes").num_columns(2).show(ui, |ui| {
+            for (name, value) in fields {
+                ui.label(name);
+                ui.text_edit_singleline(value);
+                ui.end_row();
+            }
+        });
+        if ui.button("Reset").clicked() {
+            *self = Self::default();
+        }
+    }
+}
+
+/// A server to query and how to reach it.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct ServerProfile {
+    pub(crate) name: String,
+    /// Host and port, without a scheme.
+    pub(crate) url: String,
+    pub(crate) transport: Transport,
+    pub(crate) timeouts: Timeouts,
+    pub(crate) routes: Routes,
+}
+
+impl Default for ServerProfile {
+    fn default() -> Self {
+        Self {
+            name: "Default".to_string(),
+            url: String::new(),
+            transport: Transport::default(),
+            timeouts: Timeouts::default(),
+            routes: Routes::default(),
+        }
+    }
+}
+
+/// Every saved server, one of which is queried.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct Profiles {
+    profiles: Vec<ServerProfile>,
+    active: usize,
+}
+
+impl Default for Profiles {
+    fn default() -> Self {
+        Self {
+            profiles: vec![ServerProfile::default()],
+            active: 0,
+        }
+    }
+}
+
+impl Profiles {
+    pub(crate) fn active(&self) -> &ServerProfile {
+        // Hand-edited storage may point past the end.
+        self.profiles
+            .get(self.active)
+            .or_else(|| self.profiles.first())
+            .expect("there is always a profile")
+    }
+
+    pub(crate) fn all(&self) -> &[ServerProfile] {
+        &self.profiles
+    }
+
+    /// Make the profile at `index` the active one.
+    pub(crate) fn select(&mut self, index: usize) {
+        self.active = index.min(self.profiles.len().saturating_sub(1));
+    }
+
+    /// Make the first profile for `url` the active one, adding a profile for
+    /// it if there is none.
+    pub(crate) fn open_url(&mut self, url: &str) {
+        if let Some(index) = self.profiles.iter().position(|profile| profile.url == url) {
+            self.active = index;
+            return;
+        }
+        let profile = ServerProfile {
+            name: url.to_string(),
+            url: url.to_string(),
+            ..self.active().clone()
+        };
+        self.profiles.push(profile);
+        self.active = self.profiles.len() - 1;
+    }
+
+    pub(crate) fn active_mut(&mut self) -> &mut ServerProfile {
+        if self.profiles.is_empty() {
+            self.profiles.push(ServerProfile::default());
+        }
+        self.active = self.active.min(self.profiles.len() - 1);
+        &mut self.profiles[self.active]
+    }
+
+    /// Pick, add, remove and edit profiles. Returns whether requests now go
+    /// somewhere else.
+    pub(crate) fn ui(&mut self, ui: &mut Ui) -> bool {
+        let before = self.active().clone();
+        ui.horizontal(|ui| {
+            ui.label("Server: ");
+            ComboBox::from_id_source("server_profile")
+                .selected_text(self.active().name.as_str())
+                .show_ui(ui, |ui| {
+                    for (index, profile) in self.profiles.iter().enumerate() {
+                        ui.selectable_value(&mut self.active, index, profile.name.as_str());
+                    }
+                });
+            if ui.small_button("+").on_hover_text("Add a server").clicked() {
+                let profile = ServerProfile {
+                    name: format!("Server {}", self.profiles.len() + 1),
+                    ..self.active().clone()
+                };
+                self.profiles.push(profile);
+                self.active = self.profiles.len() - 1;
+            }
+            if self.profiles.len() > 1
+                && ui
+                    .small_button("✖")
+                    .on_hover_text("Remove this server")
+                    .clicked()
+            {
+                self.profiles.remove(self.active);
+                self.active = self.active.saturating_sub(1);
+            }
+        });
+        let profile = self.active_mut();
+        ui.horizontal(|ui| {
+            ui.label("Name: ");
+            TextEdit::singleline(&mut profile.name)
+                .desired_width(f32::INFINITY)
+                .show(ui);
+        });
+        ui.horizontal(|ui| {
+            ui.label("URL: ");
+            TextEdit::singleline(&mut profile.url)
+                .hint_text("127.0.0.1:9090")
+                .desired_width(f32::INFINITY)
+                .show(ui);
+        });
+        ui.horizontal(|ui| {
+            ui.label("Transport: ");
+            for transport in Transport::ALL {
+                ui.selectable_value(&mut profile.transport, transport, transport.to_string());
+            }
+        });
+        ui.horizontal(|ui| {
+            let timeouts = &mut profile.timeouts;
+            ui.label("Timeouts: ");
+            ui.add(
+                DragValue::new(&mut timeouts.connect)
+                    .clamp_range(0.1..=600.0)
+                    .speed(0.1)
+                    .suffix(" s"),
+            )
+            .on_hover_text("Give up on connecting after this long");
+            ui.add(
+                DragValue::new(&mut timeouts.read)
+                    .clamp_range(0.1..=600.0)
+                    .speed(0.1)
+                    .suffix(" s"),
+            )
+            .on_hover_text("Give up on a response after this long once connected");
+        });
+        ui.collapsing("Routes", |ui| profile.routes.ui(ui));
+        let after = self.active();
+        after.url != before.url
+            || after.transport != before.transport
+            || after.routes != before.routes
+    }
+}
@@ -0,0 +1,320 @@
+//! A console for asking the server one query by hand, as in
+//! `parents d9-0-4-0`, and reading the JSON it answers with.
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use egui::{Context, Key, RichText, ScrollArea, TextEdit};
+
+use crate::{
+    cache::ResponseCache,
+    graphs::GraphsContainer,
+    handle::{Handle, Operation},
+    http::{self, DataSource, Envelope, Generation, Request, RequestId, RequestKind},
+    workspace::{self, QUERY},
+};
+
+/// How queries are written, shown under the input.
+const USAGE: &str = "parents|count|dependees|child|status|times|tag|tree|blob|canonical|exists HANDLE [OPERATION], or roots";
+
+/// How many queries and their answers are kept.
+const HISTORY: usize = 50;
+
+/// The request `query` asks for: an endpoint, then a handle in hex or
+/// base64, then an operation for the endpoints that take one.
+pub(crate) fn parse_query(query: &str) -> Result<RequestKind> {
+    let mut words = query.split_whitespace();
+    let endpoint = words.next().context("type an endpoint, then a handle")?;
+    if endpoint == "roots" {
+        return Ok(RequestKind::Roots);
+    }
+    let handle = Handle::parse(words.next().context("missing handle")?)?;
+    let operation = words.next().map(parse_operation).transpose()?;
+    if let Some(extra) = words.next() {
+        bail!("unexpected {extra:?} after the operation");
+    }
+    let required = || operation.with_context(|| format!("{endpoint} needs an operation"));
+    Ok(match endpoint {
+        "parents" => RequestKind::Parents {
+            generation: Generation::default(),
+            handle,
+            after: None,
+            operation,
+        },
+        "count" | "parent_count" => RequestKind::ParentCount { handle },
+        "dependees" => RequestKind::Dependees {
+            generation: Generation::default(),
+            index: 0,
+            handle,
+            operation: required()?,
+            after: None,
+        },
+        "child" | "outcome" => RequestKind::Outcome {
+            handle,
+            operation: required()?,
+        },
+        "status" => RequestKind::Status {
+            handle,
+            operation: required()?,
+        },
+        "times" | "task_times" => RequestKind::TaskTimes { handle },
+        "tag" => RequestKind::Tag { handle },
+        "tree" => RequestKind::Tree { handle },
+        "blob" => RequestKind::Blob { handle },
+        "canonical" => RequestKind::Canonical { handle },
+        "exists" => RequestKind::Exists { handle },
+        other => bail!("unknown endpoint {other:?}"),
+    })
+}
+
+/// An operation by name, in any case, or by number.
+fn parse_operation(word: &str) -> Result<Operation> {
+    match word.to_ascii_lowercase().as_str() {
+        "apply" => Ok(Operation::Apply),
+        "eval" => Ok(Operation::Eval),
+        "fill" => Ok(Operation::Fill),
+        number => number
+            .parse::<u8>()
+            .map_err(|_| anyhow!("unknown operation {word:?}"))?
+            .try_into(),
+    }
+}
+
+/// A query sent, and the server's answer once it arrives.
+struct Exchange {
+    query: String,
+    id: RequestId,
+    /// The JSON answer, indented, or why there is none.
+    answer: Option<Result<String, String>>,
+    /// Why the answer was not merged into the graph, if merging was asked
+    /// for and could not be done.
+    unmerged: Option<&'static str>,
+    /// Whether the answer is to be applied to the graph once it arrives.
+    merging: bool,
+}
+
+/// The "Query" window, with the queries asked so far, newest first.
+pub(crate) struct QueryConsole {
+    open: bool,
+    input: String,
+    /// Whether answers are also applied to the graph, as if the viewer had
+    /// asked.
+    merge: bool,
+    history: Vec<Exchange>,
+    tx: Sender<(Request, Result<String>)>,
+    rx: Receiver<(Request, Result<String>)>,
+}
+
+impl Default for QueryConsole {
+    fn default() -> Self {
+        let (tx, rx) = unbounded();
+        Self {
+            open: false,
+            input: String::new(),
+            merge: false,
+            history: vec![],
+            tx,
+            rx,
+        }
+    }
+}
+
+impl QueryConsole {
+    pub(crate) fn button(&mut self, ui: &mut egui::Ui) {
+        if ui
+            .button("Query console")
+            .on_hover_text("Ask the server a query by hand and read its JSON answer")
+            .clicked()
+        {
+            self.open = true;
+        }
+    }
+
+    /// Show the window while it is open, and take in answers. Answers to
+    /// merge are parsed as the viewer would and sent on over `tx`, cached
+    /// like any other.
+    pub(crate) fn show(
+        &mut self,
+        ctx: &Context,
+        source: &Arc<DataSource>,
+        url: &str,
+        tx: &Sender<Envelope>,
+        cache: &ResponseCache,
+        graph: Option<&GraphsContainer>,
+    ) {
+        for (request, result) in self.rx.try_iter() {
+            let Some(exchange) = self.history.iter_mut().find(|e| e.id == request.id) else {
+                continue;
+            };
+            if let (true, Ok(body)) = (exchange.merging, &result) {
+                let kind = request.kind.clone();
+                let parse = move |body: &str| http::parse_response(&kind, body);
+                let response = if request.kind.is_cached() {
+                    cache.inserting(ctx.clone(), &request.kind, |body: &str| parse(body))(body)
+                } else {
+                    parse(body)
+                };
+                let envelope = Envelope {
+                    request: request.clone(),
+                    result: response,
+                };
+                http::deliver(ctx, tx, envelope);
+            }
+            exchange.answer = Some(
+                result
+                    .and_then(|body| {
+                        let json: serde_json::Value =
+                            serde_json::from_str(&body).context("parsing json")?;
+                        Ok(serde_json::to_string_pretty(&json)?)
+                    })
+                    .map_err(|e| format!("{e:#}")),
+            );
+        }
+        let mut submitted = None;
+        let window = egui::Window::new(QUERY)
+            .open(&mut self.open)
+            .default_size([480.0, 400.0]);
+        let shown = workspace::place(ctx, QUERY, window).show(ctx, |ui| {
+            let parsed = parse_query(&self.input);
+            ui.horizontal(|ui| {
+                let input = ui.add(
+                    TextEdit::singleline(&mut self.input)
+                        .hint_text("parents d9-0-4-0")
+                        .font(egui::TextStyle::Monospace)
+                        .desired_width(300.0),
+                );
+                let entered = input.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+                let send = ui.add_enabled(parsed.is_ok(), egui::Button::new("Send"));
+                if (entered || send.clicked()) && parsed.is_ok() {
+                    submitted = parsed.as_ref().ok().cloned();
+                    input.request_focus();
+                }
+            });
+            ui.checkbox(&mut self.merge, "Merge into the graph")
+                .on_hover_text("Also apply the answer to the graph, as if the viewer had asked");
+            match &parsed {
+                Err(e) if !self.input.trim().is_empty() => {
+                    ui.colored_label(ui.visuals().error_fg_color, format!("{e:#}"));
+                }
+                _ => {
+                    ui.weak(USAGE);
+                }
+            }
+            ui.separator();
+            ScrollArea::vertical().show(ui, |ui| {
+                for exchange in &self.history {
+                    ui.label(RichText::new(&exchange.query).monospace().strong());
+                    if let Some(reason) = exchange.unmerged {
+                        ui.colored_label(ui.visuals().warn_fg_color, reason);
+                    }
+                    match &exchange.answer {
+                        None => {
+                            ui.spinner();
+                        }
+                        Some(Ok(json)) => {
+                            ui.monospace(json);
+                        }
+                        Some(Err(e)) => {
+                            ui.colored_label(ui.visuals().error_fg_color, e);
+                        }
+                    }
+                    ui.separator();
+                }
+            });
+        });
+        workspace::track(ctx, QUERY, &shown);
+        let Some(mut kind) = submitted else {
+            return;
+        };
+        let unmerged = if self.merge {
+            mergeable(graph, &kind).err()
+        } else {
+            None
+        };
+        // Parents merged in are for the graphs drawn now.
+        if let (Some(graph), RequestKind::Parents { generation, .. }) = (graph, &mut kind) {
+            *generation = graph.generation();
+        }
+        let request = Request::new(kind);
+        self.history.insert(
+            0,
+            Exchange {
+                query: self.input.trim().to_string(),
+                id: request.id,
+                answer: None,
+                unmerged,
+                merging: self.merge && unmerged.is_none(),
+            },
+        );
+        self.history.truncate(HISTORY);
+        http::get_raw(
+            source.clone(),
+            ctx.clone(),
+            request,
+            url.to_string(),
+            self.tx.clone(),
+        );
+    }
+}
+
+/// Whether the answer to `kind` has a place in `graph`, or why not.
+fn mergeable(graph: Option<&GraphsContainer>, kind: &RequestKind) -> Result<(), &'static str> {
+    let Some(graph) = graph else {
+        return Err("Not merged: there is no graph yet.");
+    };
+    match kind {
+        RequestKind::Parents { handle, .. } | RequestKind::Exists { handle }
+            if !graph.in_ancestry(handle) =>
+        {
+            Err("Not merged: the handle is not in the ancestry tree.")
+        }
+        RequestKind::Dependees { .. } | RequestKind::Child { .. } => {
+            Err("Not merged: dependees belong to a task in the progress tree.")
+        }
+        RequestKind::Rerun { .. } => Err("Not merged: re-runs are started from the progress tree."),
+        RequestKind::Materialize { .. } => {
+            Err("Not merged: force loads are started from the inspector.")
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_queries() {
+        let hex = "d9-0-4-100000000000000";
+        let handle = Handle::from_hex(hex).unwrap();
+        assert_eq!(
+            parse_query(&format!("parents {hex}")).unwrap(),
+            RequestKind::Parents {
+                generation: Generation::default(),
+                handle: handle.clone(),
+                after: None,
+                operation: None
+            }
+        );
+        assert_eq!(
+            parse_query(&format!("  status {hex} EVAL ")).unwrap(),
+            RequestKind::Status {
+                handle: handle.clone(),
+                operation: Operation::Eval
+            }
+        );
+        assert_eq!(
+            parse_query(&format!("child {hex} 0")).unwrap(),
+            RequestKind::Outcome {
+                handle,
+                operation: Operation::Apply
+            }
+        );
+        assert_eq!(parse_query("roots").unwrap(), RequestKind::Roots);
+        assert!(parse_query(&format!("status {hex}")).is_err());
+        assert!(parse_query(&format!("tree {hex} apply extra")).is_err());
+        assert!(parse_query(&format!("leaves {hex}")).is_err());
+        assert!(parse_query("tree").is_err());
+    }
+}
@@ -0,0 +1,152 @@
+//! A zip to attach to a bug report on the fix runtime: what the user saw
+//! and what the server answered while they saw it.
+
+use std::{
+    fs::File,
+    io::{Seek, Write},
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+use egui::{TextEdit, Ui};
+use zip::{write::FileOptions, ZipWriter};
+
+use crate::{
+    changelog,
+    image_export::{ImageFormat, Picture},
+    trace::Trace,
+};
+
+/// Everything that goes into a report.
+pub(crate) struct Report {
+    /// What went wrong, in the reporter's words.
+    pub(crate) description: String,
+    pub(crate) server: String,
+    pub(crate) target: String,
+    /// The session, as the Developer section saves it.
+    pub(crate) session: String,
+    pub(crate) trace: Trace,
+    /// The error console's entries, as text.
+    pub(crate) errors: String,
+    /// The ancestry graph, if there is one.
+    pub(crate) plot: Option<Picture>,
+}
+
+impl Report {
+    /// Write the report as a zip of one file per part.
+    pub(crate) fn write(&self, out: impl Write + Seek) -> Result<()> {
+        let mut zip = ZipWriter::new(out);
+        let options = FileOptions::default();
+        zip.start_file("report.txt", options)?;
+        write!(
+            zip,
+            "fix_viewer {}\nserver: {}\ntarget: {}\n\n{}\n",
+            changelog::current_version(),
+            self.server,
+            self.target,
+            self.description.trim(),
+        )?;
+        zip.start_file("session.json", options)?;
+        zip.write_all(self.session.as_bytes())?;
+        zip.start_file("trace.jsonl", options)?;
+        self.trace.write(&mut zip).context("writing trace")?;
+        zip.start_file("errors.txt", options)?;
+        zip.write_all(self.errors.as_bytes())?;
+        if let Some(plot) = &self.plot {
+            zip.start_file("ancestry.png", options)?;
+            plot.encode(ImageFormat::Png, &mut zip)
+                .context("drawing the plot")?;
+        }
+        zip.finish()?;
+        Ok(())
+    }
+}
+
+/// Report controls for the developer panel.
+#[derive(Default)]
+pub(crate) struct ReportPanel {
+    description: String,
+    path: String,
+    status: String,
+}
+
+impl ReportPanel {
+    const DEFAULT_PATH: &'static str = "report.zip";
+
+    /// `gather` collects the report once it is asked for.
+    pub(crate) fn ui(&mut self, ui: &mut Ui, gather: impl FnOnce(&mut Ui, String) -> Report) {
+        TextEdit::multiline(&mut self.description)
+            .hint_text("What went wrong, and what you expected")
+            .desired_rows(3)
+            .desired_width(f32::INFINITY)
+            .show(ui);
+        ui.horizontal(|ui| {
+            ui.label("Report: ");
+            TextEdit::singleline(&mut self.path)
+                .hint_text(Self::DEFAULT_PATH)
+                .desired_width(f32::INFINITY)
+                .show(ui);
+        });
+        if ui
+            .button("Report issue")
+            .on_hover_text(
+                "Zip the session, recent requests, errors and a picture of the ancestry graph",
+            )
+            .clicked()
+        {
+            let path = if self.path.is_empty() {
+                Self::DEFAULT_PATH
+            } else {
+                self.path.as_str()
+            };
+            let report = gather(ui, self.description.clone());
+            self.status = match save(&report, Path::new(path)) {
+                Ok(()) => format!("Saved the report to {path}; attach it to the issue."),
+                Err(e) => format!("Saving the report to {path} failed: {e:#}."),
+            };
+        }
+        if !self.status.is_empty() {
+            ui.label(self.status.as_str());
+        }
+    }
+}
+
+fn save(report: &Report, path: &Path) -> Result<()> {
+    let file = File::create(path).context("creating report file")?;
+    report.write(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use super::*;
+
+    #[test]
+    fn zips_every_part() {
+        let report = Report {
+            description: "parents never arrive\n".to_string(),
+            server: "http://localhost:9090".to_string(),
+            target: "0-0-0-2400000000000000".to_string(),
+            session: "{}".to_string(),
+            trace: Trace::default(),
+            errors: "00:00:01 target\n".to_string(),
+            plot: None,
+        };
+        let mut out = Cursor::new(vec![]);
+        report.write(&mut out).unwrap();
+        let mut zip = zip::ZipArchive::new(out).unwrap();
+        let mut names: Vec<_> = zip.file_names().collect();
+        names.sort_unstable();
+        assert_eq!(
+            names,
+            ["errors.txt", "report.txt", "session.json", "trace.jsonl"]
+        );
+        let mut text = String::new();
+        zip.by_name("report.txt")
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        assert!(text.ends_with("target: 0-0-0-2400000000000000\n\nparents never arrive\n"));
+    }
+}
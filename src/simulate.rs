@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use egui::{Slider, Ui};
+
+/// Artificial network conditions applied to every request, so that loading
+/// states and error handling can be exercised without a flaky server.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub(crate) struct NetworkSimulation {
+    pub(crate) enabled: bool,
+    /// Delay added before every request is sent.
+    pub(crate) latency_ms: u32,
+    /// Up to this much extra delay, chosen per request.
+    pub(crate) jitter_ms: u32,
+    /// Chance in [0, 1] that a request fails instead of being sent.
+    pub(crate) failure_rate: f32,
+}
+
+impl Default for NetworkSimulation {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            latency_ms: 500,
+            jitter_ms: 250,
+            failure_rate: 0.1,
+        }
+    }
+}
+
+impl NetworkSimulation {
+    /// Pick the delay for a request and whether it should fail.
+    /// The same seed always gives the same outcome.
+    pub(crate) fn sample(&self, seed: u64) -> (Duration, bool) {
+        let random = splitmix64(seed);
+        // Use the low and high halves as two independent draws.
+        let jitter = if self.jitter_ms == 0 {
+            0
+        } else {
+            (random & 0xFFFF_FFFF) % (u64::from(self.jitter_ms) + 1)
+        };
+        let fail = ((random >> 32) as f32 / u32::MAX as f32) < self.failure_rate;
+        (
+            Duration::from_millis(u64::from(self.latency_ms) + jitter),
+            fail,
+        )
+    }
+
+    /// Controls for the developer panel. Returns whether anything changed.
+    pub(crate) fn ui(&mut self, ui: &mut Ui) -> bool {
+        let before = *self;
+        ui.checkbox(&mut self.enabled, "Simulate network");
+        ui.add_enabled_ui(self.enabled, |ui| {
+            ui.add(Slider::new(&mut self.latency_ms, 0..=5000).text("latency (ms)"));
+            ui.add(Slider::new(&mut self.jitter_ms, 0..=5000).text("jitter (ms)"));
+            ui.add(Slider::new(&mut self.failure_rate, 0.0..=1.0).text("failure rate"));
+        });
+        before != *self
+    }
+}
+
+/// Wait without blocking the UI thread, on both native and web.
+pub(crate) async fn sleep(duration: Duration) {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let promise = js_sys::Promise::new(&mut |resolve, _| {
+            let window = web_sys::window().expect("Missing window.");
+            let _ = window.set_timeout_with_callback_and_timeout_and_arguments_0(
+                &resolve,
+                duration.as_millis() as i32,
+            );
+        });
+        let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    tokio::time::sleep(duration).await;
+}
+
+/// A small, well mixed hash so that consecutive seeds give unrelated outcomes.
+fn splitmix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,192 @@
+use std::fmt::Display;
+
+use egui::{Color32, Ui, Visuals};
+
+use crate::handle::Operation;
+
+/// The look of the whole app, picked in the View section.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub(crate) enum Theme {
+    #[default]
+    Dark,
+    Light,
+    /// White on black with saturated graph colors.
+    HighContrast,
+}
+
+impl Theme {
+    pub(crate) const ALL: [Theme; 3] = [Theme::Dark, Theme::Light, Theme::HighContrast];
+
+    pub(crate) fn visuals(self) -> Visuals {
+        match self {
+            Theme::Dark => Visuals::dark(),
+            Theme::Light => Visuals::light(),
+            Theme::HighContrast => {
+                let mut visuals = Visuals::dark();
+                visuals.override_text_color = Some(Color32::WHITE);
+                visuals.panel_fill = Color32::BLACK;
+                visuals.window_fill = Color32::BLACK;
+                visuals.extreme_bg_color = Color32::BLACK;
+                visuals.hyperlink_color = Color32::from_rgb(0, 255, 255);
+                visuals.widgets.noninteractive.fg_stroke.color = Color32::WHITE;
+                visuals.widgets.active.fg_stroke.color = Color32::WHITE;
+                visuals
+            }
+        }
+    }
+
+    pub(crate) fn palette(self) -> Palette {
+        match self {
+            Theme::Dark => Palette::default(),
+            Theme::Light => Palette {
+                apply: Color32::from_rgb(0, 150, 0),
+                eval: Color32::from_rgb(20, 20, 220),
+                fill: Color32::from_rgb(200, 0, 0),
+                restricted: Color32::from_rgb(210, 110, 0),
+                highlight: Color32::BLUE,
+            },
+            Theme::HighContrast => Palette {
+                apply: Color32::from_rgb(0, 255, 0),
+                eval: Color32::from_rgb(80, 160, 255),
+                fill: Color32::from_rgb(255, 64, 64),
+                restricted: Color32::YELLOW,
+                highlight: Color32::WHITE,
+            },
+        }
+    }
+}
+
+impl Display for Theme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Theme::Dark => "Dark",
+            Theme::Light => "Light",
+            Theme::HighContrast => "High contrast",
+        })
+    }
+}
+
+/// Where the colors of the operations come from, picked in the View section.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub(crate) enum OperationColors {
+    /// The colors of the theme.
+    #[default]
+    Theme,
+    /// Colors from Okabe and Ito's palette, told apart with any common kind
+    /// of color blindness.
+    ColorblindSafe,
+    /// Colors picked by hand.
+    Custom,
+}
+
+impl OperationColors {
+    pub(crate) const ALL: [OperationColors; 3] = [
+        OperationColors::Theme,
+        OperationColors::ColorblindSafe,
+        OperationColors::Custom,
+    ];
+}
+
+impl Display for OperationColors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            OperationColors::Theme => "Theme",
+            OperationColors::ColorblindSafe => "Colorblind safe",
+            OperationColors::Custom => "Custom",
+        })
+    }
+}
+
+/// Operation colors picked by hand, in sRGB.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct CustomColors {
+    apply: [u8; 3],
+    eval: [u8; 3],
+    fill: [u8; 3],
+}
+
+impl Default for CustomColors {
+    fn default() -> Self {
+        let palette = Palette::default();
+        let rgb = |color: Color32| [color.r(), color.g(), color.b()];
+        Self {
+            apply: rgb(palette.apply),
+            eval: rgb(palette.eval),
+            fill: rgb(palette.fill),
+        }
+    }
+}
+
+impl CustomColors {
+    /// A color picker for each operation. Returns whether one was changed.
+    pub(crate) fn ui(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            for (operation, color) in [
+                (Operation::Apply, &mut self.apply),
+                (Operation::Eval, &mut self.eval),
+                (Operation::Fill, &mut self.fill),
+            ] {
+                changed |= ui.color_edit_button_srgb(color).changed();
+                ui.label(operation.to_string());
+            }
+        });
+        changed
+    }
+}
+
+/// Colors the graphs draw with that do not come from the egui visuals.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Palette {
+    apply: Color32,
+    eval: Color32,
+    fill: Color32,
+    /// Outline of elements whose handle the server will not resolve.
+    pub(crate) restricted: Color32,
+    /// Tint over the element under the pointer.
+    pub(crate) highlight: Color32,
+}
+
+impl Palette {
+    /// Color of arrows and bars for `operation`.
+    pub(crate) fn operation(&self, operation: Operation) -> Color32 {
+        match operation {
+            Operation::Apply => self.apply,
+            Operation::Eval => self.eval,
+            Operation::Fill => self.fill,
+        }
+    }
+
+    /// This palette with the operations in the colors `colors` picks.
+    pub(crate) fn with_operations(self, colors: OperationColors, custom: &CustomColors) -> Self {
+        let rgb = |[r, g, b]: [u8; 3]| Color32::from_rgb(r, g, b);
+        let (apply, eval, fill) = match colors {
+            OperationColors::Theme => return self,
+            OperationColors::ColorblindSafe => (
+                Color32::from_rgb(0, 158, 115),
+                Color32::from_rgb(0, 114, 178),
+                Color32::from_rgb(213, 94, 0),
+            ),
+            OperationColors::Custom => (rgb(custom.apply), rgb(custom.eval), rgb(custom.fill)),
+        };
+        Self {
+            apply,
+            eval,
+            fill,
+            ..self
+        }
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            apply: Color32::GREEN,
+            eval: Color32::from_rgb(20, 20, 255),
+            fill: Color32::RED,
+            restricted: Color32::from_rgb(255, 165, 0),
+            highlight: Color32::BLUE,
+        }
+    }
+}
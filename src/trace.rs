@@ -0,0 +1,273 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{anyhow, Context, Result};
+use egui::{TextEdit, Ui};
+
+/// One request and what the server answered: the response body, or why no
+/// body was received.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub(crate) struct TraceEntry {
+    /// Path and query of the request, without the server it was sent to.
+    pub(crate) path: String,
+    pub(crate) response: Result<String, String>,
+}
+
+/// Every request made while recording, in the order they were answered.
+/// Saved as JSON lines, one entry per line.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Trace {
+    entries: Vec<TraceEntry>,
+}
+
+impl Trace {
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub(crate) fn read(reader: impl BufRead) -> Result<Self> {
+        let mut entries = vec![];
+        for (number, line) in reader.lines().enumerate() {
+            let line = line.context("reading trace")?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = serde_json::from_str(&line)
+                .with_context(|| format!("parsing trace line {}", number + 1))?;
+            entries.push(entry);
+        }
+        Ok(Self { entries })
+    }
+
+    pub(crate) fn write(&self, mut out: impl Write) -> Result<()> {
+        for entry in &self.entries {
+            serde_json::to_writer(&mut out, entry)?;
+            out.write_all(b"\n")?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// Answers requests from a recorded trace. A path requested more than once
+/// while recording is answered with its last response.
+#[derive(Debug)]
+pub(crate) struct Replay {
+    responses: HashMap<String, Result<String, String>>,
+}
+
+impl Replay {
+    pub(crate) fn new(trace: Trace) -> Self {
+        Self {
+            responses: trace
+                .entries
+                .into_iter()
+                .map(|entry| (entry.path, entry.response))
+                .collect(),
+        }
+    }
+
+    /// The recorded response body for `url`.
+    pub(crate) fn respond(&self, url: &str) -> Result<String> {
+        let path = path_of(url);
+        match self.responses.get(path) {
+            Some(Ok(body)) => Ok(body.clone()),
+            Some(Err(e)) => Err(anyhow!("{e}")),
+            None => Err(anyhow!(
+                "request failed: {path} is not in the replayed trace"
+            )),
+        }
+    }
+}
+
+/// How many of the latest requests are kept for bug reports, recording or
+/// not.
+const RECENT: usize = 200;
+
+/// Records requests into a trace or replays them from one. Shared between
+/// the UI and the in-flight requests.
+#[derive(Default)]
+pub(crate) struct Tracer {
+    recording: Mutex<Option<Trace>>,
+    replay: Mutex<Option<Arc<Replay>>>,
+    recent: Mutex<VecDeque<TraceEntry>>,
+}
+
+impl Tracer {
+    pub(crate) fn start_recording(&self) {
+        *self.recording.lock().unwrap() = Some(Trace::default());
+    }
+
+    /// Stop recording and hand out everything recorded so far.
+    pub(crate) fn stop_recording(&self) -> Option<Trace> {
+        self.recording.lock().unwrap().take()
+    }
+
+    /// Number of requests recorded, if recording.
+    pub(crate) fn recorded(&self) -> Option<usize> {
+        self.recording.lock().unwrap().as_ref().map(Trace::len)
+    }
+
+    pub(crate) fn record(&self, url: &str, response: &Result<String>) {
+        let entry = TraceEntry {
+            path: path_of(url).to_string(),
+            response: response.as_ref().map_err(|e| format!("{e:#}")).cloned(),
+        };
+        let mut recent = self.recent.lock().unwrap();
+        if recent.len() == RECENT {
+            recent.pop_front();
+        }
+        recent.push_back(entry.clone());
+        if let Some(trace) = self.recording.lock().unwrap().as_mut() {
+            trace.entries.push(entry);
+        }
+    }
+
+    /// The latest requests, whether or not they were being recorded.
+    pub(crate) fn recent(&self) -> Trace {
+        Trace {
+            entries: self.recent.lock().unwrap().iter().cloned().collect(),
+        }
+    }
+
+    pub(crate) fn set_replay(&self, replay: Option<Replay>) {
+        *self.replay.lock().unwrap() = replay.map(Arc::new);
+    }
+
+    pub(crate) fn replay(&self) -> Option<Arc<Replay>> {
+        self.replay.lock().unwrap().clone()
+    }
+}
+
+/// The part of `url` after the server, so a trace can be replayed against
+/// any server URL.
+fn path_of(url: &str) -> &str {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    without_scheme
+        .find('/')
+        .map_or("/", |start| &without_scheme[start..])
+}
+
+/// Trace controls for the developer panel.
+#[derive(Default)]
+pub(crate) struct TracePanel {
+    path: String,
+    status: String,
+}
+
+impl TracePanel {
+    const DEFAULT_PATH: &'static str = "trace.jsonl";
+
+    pub(crate) fn ui(&mut self, ui: &mut Ui, tracer: &Tracer) {
+        ui.horizontal(|ui| {
+            ui.label("Trace: ");
+            TextEdit::singleline(&mut self.path)
+                .hint_text(Self::DEFAULT_PATH)
+                .desired_width(f32::INFINITY)
+                .show(ui);
+        });
+        let path = if self.path.is_empty() {
+            Self::DEFAULT_PATH
+        } else {
+            self.path.as_str()
+        };
+        ui.horizontal(|ui| match tracer.recorded() {
+            Some(recorded) => {
+                if ui
+                    .button(format!("Stop and save ({recorded} requests)"))
+                    .clicked()
+                {
+                    let trace = tracer.stop_recording().unwrap_or_default();
+                    self.status = match save(&trace, Path::new(path)) {
+                        Ok(()) => format!("Saved {} requests to {path}.", trace.len()),
+                        Err(e) => format!("Saving trace to {path} failed: {e:#}."),
+                    };
+                }
+            }
+            None => {
+                if ui.button("Record").clicked() {
+                    tracer.start_recording();
+                    self.status.clear();
+                }
+                if tracer.replay().is_some() {
+                    if ui.button("Stop replay").clicked() {
+                        tracer.set_replay(None);
+                        self.status = "Requests go to the server again.".to_string();
+                    }
+                } else if ui.button("Replay").clicked() {
+                    self.status = match load(Path::new(path)) {
+                        Ok(trace) => {
+                            let len = trace.len();
+                            tracer.set_replay(Some(Replay::new(trace)));
+                            format!("Replaying {len} requests from {path}.")
+                        }
+                        Err(e) => format!("Loading trace from {path} failed: {e:#}."),
+                    };
+                }
+            }
+        });
+        if !self.status.is_empty() {
+            ui.label(self.status.as_str());
+        }
+    }
+}
+
+fn save(trace: &Trace, path: &Path) -> Result<()> {
+    let file = File::create(path).context("creating trace file")?;
+    trace.write(BufWriter::new(file))
+}
+
+fn load(path: &Path) -> Result<Trace> {
+    let file = File::open(path).context("opening trace file")?;
+    Trace::read(BufReader::new(file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replays_recorded_responses() {
+        let tracer = Tracer::default();
+        tracer.record("http://a:1/parents?handle=x", &Ok("ignored".to_string()));
+        tracer.start_recording();
+        tracer.record("http://a:1/parents?handle=x", &Ok("{}".to_string()));
+        tracer.record("http://a:1/tag?handle=y", &Err(anyhow!("request failed")));
+        tracer.record(
+            "http://a:1/parents?handle=x",
+            &Ok("{\"parents\":[]}".to_string()),
+        );
+        let trace = tracer.stop_recording().unwrap();
+        assert_eq!(trace.len(), 3);
+
+        let mut file = vec![];
+        trace.write(&mut file).unwrap();
+        let trace = Trace::read(file.as_slice()).unwrap();
+        assert_eq!(trace.len(), 3);
+
+        // Replayed against a different server.
+        let replay = Replay::new(trace);
+        assert_eq!(
+            replay.respond("http://b:2/parents?handle=x").unwrap(),
+            "{\"parents\":[]}"
+        );
+        assert!(replay.respond("http://b:2/tag?handle=y").is_err());
+        assert!(replay.respond("http://b:2/tag?handle=z").is_err());
+    }
+
+    #[test]
+    fn keeps_recent_requests() {
+        let tracer = Tracer::default();
+        for i in 0..RECENT + 5 {
+            tracer.record(&format!("http://a:1/tag?handle={i}"), &Ok(String::new()));
+        }
+        let recent = tracer.recent();
+        assert_eq!(recent.len(), RECENT);
+        assert_eq!(recent.entries[0].path, "/tag?handle=5");
+    }
+}
@@ -0,0 +1,249 @@
+//! Where the windows sit: arranged for a wide monitor or a presentation, or
+//! as a layout saved by name and restored from storage.
+
+use std::collections::BTreeMap;
+
+use egui::{Context, Id, InnerResponse, Rect, Ui, Vec2, Window};
+
+pub(crate) const ANCESTRY: &str = "Ancestry Tree";
+pub(crate) const PROGRESS: &str = "Progress Tree";
+pub(crate) const TIMELINE: &str = "Task Timeline";
+pub(crate) const INSPECTOR: &str = "Inspector";
+pub(crate) const LOG: &str = "Log";
+pub(crate) const ERRORS: &str = "Errors";
+pub(crate) const LATENCY: &str = "Request latency";
+pub(crate) const QUERY: &str = "Query";
+
+/// Each window's share of the area below the top bar and beside the
+/// controls, as left, top, width and height.
+type Fractions = &'static [(&'static str, [f32; 4])];
+
+/// The plots in columns side by side, with the inspector and log at the right.
+const WIDE: Fractions = &[
+    (ANCESTRY, [0.0, 0.0, 0.5, 1.0]),
+    (PROGRESS, [0.5, 0.0, 0.25, 0.6]),
+    (TIMELINE, [0.5, 0.6, 0.25, 0.4]),
+    (INSPECTOR, [0.75, 0.0, 0.25, 0.6]),
+    (LOG, [0.75, 0.6, 0.25, 0.4]),
+];
+
+/// A large ancestry tree beside the inspector, with the rest below.
+const PRESENTATION: Fractions = &[
+    (ANCESTRY, [0.0, 0.0, 0.7, 0.75]),
+    (INSPECTOR, [0.7, 0.0, 0.3, 0.75]),
+    (PROGRESS, [0.0, 0.75, 0.5, 0.25]),
+    (TIMELINE, [0.5, 0.75, 0.5, 0.25]),
+];
+
+/// Where a window sits, in points.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub(crate) struct Placement {
+    min: [f32; 2],
+    size: [f32; 2],
+}
+
+impl Placement {
+    fn from_rect(rect: Rect) -> Self {
+        Self {
+            min: [rect.min.x, rect.min.y],
+            size: [rect.width(), rect.height()],
+        }
+    }
+}
+
+/// Where each window sits, keyed by its title.
+pub(crate) type Layout = BTreeMap<String, Placement>;
+
+/// The layout to apply when the windows are next shown.
+enum Arrangement {
+    Fractions(Fractions),
+    Saved(Layout),
+}
+
+/// The windows' layouts, saved with the rest of the storage.
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct Workspace {
+    /// Layouts saved by name.
+    layouts: BTreeMap<String, Layout>,
+    /// The layout applied last. Windows start there until they are moved.
+    applied: Layout,
+    /// Bumped each time a layout is applied, so that windows forget where
+    /// they were moved to since.
+    generation: u64,
+    /// The name to save the current layout under.
+    #[serde(skip)]
+    name: String,
+    #[serde(skip)]
+    pending: Option<Arrangement>,
+}
+
+/// What the windows share with the workspace while they are shown.
+#[derive(Clone, Default)]
+struct Shared {
+    generation: u64,
+    applied: Layout,
+    /// Where each window was last shown.
+    seen: Layout,
+}
+
+fn shared_id() -> Id {
+    Id::new("workspace")
+}
+
+impl Workspace {
+    /// Apply any layout picked since the last frame to `area`, the space the
+    /// windows are arranged in. Call before showing the windows.
+    pub(crate) fn begin(&mut self, ctx: &Context, area: Rect) {
+        match self.pending.take() {
+            Some(Arrangement::Fractions(fractions)) => {
+                self.apply(arrange(fractions, area));
+            }
+            Some(Arrangement::Saved(layout)) => self.apply(layout),
+            None => {}
+        }
+        ctx.data_mut(|data| {
+            let shared: &mut Shared = data.get_temp_mut_or_default(shared_id());
+            if shared.generation != self.generation || shared.applied != self.applied {
+                *shared = Shared {
+                    generation: self.generation,
+                    applied: self.applied.clone(),
+                    seen: Layout::new(),
+                };
+            }
+        });
+    }
+
+    fn apply(&mut self, layout: Layout) {
+        self.applied = layout;
+        self.generation += 1;
+    }
+
+    pub(crate) fn ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Arrange:");
+            if ui
+                .button("Wide")
+                .on_hover_text("The plots side by side, for wide monitors")
+                .clicked()
+            {
+                self.pending = Some(Arrangement::Fractions(WIDE));
+            }
+            if ui
+                .button("Presentation")
+                .on_hover_text("A large ancestry tree beside the inspector")
+                .clicked()
+            {
+                self.pending = Some(Arrangement::Fractions(PRESENTATION));
+            }
+        });
+        let mut deleted = None;
+        for (name, layout) in &self.layouts {
+            ui.horizontal(|ui| {
+                ui.label(name);
+                if ui.small_button("Apply").clicked() {
+                    self.pending = Some(Arrangement::Saved(layout.clone()));
+                }
+                if ui.small_button("🗑").on_hover_text("Delete").clicked() {
+                    deleted = Some(name.clone());
+                }
+            });
+        }
+        if let Some(name) = deleted {
+            self.layouts.remove(&name);
+        }
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.name).hint_text("Layout name"));
+            let save = ui.add_enabled(!self.name.trim().is_empty(), egui::Button::new("Save"));
+            if save
+                .on_hover_text("Save where the windows are now")
+                .clicked()
+            {
+                let seen = ui.ctx().data_mut(|data| {
+                    data.get_temp_mut_or_default::<Shared>(shared_id())
+                        .seen
+                        .clone()
+                });
+                self.layouts
+                    .insert(std::mem::take(&mut self.name).trim().to_string(), seen);
+            }
+        });
+    }
+}
+
+/// `fractions` of `area`, in points.
+fn arrange(fractions: Fractions, area: Rect) -> Layout {
+    fractions
+        .iter()
+        .map(|(title, [left, top, width, height])| {
+            let min = area.min + area.size() * Vec2::new(*left, *top);
+            let size = area.size() * Vec2::new(*width, *height);
+            let rect = Rect::from_min_size(min, size);
+            (title.to_string(), Placement::from_rect(rect))
+        })
+        .collect()
+}
+
+/// `window`, titled `title`, where the applied layout puts it.
+pub(crate) fn place<'open>(ctx: &Context, title: &str, window: Window<'open>) -> Window<'open> {
+    let shared = ctx.data_mut(|data| data.get_temp::<Shared>(shared_id()));
+    let Some(shared) = shared else {
+        return window;
+    };
+    // A new id makes egui forget where the window was moved to.
+    let window = match shared.generation {
+        0 => window,
+        generation => window.id(Id::new(title).with(generation)),
+    };
+    let Some(placement) = shared.applied.get(title) else {
+        return window;
+    };
+    // The size egui is given excludes the frame and title bar.
+    let style = ctx.style();
+    let chrome = style.spacing.window_margin.sum()
+        + Vec2::new(
+            0.0,
+            style.spacing.interact_size.y + style.spacing.item_spacing.y,
+        );
+    let size = Vec2::from(placement.size) - chrome;
+    window
+        .default_pos(placement.min)
+        .default_size(size.max(Vec2::splat(50.0)))
+}
+
+/// Remember where the window titled `title` was shown, to be saved with the
+/// layout.
+pub(crate) fn track<R>(ctx: &Context, title: &str, shown: &Option<InnerResponse<R>>) {
+    let Some(shown) = shown else {
+        return;
+    };
+    ctx.data_mut(|data| {
+        data.get_temp_mut_or_default::<Shared>(shared_id())
+            .seen
+            .insert(title.to_string(), Placement::from_rect(shown.response.rect));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arranges_within_area() {
+        let area = Rect::from_min_size(egui::pos2(200.0, 30.0), Vec2::new(1000.0, 600.0));
+        for fractions in [WIDE, PRESENTATION] {
+            for placement in arrange(fractions, area).values() {
+                let rect = Rect::from_min_size(placement.min.into(), placement.size.into());
+                assert!(area.expand(0.01).contains_rect(rect), "{placement:?}");
+            }
+        }
+        let wide = arrange(WIDE, area);
+        assert_eq!(
+            wide[ANCESTRY],
+            Placement {
+                min: [200.0, 30.0],
+                size: [500.0, 600.0]
+            }
+        );
+    }
+}